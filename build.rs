@@ -0,0 +1,6 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // The gRPC service is generated from its protobuf definition, which needs protoc installed
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/rocksdb_tool.proto")?;
+    Ok(())
+}
@@ -0,0 +1,51 @@
+/*
+ * C interface of playground-rocksdb-tool, built with
+ *
+ *   cargo rustc --release --lib --features cdylib --crate-type cdylib
+ *
+ * Functions return 0 on success and the CLI exit code of the error otherwise, rdbt_last_error()
+ * gives the message of the last failed call on the calling thread. JSON strings returned through
+ * out_json are the objects printed by `--output json` and are freed with rdbt_free_string().
+ */
+#ifndef ROCKSDB_TOOL_H
+#define ROCKSDB_TOOL_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct RdbtHandle RdbtHandle;
+
+/* Opens a database read-only, or as a secondary instance when secondary is non-zero, which
+ * catches up with the primary at the start of every call and keeps its files in a scratch
+ * directory of its own, NULL on failure */
+RdbtHandle *rdbt_open(const char *db_path, int secondary);
+
+/* Closes a database, NULL is ignored */
+void rdbt_close(RdbtHandle *handle);
+
+/* Looks up a key, returns 2 when it is not stored */
+int rdbt_check(const RdbtHandle *handle, const char *cf, const uint8_t *key, size_t key_len,
+               char **out_json);
+
+/* Counts the records of a column family */
+int rdbt_count(const RdbtHandle *handle, const char *cf, uint64_t *out_count);
+
+/* Walks the merkle tree below a 32-byte root, returns 0 whether or not the tree has issues */
+int rdbt_verify_merkle(const RdbtHandle *handle, const uint8_t *root, int include_defaults,
+                       uint64_t *out_issues, char **out_json);
+
+/* Message of the last failed call on this thread, valid until the next call, NULL if none failed */
+const char *rdbt_last_error(void);
+
+/* Frees a string returned through out_json, NULL is ignored */
+void rdbt_free_string(char *json);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* ROCKSDB_TOOL_H */
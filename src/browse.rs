@@ -0,0 +1,293 @@
+//! Interactive terminal browser over the column families of a database
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{Frame, Terminal};
+use rocksdb::{Direction as IterDirection, IteratorMode};
+use std::io;
+
+use crate::db::DbHandle;
+use crate::output::to_hex;
+use crate::record::DecoderRegistry;
+
+/// Browser state: the page of keys shown for the current column family and the key search
+struct Browser<'a> {
+    db: &'a DbHandle,
+    decoders: &'a DecoderRegistry,
+    cf_index: usize,
+    page_size: usize,
+    /// First key of every page before the current one, popped when paging back
+    page_starts: Vec<Vec<u8>>,
+    page: Vec<(Box<[u8]>, Box<[u8]>)>,
+    has_next_page: bool,
+    list_state: ListState,
+    /// Hex digits typed in search mode, keys are restricted to this prefix
+    search: String,
+    searching: bool,
+    status: String,
+}
+
+impl<'a> Browser<'a> {
+    fn new(db: &'a DbHandle, decoders: &'a DecoderRegistry, page_size: usize) -> Self {
+        Browser {
+            db,
+            decoders,
+            cf_index: 0,
+            page_size,
+            page_starts: Vec::new(),
+            page: Vec::new(),
+            has_next_page: false,
+            list_state: ListState::default(),
+            search: String::new(),
+            searching: false,
+            status: String::new(),
+        }
+    }
+
+    fn cf_name(&self) -> &str {
+        &self.db.cf_names()[self.cf_index]
+    }
+
+    /// Key prefix typed in search mode, an odd trailing hex digit is ignored until it is completed
+    fn prefix(&self) -> Vec<u8> {
+        let even = self.search.len() / 2 * 2;
+        hex::decode(&self.search[..even]).unwrap_or_default()
+    }
+
+    /// Loads the page of keys starting at `start`, or at the search prefix when `start` is `None`
+    fn load_page(&mut self, start: Option<Vec<u8>>) {
+        let prefix = self.prefix();
+        let start = start.unwrap_or_else(|| prefix.clone());
+        let cf = match self.db.cf(self.cf_name()) {
+            Ok(cf) => cf,
+            Err(e) => {
+                self.status = e.to_string();
+                return;
+            }
+        };
+
+        let mut page = Vec::with_capacity(self.page_size + 1);
+        let iter = self.db.iterator_cf_opt(
+            cf,
+            self.db.read_options(),
+            IteratorMode::From(&start, IterDirection::Forward),
+        );
+        for item in iter {
+            match item {
+                Ok((key, value)) => {
+                    if !key.starts_with(&prefix) || page.len() > self.page_size {
+                        break;
+                    }
+                    page.push((key, value));
+                }
+                Err(e) => {
+                    self.status = format!("Read error: {}", e);
+                    break;
+                }
+            }
+        }
+
+        // One extra key is read to know whether a next page exists
+        self.has_next_page = page.len() > self.page_size;
+        page.truncate(self.page_size);
+        self.page = page;
+        self.list_state
+            .select(if self.page.is_empty() { None } else { Some(0) });
+    }
+
+    fn reload(&mut self) {
+        self.page_starts.clear();
+        self.load_page(None);
+    }
+
+    fn next_page(&mut self) {
+        if !self.has_next_page {
+            return;
+        }
+        if let (Some((first, _)), Some((last, _))) = (self.page.first(), self.page.last()) {
+            let first = first.to_vec();
+            // The smallest key after `last` is `last` followed by a zero byte
+            let mut next = last.to_vec();
+            next.push(0);
+            self.page_starts.push(first);
+            self.load_page(Some(next));
+        }
+    }
+
+    fn previous_page(&mut self) {
+        if let Some(start) = self.page_starts.pop() {
+            self.load_page(Some(start));
+        }
+    }
+
+    fn select(&mut self, delta: isize) {
+        if self.page.is_empty() {
+            return;
+        }
+        let current = self.list_state.selected().unwrap_or(0) as isize;
+        let next = (current + delta).clamp(0, self.page.len() as isize - 1);
+        self.list_state.select(Some(next as usize));
+    }
+
+    fn switch_cf(&mut self) {
+        self.cf_index = (self.cf_index + 1) % self.db.cf_names().len();
+        self.reload();
+    }
+
+    fn detail(&self) -> String {
+        let (key, value) = match self.list_state.selected().and_then(|i| self.page.get(i)) {
+            Some(entry) => entry,
+            None => return "No key selected".to_string(),
+        };
+
+        let decoded = match self.decoders.for_cf(self.cf_name()).decode(value) {
+            Ok(Some(record)) => record.to_string(),
+            Ok(None) => "No decoder for this column family".to_string(),
+            Err(e) => format!("Failed to decode value: {}", e),
+        };
+        format!(
+            "Key: {}\n\nValue ({} bytes): {}\n\n{}",
+            to_hex(key),
+            value.len(),
+            to_hex(value),
+            decoded
+        )
+    }
+
+    /// Handles a key press, returns false when the browser should exit
+    fn handle_key(&mut self, code: KeyCode) -> bool {
+        if self.searching {
+            match code {
+                KeyCode::Esc => {
+                    self.searching = false;
+                    self.search.clear();
+                    self.reload();
+                }
+                KeyCode::Enter => self.searching = false,
+                KeyCode::Backspace => {
+                    self.search.pop();
+                    self.reload();
+                }
+                KeyCode::Char(c) if c.is_ascii_hexdigit() => {
+                    self.search.push(c.to_ascii_lowercase());
+                    self.reload();
+                }
+                _ => {}
+            }
+            return true;
+        }
+
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('/') => self.searching = true,
+            KeyCode::Tab => self.switch_cf(),
+            KeyCode::Down | KeyCode::Char('j') => self.select(1),
+            KeyCode::Up | KeyCode::Char('k') => self.select(-1),
+            KeyCode::PageDown | KeyCode::Char('n') => self.next_page(),
+            KeyCode::PageUp | KeyCode::Char('p') => self.previous_page(),
+            _ => {}
+        }
+        true
+    }
+}
+
+fn draw<B: Backend>(f: &mut Frame<B>, browser: &mut Browser) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(3)].as_ref())
+        .split(f.size());
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+        .split(rows[0]);
+
+    let items: Vec<ListItem> = browser
+        .page
+        .iter()
+        .map(|(key, _)| ListItem::new(to_hex(key)))
+        .collect();
+    let title = format!(
+        "{} (page {}{})",
+        browser.cf_name(),
+        browser.page_starts.len() + 1,
+        if browser.has_next_page { ", more" } else { "" }
+    );
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    f.render_stateful_widget(list, columns[0], &mut browser.list_state);
+
+    let detail = Paragraph::new(browser.detail())
+        .block(Block::default().borders(Borders::ALL).title("Record"))
+        .wrap(Wrap { trim: false });
+    f.render_widget(detail, columns[1]);
+
+    let footer = if browser.searching {
+        format!("Search prefix (hex): {}_  [Enter] keep  [Esc] clear", browser.search)
+    } else if !browser.status.is_empty() {
+        browser.status.clone()
+    } else {
+        let prefix = if browser.search.is_empty() {
+            String::new()
+        } else {
+            format!("prefix {}  ", browser.search)
+        };
+        format!(
+            "{}[j/k] select  [n/p] page  [Tab] column family  [/] search  [q] quit",
+            prefix
+        )
+    };
+    f.render_widget(
+        Paragraph::new(footer).block(Block::default().borders(Borders::ALL)),
+        rows[1],
+    );
+}
+
+/// Runs the browser until the user quits, restoring the terminal afterwards
+pub fn run(
+    db: &DbHandle,
+    decoders: &DecoderRegistry,
+    page_size: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if db.cf_names().is_empty() {
+        return Err("Database has no column families".into());
+    }
+
+    let mut browser = Browser::new(db, decoders, page_size.max(1));
+    browser.reload();
+
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    let result = event_loop(&mut terminal, &mut browser);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    result
+}
+
+fn event_loop<B: Backend>(
+    terminal: &mut Terminal<B>,
+    browser: &mut Browser,
+) -> Result<(), Box<dyn std::error::Error>> {
+    loop {
+        terminal.draw(|f| draw(f, browser))?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press && !browser.handle_key(key.code) {
+                return Ok(());
+            }
+        }
+    }
+}
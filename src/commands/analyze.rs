@@ -0,0 +1,65 @@
+use std::collections::BTreeMap;
+
+use crate::db::DbHandle;
+use crate::output::{AnalyzeReport, HistogramBucket};
+
+/// Scans a column family and reports how key and value sizes and leading key bytes are distributed
+pub fn analyze(db: &DbHandle, target_cf: &str) -> Result<AnalyzeReport, Box<dyn std::error::Error>> {
+    let cf = db.cf(target_cf)?;
+
+    let mut records = 0u64;
+    let mut key_bytes = 0u64;
+    let mut value_bytes = 0u64;
+    let mut key_lengths: BTreeMap<u64, u64> = BTreeMap::new();
+    // Value sizes vary too much for exact lengths, bucket them by power of two
+    let mut value_length_buckets: BTreeMap<u32, u64> = BTreeMap::new();
+    let mut first_bytes = vec![0u64; 256];
+
+    for item in db.iterator_cf_opt(cf, db.read_options(), rocksdb::IteratorMode::Start) {
+        let (key, value) = item?;
+        records += 1;
+        key_bytes += key.len() as u64;
+        value_bytes += value.len() as u64;
+        *key_lengths.entry(key.len() as u64).or_default() += 1;
+        *value_length_buckets.entry(log2_bucket(value.len())).or_default() += 1;
+        if let Some(first) = key.first() {
+            first_bytes[*first as usize] += 1;
+        }
+    }
+
+    Ok(AnalyzeReport {
+        column_family: target_cf.to_string(),
+        records,
+        key_bytes,
+        value_bytes,
+        key_lengths: key_lengths
+            .into_iter()
+            .map(|(len, count)| HistogramBucket {
+                min: len,
+                max: len,
+                count,
+            })
+            .collect(),
+        value_lengths: value_length_buckets
+            .into_iter()
+            .map(|(bucket, count)| {
+                let (min, max) = bucket_bounds(bucket);
+                HistogramBucket { min, max, count }
+            })
+            .collect(),
+        first_bytes,
+    })
+}
+
+/// Bucket 0 holds empty values, bucket k holds lengths in [2^(k-1), 2^k)
+pub(crate) fn log2_bucket(len: usize) -> u32 {
+    usize::BITS - len.leading_zeros()
+}
+
+pub(crate) fn bucket_bounds(bucket: u32) -> (u64, u64) {
+    if bucket == 0 {
+        (0, 0)
+    } else {
+        (1u64 << (bucket - 1), (1u64 << bucket) - 1)
+    }
+}
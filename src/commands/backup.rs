@@ -0,0 +1,118 @@
+use rocksdb::backup::{BackupEngine, BackupEngineInfo, BackupEngineOptions, RestoreOptions};
+use rocksdb::Env;
+use std::path::Path;
+
+use crate::db::DbHandle;
+use crate::output::{BackupInfo, BackupSummary, RestoreSummary};
+
+fn open_engine(backup_dir: &Path) -> Result<BackupEngine, Box<dyn std::error::Error>> {
+    let opts = BackupEngineOptions::new(backup_dir)?;
+    Ok(BackupEngine::open(&opts, &Env::new()?)?)
+}
+
+fn backup_info(engine: &BackupEngine, info: &BackupEngineInfo, verify: bool) -> BackupInfo {
+    let (verified, verify_error) = if verify {
+        match engine.verify_backup(info.backup_id) {
+            Ok(()) => (Some(true), None),
+            Err(e) => (Some(false), Some(e.to_string())),
+        }
+    } else {
+        (None, None)
+    };
+
+    BackupInfo {
+        backup_id: info.backup_id,
+        timestamp: info.timestamp,
+        size: info.size,
+        num_files: info.num_files,
+        verified,
+        verify_error,
+    }
+}
+
+/// Adds a backup of `db` to `backup_dir` and verifies it
+///
+/// Table files already present in earlier backups are shared rather than copied again, so only
+/// data written since the last backup is added. With `keep_last` the oldest backups beyond that
+/// number are removed afterwards.
+pub fn backup(
+    db: &DbHandle,
+    backup_dir: &Path,
+    keep_last: Option<usize>,
+) -> Result<BackupSummary, Box<dyn std::error::Error>> {
+    let mut engine = open_engine(backup_dir)?;
+    let before = engine.get_backup_info().len();
+
+    engine.create_new_backup_flush(&**db, true)?;
+
+    let infos = engine.get_backup_info();
+    let latest = infos
+        .iter()
+        .max_by_key(|info| info.backup_id)
+        .ok_or("Backup engine reported no backups after creating one")?;
+    let backup = backup_info(&engine, latest, true);
+    if let Some(error) = &backup.verify_error {
+        return Err(format!("Backup {} failed verification: {}", backup.backup_id, error).into());
+    }
+
+    let mut purged = 0;
+    if let Some(keep_last) = keep_last {
+        if keep_last == 0 {
+            return Err("--keep-last must be at least 1".into());
+        }
+        engine.purge_old_backups(keep_last)?;
+        purged = (before + 1).saturating_sub(engine.get_backup_info().len());
+    }
+
+    Ok(BackupSummary {
+        backup_dir: backup_dir.display().to_string(),
+        backup,
+        purged,
+    })
+}
+
+/// Lists the backups in `backup_dir`, oldest first, optionally checking each one's files
+pub fn list_backups(
+    backup_dir: &Path,
+    verify: bool,
+) -> Result<Vec<BackupInfo>, Box<dyn std::error::Error>> {
+    let engine = open_engine(backup_dir)?;
+    let mut infos = engine.get_backup_info();
+    infos.sort_by_key(|info| info.backup_id);
+    Ok(infos
+        .iter()
+        .map(|info| backup_info(&engine, info, verify))
+        .collect())
+}
+
+/// Restores backup `backup_id`, or the latest backup, into `db_path`
+///
+/// `db_path` must not contain anything yet, restoring would replace the files of a database there.
+pub fn restore_backup(
+    backup_dir: &Path,
+    db_path: &Path,
+    backup_id: Option<u32>,
+) -> Result<RestoreSummary, Box<dyn std::error::Error>> {
+    if db_path.exists() && std::fs::read_dir(db_path)?.next().is_some() {
+        return Err(format!("Restore target {:?} is not empty", db_path).into());
+    }
+
+    let mut engine = open_engine(backup_dir)?;
+    let backup_id = match backup_id {
+        Some(id) => id,
+        None => engine
+            .get_backup_info()
+            .iter()
+            .map(|info| info.backup_id)
+            .max()
+            .ok_or_else(|| format!("No backups found in {:?}", backup_dir))?,
+    };
+    engine.verify_backup(backup_id)?;
+    engine.restore_from_backup(db_path, db_path, &RestoreOptions::default(), backup_id)?;
+
+    Ok(RestoreSummary {
+        backup_dir: backup_dir.display().to_string(),
+        db_path: db_path.display().to_string(),
+        backup_id,
+    })
+}
@@ -0,0 +1,88 @@
+use std::sync::mpsc;
+
+use crate::db::DbHandle;
+use crate::error::Error;
+use crate::output::{BatchSummary, CheckResult};
+use crate::record::RecordDecoder;
+
+/// Number of parsed batches buffered ahead of the lookups
+const PIPELINE_DEPTH: usize = 4;
+
+/// Looks up many keys in batches of `batch_size`, passing the result for each key to `on_result` in input order
+///
+/// Keys are pulled from `keys` on a separate thread so parsing the next batches overlaps with the
+/// lookups of the current one. Each batch is read with a single `batched_multi_get_cf_opt` call,
+/// with async IO so RocksDB can issue the block reads of a batch in parallel where it supports it.
+pub fn check_batch<I, F>(
+    db: &DbHandle,
+    target_cf: &str,
+    keys: I,
+    batch_size: usize,
+    decoder: &dyn RecordDecoder,
+    mut on_result: F,
+) -> Result<BatchSummary, Box<dyn std::error::Error>>
+where
+    I: Iterator<Item = Result<Vec<u8>, Error>> + Send,
+    F: FnMut(CheckResult) -> Result<(), Box<dyn std::error::Error>>,
+{
+    if batch_size == 0 {
+        return Err("Batch size must be greater than 0".into());
+    }
+    let cf = db.cf(target_cf)?;
+
+    let mut read_opts = db.read_options();
+    read_opts.set_async_io(true);
+
+    let mut summary = BatchSummary {
+        column_family: target_cf.to_string(),
+        total: 0,
+        found: 0,
+        missing: 0,
+    };
+    std::thread::scope(|scope| {
+        let (tx, rx) = mpsc::sync_channel::<Result<Vec<Vec<u8>>, Error>>(PIPELINE_DEPTH);
+        scope.spawn(move || {
+            let mut batch = Vec::with_capacity(batch_size);
+            for key in keys {
+                let key = match key {
+                    Ok(key) => key,
+                    Err(e) => {
+                        let _ = tx.send(Err(e));
+                        return;
+                    }
+                };
+                batch.push(key);
+                if batch.len() == batch_size {
+                    let full = std::mem::replace(&mut batch, Vec::with_capacity(batch_size));
+                    if tx.send(Ok(full)).is_err() {
+                        // The lookups stopped on an error
+                        return;
+                    }
+                }
+            }
+            if !batch.is_empty() {
+                let _ = tx.send(Ok(batch));
+            }
+        });
+
+        for batch in rx {
+            let batch = batch?;
+            let values = db.batched_multi_get_cf_opt(cf, batch.iter(), false, &read_opts);
+            for (key, value) in batch.iter().zip(values) {
+                summary.total += 1;
+                match value? {
+                    Some(value) => {
+                        summary.found += 1;
+                        let decoded = decoder.decode(&value)?;
+                        on_result(CheckResult::found(target_cf, key, &value, decoded))?;
+                    }
+                    None => {
+                        summary.missing += 1;
+                        on_result(CheckResult::not_found(target_cf, key))?;
+                    }
+                }
+            }
+        }
+        Ok(summary)
+    })
+}
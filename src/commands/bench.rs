@@ -0,0 +1,358 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+use rayon::ThreadPool;
+use rocksdb::{WriteBatch, WriteOptions};
+
+use crate::db::DbHandle;
+use crate::error::Error;
+use crate::output::{
+    BenchReport, BenchWriteSummary, GetLatency, MultiGetThroughput, ScanThroughput, WriteBenchCase,
+};
+
+/// Settings of a read benchmark
+pub struct BenchConfig {
+    /// Number of keys sampled from the column family for the random reads
+    pub sample_size: usize,
+    /// How long each of the three phases runs
+    pub duration: Duration,
+    /// Threads issuing random gets and multi-gets
+    pub threads: usize,
+    /// Keys per multi-get call
+    pub batch_size: usize,
+    /// Seed of the key sampling and the read order, the same seed reads the same keys on every machine
+    pub seed: u64,
+}
+
+/// How durable the writes of a write benchmark are
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum WriteDurability {
+    /// Skip the write-ahead log, writes are lost on a crash until flushed
+    NoWal,
+    /// Write the WAL without syncing it, RocksDB's default
+    Async,
+    /// Sync the WAL to disk before each write returns
+    Sync,
+}
+
+impl WriteDurability {
+    /// Name of the durability as given on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            WriteDurability::NoWal => "no-wal",
+            WriteDurability::Async => "async",
+            WriteDurability::Sync => "sync",
+        }
+    }
+
+    fn write_options(self) -> WriteOptions {
+        let mut opts = WriteOptions::default();
+        match self {
+            WriteDurability::NoWal => opts.disable_wal(true),
+            WriteDurability::Async => {},
+            WriteDurability::Sync => opts.set_sync(true),
+        }
+        opts
+    }
+}
+
+/// Settings of a write benchmark, every combination of value size, durability and method is one case
+pub struct WriteBenchConfig {
+    /// Sizes of the written values in bytes
+    pub value_sizes: Vec<usize>,
+    pub durabilities: Vec<WriteDurability>,
+    /// Records per WriteBatch of the batch cases
+    pub batch_size: usize,
+    /// How long each case runs
+    pub duration: Duration,
+    /// Seed of the random keys and values
+    pub seed: u64,
+}
+
+/// Column family the write benchmark writes to
+const WRITE_BENCH_CF: &str = "bench";
+
+/// Records between two deadline checks of the scan phase
+const SCAN_CHECK_INTERVAL: u64 = 1024;
+
+/// Measures random get latency, sequential scan throughput and multi-get throughput of a column family
+///
+/// Keys are sampled by seeking to random positions, which picks them evenly when they are hashes
+/// like the keys of merkle_records and data_records. Gets and multi-gets then read random keys of
+/// the sample from `config.threads` threads for `config.duration` each, the scan reads from the
+/// first key on one thread until the duration is up or the column family ends.
+pub fn bench(
+    db: &DbHandle,
+    target_cf: &str,
+    config: &BenchConfig,
+) -> Result<BenchReport, Box<dyn std::error::Error>> {
+    if config.sample_size == 0 || config.threads == 0 || config.batch_size == 0 {
+        return Err("Sample size, threads and batch size must be greater than 0".into());
+    }
+
+    let sample = sample_keys(db, target_cf, config.sample_size, config.seed)?;
+    if sample.is_empty() {
+        return Err(format!("Column family '{}' is empty", target_cf).into());
+    }
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(config.threads)
+        .build()?;
+    let get = bench_gets(db, target_cf, &sample, config, &pool)?;
+    let scan = bench_scan(db, target_cf, config.duration)?;
+    let multi_get = bench_multi_gets(db, target_cf, &sample, config, &pool)?;
+
+    Ok(BenchReport {
+        column_family: target_cf.to_string(),
+        sampled_keys: sample.len(),
+        threads: config.threads,
+        duration_secs: config.duration.as_secs_f64(),
+        seed: config.seed,
+        get,
+        scan,
+        multi_get,
+    })
+}
+
+/// xorshift64* generator, enough to pick keys without a dependency on a random number crate
+pub(crate) struct XorShift(u64);
+
+impl XorShift {
+    pub(crate) fn new(seed: u64) -> Self {
+        // The state must not be zero
+        XorShift(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    pub(crate) fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+fn sample_keys(
+    db: &DbHandle,
+    target_cf: &str,
+    sample_size: usize,
+    seed: u64,
+) -> Result<Vec<Vec<u8>>, Error> {
+    let cf = db.cf(target_cf)?;
+    let mut rng = XorShift::new(seed);
+    let mut iter = db.raw_iterator_cf_opt(cf, db.read_options());
+
+    let mut sample = Vec::with_capacity(sample_size);
+    for _ in 0..sample_size {
+        let target: Vec<u8> = (0..4).flat_map(|_| rng.next().to_be_bytes()).collect();
+        iter.seek(&target);
+        if !iter.valid() {
+            // Past the last key, wrap around to the first
+            iter.seek_to_first();
+        }
+        match iter.key() {
+            Some(key) => sample.push(key.to_vec()),
+            None => break,
+        }
+    }
+    iter.status()?;
+    Ok(sample)
+}
+
+fn bench_gets(
+    db: &DbHandle,
+    target_cf: &str,
+    sample: &[Vec<u8>],
+    config: &BenchConfig,
+    pool: &ThreadPool,
+) -> Result<GetLatency, Error> {
+    let started = Instant::now();
+    let per_thread = pool.broadcast(|ctx| -> Result<Vec<u32>, Error> {
+        let cf = db.cf(target_cf)?;
+        let mut rng = XorShift::new(config.seed.wrapping_add(ctx.index() as u64 + 1));
+        let read_opts = db.read_options();
+        let mut latencies = Vec::new();
+        while started.elapsed() < config.duration {
+            let key = &sample[rng.below(sample.len())];
+            let begin = Instant::now();
+            db.get_pinned_cf_opt(cf, key, &read_opts)?;
+            latencies.push(begin.elapsed().as_nanos().min(u32::MAX as u128) as u32);
+        }
+        Ok(latencies)
+    });
+    let elapsed = started.elapsed().as_secs_f64();
+
+    let mut latencies = Vec::new();
+    for thread_latencies in per_thread {
+        latencies.extend(thread_latencies?);
+    }
+    latencies.sort_unstable();
+    let percentile = |q: f64| match latencies.len() {
+        0 => 0.0,
+        len => latencies[((len - 1) as f64 * q).round() as usize] as f64 / 1000.0,
+    };
+
+    Ok(GetLatency {
+        ops: latencies.len() as u64,
+        ops_per_sec: latencies.len() as f64 / elapsed,
+        p50_us: percentile(0.50),
+        p95_us: percentile(0.95),
+        p99_us: percentile(0.99),
+        max_us: percentile(1.0),
+    })
+}
+
+fn bench_scan(db: &DbHandle, target_cf: &str, duration: Duration) -> Result<ScanThroughput, Error> {
+    let cf = db.cf(target_cf)?;
+    let started = Instant::now();
+
+    let mut records = 0;
+    let mut bytes = 0;
+    for item in db.iterator_cf_opt(cf, db.read_options(), rocksdb::IteratorMode::Start) {
+        let (key, value) = item?;
+        records += 1;
+        bytes += (key.len() + value.len()) as u64;
+        if records % SCAN_CHECK_INTERVAL == 0 && started.elapsed() >= duration {
+            break;
+        }
+    }
+    let elapsed = started.elapsed().as_secs_f64();
+
+    Ok(ScanThroughput {
+        records,
+        bytes,
+        elapsed_secs: elapsed,
+        records_per_sec: records as f64 / elapsed,
+        mib_per_sec: bytes as f64 / elapsed / (1 << 20) as f64,
+    })
+}
+
+fn bench_multi_gets(
+    db: &DbHandle,
+    target_cf: &str,
+    sample: &[Vec<u8>],
+    config: &BenchConfig,
+    pool: &ThreadPool,
+) -> Result<MultiGetThroughput, Error> {
+    let started = Instant::now();
+    let per_thread = pool.broadcast(|ctx| -> Result<u64, Error> {
+        let cf = db.cf(target_cf)?;
+        let mut rng = XorShift::new(config.seed.wrapping_add(ctx.index() as u64 + 1));
+        let read_opts = db.read_options();
+        let mut keys = 0;
+        while started.elapsed() < config.duration {
+            let batch: Vec<&Vec<u8>> = (0..config.batch_size)
+                .map(|_| &sample[rng.below(sample.len())])
+                .collect();
+            for value in db.batched_multi_get_cf_opt(cf, batch, false, &read_opts) {
+                value?;
+            }
+            keys += config.batch_size as u64;
+        }
+        Ok(keys)
+    });
+    let elapsed = started.elapsed().as_secs_f64();
+
+    let mut keys = 0;
+    for thread_keys in per_thread {
+        keys += thread_keys?;
+    }
+    Ok(MultiGetThroughput {
+        keys,
+        batch_size: config.batch_size,
+        keys_per_sec: keys as f64 / elapsed,
+    })
+}
+
+/// Measures put and WriteBatch throughput by writing random 32-byte keys to a scratch database
+///
+/// Keys are random like merkle and data record hashes. Each case of `config` writes for
+/// `config.duration` with single puts, then with batches of `config.batch_size`, and is passed to
+/// `on_case` when done. The scratch database must not exist yet and is removed afterwards unless
+/// `keep` is set, so a real database can never be written to by mistake.
+pub fn bench_write<F>(
+    scratch_path: &Path,
+    config: &WriteBenchConfig,
+    keep: bool,
+    mut on_case: F,
+) -> Result<BenchWriteSummary, Box<dyn std::error::Error>>
+where
+    F: FnMut(WriteBenchCase) -> Result<(), Box<dyn std::error::Error>>,
+{
+    if scratch_path.exists() {
+        return Err(format!("Scratch database {:?} already exists", scratch_path).into());
+    }
+    if config.batch_size == 0 {
+        return Err("Batch size must be greater than 0".into());
+    }
+
+    let db = DbHandle::open_read_write(scratch_path, &[WRITE_BENCH_CF])?;
+    let cf = db.cf(WRITE_BENCH_CF)?;
+    let mut rng = XorShift::new(config.seed);
+
+    let mut summary = BenchWriteSummary {
+        path: scratch_path.display().to_string(),
+        cases: 0,
+        records: 0,
+        kept: keep,
+    };
+    for &value_size in &config.value_sizes {
+        for &durability in &config.durabilities {
+            let opts = durability.write_options();
+            for batch_size in [1, config.batch_size] {
+                let started = Instant::now();
+                let mut records = 0;
+                while started.elapsed() < config.duration {
+                    if batch_size == 1 {
+                        let (key, value) = random_record(&mut rng, value_size);
+                        db.put_cf_opt(cf, key, value, &opts)?;
+                    } else {
+                        let mut batch = WriteBatch::default();
+                        for _ in 0..batch_size {
+                            let (key, value) = random_record(&mut rng, value_size);
+                            batch.put_cf(cf, key, value);
+                        }
+                        db.write_opt(batch, &opts)?;
+                    }
+                    records += batch_size as u64;
+                }
+                let elapsed = started.elapsed().as_secs_f64();
+
+                summary.cases += 1;
+                summary.records += records;
+                on_case(WriteBenchCase {
+                    value_size,
+                    durability: durability.name(),
+                    batch_size,
+                    records,
+                    elapsed_secs: elapsed,
+                    records_per_sec: records as f64 / elapsed,
+                    mib_per_sec: (records * (32 + value_size as u64)) as f64
+                        / elapsed
+                        / (1 << 20) as f64,
+                })?;
+            }
+        }
+    }
+
+    drop(db);
+    if !keep {
+        rocksdb::DB::destroy(&rocksdb::Options::default(), scratch_path)?;
+    }
+    Ok(summary)
+}
+
+fn random_record(rng: &mut XorShift, value_size: usize) -> (Vec<u8>, Vec<u8>) {
+    let key = (0..4).flat_map(|_| rng.next().to_be_bytes()).collect();
+    let mut value: Vec<u8> = (0..(value_size + 7) / 8)
+        .flat_map(|_| rng.next().to_le_bytes())
+        .collect();
+    value.truncate(value_size);
+    (key, value)
+}
@@ -0,0 +1,50 @@
+use crate::db::DbHandle;
+use crate::output::{BoundsReport, CfBounds, KeyView};
+
+/// Finds the first and last key of each column family and the key range their SST files cover
+///
+/// The first and last keys come from an iterator, so they include unflushed writes and skip
+/// deleted keys. The SST range is the smallest and largest key recorded in the metadata of the
+/// live files, which may be deleted keys, and misses keys still in the memtables.
+pub fn bounds(
+    db: &DbHandle,
+    cf_names: &[String],
+) -> Result<BoundsReport, Box<dyn std::error::Error>> {
+    let live_files = db.live_files()?;
+
+    let mut column_families = Vec::with_capacity(cf_names.len());
+    for name in cf_names {
+        let cf = db.cf(name)?;
+        let mut iter = db.raw_iterator_cf_opt(cf, db.read_options());
+        iter.seek_to_first();
+        let first_key = iter.key().map(KeyView::new);
+        iter.seek_to_last();
+        let last_key = iter.key().map(KeyView::new);
+        iter.status()?;
+
+        let files: Vec<_> = live_files
+            .iter()
+            .filter(|file| &file.column_family_name == name)
+            .collect();
+        let sst_smallest_key = files
+            .iter()
+            .filter_map(|file| file.start_key.as_deref())
+            .min()
+            .map(KeyView::new);
+        let sst_largest_key = files
+            .iter()
+            .filter_map(|file| file.end_key.as_deref())
+            .max()
+            .map(KeyView::new);
+
+        column_families.push(CfBounds {
+            name: name.clone(),
+            first_key,
+            last_key,
+            sst_files: files.len(),
+            sst_smallest_key,
+            sst_largest_key,
+        });
+    }
+    Ok(BoundsReport { column_families })
+}
@@ -0,0 +1,66 @@
+use rocksdb::properties;
+
+use crate::db::DbHandle;
+use crate::output::{CfInfo, CreateCfSummary, DropCfSummary};
+
+/// Lists every column family of the database with its estimated key count and sizes
+pub fn list_cf(db: &DbHandle) -> Result<Vec<CfInfo>, Box<dyn std::error::Error>> {
+    let mut infos = Vec::new();
+    for name in db.cf_names() {
+        infos.push(cf_info(db, name)?);
+    }
+    Ok(infos)
+}
+
+fn cf_info(db: &DbHandle, name: &str) -> Result<CfInfo, Box<dyn std::error::Error>> {
+    let cf = db.cf(name)?;
+    Ok(CfInfo {
+        name: name.to_string(),
+        estimated_keys: db.property_int_value_cf(cf, properties::ESTIMATE_NUM_KEYS)?,
+        sst_files_size: db.property_int_value_cf(cf, properties::TOTAL_SST_FILES_SIZE)?,
+        live_data_size: db.property_int_value_cf(cf, properties::ESTIMATE_LIVE_DATA_SIZE)?,
+    })
+}
+
+/// Creates empty column families, the database must be open in read-write mode
+///
+/// Fails before creating any of them when one already exists.
+pub fn create_cf<N: AsRef<str>>(
+    db: &mut DbHandle,
+    names: &[N],
+) -> Result<CreateCfSummary, Box<dyn std::error::Error>> {
+    for name in names {
+        if db.cf_names().iter().any(|existing| existing == name.as_ref()) {
+            return Err(format!("Column family '{}' already exists", name.as_ref()).into());
+        }
+    }
+
+    for name in names {
+        db.create_cf(name.as_ref())?;
+    }
+    Ok(CreateCfSummary {
+        column_families: names.iter().map(|name| name.as_ref().to_string()).collect(),
+    })
+}
+
+/// Drops column families with all of their data, the database must be open in read-write mode
+///
+/// Their sizes are reported as they were just before. Fails before dropping any of them when one
+/// does not exist or is the default column family, which RocksDB cannot drop.
+pub fn drop_cf<N: AsRef<str>>(
+    db: &mut DbHandle,
+    names: &[N],
+) -> Result<DropCfSummary, Box<dyn std::error::Error>> {
+    let mut column_families = Vec::with_capacity(names.len());
+    for name in names {
+        if name.as_ref() == rocksdb::DEFAULT_COLUMN_FAMILY_NAME {
+            return Err("The default column family cannot be dropped".into());
+        }
+        column_families.push(cf_info(db, name.as_ref())?);
+    }
+
+    for name in names {
+        db.drop_cf(name.as_ref())?;
+    }
+    Ok(DropCfSummary { column_families })
+}
@@ -0,0 +1,50 @@
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use zkwasm_host_circuits::host::datahash::DataHashRecord;
+
+use crate::db::DbHandle;
+use crate::interpret::{interpret, ValueType};
+use crate::output::{CheckResult, DataDump};
+use crate::record::RecordDecoder;
+
+/// Looks up a single key, decodes its value with `decoder` and reads it as `value_type`
+pub fn check(
+    db: &DbHandle,
+    target_cf: &str,
+    key: &[u8],
+    decoder: &dyn RecordDecoder,
+    value_type: ValueType,
+) -> Result<CheckResult, Box<dyn std::error::Error>> {
+    let cf = db.cf(target_cf)?;
+
+    match db.get_cf_opt(cf, key, &db.read_options()) {
+        Ok(Some(value)) => {
+            // Try to display the value in different formats for convenience
+            let decoded = {
+                let _span = tracing::trace_span!("decode", decoder = decoder.name()).entered();
+                decoder.decode(&value)?
+            };
+            let mut result = CheckResult::found(target_cf, key, &value, decoded);
+            if value_type != ValueType::Auto {
+                result.interpretations = interpret(&value, value_type)?;
+            }
+            Ok(result)
+        }
+        Ok(None) => Ok(CheckResult::not_found(target_cf, key)),
+        Err(e) => Err(format!("Database error: {}", e).into()),
+    }
+}
+
+/// Writes the data payload of a `DataHashRecord` to `path`, returning its length and SHA-256 digest
+pub fn dump_record_data(
+    record: &DataHashRecord,
+    path: &Path,
+) -> Result<DataDump, Box<dyn std::error::Error>> {
+    std::fs::write(path, &record.data)?;
+
+    Ok(DataDump {
+        path: path.display().to_string(),
+        len: record.data.len(),
+        sha256: hex::encode(Sha256::digest(&record.data)),
+    })
+}
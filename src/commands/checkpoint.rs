@@ -0,0 +1,65 @@
+use rocksdb::checkpoint::Checkpoint;
+use std::path::Path;
+
+use crate::db::DbHandle;
+use crate::output::CheckpointSummary;
+
+/// Sum of the sizes of the regular files directly inside `dir`
+pub(crate) fn dir_size(dir: &Path) -> std::io::Result<u64> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let metadata = entry?.metadata()?;
+        if metadata.is_file() {
+            size += metadata.len();
+        }
+    }
+    Ok(size)
+}
+
+/// Creates a consistent point-in-time copy of `db` in `path`, which must not exist yet
+///
+/// RocksDB always checkpoints every column family, when `keep_cfs` is given the others are
+/// dropped from the copy afterwards. The default column family cannot be dropped and is kept.
+pub fn checkpoint<N: AsRef<str>>(
+    db: &DbHandle,
+    path: &Path,
+    keep_cfs: Option<&[N]>,
+) -> Result<CheckpointSummary, Box<dyn std::error::Error>> {
+    if path.exists() {
+        return Err(format!("Checkpoint path {:?} already exists", path).into());
+    }
+    if let Some(keep) = keep_cfs {
+        for name in keep {
+            db.cf(name.as_ref())?;
+        }
+    }
+
+    Checkpoint::new(&**db)?.create_checkpoint(path)?;
+
+    let mut copy = DbHandle::open_read_write(path, db.cf_names())?;
+    let mut dropped = Vec::new();
+    if let Some(keep) = keep_cfs {
+        let to_drop: Vec<String> = copy
+            .cf_names()
+            .iter()
+            .filter(|name| {
+                name.as_str() != rocksdb::DEFAULT_COLUMN_FAMILY_NAME
+                    && !keep.iter().any(|kept| kept.as_ref() == name.as_str())
+            })
+            .cloned()
+            .collect();
+        for name in to_drop {
+            copy.drop_cf(&name)?;
+            dropped.push(name);
+        }
+    }
+    let column_families = copy.cf_names().to_vec();
+    drop(copy);
+
+    Ok(CheckpointSummary {
+        path: path.display().to_string(),
+        column_families,
+        dropped,
+        files_size: dir_size(path)?,
+    })
+}
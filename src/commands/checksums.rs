@@ -0,0 +1,128 @@
+use rocksdb::LiveFile;
+
+use super::resume::{CheckpointFile, ScanCheckpoint};
+use crate::db::DbHandle;
+use crate::output::{to_hex, ChecksumCorruption, ChecksumSummary, KeyView};
+
+/// Reads every block of the column families in `cf_names` with checksum verification, passing each
+/// corruption found to `on_corruption`
+///
+/// A full iteration reads every data block of every live SST file, the merging iterator visits
+/// overwritten entries too. rust-rocksdb does not expose `DB::VerifyChecksum`, this covers the same
+/// blocks. After a corruption the scan resumes past the key range of the damaged file so one bad
+/// block does not hide others. `on_progress` is called with the size of every record read. With
+/// `checkpoint`, the last read key of the column family being verified is saved periodically and
+/// a resumed verification continues after it, skipping the column families already verified.
+pub fn verify_checksums<F, P>(
+    db: &DbHandle,
+    cf_names: &[String],
+    checkpoint: Option<&CheckpointFile>,
+    mut on_corruption: F,
+    mut on_progress: P,
+) -> Result<ChecksumSummary, Box<dyn std::error::Error>>
+where
+    F: FnMut(ChecksumCorruption) -> Result<(), Box<dyn std::error::Error>>,
+    P: FnMut(usize),
+{
+    let live_files = db.live_files()?;
+    let scan = format!("column families {}", cf_names.join(", "));
+    let checkpoint = ScanCheckpoint::open(checkpoint, "verify-checksums", scan)?;
+
+    let mut summary = ChecksumSummary {
+        column_families: cf_names.to_vec(),
+        sst_files: live_files
+            .iter()
+            .filter(|file| cf_names.contains(&file.column_family_name))
+            .count() as u64,
+        records: checkpoint.resumed_from(),
+        corruptions: checkpoint.corruptions(),
+        resumed_from: checkpoint.resumed_from(),
+    };
+    for name in cf_names {
+        let cf = db.cf(name)?;
+        let position = checkpoint.position(name);
+        if position.finished {
+            continue;
+        }
+        let mut cf_records = position.processed;
+        let files: Vec<&LiveFile> = live_files
+            .iter()
+            .filter(|file| &file.column_family_name == name)
+            .collect();
+
+        let mut last_key: Option<Box<[u8]>> = None;
+        let mut resume_from = position.next_key()?;
+        let mut skipped: Vec<&str> = Vec::new();
+        loop {
+            let mut opts = db.read_options();
+            opts.set_verify_checksums(true);
+            // Verification reads everything once, caching it would only evict useful blocks
+            opts.fill_cache(false);
+            let mut iter = db.raw_iterator_cf_opt(cf, opts);
+            match &resume_from {
+                Some(key) => iter.seek(key),
+                None => iter.seek_to_first(),
+            }
+
+            while iter.valid() {
+                if let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+                    summary.records += 1;
+                    cf_records += 1;
+                    db.pace(key.len() + value.len(), summary.records);
+                    on_progress(key.len() + value.len());
+                    if checkpoint.due() {
+                        checkpoint.set_totals(None, summary.corruptions);
+                        checkpoint.advance(name, key, cf_records)?;
+                    }
+                    last_key = Some(key.into());
+                }
+                iter.next();
+            }
+
+            let error = match iter.status() {
+                Ok(()) => break,
+                Err(e) => e,
+            };
+            summary.corruptions += 1;
+            let file = damaged_file(&files, error.as_ref());
+            on_corruption(ChecksumCorruption {
+                column_family: name.clone(),
+                file: file.map(|file| file.name.clone()),
+                level: file.map(|file| file.level),
+                smallest_key: file.and_then(|file| file.start_key.as_deref()).map(KeyView::new),
+                largest_key: file.and_then(|file| file.end_key.as_deref()).map(KeyView::new),
+                last_good_key: last_key.as_deref().map(to_hex),
+                error: error.into_string(),
+            })?;
+
+            // Without a file to skip, or when it was skipped before, the rest cannot be read
+            let file = match file {
+                Some(file) if !skipped.contains(&file.name.as_str()) => file,
+                _ => break,
+            };
+            let end_key = match &file.end_key {
+                Some(end_key) => end_key,
+                None => break,
+            };
+            skipped.push(&file.name);
+            // The smallest key after the file's range is its last key followed by a zero byte
+            let mut next = end_key.clone();
+            next.push(0);
+            resume_from = Some(next);
+        }
+        checkpoint.set_totals(None, summary.corruptions);
+        checkpoint.finish(name, cf_records)?;
+    }
+    checkpoint.complete()?;
+
+    Ok(summary)
+}
+
+/// The live file a read error names, RocksDB corruption errors include the path of the damaged file
+fn damaged_file<'a>(files: &[&'a LiveFile], message: &str) -> Option<&'a LiveFile> {
+    // Live file names are like "/000123.sst"
+    files
+        .iter()
+        .find(|file| message.contains(file.name.as_str()))
+        .copied()
+}
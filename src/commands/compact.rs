@@ -0,0 +1,43 @@
+use rocksdb::{properties, BottommostLevelCompaction, CompactOptions};
+use std::time::Instant;
+
+use crate::commands::stats::files_per_level;
+use crate::db::DbHandle;
+use crate::output::CompactSummary;
+use crate::range::KeyRange;
+
+/// Runs a manual compaction of `range` in a column family and reports the files before and after
+///
+/// With `bottommost` the last level is rewritten as well, which drops deleted and overwritten
+/// data that a regular compaction would leave in place.
+pub fn compact(
+    db: &DbHandle,
+    target_cf: &str,
+    range: &KeyRange,
+    bottommost: bool,
+) -> Result<CompactSummary, Box<dyn std::error::Error>> {
+    let cf = db.cf(target_cf)?;
+
+    let files_before = files_per_level(db, cf)?;
+    let size_before = db.property_int_value_cf(cf, properties::TOTAL_SST_FILES_SIZE)?;
+
+    let mut opts = CompactOptions::default();
+    opts.set_exclusive_manual_compaction(true);
+    if bottommost {
+        opts.set_bottommost_level_compaction(BottommostLevelCompaction::ForceOptimized);
+    }
+
+    let started = Instant::now();
+    db.compact_range_cf_opt(cf, range.start.as_deref(), range.end.as_deref(), &opts);
+    let elapsed_secs = started.elapsed().as_secs_f64();
+
+    Ok(CompactSummary {
+        column_family: target_cf.to_string(),
+        bottommost,
+        files_before,
+        files_after: files_per_level(db, cf)?,
+        size_before,
+        size_after: db.property_int_value_cf(cf, properties::TOTAL_SST_FILES_SIZE)?,
+        elapsed_secs,
+    })
+}
@@ -0,0 +1,177 @@
+use rocksdb::{IngestExternalFileOptions, Options};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use super::options::show_options;
+use super::sst::write_sst_file_with_options;
+use super::stats::NUM_LEVELS;
+use crate::db::{self, set_compression, CompressionCodec, DbHandle, DbOptions};
+use crate::output::{
+    CfCompression, CfRecompression, CompressionReport, LevelCompression, RecompressSummary,
+};
+use crate::range::{self, KeyRange};
+
+/// Option value RocksDB writes when the bottommost level uses the codec of the other levels
+const BOTTOMMOST_UNSET: &str = "kDisableCompressionOption";
+
+/// Reports per column family and level the codec new SST files get and the size of the live files,
+/// compressed and estimated uncompressed
+///
+/// Codecs come from the latest OPTIONS file in `db_path` and are missing without one. The
+/// uncompressed size is estimated with RocksDB's `rocksdb.compression-ratio-at-level<N>` property,
+/// which samples the files of a level.
+pub fn compression_report(
+    db: &DbHandle,
+    db_path: &Path,
+    cf_names: &[String],
+) -> Result<CompressionReport, Box<dyn std::error::Error>> {
+    let cf_options: BTreeMap<String, BTreeMap<String, String>> =
+        match db::latest_options_file(db_path)? {
+            Some(_) => show_options(db_path, None)?
+                .column_families
+                .into_iter()
+                .map(|cf| (cf.name, cf.options))
+                .collect(),
+            None => BTreeMap::new(),
+        };
+    let live_files = db.live_files()?;
+
+    let mut column_families = Vec::with_capacity(cf_names.len());
+    for name in cf_names {
+        let cf = db.cf(name)?;
+        let files: Vec<_> = live_files
+            .iter()
+            .filter(|file| &file.column_family_name == name)
+            .collect();
+        let bottommost = files.iter().map(|file| file.level).max();
+
+        let mut levels = Vec::with_capacity(NUM_LEVELS);
+        for level in 0..NUM_LEVELS {
+            let level_files: Vec<_> =
+                files.iter().filter(|file| file.level as usize == level).collect();
+            let size: u64 = level_files.iter().map(|file| file.size as u64).sum();
+            let property = format!("rocksdb.compression-ratio-at-level{}", level);
+            // The ratio is uncompressed over compressed size, negative for levels without files
+            let ratio = db
+                .property_value_cf(cf, property.as_str())?
+                .and_then(|value| value.trim().parse::<f64>().ok())
+                .filter(|ratio| *ratio > 0.0);
+            let is_bottommost = bottommost == Some(level as i32);
+            levels.push(LevelCompression {
+                level,
+                codec: cf_options
+                    .get(name)
+                    .and_then(|options| level_codec(options, level, is_bottommost)),
+                files: level_files.len(),
+                size,
+                ratio,
+                estimated_raw_size: ratio.map(|ratio| (size as f64 * ratio) as u64),
+            });
+        }
+
+        column_families.push(CfCompression {
+            name: name.clone(),
+            size: levels.iter().map(|level| level.size).sum(),
+            estimated_raw_size: levels
+                .iter()
+                .map(|level| level.estimated_raw_size.unwrap_or(level.size))
+                .sum(),
+            levels,
+        });
+    }
+    Ok(CompressionReport { column_families })
+}
+
+/// Codec RocksDB compresses new files of `level` with, from the column family's options
+fn level_codec(
+    options: &BTreeMap<String, String>,
+    level: usize,
+    is_bottommost: bool,
+) -> Option<String> {
+    if is_bottommost {
+        if let Some(codec) = options.get("bottommost_compression") {
+            if codec != BOTTOMMOST_UNSET {
+                return Some(codec.clone());
+            }
+        }
+    }
+    // A per level list overrides the single codec, its last entry applies to deeper levels
+    match options.get("compression_per_level").filter(|list| !list.is_empty()) {
+        Some(list) => {
+            let codecs: Vec<&str> = list.split(':').collect();
+            Some(codecs[level.min(codecs.len() - 1)].to_string())
+        },
+        None => options.get("compression").cloned(),
+    }
+}
+
+/// Rewrites column families into a new database whose files are all compressed with `codec`
+///
+/// `destination` must not exist yet. Every column family is written to one SST file compressed
+/// with `codec`, at `level` if given, which is ingested into the new database. The new database
+/// keeps compressing with `codec` when it is written to later.
+pub fn recompress<N: AsRef<str>>(
+    db: &DbHandle,
+    destination: &Path,
+    cf_names: &[N],
+    codec: CompressionCodec,
+    level: Option<i32>,
+) -> Result<RecompressSummary, Box<dyn std::error::Error>> {
+    if destination.exists() {
+        return Err(format!("Destination {} already exists", destination.display()).into());
+    }
+    // Fail on a missing column family before anything is written
+    for name in cf_names {
+        db.cf(name.as_ref())?;
+    }
+
+    let target = DbHandle::open_read_write_with_options(
+        destination,
+        cf_names,
+        DbOptions::default().with_compression(codec, level),
+    )?;
+    let staging = destination.with_extension("recompress-staging");
+    fs::create_dir_all(&staging)?;
+
+    let mut sst_opts = Options::default();
+    set_compression(&mut sst_opts, codec, level);
+    let source_files = db.live_files()?;
+
+    let mut column_families = Vec::with_capacity(cf_names.len());
+    for name in cf_names {
+        let name = name.as_ref();
+        let pairs = range::iter_range(db, db.cf(name)?, &KeyRange::default(), false);
+        let sst_path = staging.join(format!("{}.sst", name));
+        let (records, _) = write_sst_file_with_options(&sst_path, &sst_opts, pairs)?;
+        if records > 0 {
+            let mut opts = IngestExternalFileOptions::default();
+            opts.set_move_files(true);
+            target.ingest_external_file_cf_opts(target.cf(name)?, &opts, vec![&sst_path])?;
+        }
+
+        column_families.push(CfRecompression {
+            column_family: name.to_string(),
+            records,
+            source_size: source_files
+                .iter()
+                .filter(|file| file.column_family_name == name)
+                .map(|file| file.size as u64)
+                .sum(),
+            size: target
+                .live_files()?
+                .iter()
+                .filter(|file| file.column_family_name == name)
+                .map(|file| file.size as u64)
+                .sum(),
+        });
+    }
+    fs::remove_dir_all(&staging)?;
+
+    Ok(RecompressSummary {
+        destination: destination.display().to_string(),
+        codec: codec.name().to_string(),
+        level,
+        column_families,
+    })
+}
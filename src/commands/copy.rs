@@ -0,0 +1,100 @@
+use clap::ValueEnum;
+use rocksdb::{IngestExternalFileOptions, WriteBatch};
+use std::fs;
+use std::path::Path;
+
+use super::sst::write_sst_file;
+use crate::db::DbHandle;
+use crate::output::{CfCopy, CopySummary};
+use crate::range::{self, KeyRange};
+
+/// Number of records written per WriteBatch when copying with batches
+const COPY_BATCH_SIZE: usize = 10_000;
+
+/// How records are written into the destination database
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum CopyMethod {
+    /// Write one SST file per column family and ingest it, fastest for large column families
+    Sst,
+    /// Write the records through WriteBatches, like any other writer would
+    Batch,
+}
+
+/// Copies column families, optionally restricted to a key range, into a new database
+///
+/// `destination` must not exist yet. With [`CopyMethod::Sst`] the SST files are staged in a
+/// sibling directory of `destination` and moved into it on ingestion.
+pub fn copy<N: AsRef<str>>(
+    db: &DbHandle,
+    destination: &Path,
+    cf_names: &[N],
+    range: &KeyRange,
+    method: CopyMethod,
+) -> Result<CopySummary, Box<dyn std::error::Error>> {
+    if destination.exists() {
+        return Err(format!("Destination {} already exists", destination.display()).into());
+    }
+    // Fail on a missing column family before anything is written
+    for name in cf_names {
+        db.cf(name.as_ref())?;
+    }
+
+    let target = DbHandle::open_read_write(destination, cf_names)?;
+    let staging = destination.with_extension("copy-staging");
+    if method == CopyMethod::Sst {
+        fs::create_dir_all(&staging)?;
+    }
+
+    let mut column_families = Vec::with_capacity(cf_names.len());
+    for name in cf_names {
+        let name = name.as_ref();
+        let pairs = range::iter_range(db, db.cf(name)?, range, false);
+        let target_cf = target.cf(name)?;
+
+        let (records, bytes) = match method {
+            CopyMethod::Sst => {
+                let sst_path = staging.join(format!("{}.sst", name));
+                let (records, bytes) = write_sst_file(&sst_path, pairs)?;
+                if records > 0 {
+                    let mut opts = IngestExternalFileOptions::default();
+                    opts.set_move_files(true);
+                    target.ingest_external_file_cf_opts(target_cf, &opts, vec![&sst_path])?;
+                }
+                (records, bytes)
+            }
+            CopyMethod::Batch => {
+                let mut records = 0u64;
+                let mut bytes = 0u64;
+                let mut batch = WriteBatch::default();
+                for item in pairs {
+                    let (key, value) = item?;
+                    records += 1;
+                    bytes += (key.len() + value.len()) as u64;
+                    batch.put_cf(target_cf, key, value);
+                    if batch.len() >= COPY_BATCH_SIZE {
+                        target.write(std::mem::take(&mut batch))?;
+                    }
+                }
+                if !batch.is_empty() {
+                    target.write(batch)?;
+                }
+                (records, bytes)
+            }
+        };
+
+        column_families.push(CfCopy {
+            column_family: name.to_string(),
+            records,
+            bytes,
+        });
+    }
+
+    if method == CopyMethod::Sst {
+        fs::remove_dir_all(&staging)?;
+    }
+
+    Ok(CopySummary {
+        destination: destination.display().to_string(),
+        column_families,
+    })
+}
@@ -0,0 +1,139 @@
+use rayon::prelude::*;
+use std::time::Instant;
+
+use super::resume::{CheckpointFile, ScanCheckpoint};
+use crate::db::DbHandle;
+use crate::error::Error;
+use crate::output::CountResult;
+use crate::output::to_hex;
+use crate::range::{self, KeyRange};
+
+/// Number of keys counted between two progress callbacks
+const PROGRESS_INTERVAL: u64 = 10_000;
+
+/// Counts the records of a column family within `range` by iterating over all of them
+///
+/// The keyspace is split into shards by leading key byte which are counted on a pool of `threads`
+/// threads, shards outside the range are skipped. `on_progress` is called with the number of keys
+/// counted since its previous call. With `checkpoint`, the last counted key of every shard is
+/// saved periodically and a resumed count continues each shard after it.
+pub fn count<F>(
+    db: &DbHandle,
+    target_cf: &str,
+    range: &KeyRange,
+    threads: usize,
+    checkpoint: Option<&CheckpointFile>,
+    on_progress: F,
+) -> Result<CountResult, Box<dyn std::error::Error>>
+where
+    F: Fn(u64) + Sync,
+{
+    // Fail early on a missing column family rather than once per shard
+    db.cf(target_cf)?;
+
+    let start_key = range.start.as_deref().map(to_hex);
+    let end_key = range.end.as_deref().map(to_hex);
+    let scan = format!(
+        "column family '{}' from {} to {}",
+        target_cf,
+        start_key.as_deref().unwrap_or("the first key"),
+        end_key.as_deref().unwrap_or("the last key")
+    );
+    let checkpoint = ScanCheckpoint::open(checkpoint, "count", scan)?;
+
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build()?;
+    let started = Instant::now();
+
+    let count = pool.install(|| {
+        (0..range::SHARD_COUNT)
+            .into_par_iter()
+            .map(|shard| {
+                count_shard(db, target_cf, shard as u8, range, &checkpoint, &on_progress)
+            })
+            .sum::<Result<u64, Error>>()
+    })?;
+    let resumed_from = checkpoint.resumed_from();
+    checkpoint.complete()?;
+
+    let elapsed = started.elapsed().as_secs_f64();
+    let counted = count - resumed_from;
+    Ok(CountResult {
+        column_family: target_cf.to_string(),
+        count,
+        start_key,
+        end_key,
+        approximate: false,
+        resumed_from,
+        elapsed_secs: elapsed,
+        keys_per_sec: if elapsed > 0.0 { counted as f64 / elapsed } else { 0.0 },
+    })
+}
+
+/// Reads RocksDB's `rocksdb.estimate-num-keys` property instead of iterating
+///
+/// The estimate is instant but can be off when the column family holds overwritten or deleted keys.
+pub fn count_approximate(
+    db: &DbHandle,
+    target_cf: &str,
+) -> Result<CountResult, Box<dyn std::error::Error>> {
+    let cf = db.cf(target_cf)?;
+    let started = Instant::now();
+
+    let count = db
+        .property_int_value_cf(cf, rocksdb::properties::ESTIMATE_NUM_KEYS)?
+        .ok_or("RocksDB did not report rocksdb.estimate-num-keys")?;
+
+    Ok(CountResult {
+        column_family: target_cf.to_string(),
+        count,
+        start_key: None,
+        end_key: None,
+        approximate: true,
+        resumed_from: 0,
+        elapsed_secs: started.elapsed().as_secs_f64(),
+        keys_per_sec: 0.0,
+    })
+}
+
+fn count_shard<F: Fn(u64)>(
+    db: &DbHandle,
+    target_cf: &str,
+    shard: u8,
+    range: &KeyRange,
+    checkpoint: &ScanCheckpoint,
+    on_progress: &F,
+) -> Result<u64, Error> {
+    let cf = db.cf(target_cf)?;
+    let scope = format!("{:02x}", shard);
+    let position = checkpoint.position(&scope);
+    if position.finished {
+        return Ok(position.processed);
+    }
+
+    // A resumed shard continues after its last counted key, which lies within the range
+    let range = match position.next_key()? {
+        Some(next) => KeyRange::new(Some(next), range.end.clone()),
+        None => range.clone(),
+    };
+    let opts = match range::shard_read_options_in(shard, &range, db.read_options()) {
+        Some(opts) => opts,
+        None => return Ok(position.processed),
+    };
+    let iter = db.iterator_cf_opt(cf, opts, rocksdb::IteratorMode::Start);
+
+    let mut count = position.processed;
+    let mut counted = 0;
+    for item in iter {
+        let (key, _) = item?;
+        count += 1;
+        counted += 1;
+        if counted % PROGRESS_INTERVAL == 0 {
+            on_progress(PROGRESS_INTERVAL);
+            checkpoint.advance(&scope, &key, count)?;
+        }
+    }
+    on_progress(counted % PROGRESS_INTERVAL);
+    checkpoint.finish(&scope, count)?;
+
+    Ok(count)
+}
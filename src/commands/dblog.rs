@@ -0,0 +1,163 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::output::{DbLogReport, LogBucket};
+
+/// Summarizes the background work RocksDB logged in an info LOG file over time
+///
+/// Flushes and compactions are read from the `EVENT_LOG_v1` JSON lines, write slowdowns and
+/// stops from the "Stalling writes" and "Stopping writes" warnings. Events are counted per
+/// bucket of `bucket_secs` seconds, from the first timestamped line, to line them up with
+/// prover slowdowns. Lines without a timestamp, such as the options dump, are skipped.
+pub fn analyze_log(
+    file: &Path,
+    bucket_secs: u64,
+) -> Result<DbLogReport, Box<dyn std::error::Error>> {
+    if bucket_secs == 0 {
+        return Err("Bucket length must be greater than 0".into());
+    }
+
+    let mut report = DbLogReport {
+        file: file.display().to_string(),
+        first_time: None,
+        last_time: None,
+        flushes: 0,
+        compactions: 0,
+        compaction_secs: 0.0,
+        compaction_output_bytes: 0,
+        slowdowns: 0,
+        stops: 0,
+        stall_reasons: BTreeMap::new(),
+        buckets: Vec::new(),
+    };
+    let mut start = None;
+    for line in BufReader::new(File::open(file)?).lines() {
+        let line = line?;
+        let time = match parse_timestamp(&line) {
+            Some(time) => time,
+            None => continue,
+        };
+        let start = *start.get_or_insert(time - time % bucket_secs as i64);
+        report.first_time.get_or_insert_with(|| format_timestamp(time));
+        report.last_time = Some(format_timestamp(time));
+
+        // Lines older than the first one, after the clock was set back, go to the first bucket
+        let index = ((time - start).max(0) as u64 / bucket_secs) as usize;
+        while report.buckets.len() <= index {
+            let bucket_start = start + (report.buckets.len() as u64 * bucket_secs) as i64;
+            report.buckets.push(LogBucket {
+                start: format_timestamp(bucket_start),
+                ..LogBucket::default()
+            });
+        }
+        let bucket = &mut report.buckets[index];
+
+        if let Some(event) = line.split_once("EVENT_LOG_v1 ").map(|(_, json)| json) {
+            let event: serde_json::Value = match serde_json::from_str(event) {
+                Ok(event) => event,
+                Err(_) => continue,
+            };
+            match event["event"].as_str() {
+                Some("flush_finished") => {
+                    report.flushes += 1;
+                    bucket.flushes += 1;
+                },
+                Some("compaction_finished") => {
+                    let secs = event["compaction_time_micros"].as_u64().unwrap_or(0) as f64 / 1e6;
+                    report.compactions += 1;
+                    report.compaction_secs += secs;
+                    report.compaction_output_bytes +=
+                        event["total_output_size"].as_u64().unwrap_or(0);
+                    bucket.compactions += 1;
+                    bucket.compaction_secs += secs;
+                },
+                _ => {},
+            }
+        } else if let Some(reason) = stall_reason(&line, "Stalling writes because ") {
+            report.slowdowns += 1;
+            bucket.slowdowns += 1;
+            *report.stall_reasons.entry(reason).or_insert(0) += 1;
+        } else if let Some(reason) = stall_reason(&line, "Stopping writes because ") {
+            report.stops += 1;
+            bucket.stops += 1;
+            *report.stall_reasons.entry(reason).or_insert(0) += 1;
+        }
+    }
+
+    Ok(report)
+}
+
+/// The reason of a stall warning with its numbers replaced by `N`, so equal reasons group together
+fn stall_reason(line: &str, marker: &str) -> Option<String> {
+    let (_, reason) = line.split_once(marker)?;
+    let mut normalized = String::with_capacity(reason.len());
+    let mut in_number = false;
+    for c in reason.trim().chars() {
+        if c.is_ascii_digit() {
+            if !in_number {
+                normalized.push('N');
+            }
+            in_number = true;
+        } else {
+            normalized.push(c);
+            in_number = false;
+        }
+    }
+    Some(format!("{}{}", marker, normalized))
+}
+
+/// Seconds since the Unix epoch of a line starting with a `2023/05/01-12:34:56.789012` timestamp
+///
+/// RocksDB writes local time, the seconds are only compared with each other.
+fn parse_timestamp(line: &str) -> Option<i64> {
+    let stamp = line.get(..19)?;
+    let bytes = stamp.as_bytes();
+    let separators = [(4, b'/'), (7, b'/'), (10, b'-'), (13, b':'), (16, b':')];
+    if separators.iter().any(|&(index, separator)| bytes[index] != separator) {
+        return None;
+    }
+    let field = |range: std::ops::Range<usize>| stamp.get(range)?.parse::<i64>().ok();
+    let days = days_from_civil(field(0..4)?, field(5..7)?, field(8..10)?);
+    Some(days * 86_400 + field(11..13)? * 3600 + field(14..16)? * 60 + field(17..19)?)
+}
+
+fn format_timestamp(secs: i64) -> String {
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    let secs = secs.rem_euclid(86_400);
+    format!(
+        "{:04}/{:02}/{:02}-{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+// Conversions between dates and days since 1970-01-01 in the proleptic Gregorian calendar,
+// from Howard Hinnant's chrono-compatible date algorithms
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -0,0 +1,30 @@
+use crate::output::{DecodeAttempt, DecodeResult};
+use crate::record::RecordDecoder;
+
+/// Decodes `value` with each of `decoders`, without a database
+///
+/// Every decoder is tried and reported, so a value of unknown origin shows which record types it
+/// parses as. With a single decoder its error is returned instead.
+pub fn decode(
+    value: &[u8],
+    decoders: &[&dyn RecordDecoder],
+) -> Result<DecodeResult, Box<dyn std::error::Error>> {
+    let mut attempts = Vec::with_capacity(decoders.len());
+    for decoder in decoders {
+        let attempt = match decoder.decode(value) {
+            Ok(record) => DecodeAttempt {
+                decoder: decoder.name(),
+                record,
+                error: None,
+            },
+            Err(e) if decoders.len() == 1 => return Err(e.into()),
+            Err(e) => DecodeAttempt {
+                decoder: decoder.name(),
+                record: None,
+                error: Some(e.to_string()),
+            },
+        };
+        attempts.push(attempt);
+    }
+    Ok(DecodeResult::new(value, attempts))
+}
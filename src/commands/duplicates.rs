@@ -0,0 +1,78 @@
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use crate::db::DbHandle;
+use crate::output::{to_hex, DuplicateGroup, DuplicatesSummary};
+
+/// Number of keys kept per group of identical values, the rest are only counted
+pub const MAX_KEYS_PER_GROUP: usize = 10;
+
+struct ValueGroup {
+    size: u64,
+    count: u64,
+    keys: Vec<Box<[u8]>>,
+}
+
+/// Hashes every value of a column family and passes groups of keys sharing a value to `on_group`
+///
+/// Groups are reported by decreasing potential savings, at most `limit` of them. Memory use
+/// grows with the number of distinct values.
+pub fn find_duplicates<F>(
+    db: &DbHandle,
+    target_cf: &str,
+    limit: Option<usize>,
+    mut on_group: F,
+) -> Result<DuplicatesSummary, Box<dyn std::error::Error>>
+where
+    F: FnMut(DuplicateGroup) -> Result<(), Box<dyn std::error::Error>>,
+{
+    let cf = db.cf(target_cf)?;
+
+    let mut records = 0u64;
+    let mut groups: HashMap<[u8; 32], ValueGroup> = HashMap::new();
+    for item in db.iterator_cf_opt(cf, db.read_options(), rocksdb::IteratorMode::Start) {
+        let (key, value) = item?;
+        records += 1;
+
+        let group = groups
+            .entry(Sha256::digest(&value).into())
+            .or_insert_with(|| ValueGroup {
+                size: value.len() as u64,
+                count: 0,
+                keys: Vec::new(),
+            });
+        group.count += 1;
+        if group.keys.len() < MAX_KEYS_PER_GROUP {
+            group.keys.push(key);
+        }
+    }
+
+    let distinct_values = groups.len() as u64;
+    let mut duplicates: Vec<([u8; 32], ValueGroup)> =
+        groups.into_iter().filter(|(_, group)| group.count > 1).collect();
+    duplicates.sort_by_key(|(_, group)| std::cmp::Reverse((group.count - 1) * group.size));
+
+    let mut summary = DuplicatesSummary {
+        column_family: target_cf.to_string(),
+        records,
+        distinct_values,
+        duplicate_groups: duplicates.len() as u64,
+        duplicate_records: duplicates.iter().map(|(_, group)| group.count).sum(),
+        potential_savings: 0,
+    };
+    for (_, group) in &duplicates {
+        summary.potential_savings += (group.count - 1) * group.size;
+    }
+
+    for (digest, group) in duplicates.into_iter().take(limit.unwrap_or(usize::MAX)) {
+        on_group(DuplicateGroup {
+            value_sha256: hex::encode(digest),
+            value_size: group.size,
+            count: group.count,
+            keys: group.keys.iter().map(|key| to_hex(key)).collect(),
+            savings: (group.count - 1) * group.size,
+        })?;
+    }
+
+    Ok(summary)
+}
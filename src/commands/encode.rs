@@ -0,0 +1,99 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::path::Path;
+use zkwasm_host_circuits::host::{datahash::DataHashRecord, mongomerkle::MerkleRecord};
+
+use crate::error::Error;
+use crate::key::{parse_key_as, KeyFormat};
+use crate::merkle::to_hash;
+use crate::output::{to_hex, EncodeResult};
+use crate::record::DecodedRecord;
+
+/// Type of the record built from command line fields
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum RecordType {
+    /// `MerkleRecord`, as stored in merkle_records
+    Merkle,
+    /// `DataHashRecord`, as stored in data_records
+    Data,
+}
+
+/// Fields of a record to encode, in the shape of the decoded records printed by `--output json`
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RecordFields {
+    MerkleRecord {
+        index: u64,
+        hash: String,
+        left: Option<String>,
+        right: Option<String>,
+        data: Option<String>,
+    },
+    DataHashRecord {
+        hash: String,
+        data: String,
+    },
+}
+
+impl RecordFields {
+    /// Reads the fields from a JSON file holding one decoded record object
+    pub fn from_json_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let text = std::fs::read_to_string(path)?;
+        serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid record in {}: {}", path.display(), e).into())
+    }
+
+    /// Parses the hashes and payload in `format` into a record
+    pub fn into_record(self, format: KeyFormat) -> Result<DecodedRecord, Error> {
+        let hash = |value: &str| to_hash(&parse_key_as(value, format, false)?);
+        let optional_hash = |value: Option<String>| value.as_deref().map(hash).transpose();
+        match self {
+            RecordFields::MerkleRecord {
+                index,
+                hash: node_hash,
+                left,
+                right,
+                data,
+            } => Ok(DecodedRecord::Merkle(MerkleRecord {
+                index,
+                hash: hash(&node_hash)?,
+                left: optional_hash(left)?,
+                right: optional_hash(right)?,
+                data: optional_hash(data)?,
+            })),
+            RecordFields::DataHashRecord {
+                hash: data_hash,
+                data,
+            } => Ok(DecodedRecord::Data(DataHashRecord {
+                hash: hash(&data_hash)?,
+                data: parse_key_as(&data, format, false)?,
+            })),
+        }
+    }
+}
+
+/// Serializes `record` into the key and value it is stored under
+///
+/// The value is decoded and serialized again to check the encoding round-trips to the same bytes.
+pub fn encode(record: DecodedRecord) -> Result<EncodeResult, Box<dyn std::error::Error>> {
+    let (key, value, round_trip) = match &record {
+        DecodedRecord::Merkle(merkle) => {
+            let value = merkle.to_slice();
+            let round_trip = MerkleRecord::from_slice(&value)?.to_slice() == value;
+            (merkle.hash.to_vec(), value, round_trip)
+        }
+        DecodedRecord::Data(data) => {
+            let value = data.to_slice();
+            let round_trip = DataHashRecord::from_slice(&value)?.to_slice() == value;
+            (data.hash.to_vec(), value, round_trip)
+        }
+    };
+
+    Ok(EncodeResult {
+        key: to_hex(&key),
+        value: to_hex(&value),
+        len: value.len(),
+        round_trip,
+        record,
+    })
+}
@@ -0,0 +1,42 @@
+use crate::db::DbHandle;
+use crate::output::ExistsSummary;
+
+/// Checks which of `keys` are stored in a column family, passing every key that is not to `on_missing`
+///
+/// `key_may_exist_cf` answers from the memtables and Bloom filters without reading data blocks and
+/// a `false` from it is definite, so only the keys it may hold are confirmed with a full get. Column
+/// families without Bloom filters make every key a potential hit and gain nothing over plain gets.
+pub fn exists<F>(
+    db: &DbHandle,
+    target_cf: &str,
+    keys: &[Vec<u8>],
+    mut on_missing: F,
+) -> Result<ExistsSummary, Box<dyn std::error::Error>>
+where
+    F: FnMut(&[u8]) -> Result<(), Box<dyn std::error::Error>>,
+{
+    let cf = db.cf(target_cf)?;
+
+    let mut summary = ExistsSummary {
+        column_family: target_cf.to_string(),
+        total: 0,
+        found: 0,
+        definitely_missing: 0,
+        false_positives: 0,
+    };
+    let read_opts = db.read_options();
+    for key in keys {
+        summary.total += 1;
+        if !db.key_may_exist_cf_opt(cf, key, &read_opts) {
+            summary.definitely_missing += 1;
+            on_missing(key)?;
+        } else if db.get_pinned_cf_opt(cf, key, &read_opts)?.is_some() {
+            summary.found += 1;
+        } else {
+            summary.false_positives += 1;
+            on_missing(key)?;
+        }
+    }
+
+    Ok(summary)
+}
@@ -0,0 +1,129 @@
+use rayon::prelude::*;
+use regex::Regex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc;
+
+use crate::db::DbHandle;
+use crate::error::Error;
+use crate::output::GrepSummary;
+use crate::paginate::{Pagination, Paginator, Step};
+use crate::range;
+
+/// What values are matched against
+pub enum ValuePattern {
+    /// Values containing this byte sequence
+    Bytes(Vec<u8>),
+    /// Values whose UTF-8 interpretation matches this regex, invalid sequences are replaced
+    Regex(Regex),
+}
+
+impl ValuePattern {
+    pub fn matches(&self, value: &[u8]) -> bool {
+        match self {
+            ValuePattern::Bytes(needle) => {
+                needle.is_empty() || value.windows(needle.len()).any(|window| window == needle)
+            }
+            ValuePattern::Regex(regex) => regex.is_match(&String::from_utf8_lossy(value)),
+        }
+    }
+}
+
+/// Scans a column family on `threads` threads and passes the key of every matching value to `on_match`
+///
+/// Matches arrive in no particular order when more than one thread is used, so `page` only selects
+/// the same matches across runs with a single thread. Scanning stops once the matches selected by
+/// `page` have been reported.
+pub fn grep<F>(
+    db: &DbHandle,
+    target_cf: &str,
+    pattern: &ValuePattern,
+    threads: usize,
+    page: Pagination,
+    mut on_match: F,
+) -> Result<GrepSummary, Box<dyn std::error::Error>>
+where
+    F: FnMut(&[u8], &[u8]) -> Result<(), Box<dyn std::error::Error>>,
+{
+    db.cf(target_cf)?;
+
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build()?;
+    let stop = AtomicBool::new(false);
+    let scanned = AtomicU64::new(0);
+    let (tx, rx) = mpsc::sync_channel::<Result<(Box<[u8]>, Box<[u8]>), Error>>(1024);
+
+    let mut paginator = Paginator::new(page);
+    std::thread::scope(|scope| -> Result<(), Box<dyn std::error::Error>> {
+        scope.spawn(|| {
+            pool.install(|| {
+                (0..range::SHARD_COUNT)
+                    .into_par_iter()
+                    .for_each_with(tx, |tx, shard| {
+                        grep_shard(db, target_cf, shard as u8, pattern, &stop, &scanned, tx)
+                    })
+            })
+        });
+
+        // Dropping the receiver when leaving the loop makes the shard scanners stop sending
+        for item in rx {
+            let (key, value) = item?;
+            match paginator.step() {
+                Step::Skip => continue,
+                Step::List => on_match(&key, &value)?,
+                Step::Stop => break,
+            }
+        }
+        stop.store(true, Ordering::Relaxed);
+        Ok(())
+    })?;
+
+    Ok(GrepSummary {
+        column_family: target_cf.to_string(),
+        scanned: scanned.load(Ordering::Relaxed),
+        matches: paginator.listed(),
+        skipped: paginator.skipped(),
+        next_skip: paginator.next_skip(),
+    })
+}
+
+fn grep_shard(
+    db: &DbHandle,
+    target_cf: &str,
+    shard: u8,
+    pattern: &ValuePattern,
+    stop: &AtomicBool,
+    scanned: &AtomicU64,
+    tx: &mut mpsc::SyncSender<Result<(Box<[u8]>, Box<[u8]>), Error>>,
+) {
+    let cf = match db.cf(target_cf) {
+        Ok(cf) => cf,
+        Err(e) => {
+            let _ = tx.send(Err(e));
+            return;
+        }
+    };
+
+    let iter = db.iterator_cf_opt(
+        cf,
+        range::shard_read_options(shard, db.read_options()),
+        rocksdb::IteratorMode::Start,
+    );
+    for item in iter {
+        if stop.load(Ordering::Relaxed) {
+            return;
+        }
+        let sent = match item {
+            Ok((key, value)) => {
+                scanned.fetch_add(1, Ordering::Relaxed);
+                if !pattern.matches(&value) {
+                    continue;
+                }
+                tx.send(Ok((key, value)))
+            }
+            Err(e) => tx.send(Err(e.into())),
+        };
+        if sent.is_err() {
+            // The receiver is gone, either the limit was reached or reporting failed
+            return;
+        }
+    }
+}
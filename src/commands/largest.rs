@@ -0,0 +1,64 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+
+use crate::commands::analyze::{bucket_bounds, log2_bucket};
+use crate::db::DbHandle;
+use crate::output::{to_hex, LargestValuesReport, SizeBucket, ValueSize};
+
+/// Scans a column family for the `n` largest values and totals value bytes per power-of-two size bucket
+pub fn largest_values(
+    db: &DbHandle,
+    target_cf: &str,
+    n: usize,
+) -> Result<LargestValuesReport, Box<dyn std::error::Error>> {
+    let cf = db.cf(target_cf)?;
+
+    let mut records = 0u64;
+    let mut value_bytes = 0u64;
+    // Min-heap of the largest values seen so far, the smallest of them is evicted first
+    let mut largest: BinaryHeap<Reverse<(usize, Box<[u8]>)>> = BinaryHeap::with_capacity(n + 1);
+    let mut buckets: BTreeMap<u32, (u64, u64)> = BTreeMap::new();
+
+    for item in db.iterator_cf_opt(cf, db.read_options(), rocksdb::IteratorMode::Start) {
+        let (key, value) = item?;
+        records += 1;
+        value_bytes += value.len() as u64;
+
+        let bucket = buckets.entry(log2_bucket(value.len())).or_default();
+        bucket.0 += 1;
+        bucket.1 += value.len() as u64;
+
+        if n > 0 {
+            largest.push(Reverse((value.len(), key)));
+            if largest.len() > n {
+                largest.pop();
+            }
+        }
+    }
+
+    Ok(LargestValuesReport {
+        column_family: target_cf.to_string(),
+        records,
+        value_bytes,
+        largest: largest
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((size, key))| ValueSize {
+                key: to_hex(&key),
+                size: size as u64,
+            })
+            .collect(),
+        buckets: buckets
+            .into_iter()
+            .map(|(bucket, (count, bytes))| {
+                let (min, max) = bucket_bounds(bucket);
+                SizeBucket {
+                    min,
+                    max,
+                    count,
+                    bytes,
+                }
+            })
+            .collect(),
+    })
+}
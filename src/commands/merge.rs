@@ -0,0 +1,130 @@
+use clap::ValueEnum;
+use rocksdb::WriteBatch;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::db::DbHandle;
+use crate::output::{to_hex, CfMerge, MergeSummary};
+use crate::range::{self, KeyRange};
+
+/// Number of records written per WriteBatch into the merged database
+const MERGE_BATCH_SIZE: usize = 10_000;
+
+/// Which value is kept for a key stored with different values in several sources
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ConflictPolicy {
+    /// Keep the value of the source listed first
+    FirstWins,
+    /// Keep the value of the source listed last
+    LastWins,
+    /// Stop the merge at the first conflicting key
+    Fail,
+}
+
+impl ConflictPolicy {
+    fn name(&self) -> &'static str {
+        match self {
+            ConflictPolicy::FirstWins => "first-wins",
+            ConflictPolicy::LastWins => "last-wins",
+            ConflictPolicy::Fail => "fail",
+        }
+    }
+}
+
+/// Writes the union of column families of several databases into a new database
+///
+/// `destination` must not exist yet. Column families missing from a source are taken from the
+/// others. Each column family is merged in one pass over sorted iterators of all sources, keys
+/// stored with different values in several of them are resolved with the column family's entry
+/// in `policies`, `default_policy` if it has none. A failed merge leaves a partial destination.
+pub fn merge<N: AsRef<str>>(
+    sources: &[(String, DbHandle)],
+    destination: &Path,
+    cf_names: &[N],
+    default_policy: ConflictPolicy,
+    policies: &BTreeMap<String, ConflictPolicy>,
+) -> Result<MergeSummary, Box<dyn std::error::Error>> {
+    if sources.len() < 2 {
+        return Err("At least two databases are needed to merge".into());
+    }
+    if destination.exists() {
+        return Err(format!("Destination {} already exists", destination.display()).into());
+    }
+    for name in cf_names {
+        if !sources.iter().any(|(_, db)| db.cf(name.as_ref()).is_ok()) {
+            return Err(crate::Error::MissingColumnFamily(name.as_ref().to_string()).into());
+        }
+    }
+
+    let target = DbHandle::open_read_write(destination, cf_names)?;
+
+    let mut column_families = Vec::with_capacity(cf_names.len());
+    for name in cf_names {
+        let name = name.as_ref();
+        let policy = policies.get(name).copied().unwrap_or(default_policy);
+        let target_cf = target.cf(name)?;
+
+        // Sources without the column family contribute nothing to it
+        let mut iters = Vec::new();
+        for (path, db) in sources {
+            if let Ok(cf) = db.cf(name) {
+                iters.push((path, range::iter_range(db, cf, &KeyRange::default(), false)));
+            }
+        }
+        let mut heads = Vec::with_capacity(iters.len());
+        for (_, iter) in iters.iter_mut() {
+            heads.push(iter.next().transpose()?);
+        }
+
+        let mut merged = CfMerge {
+            column_family: name.to_string(),
+            policy: policy.name().to_string(),
+            records: 0,
+            conflicts: 0,
+        };
+        let mut batch = WriteBatch::default();
+        while let Some(key) = heads.iter().flatten().map(|(key, _)| key).min().cloned() {
+            // Sources holding the smallest key, in the order they were given
+            let holders: Vec<usize> = (0..heads.len())
+                .filter(|&i| matches!(&heads[i], Some((head, _)) if *head == key))
+                .collect();
+            let value_of = |i: usize| &heads[i].as_ref().expect("holder has a head").1;
+
+            let first = holders[0];
+            let last = holders[holders.len() - 1];
+            if holders.iter().any(|&i| value_of(i) != value_of(first)) {
+                merged.conflicts += 1;
+                if policy == ConflictPolicy::Fail {
+                    let paths: Vec<&str> = holders.iter().map(|&i| iters[i].0.as_str()).collect();
+                    return Err(format!(
+                        "Key {} of column family '{}' has different values in {}",
+                        to_hex(&key),
+                        name,
+                        paths.join(", ")
+                    )
+                    .into());
+                }
+            }
+            let kept = if policy == ConflictPolicy::LastWins { last } else { first };
+            batch.put_cf(target_cf, &key, value_of(kept));
+            merged.records += 1;
+            if batch.len() >= MERGE_BATCH_SIZE {
+                target.write(std::mem::take(&mut batch))?;
+            }
+
+            for i in holders {
+                heads[i] = iters[i].1.next().transpose()?;
+            }
+        }
+        if !batch.is_empty() {
+            target.write(batch)?;
+        }
+        column_families.push(merged);
+    }
+
+    Ok(MergeSummary {
+        destination: destination.display().to_string(),
+        sources: sources.iter().map(|(path, _)| path.clone()).collect(),
+        column_families,
+    })
+}
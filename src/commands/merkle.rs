@@ -0,0 +1,1013 @@
+use rocksdb::WriteBatch;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use zkwasm_host_circuits::host::mongomerkle::MerkleRecord;
+
+use crate::db::DbHandle;
+use crate::interpret::ValueType;
+use crate::merkle::{self, MerkleIssue};
+use crate::output::{
+    to_hex, CheckResult, ComputeRootReport, CrossCheckIssue, CrossCheckSummary, LeafEntry,
+    LeafListSummary, LevelDivergence, MerkleStatsReport, OrphanSummary, ParentEntry, ParentSummary,
+    PathStepEntry, PathSummary, SubtreeImportSummary, SubtreeSummary, VerifySummary,
+};
+use crate::paginate::{Pagination, Paginator, Step};
+use crate::record::{DataHashRecordDecoder, DecodedRecord, MerkleRecordDecoder};
+use crate::{DATA_CF_NAME, DEFAULT_CF_NAMES, MERKLE_CF_NAME};
+
+/// Number of orphan deletions written per batch
+const DELETE_BATCH_SIZE: usize = 10_000;
+
+/// Number of records written per batch into a subtree export
+const SUBTREE_BATCH_SIZE: usize = 10_000;
+
+/// Walks the tree stored in merkle_records from `root` and reports every inconsistency found
+///
+/// Unstored empty subtrees are not issues, with `include_defaults` they count toward the visited
+/// nodes and leaves. `on_node` is called with the value size of every node read.
+pub fn verify_merkle<F>(
+    db: &DbHandle,
+    root: [u8; 32],
+    include_defaults: bool,
+    on_node: F,
+) -> Result<(Vec<MerkleIssue>, VerifySummary), Box<dyn std::error::Error>>
+where
+    F: FnMut(usize),
+{
+    let cf = db.cf(MERKLE_CF_NAME)?;
+
+    let report = merkle::verify_tree(db, cf, &merkle::default_hashes()?, root, on_node)?;
+    let (default_nodes, default_leaves) = if include_defaults {
+        (report.default_nodes, report.default_leaves)
+    } else {
+        (0, 0)
+    };
+    let summary = VerifySummary {
+        root: to_hex(&root),
+        nodes_visited: report.nodes_visited + default_nodes,
+        leaves: report.leaves + default_leaves,
+        default_nodes: report.default_nodes,
+        defaults_included: include_defaults,
+        issues: report.issues.len() as u64,
+    };
+
+    Ok((report.issues, summary))
+}
+
+/// Counts the nodes per level of the tree stored in merkle_records below `root` and its anomalies
+///
+/// With `include_defaults` the unstored empty subtrees count toward the nodes, leaves and depth.
+pub fn merkle_stats(
+    db: &DbHandle,
+    root: [u8; 32],
+    include_defaults: bool,
+) -> Result<MerkleStatsReport, Box<dyn std::error::Error>> {
+    let cf = db.cf(MERKLE_CF_NAME)?;
+
+    let shape = merkle::tree_shape(db, cf, &merkle::default_hashes()?, root)?;
+    let (mut nodes, mut leaves, mut max_depth) = (shape.nodes, shape.leaves, shape.max_depth);
+    if include_defaults {
+        for (depth, count) in shape.default_per_level.iter().enumerate() {
+            if *count == 0 {
+                continue;
+            }
+            nodes += count;
+            max_depth = max_depth.max(depth);
+            if depth == merkle::MERKLE_DEPTH {
+                leaves += count;
+            }
+        }
+    }
+    Ok(MerkleStatsReport {
+        root: to_hex(&root),
+        expected_depth: merkle::MERKLE_DEPTH,
+        max_depth,
+        nodes,
+        leaves,
+        defaults_included: include_defaults,
+        nodes_per_level: shape.nodes_per_level,
+        default_per_level: shape.default_per_level,
+        unstored_per_level: shape.unstored_per_level,
+        single_child_nodes: shape.single_child_nodes,
+        shallow_leaves: shape.shallow_leaves,
+        too_deep_nodes: shape.too_deep_nodes,
+        index_mismatches: shape.index_mismatches,
+        undecodable_nodes: shape.undecodable_nodes,
+    })
+}
+
+/// Identifies the leaf a merkle path is extracted for
+pub enum LeafSelector {
+    Index(u64),
+    Hash([u8; 32]),
+}
+
+/// Reconstructs the authentication path from a leaf up to `root`
+pub fn merkle_path(
+    db: &DbHandle,
+    root: [u8; 32],
+    leaf: LeafSelector,
+    expected_root: Option<[u8; 32]>,
+) -> Result<(Vec<PathStepEntry>, PathSummary), Box<dyn std::error::Error>> {
+    let cf = db.cf(MERKLE_CF_NAME)?;
+
+    let leaf_index = match leaf {
+        LeafSelector::Index(index) => index,
+        LeafSelector::Hash(hash) => {
+            merkle::get_record(db, cf, &hash)?
+                .ok_or_else(|| format!("Leaf {} not found", to_hex(&hash)))?
+                .index
+        }
+    };
+    if !merkle::leaf_index_range().contains(&leaf_index) {
+        return Err(format!(
+            "Index {} is not a leaf index, leaves range over {:?}",
+            leaf_index,
+            merkle::leaf_index_range()
+        )
+        .into());
+    }
+
+    let defaults = merkle::default_hashes()?;
+    let steps = merkle::authentication_path(db, cf, &defaults, root, leaf_index)?;
+    let leaf_hash = steps.first().map(|step| step.hash).unwrap_or(root);
+    let entries = steps
+        .iter()
+        .enumerate()
+        .map(|(level, step)| PathStepEntry {
+            depth: (steps.len() - level) as u64,
+            index: step.index,
+            hash: to_hex(&step.hash),
+            sibling_index: step.sibling_index,
+            sibling_hash: to_hex(&step.sibling_hash),
+            sibling_is_default: merkle::is_default(
+                &defaults,
+                step.sibling_index,
+                &step.sibling_hash,
+            ),
+            sibling_side: if step.sibling_is_left { "left" } else { "right" },
+        })
+        .collect();
+
+    let computed_root = merkle::compute_root(&leaf_hash, &steps);
+    let summary = PathSummary {
+        leaf_index,
+        leaf_hash: to_hex(&leaf_hash),
+        computed_root: to_hex(&computed_root),
+        expected_root: expected_root.map(|root| to_hex(&root)),
+        matches: expected_root.map(|root| root == computed_root),
+    };
+
+    Ok((entries, summary))
+}
+
+/// Looks up the leaf at heap index `index` below `root` and the data record it points to
+///
+/// merkle_records is keyed by node hash, so the key of the leaf is found by walking down the path
+/// from `root` to `index`.
+pub fn get_leaf(
+    db: &DbHandle,
+    root: [u8; 32],
+    index: u64,
+) -> Result<(CheckResult, Option<CheckResult>), Box<dyn std::error::Error>> {
+    let cf = db.cf(MERKLE_CF_NAME)?;
+    if !merkle::leaf_index_range().contains(&index) {
+        return Err(format!(
+            "Index {} is not a leaf index, leaves range over {:?}",
+            index,
+            merkle::leaf_index_range()
+        )
+        .into());
+    }
+
+    let steps = merkle::authentication_path(db, cf, &merkle::default_hashes()?, root, index)?;
+    let hash = steps.first().map(|step| step.hash).unwrap_or(root);
+    let leaf = super::check(db, MERKLE_CF_NAME, &hash, &MerkleRecordDecoder, ValueType::Auto)?;
+    let data = match &leaf.decoded {
+        Some(DecodedRecord::Merkle(MerkleRecord {
+            data: Some(data_hash),
+            ..
+        })) => {
+            let decoder = &DataHashRecordDecoder;
+            Some(super::check(db, DATA_CF_NAME, data_hash, decoder, ValueType::Auto)?)
+        }
+        _ => None,
+    };
+
+    Ok((leaf, data))
+}
+
+/// Walks the tree below `root` in index order and passes its stored leaves to `on_leaf`
+///
+/// Only leaves with an index in `indices` are listed, subtrees outside it are not read. Of those,
+/// the ones selected by `page` are listed, the summary holds the `--skip` value of the next page
+/// when more leaves follow. Unstored empty subtrees hold no leaves and are counted as defaults.
+pub fn list_leaves<F>(
+    db: &DbHandle,
+    root: [u8; 32],
+    indices: std::ops::RangeInclusive<u64>,
+    page: Pagination,
+    mut on_leaf: F,
+) -> Result<LeafListSummary, Box<dyn std::error::Error>>
+where
+    F: FnMut(LeafEntry) -> Result<(), Box<dyn std::error::Error>>,
+{
+    let cf = db.cf(MERKLE_CF_NAME)?;
+    let root_index = merkle::get_record(db, cf, &root)?
+        .ok_or_else(|| format!("Root {} not found", to_hex(&root)))?
+        .index;
+
+    let defaults = merkle::default_hashes()?;
+    let mut paginator = Paginator::new(page);
+    let (mut default_nodes, mut missing_nodes) = (0, 0);
+    let (mut undecodable_nodes, mut too_deep_nodes) = (0, 0);
+    let read_opts = db.read_options();
+    let mut stack = vec![(root, root_index)];
+    while let Some((hash, index)) = stack.pop() {
+        // A corrupt root index or a node with children at leaf depth has no leaf span
+        if merkle::depth_of(index) > merkle::MERKLE_DEPTH {
+            too_deep_nodes += 1;
+            continue;
+        }
+        let (first, last) = merkle::leaf_span(index);
+        if last < *indices.start() || first > *indices.end() {
+            continue;
+        }
+        let value = match db.get_cf_opt(cf, hash, &read_opts)? {
+            Some(value) => value,
+            None if merkle::is_default(&defaults, index, &hash) => {
+                default_nodes += 1;
+                continue;
+            }
+            None => {
+                missing_nodes += 1;
+                continue;
+            }
+        };
+        let record = match MerkleRecord::from_slice(&value) {
+            Ok(record) => record,
+            Err(_) => {
+                // Reported by validate-records, the leaves below it are unknown
+                undecodable_nodes += 1;
+                continue;
+            }
+        };
+        if let (Some(left), Some(right)) = (record.left, record.right) {
+            stack.push((right, merkle::right_child_index(index)));
+            stack.push((left, merkle::left_child_index(index)));
+            continue;
+        }
+        if merkle::depth_of(index) != merkle::MERKLE_DEPTH {
+            continue;
+        }
+
+        match paginator.step() {
+            Step::Skip => continue,
+            Step::List => on_leaf(LeafEntry {
+                index,
+                hash: to_hex(&hash),
+                data_hash: record.data.map(|data| to_hex(&data)),
+            })?,
+            Step::Stop => break,
+        }
+    }
+
+    Ok(LeafListSummary {
+        root: to_hex(&root),
+        listed: paginator.listed(),
+        skipped: paginator.skipped(),
+        next_skip: paginator.next_skip(),
+        default_nodes,
+        missing_nodes,
+        undecodable_nodes,
+        too_deep_nodes,
+    })
+}
+
+/// Passes every merkle_records key not reachable from any of `roots` to `on_orphan`
+///
+/// With `delete` the orphans are removed as well, which needs a read-write handle. Keys that are
+/// not 32-byte hashes can never be reached and are reported as orphans too.
+pub fn find_orphans<F>(
+    db: &DbHandle,
+    roots: &[[u8; 32]],
+    delete: bool,
+    mut on_orphan: F,
+) -> Result<OrphanSummary, Box<dyn std::error::Error>>
+where
+    F: FnMut(&[u8]) -> Result<(), Box<dyn std::error::Error>>,
+{
+    let cf = db.cf(MERKLE_CF_NAME)?;
+    let reachable = merkle::reachable_hashes(db, cf, roots)?;
+
+    let mut scanned = 0u64;
+    let mut orphans = 0u64;
+    let mut batch = WriteBatch::default();
+    for item in db.iterator_cf_opt(cf, db.read_options(), rocksdb::IteratorMode::Start) {
+        let (key, _) = item?;
+        scanned += 1;
+
+        let is_reachable =
+            <[u8; 32]>::try_from(&*key).map_or(false, |hash| reachable.contains(&hash));
+        if is_reachable {
+            continue;
+        }
+        on_orphan(&key)?;
+        orphans += 1;
+
+        if delete {
+            batch.delete_cf(cf, &key);
+            if batch.len() >= DELETE_BATCH_SIZE {
+                db.write(std::mem::take(&mut batch))?;
+            }
+        }
+    }
+    if delete && !batch.is_empty() {
+        db.write(batch)?;
+    }
+
+    Ok(OrphanSummary {
+        roots: roots.len() as u64,
+        reachable: reachable.len() as u64,
+        scanned,
+        orphans,
+        deleted: delete,
+    })
+}
+
+/// Recomputes the root of the tree stored below `root` from its leaves and compares every
+/// recomputed node with the hash the stored tree holds at that position
+///
+/// The leaves are the childless nodes reachable from `root`, or `leaves` as `(index, leaf hash)`
+/// pairs when given. Positions without a leaf hold the empty subtree hashes of mongomerkle. Levels
+/// are compared from the leaves up, so the first reported divergence is the deepest one, where the
+/// stored tree first disagrees with its leaves.
+///
+/// A stored node is walked once. When it is referenced again at another position its subtree is
+/// not read a second time, the node hash is taken for that position as it stands. Unstored nodes
+/// holding the empty subtree hash of their depth are counted as defaults, other unstored nodes as
+/// missing.
+pub fn recompute_root(
+    db: &DbHandle,
+    root: [u8; 32],
+    leaves: Option<Vec<(u64, [u8; 32])>>,
+) -> Result<ComputeRootReport, Box<dyn std::error::Error>> {
+    let cf = db.cf(MERKLE_CF_NAME)?;
+    let root_index = merkle::get_record(db, cf, &root)?
+        .ok_or_else(|| format!("Root {} not found", to_hex(&root)))?
+        .index;
+    let root_depth = merkle::depth_of(root_index);
+    if root_depth > merkle::MERKLE_DEPTH {
+        return Err(format!(
+            "Root {} has index {}, below the leaves at depth {}",
+            to_hex(&root),
+            root_index,
+            merkle::MERKLE_DEPTH
+        )
+        .into());
+    }
+    let default_hashes = merkle::default_hashes()?;
+
+    // Hash the stored tree claims at every position it references, and its leaves
+    let read_opts = db.read_options();
+    let mut claimed: HashMap<u64, [u8; 32]> = HashMap::new();
+    let mut stored_leaves = BTreeMap::new();
+    // Positions of nodes already walked at another position, folded in as they are stored
+    let mut shared = BTreeMap::new();
+    let mut visited = HashSet::new();
+    let mut stack = vec![(root, root_index)];
+    let (mut default_nodes, mut missing_nodes) = (0, 0);
+    while let Some((hash, index)) = stack.pop() {
+        claimed.insert(index, hash);
+        let value = match db.get_cf_opt(cf, hash, &read_opts)? {
+            Some(value) => value,
+            None if merkle::is_default(&default_hashes, index, &hash) => {
+                default_nodes += 1;
+                continue;
+            }
+            None => {
+                missing_nodes += 1;
+                continue;
+            }
+        };
+        if !visited.insert(hash) {
+            if merkle::depth_of(index) >= merkle::MERKLE_DEPTH {
+                stored_leaves.insert(index, hash);
+            } else {
+                shared.insert(index, hash);
+            }
+            continue;
+        }
+        // Positions below a node that fails to decode are left unclaimed
+        let record = match MerkleRecord::from_slice(&value) {
+            Ok(record) => record,
+            Err(_) => continue,
+        };
+        // Children are placed below the position the parent implies and nothing is walked below
+        // the leaves, so the walk ends even when corrupt child hashes point back up the tree
+        if merkle::depth_of(index) >= merkle::MERKLE_DEPTH {
+            stored_leaves.insert(index, hash);
+        } else if let (Some(left), Some(right)) = (record.left, record.right) {
+            stack.push((right, merkle::right_child_index(index)));
+            stack.push((left, merkle::left_child_index(index)));
+        }
+    }
+
+    let mut level: BTreeMap<u64, [u8; 32]> = match leaves {
+        Some(leaves) => {
+            shared.clear();
+            leaves.into_iter().collect()
+        }
+        None => stored_leaves,
+    };
+    let leaf_count = level.len() as u64;
+    for index in level.keys() {
+        let depth = merkle::depth_of(*index);
+        // Checked at leaf depth first, a corrupt index near u64::MAX would overflow otherwise
+        let below_root = depth == merkle::MERKLE_DEPTH
+            && (index + 1) >> (depth - root_depth) == root_index + 1;
+        if !below_root {
+            return Err(format!("Index {} is not a leaf below index {}", index, root_index).into());
+        }
+    }
+
+    let mut report = ComputeRootReport {
+        root: to_hex(&root),
+        root_index,
+        leaves: leaf_count,
+        default_nodes,
+        missing_nodes,
+        computed_root: String::new(),
+        matches: false,
+        divergent_levels: Vec::new(),
+    };
+    let mut depth = merkle::MERKLE_DEPTH;
+    loop {
+        level.extend(shared.iter().filter(|(index, _)| merkle::depth_of(**index) == depth));
+        let mut divergence: Option<LevelDivergence> = None;
+        for (index, computed) in &level {
+            let stored = match claimed.get(index) {
+                Some(stored) if stored != computed => stored,
+                _ => continue,
+            };
+            let entry = divergence.get_or_insert_with(|| LevelDivergence {
+                depth,
+                first_index: *index,
+                stored: to_hex(stored),
+                computed: to_hex(computed),
+                mismatches: 0,
+            });
+            entry.mismatches += 1;
+        }
+        report.divergent_levels.extend(divergence);
+
+        if depth == root_depth {
+            break;
+        }
+        // Fold every node with its sibling, missing siblings are empty subtrees
+        let mut parents = BTreeMap::new();
+        for &index in level.keys() {
+            let parent = (index - 1) / 2;
+            if parents.contains_key(&parent) {
+                continue;
+            }
+            let sibling_of = |child: u64| level.get(&child).unwrap_or(&default_hashes[depth]);
+            let left = sibling_of(merkle::left_child_index(parent));
+            let right = sibling_of(merkle::right_child_index(parent));
+            parents.insert(parent, merkle::hash_children(left, right));
+        }
+        level = parents;
+        depth -= 1;
+    }
+
+    let computed_root = level.get(&root_index).copied().unwrap_or(default_hashes[root_depth]);
+    report.computed_root = to_hex(&computed_root);
+    report.matches = computed_root == root;
+    Ok(report)
+}
+
+/// Scans merkle_records for nodes whose left or right child is `hash`, passing each to `on_parent`
+///
+/// A hash can have several parents, for example identical subtrees of different trees or
+/// versions.
+pub fn find_parents<F>(
+    db: &DbHandle,
+    hash: [u8; 32],
+    mut on_parent: F,
+) -> Result<ParentSummary, Box<dyn std::error::Error>>
+where
+    F: FnMut(ParentEntry) -> Result<(), Box<dyn std::error::Error>>,
+{
+    let cf = db.cf(MERKLE_CF_NAME)?;
+
+    let mut summary = ParentSummary {
+        hash: to_hex(&hash),
+        scanned: 0,
+        parents: 0,
+        undecodable_nodes: 0,
+    };
+    for item in db.iterator_cf_opt(cf, db.read_options(), rocksdb::IteratorMode::Start) {
+        let (key, value) = item?;
+        summary.scanned += 1;
+        let record = match MerkleRecord::from_slice(&value) {
+            Ok(record) => record,
+            Err(_) => {
+                summary.undecodable_nodes += 1;
+                continue;
+            }
+        };
+
+        let side = if record.left == Some(hash) {
+            "left"
+        } else if record.right == Some(hash) {
+            "right"
+        } else {
+            continue;
+        };
+        summary.parents += 1;
+        on_parent(ParentEntry {
+            hash: to_hex(&key),
+            index: record.index,
+            side,
+        })?;
+    }
+
+    Ok(summary)
+}
+
+/// Copies the nodes of the tree below `root` and the data records of its leaves into a new
+/// database at `destination`, a minimal dataset reproducing that tree
+///
+/// `destination` must not exist yet. Nodes shared by several branches are copied once. Referenced
+/// nodes and data records that are not stored are counted, unstored nodes holding the empty
+/// subtree hash of their depth as defaults, and nodes that fail to decode are copied without
+/// descending into them.
+pub fn export_subtree(
+    db: &DbHandle,
+    root: [u8; 32],
+    destination: &Path,
+) -> Result<SubtreeSummary, Box<dyn std::error::Error>> {
+    if destination.exists() {
+        return Err(format!("Destination {} already exists", destination.display()).into());
+    }
+    let merkle_cf = db.cf(MERKLE_CF_NAME)?;
+    let data_cf = db.cf(DATA_CF_NAME)?;
+    // An undecodable root is still copied, nothing is walked below it
+    let root_index = match db.get_cf_opt(merkle_cf, root, &db.read_options())? {
+        Some(value) => MerkleRecord::from_slice(&value).map_or(0, |record| record.index),
+        None => return Err(format!("Root {} not found", to_hex(&root)).into()),
+    };
+    let defaults = merkle::default_hashes()?;
+
+    let target = DbHandle::open_read_write(destination, &DEFAULT_CF_NAMES)?;
+    let target_merkle_cf = target.cf(MERKLE_CF_NAME)?;
+    let target_data_cf = target.cf(DATA_CF_NAME)?;
+
+    let mut summary = SubtreeSummary {
+        root: to_hex(&root),
+        destination: destination.display().to_string(),
+        nodes: 0,
+        leaves: 0,
+        data_records: 0,
+        bytes: 0,
+        default_nodes: 0,
+        missing_nodes: 0,
+        missing_data: 0,
+        undecodable_nodes: 0,
+    };
+    let read_opts = db.read_options();
+    let mut visited = HashSet::new();
+    // (hash, position below the root)
+    let mut stack = vec![(root, root_index)];
+    let mut batch = WriteBatch::default();
+    while let Some((hash, index)) = stack.pop() {
+        let value = match db.get_cf_opt(merkle_cf, hash, &read_opts)? {
+            Some(value) => value,
+            None if merkle::is_default(&defaults, index, &hash) => {
+                summary.default_nodes += 1;
+                continue;
+            }
+            None => {
+                summary.missing_nodes += 1;
+                continue;
+            }
+        };
+        if !visited.insert(hash) {
+            continue;
+        }
+        summary.nodes += 1;
+        summary.bytes += (hash.len() + value.len()) as u64;
+        batch.put_cf(target_merkle_cf, hash, &value);
+
+        match MerkleRecord::from_slice(&value) {
+            Ok(record) => match (record.left, record.right, record.data) {
+                (Some(left), Some(right), _) if merkle::depth_of(index) < merkle::MERKLE_DEPTH => {
+                    stack.push((right, merkle::right_child_index(index)));
+                    stack.push((left, merkle::left_child_index(index)));
+                }
+                (None, None, Some(data_hash)) => {
+                    summary.leaves += 1;
+                    match db.get_cf_opt(data_cf, data_hash, &read_opts)? {
+                        Some(data) => {
+                            summary.data_records += 1;
+                            summary.bytes += (data_hash.len() + data.len()) as u64;
+                            batch.put_cf(target_data_cf, data_hash, data);
+                        }
+                        None => summary.missing_data += 1,
+                    }
+                }
+                _ => {}
+            },
+            Err(_) => summary.undecodable_nodes += 1,
+        }
+
+        if batch.len() >= SUBTREE_BATCH_SIZE {
+            target.write(std::mem::take(&mut batch))?;
+        }
+    }
+    if !batch.is_empty() {
+        target.write(batch)?;
+    }
+
+    Ok(summary)
+}
+
+/// Copies the tree below `root` in `source` and the data records of its leaves into `target`,
+/// typically a subtree written by [`export_subtree`] grafted into a test database
+///
+/// Without `root` the node with the lowest index in `source` is taken as the root. With
+/// `new_root_index` the root is moved to that index and every node below it to the matching
+/// position, which must lie at the same depth so that leaves stay leaves. Hashes do not depend
+/// on indices, so only the stored indices change. Records already in `target` are overwritten.
+///
+/// Nodes are placed by their position below the root rather than the index stored in them, which
+/// may belong to another tree sharing the node. Nothing below `MERKLE_DEPTH` is copied. Unstored
+/// nodes are counted, as defaults when they hold the empty subtree hash of their depth.
+pub fn import_subtree(
+    source: &DbHandle,
+    target: &DbHandle,
+    root: Option<[u8; 32]>,
+    new_root_index: Option<u64>,
+) -> Result<SubtreeImportSummary, Box<dyn std::error::Error>> {
+    let merkle_cf = source.cf(MERKLE_CF_NAME)?;
+    let data_cf = source.cf(DATA_CF_NAME)?;
+    let target_merkle_cf = target.cf(MERKLE_CF_NAME)?;
+    let target_data_cf = target.cf(DATA_CF_NAME)?;
+
+    let root = match root {
+        Some(root) => root,
+        None => lowest_index_node(source)?.ok_or("The source holds no merkle records")?,
+    };
+    let root_index = merkle::get_record(source, merkle_cf, &root)?
+        .ok_or_else(|| format!("Root {} not found", to_hex(&root)))?
+        .index;
+    if merkle::depth_of(root_index) > merkle::MERKLE_DEPTH {
+        return Err(format!(
+            "Root {} has index {}, below the leaves at depth {}",
+            to_hex(&root),
+            root_index,
+            merkle::MERKLE_DEPTH
+        )
+        .into());
+    }
+    if let Some(new_root_index) = new_root_index {
+        if merkle::depth_of(new_root_index) != merkle::depth_of(root_index) {
+            return Err(format!(
+                "Index {} is at depth {}, the subtree root at index {} is at depth {}",
+                new_root_index,
+                merkle::depth_of(new_root_index),
+                root_index,
+                merkle::depth_of(root_index)
+            )
+            .into());
+        }
+    }
+
+    let mut summary = SubtreeImportSummary {
+        root: to_hex(&root),
+        root_index,
+        new_root_index: new_root_index.unwrap_or(root_index),
+        nodes: 0,
+        data_records: 0,
+        default_nodes: 0,
+        missing_nodes: 0,
+        overwritten: 0,
+    };
+    let defaults = merkle::default_hashes()?;
+    let read_opts = source.read_options();
+    let target_read_opts = target.read_options();
+    let mut visited = HashSet::new();
+    // (hash, position below the root)
+    let mut stack = vec![(root, root_index)];
+    let mut batch = WriteBatch::default();
+    while let Some((hash, index)) = stack.pop() {
+        let mut record = match merkle::get_record(source, merkle_cf, &hash)? {
+            Some(record) => record,
+            None if merkle::is_default(&defaults, index, &hash) => {
+                summary.default_nodes += 1;
+                continue;
+            }
+            None => {
+                summary.missing_nodes += 1;
+                continue;
+            }
+        };
+        if !visited.insert(hash) {
+            continue;
+        }
+        if let Some(new_root_index) = new_root_index {
+            record.index = remap_index(index, root_index, new_root_index)?;
+        }
+        let value = record.to_slice();
+        match target.get_pinned_cf_opt(target_merkle_cf, hash, &target_read_opts)? {
+            Some(existing) if *existing != *value => summary.overwritten += 1,
+            _ => {}
+        }
+        batch.put_cf(target_merkle_cf, hash, value);
+        summary.nodes += 1;
+
+        match (record.left, record.right, record.data) {
+            (Some(left), Some(right), _) if merkle::depth_of(index) < merkle::MERKLE_DEPTH => {
+                stack.push((right, merkle::right_child_index(index)));
+                stack.push((left, merkle::left_child_index(index)));
+            }
+            (None, None, Some(data_hash)) => {
+                if let Some(data) = source.get_cf_opt(data_cf, data_hash, &read_opts)? {
+                    batch.put_cf(target_data_cf, data_hash, data);
+                    summary.data_records += 1;
+                }
+            }
+            _ => {}
+        }
+
+        if batch.len() >= SUBTREE_BATCH_SIZE {
+            target.write(std::mem::take(&mut batch))?;
+        }
+    }
+    if !batch.is_empty() {
+        target.write(batch)?;
+    }
+
+    Ok(summary)
+}
+
+/// Moves the node at `index` below the subtree root at `root_index` to the same position below
+/// `new_root_index`: same depth below the root, same offset within that level
+fn remap_index(index: u64, root_index: u64, new_root_index: u64) -> Result<u64, String> {
+    let out_of_range = || {
+        format!(
+            "Node at index {} cannot be moved from below index {} to below index {}",
+            index, root_index, new_root_index
+        )
+    };
+    let depth = merkle::depth_of(index)
+        .checked_sub(merkle::depth_of(root_index))
+        .ok_or_else(|| format!("Node at index {} lies above the subtree root", index))?;
+    // The level `depth` below a root starts at ((root + 1) << depth) - 1 with 1 << depth nodes
+    let width = 1u64.checked_shl(depth as u32).ok_or_else(out_of_range)?;
+    let level_start = |root: u64| root.checked_add(1)?.checked_mul(width)?.checked_sub(1);
+    let position = level_start(root_index)
+        .and_then(|start| index.checked_sub(start))
+        .filter(|position| *position < width)
+        .ok_or_else(out_of_range)?;
+    level_start(new_root_index)
+        .and_then(|start| start.checked_add(position))
+        .ok_or_else(out_of_range)
+}
+
+/// Hash of the merkle record with the lowest index, the root of an exported subtree
+fn lowest_index_node(db: &DbHandle) -> Result<Option<[u8; 32]>, Box<dyn std::error::Error>> {
+    let cf = db.cf(MERKLE_CF_NAME)?;
+    let mut lowest: Option<(u64, [u8; 32])> = None;
+    for item in db.iterator_cf_opt(cf, db.read_options(), rocksdb::IteratorMode::Start) {
+        let (_, value) = item?;
+        if let Ok(record) = MerkleRecord::from_slice(&value) {
+            if lowest.map_or(true, |(index, _)| record.index < index) {
+                lowest = Some((record.index, record.hash));
+            }
+        }
+    }
+    Ok(lowest.map(|(_, hash)| hash))
+}
+
+/// Checks that the data hash of every merkle leaf is stored in data_records and that every
+/// data_records entry is the data of some leaf, passing dangling references to `on_issue`
+///
+/// Leaves of every tree in merkle_records are considered. Memory use grows with the number of
+/// leaves.
+pub fn cross_check<F>(
+    db: &DbHandle,
+    mut on_issue: F,
+) -> Result<CrossCheckSummary, Box<dyn std::error::Error>>
+where
+    F: FnMut(CrossCheckIssue) -> Result<(), Box<dyn std::error::Error>>,
+{
+    let merkle_cf = db.cf(MERKLE_CF_NAME)?;
+    let data_cf = db.cf(DATA_CF_NAME)?;
+
+    let mut summary = CrossCheckSummary {
+        leaves: 0,
+        data_records: 0,
+        undecodable_nodes: 0,
+        dangling_leaves: 0,
+        unreferenced_data: 0,
+    };
+    let read_opts = db.read_options();
+    let mut referenced = HashSet::new();
+    for item in db.iterator_cf_opt(merkle_cf, db.read_options(), rocksdb::IteratorMode::Start) {
+        let (_, value) = item?;
+        let record = match MerkleRecord::from_slice(&value) {
+            Ok(record) => record,
+            Err(_) => {
+                // Reported by validate-records, the leaf data is unknown
+                summary.undecodable_nodes += 1;
+                continue;
+            }
+        };
+        let data_hash = match (record.left, record.right, record.data) {
+            (None, None, Some(data_hash)) => data_hash,
+            _ => continue,
+        };
+        summary.leaves += 1;
+
+        if db.get_pinned_cf_opt(data_cf, data_hash, &read_opts)?.is_none() {
+            summary.dangling_leaves += 1;
+            on_issue(CrossCheckIssue::DanglingLeaf {
+                index: record.index,
+                leaf_hash: to_hex(&record.hash),
+                data_hash: to_hex(&data_hash),
+            })?;
+        }
+        referenced.insert(data_hash);
+    }
+
+    for item in db.iterator_cf_opt(data_cf, db.read_options(), rocksdb::IteratorMode::Start) {
+        let (key, _) = item?;
+        summary.data_records += 1;
+
+        let is_referenced =
+            <[u8; 32]>::try_from(&*key).map_or(false, |hash| referenced.contains(&hash));
+        if !is_referenced {
+            summary.unreferenced_data += 1;
+            on_issue(CrossCheckIssue::UnreferencedData {
+                data_hash: to_hex(&key),
+            })?;
+        }
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn put_record(db: &DbHandle, record: &MerkleRecord) {
+        let cf = db.cf(MERKLE_CF_NAME).unwrap();
+        db.put_cf(cf, record.hash, record.to_slice()).unwrap();
+    }
+
+    /// Stores a tree holding `leaf` at `leaf_index` and empty subtrees elsewhere, returns its root
+    fn store_single_leaf_tree(db: &DbHandle, leaf_index: u64, leaf: [u8; 32]) -> [u8; 32] {
+        let defaults = merkle::default_hashes().unwrap();
+        put_record(
+            db,
+            &MerkleRecord {
+                index: leaf_index,
+                hash: leaf,
+                left: None,
+                right: None,
+                data: Some(leaf),
+            },
+        );
+        let mut index = leaf_index;
+        let mut hash = leaf;
+        while index > 0 {
+            let parent = (index - 1) / 2;
+            let sibling = defaults[merkle::depth_of(index)];
+            let (left, right) = if index == merkle::left_child_index(parent) {
+                (hash, sibling)
+            } else {
+                (sibling, hash)
+            };
+            hash = merkle::hash_children(&left, &right);
+            put_record(
+                db,
+                &MerkleRecord {
+                    index: parent,
+                    hash,
+                    left: Some(left),
+                    right: Some(right),
+                    data: None,
+                },
+            );
+            index = parent;
+        }
+        hash
+    }
+
+    #[test]
+    fn recompute_root_matches_single_leaf_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DbHandle::open_read_write(dir.path(), &DEFAULT_CF_NAMES).unwrap();
+        let leaf_index = *merkle::leaf_index_range().start() + 5;
+        let root = store_single_leaf_tree(&db, leaf_index, [7; 32]);
+
+        let report = recompute_root(&db, root, None).unwrap();
+        assert_eq!(report.root_index, 0);
+        assert_eq!(report.leaves, 1);
+        // Every sibling on the path to the leaf is an unstored empty subtree
+        assert_eq!(report.default_nodes, merkle::MERKLE_DEPTH as u64);
+        assert_eq!(report.missing_nodes, 0);
+        assert!(report.matches);
+        assert!(report.divergent_levels.is_empty());
+    }
+
+    #[test]
+    fn recompute_root_without_leaves_is_the_default_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DbHandle::open_read_write(dir.path(), &DEFAULT_CF_NAMES).unwrap();
+        let leaf_index = *merkle::leaf_index_range().end();
+        let root = store_single_leaf_tree(&db, leaf_index, [7; 32]);
+
+        let report = recompute_root(&db, root, Some(Vec::new())).unwrap();
+        let defaults = merkle::default_hashes().unwrap();
+        assert_eq!(report.computed_root, to_hex(&defaults[0]));
+        assert_eq!(report.leaves, 0);
+        assert!(!report.matches);
+    }
+
+    #[test]
+    fn recompute_root_reports_the_leaf_level_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DbHandle::open_read_write(dir.path(), &DEFAULT_CF_NAMES).unwrap();
+        let leaf_index = *merkle::leaf_index_range().start() + 1;
+        let root = store_single_leaf_tree(&db, leaf_index, [7; 32]);
+
+        let report = recompute_root(&db, root, Some(vec![(leaf_index, [8; 32])])).unwrap();
+        assert!(!report.matches);
+        // Every node on the path to the changed leaf differs, from the leaf up
+        assert_eq!(report.divergent_levels.len(), merkle::MERKLE_DEPTH + 1);
+        let first = &report.divergent_levels[0];
+        assert_eq!(first.depth, merkle::MERKLE_DEPTH);
+        assert_eq!(first.first_index, leaf_index);
+        assert_eq!(first.mismatches, 1);
+        assert_eq!(report.divergent_levels.last().unwrap().depth, 0);
+    }
+
+    #[test]
+    fn recompute_root_rejects_leaves_outside_the_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DbHandle::open_read_write(dir.path(), &DEFAULT_CF_NAMES).unwrap();
+        let root = store_single_leaf_tree(&db, *merkle::leaf_index_range().start(), [7; 32]);
+
+        assert!(recompute_root(&db, root, Some(vec![(u64::MAX, [1; 32])])).is_err());
+        assert!(recompute_root(&db, root, Some(vec![(3, [1; 32])])).is_err());
+    }
+
+    #[test]
+    fn remap_index_keeps_the_position_below_the_root() {
+        // Depth 0, the root itself
+        assert_eq!(remap_index(5, 5, 6), Ok(6));
+        // Depth 1, the right child of 1 becomes the right child of 2
+        assert_eq!(remap_index(4, 1, 2), Ok(6));
+        assert_eq!(remap_index(3, 1, 2), Ok(5));
+        // Depth 32, the leaves below the root keep their offset
+        let leaves = merkle::leaf_index_range();
+        assert_eq!(remap_index(*leaves.start() + 9, 0, 0), Ok(*leaves.start() + 9));
+        assert_eq!(remap_index(*leaves.end(), 0, 0), Ok(*leaves.end()));
+        // Depth 31 below a depth 1 root, to the other half of the leaves
+        let half = 1u64 << (merkle::MERKLE_DEPTH - 1);
+        assert_eq!(remap_index(*leaves.start() + 3, 1, 2), Ok(*leaves.start() + half + 3));
+    }
+
+    #[test]
+    fn remap_index_rejects_nodes_outside_the_subtree() {
+        // Above the root
+        assert!(remap_index(0, 1, 2).is_err());
+        // Below the sibling of the root
+        assert!(remap_index(5, 1, 2).is_err());
+        // Nodes 64 levels down do not fit in a u64 heap index
+        assert!(remap_index(u64::MAX, 0, 0).is_err());
+        assert!(remap_index(u64::MAX, (1 << 63) - 1, u64::MAX - 1).is_err());
+    }
+
+    #[test]
+    fn recompute_root_rejects_a_root_below_leaf_depth() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DbHandle::open_read_write(dir.path(), &DEFAULT_CF_NAMES).unwrap();
+        let root = [9; 32];
+        put_record(
+            &db,
+            &MerkleRecord {
+                index: u64::MAX,
+                hash: root,
+                left: Some(root),
+                right: Some(root),
+                data: None,
+            },
+        );
+
+        assert!(recompute_root(&db, root, None).is_err());
+    }
+}
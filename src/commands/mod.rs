@@ -0,0 +1,92 @@
+//! Command implementations shared by the CLI and embedding callers
+
+mod analyze;
+mod backup;
+mod batch;
+mod bench;
+mod bounds;
+mod cf;
+mod check;
+mod checksums;
+mod checkpoint;
+mod compact;
+mod compression;
+mod copy;
+mod count;
+mod dblog;
+mod decode;
+mod duplicates;
+mod encode;
+mod exists;
+mod grep;
+mod largest;
+mod merge;
+mod merkle;
+#[cfg(feature = "mongo")]
+mod mongo;
+mod options;
+mod partition;
+mod patch;
+mod probe;
+mod properties;
+mod repair;
+mod resume;
+mod sample;
+mod scan;
+mod selftest;
+mod sst;
+mod stats;
+mod tail;
+mod transfer;
+mod truncate;
+mod ttl;
+mod validate;
+mod wal;
+mod write;
+
+pub use analyze::analyze;
+pub use backup::{backup, list_backups, restore_backup};
+pub use batch::check_batch;
+pub use bench::{bench, bench_write, BenchConfig, WriteBenchConfig, WriteDurability};
+pub use bounds::bounds;
+pub use cf::{create_cf, drop_cf, list_cf};
+pub use check::{check, dump_record_data};
+pub use checksums::verify_checksums;
+pub use checkpoint::checkpoint;
+pub use compact::compact;
+pub use compression::{compression_report, recompress};
+pub use copy::{copy, CopyMethod};
+pub use count::{count, count_approximate};
+pub use dblog::analyze_log;
+pub use decode::decode;
+pub use duplicates::{find_duplicates, MAX_KEYS_PER_GROUP};
+pub use encode::{encode, RecordFields, RecordType};
+pub use exists::exists;
+pub use grep::{grep, ValuePattern};
+pub use largest::largest_values;
+pub use merge::{merge, ConflictPolicy};
+pub use merkle::{
+    cross_check, export_subtree, find_orphans, find_parents, get_leaf, import_subtree,
+    list_leaves, merkle_path, merkle_stats, recompute_root, verify_merkle, LeafSelector,
+};
+#[cfg(feature = "mongo")]
+pub use mongo::{compare_mongo, migrate_from_mongo};
+pub use options::show_options;
+pub use partition::partition;
+pub use patch::{apply_patch, make_patch};
+pub use probe::probe_format;
+pub use properties::{properties, watch_properties};
+pub use repair::repair;
+pub use resume::CheckpointFile;
+pub use sample::sample;
+pub use scan::{prefix_scan, scan};
+pub use selftest::{self_test, SelfTestConfig};
+pub use sst::{export_sst, ingest_sst, sst_info};
+pub use stats::{stats, NUM_LEVELS};
+pub use tail::tail;
+pub use transfer::{export_file, export_parallel, import_file, verify_dump};
+pub use truncate::{truncate, TruncateMethod};
+pub use ttl::{record_ages, set_ttl};
+pub use validate::validate_records;
+pub use wal::{changes, wal_dump};
+pub use write::{delete, delete_range, put};
@@ -0,0 +1,232 @@
+use mongodb::bson::{doc, Bson, Document};
+use mongodb::options::FindOptions;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::db::DbHandle;
+use crate::mongo::MongoSource;
+use crate::output::{to_hex, CompareSummary, MigrationSummary, MongoDiff};
+
+/// Progress of a migration, saved after every batch so an interrupted run can resume
+#[derive(Serialize, Deserialize)]
+struct MigrationCheckpoint {
+    collection: String,
+    column_family: String,
+    /// `_id` of the last written document, in canonical extended JSON
+    last_id: serde_json::Value,
+    migrated: u64,
+}
+
+impl MigrationCheckpoint {
+    fn load(path: &Path) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_slice(&fs::read(path)?)?))
+    }
+
+    /// Writes to a temporary file first so a crash never leaves a truncated checkpoint
+    fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(self)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Diffs a MongoDB collection against a column family and passes every difference to `on_diff`
+///
+/// Every document is converted to the key and value the record is stored under in RocksDB and
+/// looked up, then the column family is scanned for keys the collection does not have. Memory
+/// use grows with the number of documents. `on_progress` is called once per document and once
+/// per RocksDB record with the number of processed items so far.
+pub fn compare_mongo<F, P>(
+    db: &DbHandle,
+    target_cf: &str,
+    source: &MongoSource,
+    mut on_diff: F,
+    mut on_progress: P,
+) -> Result<CompareSummary, Box<dyn std::error::Error>>
+where
+    F: FnMut(MongoDiff) -> Result<(), Box<dyn std::error::Error>>,
+    P: FnMut(u64),
+{
+    let cf = db.cf(target_cf)?;
+    let documents = source.open()?;
+
+    let mut summary = CompareSummary {
+        column_family: target_cf.to_string(),
+        collection: source.collection.to_string(),
+        mongo_records: 0,
+        rocksdb_records: 0,
+        matching: 0,
+        missing_in_rocksdb: 0,
+        extra_in_rocksdb: 0,
+        value_mismatches: 0,
+        invalid_documents: 0,
+    };
+
+    let read_opts = db.read_options();
+    let mut mongo_keys = HashSet::new();
+    for document in documents.find(None, None)? {
+        let document = document?;
+        summary.mongo_records += 1;
+        on_progress(summary.mongo_records);
+
+        let id = document
+            .get("_id")
+            .map_or_else(|| "without _id".to_string(), |id| id.to_string());
+        let (key, mongo_value) = match source.to_key_value(document) {
+            Ok(key_value) => key_value,
+            Err(e) => {
+                summary.invalid_documents += 1;
+                on_diff(MongoDiff::InvalidDocument {
+                    id,
+                    error: e.to_string(),
+                })?;
+                continue;
+            }
+        };
+
+        match db.get_cf_opt(cf, &key, &read_opts)? {
+            None => {
+                summary.missing_in_rocksdb += 1;
+                on_diff(MongoDiff::MissingInRocksDb { key: to_hex(&key) })?;
+            }
+            Some(rocksdb_value) if rocksdb_value != mongo_value => {
+                summary.value_mismatches += 1;
+                on_diff(MongoDiff::ValueMismatch {
+                    key: to_hex(&key),
+                    mongo_value: to_hex(&mongo_value),
+                    rocksdb_value: to_hex(&rocksdb_value),
+                })?;
+            }
+            Some(_) => summary.matching += 1,
+        }
+        mongo_keys.insert(key);
+    }
+
+    for item in db.iterator_cf_opt(cf, db.read_options(), rocksdb::IteratorMode::Start) {
+        let (key, _) = item?;
+        summary.rocksdb_records += 1;
+        on_progress(summary.mongo_records + summary.rocksdb_records);
+        if !mongo_keys.contains(key.as_ref()) {
+            summary.extra_in_rocksdb += 1;
+            on_diff(MongoDiff::ExtraInRocksDb { key: to_hex(&key) })?;
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Copies the records of a MongoDB collection into a column family with batched writes
+///
+/// Documents are read in `_id` order. With `checkpoint_file`, the last written `_id` is saved
+/// after every batch and a later run with the same file continues after it. Once all documents
+/// are written, the collection's document count is compared with the column family's record
+/// count. `on_progress` is called after every batch with the number of documents migrated so far
+/// and the estimated total.
+pub fn migrate_from_mongo<F>(
+    db: &DbHandle,
+    target_cf: &str,
+    source: &MongoSource,
+    batch_size: usize,
+    checkpoint_file: Option<&Path>,
+    mut on_progress: F,
+) -> Result<MigrationSummary, Box<dyn std::error::Error>>
+where
+    F: FnMut(u64, u64),
+{
+    if batch_size == 0 {
+        return Err("Batch size must be greater than zero".into());
+    }
+
+    let cf = db.cf(target_cf)?;
+    let collection = source.collection;
+    let documents = source.open()?;
+
+    let checkpoint = match checkpoint_file {
+        Some(path) => MigrationCheckpoint::load(path)?,
+        None => None,
+    };
+    let (filter, resumed_from) = match &checkpoint {
+        Some(checkpoint) => {
+            if checkpoint.collection != collection || checkpoint.column_family != target_cf {
+                return Err(format!(
+                    "Checkpoint file is for collection '{}' into column family '{}'",
+                    checkpoint.collection, checkpoint.column_family
+                )
+                .into());
+            }
+            let last_id = Bson::try_from(checkpoint.last_id.clone())?;
+            (Some(doc! { "_id": { "$gt": last_id } }), checkpoint.migrated)
+        }
+        None => (None, 0),
+    };
+
+    let total = documents.estimated_document_count(None)?;
+    let options = FindOptions::builder()
+        .sort(doc! { "_id": 1 })
+        .batch_size(batch_size as u32)
+        .build();
+
+    let mut migrated = resumed_from;
+    let mut last_id = None;
+    let mut batch = rocksdb::WriteBatch::default();
+    let mut write_batch = |batch: rocksdb::WriteBatch,
+                           last_id: &Option<Bson>,
+                           migrated: u64|
+     -> Result<(), Box<dyn std::error::Error>> {
+        db.write(batch)?;
+        if let (Some(path), Some(last_id)) = (checkpoint_file, last_id) {
+            MigrationCheckpoint {
+                collection: collection.to_string(),
+                column_family: target_cf.to_string(),
+                last_id: last_id.clone().into_canonical_extjson(),
+                migrated,
+            }
+            .save(path)?;
+        }
+        on_progress(migrated, total);
+        Ok(())
+    };
+
+    for document in documents.find(filter, options)? {
+        let document: Document = document?;
+        let id = document.get("_id").cloned();
+        let (key, value) = source.to_key_value(document).map_err(|e| {
+            let id = id.as_ref().map_or_else(|| "without _id".to_string(), Bson::to_string);
+            format!("Invalid MongoDB document {}: {}", id, e)
+        })?;
+        batch.put_cf(cf, key, value);
+        migrated += 1;
+        last_id = id;
+
+        if batch.len() >= batch_size {
+            write_batch(std::mem::take(&mut batch), &last_id, migrated)?;
+        }
+    }
+    if !batch.is_empty() {
+        write_batch(batch, &last_id, migrated)?;
+    }
+
+    // The collection is counted again, documents may have been added while migrating
+    let mongo_documents = documents.count_documents(None, None)?;
+    let mut rocksdb_records = 0u64;
+    for item in db.iterator_cf_opt(cf, db.read_options(), rocksdb::IteratorMode::Start) {
+        item?;
+        rocksdb_records += 1;
+    }
+
+    Ok(MigrationSummary {
+        collection: collection.to_string(),
+        column_family: target_cf.to_string(),
+        migrated,
+        resumed_from,
+        mongo_documents,
+        rocksdb_records,
+        verified: mongo_documents == rocksdb_records,
+    })
+}
@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::db;
+use crate::output::{CfOptionsInfo, OptionsReport};
+
+/// Parses the latest OPTIONS file of a database, optionally keeping a single column family
+///
+/// The file is an ini file with a `[DBOptions]` section, then a `[CFOptions "<name>"]` and a
+/// `[TableOptions/<table factory> "<name>"]` section per column family.
+pub fn show_options(
+    db_path: &Path,
+    target_cf: Option<&str>,
+) -> Result<OptionsReport, Box<dyn std::error::Error>> {
+    let file = db::latest_options_file(db_path)?
+        .ok_or_else(|| format!("No OPTIONS file found in {}", db_path.display()))?;
+    let contents = fs::read_to_string(&file)?;
+
+    let mut db_options = BTreeMap::new();
+    let mut column_families: Vec<CfOptionsInfo> = Vec::new();
+    // Section the following options belong to: DB options, or a column family and whether table options
+    let mut section: Option<(Option<usize>, bool)> = None;
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            let (kind, name) = match header.split_once(' ') {
+                Some((kind, name)) => (kind, Some(name.trim_matches('"'))),
+                None => (header, None),
+            };
+            section = match (kind, name) {
+                ("DBOptions", _) => Some((None, false)),
+                ("CFOptions", Some(name)) => {
+                    column_families.push(CfOptionsInfo {
+                        name: name.to_string(),
+                        options: BTreeMap::new(),
+                        table_options: BTreeMap::new(),
+                    });
+                    Some((Some(column_families.len() - 1), false))
+                }
+                (kind, Some(name)) if kind.starts_with("TableOptions/") => column_families
+                    .iter()
+                    .position(|cf| cf.name == name)
+                    .map(|index| (Some(index), true)),
+                // The [Version] section and anything unknown
+                _ => None,
+            };
+            continue;
+        }
+
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim().to_string(), value.trim().to_string()),
+            None => continue,
+        };
+        match section {
+            Some((None, _)) => {
+                db_options.insert(key, value);
+            }
+            Some((Some(index), false)) => {
+                column_families[index].options.insert(key, value);
+            }
+            Some((Some(index), true)) => {
+                column_families[index].table_options.insert(key, value);
+            }
+            None => {}
+        }
+    }
+
+    if let Some(target_cf) = target_cf {
+        column_families.retain(|cf| cf.name == target_cf);
+        if column_families.is_empty() {
+            return Err(crate::Error::MissingColumnFamily(target_cf.to_string()).into());
+        }
+    }
+
+    Ok(OptionsReport {
+        file: file.display().to_string(),
+        db_options,
+        column_families,
+    })
+}
@@ -0,0 +1,79 @@
+use crate::db::DbHandle;
+use crate::field::to_u64_limbs;
+use crate::output::{PartitionBucket, PartitionReport};
+
+/// Splits the keyspace of a column family into `buckets` equal ranges of one u64 limb of the key
+/// and reports the records and bytes falling in each, to plan sharding a store across databases
+///
+/// `limb` indexes the little-endian `[u64; 4]` form of 32-byte keys. Keys of any other length
+/// cannot be placed and are only counted.
+pub fn partition(
+    db: &DbHandle,
+    target_cf: &str,
+    limb: usize,
+    buckets: usize,
+) -> Result<PartitionReport, Box<dyn std::error::Error>> {
+    if limb >= 4 {
+        return Err(format!("Limb must be between 0 and 3, got {}", limb).into());
+    }
+    if buckets == 0 {
+        return Err("Number of buckets must be greater than zero".into());
+    }
+    let cf = db.cf(target_cf)?;
+
+    let mut report = PartitionReport {
+        column_family: target_cf.to_string(),
+        limb,
+        records: 0,
+        bytes: 0,
+        other_keys: 0,
+        other_bytes: 0,
+        buckets: (0..buckets)
+            .map(|index| {
+                let (first_limb, last_limb) = bucket_limbs(index, buckets);
+                PartitionBucket {
+                    index,
+                    first_limb,
+                    last_limb,
+                    records: 0,
+                    key_bytes: 0,
+                    value_bytes: 0,
+                }
+            })
+            .collect(),
+    };
+
+    for item in db.iterator_cf_opt(cf, db.read_options(), rocksdb::IteratorMode::Start) {
+        let (key, value) = item?;
+        let size = (key.len() + value.len()) as u64;
+        report.records += 1;
+        report.bytes += size;
+        let key: &[u8; 32] = match key.as_ref().try_into() {
+            Ok(key) => key,
+            Err(_) => {
+                report.other_keys += 1;
+                report.other_bytes += size;
+                continue;
+            },
+        };
+        let value_of_limb = to_u64_limbs(key)[limb];
+        let bucket = &mut report.buckets[bucket_of(value_of_limb, buckets)];
+        bucket.records += 1;
+        bucket.key_bytes += key.len() as u64;
+        bucket.value_bytes += value.len() as u64;
+    }
+
+    Ok(report)
+}
+
+/// Index of the bucket holding `limb` when the u64 range is split into `buckets` equal parts
+fn bucket_of(limb: u64, buckets: usize) -> usize {
+    ((limb as u128 * buckets as u128) >> 64) as usize
+}
+
+/// First and last limb value of bucket `index`
+fn bucket_limbs(index: usize, buckets: usize) -> (u64, u64) {
+    // Smallest limb value mapped to bucket i by `bucket_of`
+    let start = |i: usize| (((i as u128) << 64) + buckets as u128 - 1) / buckets as u128;
+    (start(index) as u64, (start(index + 1) - 1) as u64)
+}
@@ -0,0 +1,145 @@
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use crate::db::DbHandle;
+use crate::output::{ApplyPatchSummary, MakePatchSummary};
+use crate::patch::{PatchOp, PatchReader, PatchWriter};
+use crate::range::{self, KeyRange};
+
+/// Writes the puts and deletes turning column family `target_cf` of `old` into that of `new` to
+/// `file`
+///
+/// Both column families are walked once in key order side by side, keys only in `new` or with
+/// another value there become puts, keys only in `old` become deletes. `on_progress` is called
+/// with the number of bytes of every record read.
+pub fn make_patch<P>(
+    old: &DbHandle,
+    new: &DbHandle,
+    target_cf: &str,
+    file: &Path,
+    mut on_progress: P,
+) -> Result<MakePatchSummary, Box<dyn std::error::Error>>
+where
+    P: FnMut(usize),
+{
+    if file.exists() {
+        return Err(format!("{} already exists", file.display()).into());
+    }
+    let old_cf = old.cf(target_cf)?;
+    let new_cf = new.cf(target_cf)?;
+
+    let mut writer = PatchWriter::new(BufWriter::new(File::create(file)?), target_cf)?;
+    let mut summary = MakePatchSummary {
+        column_family: target_cf.to_string(),
+        file: file.display().to_string(),
+        puts: 0,
+        deletes: 0,
+        unchanged: 0,
+        bytes: 0,
+    };
+
+    let mut old_iter = range::iter_range(old, old_cf, &KeyRange::default(), false);
+    let mut new_iter = range::iter_range(new, new_cf, &KeyRange::default(), false);
+    let mut old_next = old_iter.next().transpose()?;
+    let mut new_next = new_iter.next().transpose()?;
+    loop {
+        let order = match (&old_next, &new_next) {
+            (None, None) => break,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some((old_key, _)), Some((new_key, _))) => old_key.cmp(new_key),
+        };
+        match order {
+            Ordering::Less => {
+                let (key, value) = old_next.take().expect("old record present");
+                writer.delete(&key)?;
+                summary.deletes += 1;
+                on_progress(key.len() + value.len());
+                old_next = old_iter.next().transpose()?;
+            },
+            Ordering::Greater => {
+                let (key, value) = new_next.take().expect("new record present");
+                writer.put(&key, &value)?;
+                summary.puts += 1;
+                on_progress(key.len() + value.len());
+                new_next = new_iter.next().transpose()?;
+            },
+            Ordering::Equal => {
+                let (_, old_value) = old_next.take().expect("old record present");
+                let (key, value) = new_next.take().expect("new record present");
+                if old_value != value {
+                    writer.put(&key, &value)?;
+                    summary.puts += 1;
+                } else {
+                    summary.unchanged += 1;
+                }
+                on_progress(key.len() + value.len());
+                old_next = old_iter.next().transpose()?;
+                new_next = new_iter.next().transpose()?;
+            },
+        }
+    }
+
+    writer.finish()?;
+    summary.bytes = std::fs::metadata(file)?.len();
+    Ok(summary)
+}
+
+/// Applies a patch written by [`make_patch`] to `target_cf`, in WriteBatches of `batch_size`
+/// operations
+///
+/// `target_cf` defaults to the column family the patch was made from. The whole patch is read and
+/// checked before anything is written, so a damaged or truncated patch leaves the database
+/// untouched. Without a database handle the patch is only checked and counted, which is how dry
+/// runs are done.
+pub fn apply_patch(
+    db: Option<&DbHandle>,
+    target_cf: Option<&str>,
+    file: &Path,
+    batch_size: usize,
+) -> Result<ApplyPatchSummary, Box<dyn std::error::Error>> {
+    if batch_size == 0 {
+        return Err("Batch size must be greater than zero".into());
+    }
+
+    let open = || -> Result<_, Box<dyn std::error::Error>> {
+        Ok(PatchReader::new(BufReader::new(File::open(file)?))?)
+    };
+    let mut summary = ApplyPatchSummary {
+        column_family: String::new(),
+        file: file.display().to_string(),
+        puts: 0,
+        deletes: 0,
+        dry_run: db.is_none(),
+    };
+
+    // First pass, checks every checksum and the trailer
+    let reader = open()?;
+    summary.column_family = target_cf.unwrap_or(reader.column_family()).to_string();
+    for op in reader {
+        match op? {
+            PatchOp::Put { .. } => summary.puts += 1,
+            PatchOp::Delete { .. } => summary.deletes += 1,
+        }
+    }
+
+    if let Some(db) = db {
+        let cf = db.cf(&summary.column_family)?;
+        let mut batch = rocksdb::WriteBatch::default();
+        for op in open()? {
+            match op? {
+                PatchOp::Put { key, value } => batch.put_cf(cf, key, value),
+                PatchOp::Delete { key } => batch.delete_cf(cf, key),
+            }
+            if batch.len() >= batch_size {
+                db.write(std::mem::take(&mut batch))?;
+            }
+        }
+        if !batch.is_empty() {
+            db.write(batch)?;
+        }
+    }
+    Ok(summary)
+}
@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+use zkwasm_host_circuits::host::{datahash::DataHashRecord, mongomerkle::MerkleRecord};
+
+use crate::commands::sample::sample_records;
+use crate::db::DbHandle;
+use crate::output::{to_hex, FormatProbe};
+use crate::record::DecoderRegistry;
+
+/// Largest leaf index of the depth 32 trees mongomerkle stores
+const MAX_MERKLE_INDEX: u64 = (1 << 33) - 2;
+
+/// Value lengths listed per column family, the most frequent first
+const TOP_LENGTHS: usize = 5;
+
+/// Outcome of checking one sampled value against the layout of a record type
+enum Check {
+    /// Decodes, encodes back to the same bytes and has sane fields
+    Valid,
+    /// Decodes, but the fields or the re-encoded bytes are off
+    Suspicious(String),
+    Undecodable(String),
+}
+
+fn check_merkle(key: &[u8], value: &[u8]) -> Check {
+    let record = match MerkleRecord::from_slice(value) {
+        Ok(record) => record,
+        Err(e) => return Check::Undecodable(e.to_string()),
+    };
+    let leaf = record.left.is_none() && record.right.is_none();
+    let inner = record.left.is_some() && record.right.is_some() && record.data.is_none();
+    if record.to_slice() != value {
+        Check::Suspicious("encoding the decoded record gives other bytes".to_string())
+    } else if record.hash[..] != *key {
+        Check::Suspicious(format!("hash {} differs from the key", to_hex(&record.hash)))
+    } else if record.index > MAX_MERKLE_INDEX {
+        Check::Suspicious(format!("index {} is outside a depth 32 tree", record.index))
+    } else if !leaf && !inner {
+        Check::Suspicious("node has a single child or both children and data".to_string())
+    } else {
+        Check::Valid
+    }
+}
+
+fn check_data(key: &[u8], value: &[u8]) -> Check {
+    let record = match DataHashRecord::from_slice(value) {
+        Ok(record) => record,
+        Err(e) => return Check::Undecodable(e.to_string()),
+    };
+    if record.to_slice() != value {
+        Check::Suspicious("encoding the decoded record gives other bytes".to_string())
+    } else if record.hash[..] != *key {
+        Check::Suspicious(format!("hash {} differs from the key", to_hex(&record.hash)))
+    } else {
+        Check::Valid
+    }
+}
+
+/// Samples records of each column family and checks them against the record layout of the linked
+/// zkwasm-host-circuits version, passing one [`FormatProbe`] per column family to `on_probe`
+///
+/// Column families with a merkle or data decoder are checked as that record type, others against
+/// both, keeping the type more records decode as. Values are sane when they decode, encode back to
+/// the same bytes, carry the hash they are keyed by and, for merkle records, have a leaf index of a
+/// depth 32 tree and the shape of a leaf or an inner node. A column family where most sampled
+/// values fail was likely written by an incompatible version.
+pub fn probe_format<F>(
+    db: &DbHandle,
+    cf_names: &[String],
+    decoders: &DecoderRegistry,
+    sample_size: usize,
+    seed: u64,
+    mut on_probe: F,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: FnMut(FormatProbe) -> Result<(), Box<dyn std::error::Error>>,
+{
+    if sample_size == 0 {
+        return Err("Sample size must be greater than 0".into());
+    }
+
+    for name in cf_names {
+        let mut records = Vec::new();
+        sample_records(db, name, sample_size, seed, |key, value| {
+            records.push((key.to_vec(), value.to_vec()));
+            Ok(())
+        })?;
+
+        let registered = decoders.for_cf(name).name();
+        let record_types: &[&'static str] = match registered {
+            "merkle" => &["merkle"],
+            "data" => &["data"],
+            _ => &["merkle", "data"],
+        };
+        let probes = record_types.iter().map(|&record_type| {
+            let check = if record_type == "merkle" { check_merkle } else { check_data };
+            let mut probe = FormatProbe::new(name, record_type, records.len());
+            for (key, value) in &records {
+                match check(key, value) {
+                    Check::Valid => probe.valid += 1,
+                    Check::Suspicious(reason) => {
+                        probe.suspicious += 1;
+                        probe.first_problem.get_or_insert(reason);
+                    },
+                    Check::Undecodable(reason) => {
+                        probe.undecodable += 1;
+                        probe.first_problem.get_or_insert(reason);
+                    },
+                }
+            }
+            probe
+        });
+        let mut probe = probes
+            .max_by_key(|probe| probe.records - probe.undecodable)
+            .expect("at least one record type is checked");
+
+        let mut lengths = BTreeMap::new();
+        for (_, value) in &records {
+            *lengths.entry(value.len()).or_insert(0u64) += 1;
+        }
+        let mut lengths: Vec<(usize, u64)> = lengths.into_iter().collect();
+        lengths.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        lengths.truncate(TOP_LENGTHS);
+        probe.value_lengths = lengths;
+
+        let failed = probe.suspicious + probe.undecodable;
+        probe.verdict = if probe.records == 0 {
+            "empty"
+        } else if failed == 0 {
+            "compatible"
+        } else if probe.valid == 0 && !record_types.contains(&registered) {
+            // Neither record type fits, the column family holds something else
+            "unknown"
+        } else if failed * 2 > probe.records {
+            "incompatible"
+        } else {
+            "mixed"
+        };
+
+        on_probe(probe)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use super::NUM_LEVELS;
+use crate::db::DbHandle;
+use crate::output::{CfProperties, PropertiesReport};
+
+/// Integer properties RocksDB reports per column family, without their `rocksdb.` prefix
+const INT_PROPERTIES: &[&str] = &[
+    "num-immutable-mem-table",
+    "num-immutable-mem-table-flushed",
+    "mem-table-flush-pending",
+    "num-running-flushes",
+    "compaction-pending",
+    "num-running-compactions",
+    "background-errors",
+    "cur-size-active-mem-table",
+    "cur-size-all-mem-tables",
+    "size-all-mem-tables",
+    "num-entries-active-mem-table",
+    "num-entries-imm-mem-tables",
+    "num-deletes-active-mem-table",
+    "num-deletes-imm-mem-tables",
+    "estimate-num-keys",
+    "estimate-table-readers-mem",
+    "is-file-deletions-enabled",
+    "num-snapshots",
+    "oldest-snapshot-time",
+    "num-live-versions",
+    "current-super-version-number",
+    "estimate-live-data-size",
+    "min-log-number-to-keep",
+    "min-obsolete-sst-number-to-keep",
+    "total-sst-files-size",
+    "live-sst-files-size",
+    "base-level",
+    "estimate-pending-compaction-bytes",
+    "actual-delayed-write-rate",
+    "is-write-stopped",
+    "estimate-oldest-key-time",
+    "block-cache-capacity",
+    "block-cache-usage",
+    "block-cache-pinned-usage",
+    "num-blob-files",
+    "total-blob-file-size",
+    "live-blob-file-size",
+];
+
+/// String properties RocksDB reports per column family, most of them multi-line reports
+const STRING_PROPERTIES: &[&str] = &[
+    "stats",
+    "sstables",
+    "cfstats",
+    "cf-file-histogram",
+    "dbstats",
+    "levelstats",
+    "aggregated-table-properties",
+];
+
+/// Reads every known `rocksdb.*` integer and string property of the column families in `cf_names`
+///
+/// Properties the database does not support, such as the blob properties without blob files
+/// enabled, are left out. `num-files-at-level<N>` and `compression-ratio-at-level<N>` are read for
+/// each of the [`NUM_LEVELS`] levels.
+pub fn properties(
+    db: &DbHandle,
+    cf_names: &[String],
+) -> Result<PropertiesReport, Box<dyn std::error::Error>> {
+    let mut column_families = Vec::new();
+    for name in cf_names {
+        let cf = db.cf(name)?;
+
+        let mut int_properties = BTreeMap::new();
+        for property in INT_PROPERTIES {
+            let property = format!("rocksdb.{}", property);
+            if let Some(value) = db.property_int_value_cf(cf, property.as_str())? {
+                int_properties.insert(property, value);
+            }
+        }
+
+        let mut string_properties = BTreeMap::new();
+        let per_level = (0..NUM_LEVELS).flat_map(|level| {
+            [
+                format!("num-files-at-level{}", level),
+                format!("compression-ratio-at-level{}", level),
+            ]
+        });
+        let names = STRING_PROPERTIES
+            .iter()
+            .map(|property| property.to_string())
+            .chain(per_level);
+        for property in names {
+            let property = format!("rocksdb.{}", property);
+            if let Some(value) = db.property_value_cf(cf, property.as_str())? {
+                string_properties.insert(property, value);
+            }
+        }
+
+        column_families.push(CfProperties {
+            name: name.clone(),
+            int_properties,
+            string_properties,
+        });
+    }
+    Ok(PropertiesReport { column_families })
+}
+
+/// Passes a fresh [`properties`] report to `on_report` every `interval` until it returns an error
+///
+/// Secondary instances catch up with their primary before each report, other handles keep
+/// reporting the state they were opened with.
+pub fn watch_properties<F>(
+    db: &DbHandle,
+    cf_names: &[String],
+    interval: Duration,
+    mut on_report: F,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: FnMut(PropertiesReport) -> Result<(), Box<dyn std::error::Error>>,
+{
+    loop {
+        db.catch_up()?;
+        on_report(properties(db, cf_names)?)?;
+        std::thread::sleep(interval);
+    }
+}
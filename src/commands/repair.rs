@@ -0,0 +1,93 @@
+use rocksdb::{Options, DB};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::commands::list_cf;
+use crate::db::{DbHandle, DbOptions, OpenMode};
+use crate::output::RepairSummary;
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Names and sizes of the table files directly inside `dir`
+fn sst_files(dir: &Path) -> std::io::Result<BTreeMap<String, u64>> {
+    let mut files = BTreeMap::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.ends_with(".sst") {
+            files.insert(name, entry.metadata()?.len());
+        }
+    }
+    Ok(files)
+}
+
+fn file_names(dir: &Path) -> std::io::Result<Vec<String>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        names.push(entry?.file_name().to_string_lossy().into_owned());
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Copies the database to `backup_path`, then runs the RocksDB repairer over it
+///
+/// The repairer salvages every readable table file and replays the WAL, files it cannot read
+/// are moved to the `lost` directory of the database. The copy is made first because a repair
+/// can discard data that a later manual recovery would still want.
+pub fn repair(db_path: &Path, backup_path: &Path) -> Result<RepairSummary, Box<dyn std::error::Error>> {
+    if !db_path.is_dir() {
+        return Err(format!("Database path {:?} is not a directory", db_path).into());
+    }
+    if backup_path.exists() {
+        return Err(format!("Backup path {:?} already exists", backup_path).into());
+    }
+
+    copy_dir(db_path, backup_path)
+        .map_err(|e| format!("Failed to back up {:?} to {:?}: {}", db_path, backup_path, e))?;
+    let lost_before = file_names(&db_path.join("lost"))?;
+    let before = sst_files(db_path)?;
+
+    DB::repair(&Options::default(), db_path)?;
+
+    let after = sst_files(db_path)?;
+    let lost_files = file_names(&db_path.join("lost"))?
+        .into_iter()
+        .filter(|name| !lost_before.contains(name))
+        .collect();
+
+    let db = DbHandle::open(db_path, OpenMode::ReadOnly, DbOptions::default())?;
+    let column_families = list_cf(&db)?;
+
+    Ok(RepairSummary {
+        db_path: db_path.display().to_string(),
+        backup_path: backup_path.display().to_string(),
+        sst_files_before: before.len(),
+        sst_bytes_before: before.values().sum(),
+        sst_files_after: after.len(),
+        sst_bytes_after: after.values().sum(),
+        dropped_files: before
+            .keys()
+            .filter(|name| !after.contains_key(*name))
+            .cloned()
+            .collect(),
+        lost_files,
+        column_families,
+    })
+}
@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+use crate::output::to_hex;
+
+/// Shortest time between two saves of a scan's position
+const SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// File a long scan saves its position to, so that an interrupted run can continue after the
+/// last key it processed
+pub struct CheckpointFile {
+    pub path: PathBuf,
+    /// Continue from the position saved in `path` instead of starting over
+    pub resume: bool,
+}
+
+/// How far a scan got within one column family or shard
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub(crate) struct Position {
+    /// Last processed key as 0x-prefixed hex
+    pub last_key: Option<String>,
+    /// Records processed up to and including `last_key`
+    pub processed: u64,
+    /// Whether the scan reached the end
+    pub finished: bool,
+}
+
+impl Position {
+    /// The smallest key after the last processed one, where a resumed scan starts
+    pub fn next_key(&self) -> Result<Option<Vec<u8>>> {
+        self.last_key
+            .as_deref()
+            .map(|key| {
+                let mut next = hex::decode(key.trim_start_matches("0x")).map_err(|e| {
+                    Error::KeyParse(format!("Invalid checkpoint key {}: {}", key, e))
+                })?;
+                next.push(0);
+                Ok(next)
+            })
+            .transpose()
+    }
+}
+
+/// Contents of a checkpoint file
+#[derive(Serialize, Deserialize)]
+struct SavedScan {
+    command: String,
+    /// What the scan covers, a resumed run has to cover the same
+    scan: String,
+    positions: BTreeMap<String, Position>,
+    /// Length of the output file when the positions were saved, exports truncate it to this
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    output_len: Option<u64>,
+    #[serde(default)]
+    corruptions: u64,
+}
+
+/// Positions of a running scan, saved to the checkpoint file at most every [`SAVE_INTERVAL`]
+///
+/// Without a checkpoint file positions are only kept in memory and nothing is saved.
+pub(crate) struct ScanCheckpoint<'a> {
+    path: Option<&'a Path>,
+    resumed_from: u64,
+    state: Mutex<(SavedScan, Instant)>,
+}
+
+impl<'a> ScanCheckpoint<'a> {
+    /// Starts a scan of `command` over `scan`, from the saved positions when `file` resumes
+    pub fn open(file: Option<&'a CheckpointFile>, command: &str, scan: String) -> Result<Self> {
+        let saved = match file {
+            Some(file) if file.resume => {
+                let resume_error = |message: String| Error::Resume {
+                    path: file.path.clone(),
+                    message,
+                };
+                if !file.path.exists() {
+                    return Err(resume_error("the file does not exist".to_string()));
+                }
+                let saved: SavedScan = serde_json::from_slice(&fs::read(&file.path)?)?;
+                if saved.command != command || saved.scan != scan {
+                    return Err(resume_error(format!(
+                        "it was written by {} of {}",
+                        saved.command, saved.scan
+                    )));
+                }
+                saved
+            }
+            _ => SavedScan {
+                command: command.to_string(),
+                scan,
+                positions: BTreeMap::new(),
+                output_len: None,
+                corruptions: 0,
+            },
+        };
+
+        Ok(ScanCheckpoint {
+            path: file.map(|file| file.path.as_path()),
+            resumed_from: saved.positions.values().map(|position| position.processed).sum(),
+            state: Mutex::new((saved, Instant::now())),
+        })
+    }
+
+    /// Records processed by the runs before this one
+    pub fn resumed_from(&self) -> u64 {
+        self.resumed_from
+    }
+
+    /// Saved position of `scope`, the start when the scan had not reached it
+    pub fn position(&self, scope: &str) -> Position {
+        let state = self.state.lock().expect("checkpoint lock poisoned");
+        state.0.positions.get(scope).cloned().unwrap_or_default()
+    }
+
+    /// Saved length of the output file
+    pub fn output_len(&self) -> Option<u64> {
+        self.state.lock().expect("checkpoint lock poisoned").0.output_len
+    }
+
+    /// Saved number of corruptions found
+    pub fn corruptions(&self) -> u64 {
+        self.state.lock().expect("checkpoint lock poisoned").0.corruptions
+    }
+
+    /// Whether the next [`ScanCheckpoint::advance`] saves, output has to be flushed before it
+    pub fn due(&self) -> bool {
+        self.path.is_some()
+            && self.state.lock().expect("checkpoint lock poisoned").1.elapsed() >= SAVE_INTERVAL
+    }
+
+    /// Sets the output length and corruption count saved with the next positions
+    pub fn set_totals(&self, output_len: Option<u64>, corruptions: u64) {
+        let mut state = self.state.lock().expect("checkpoint lock poisoned");
+        state.0.output_len = output_len;
+        state.0.corruptions = corruptions;
+    }
+
+    /// Records that `scope` processed `processed` records up to and including `key`, saving all
+    /// positions when due
+    pub fn advance(&self, scope: &str, key: &[u8], processed: u64) -> Result<()> {
+        self.update(scope, |position| {
+            position.last_key = Some(to_hex(key));
+            position.processed = processed;
+        })
+    }
+
+    /// Records that `scope` was scanned to the end with `processed` records
+    pub fn finish(&self, scope: &str, processed: u64) -> Result<()> {
+        self.update(scope, |position| {
+            position.processed = processed;
+            position.finished = true;
+        })
+    }
+
+    fn update<F: FnOnce(&mut Position)>(&self, scope: &str, change: F) -> Result<()> {
+        let mut state = self.state.lock().expect("checkpoint lock poisoned");
+        change(state.0.positions.entry(scope.to_string()).or_default());
+
+        let path = match self.path {
+            Some(path) if state.1.elapsed() >= SAVE_INTERVAL => path,
+            _ => return Ok(()),
+        };
+        // Writes to a temporary file first so a crash never leaves a truncated checkpoint
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(&state.0)?)?;
+        fs::rename(&tmp, path)?;
+        state.1 = Instant::now();
+        Ok(())
+    }
+
+    /// Removes the checkpoint file of a completed scan, there is nothing left to resume
+    pub fn complete(self) -> Result<()> {
+        if let Some(path) = self.path.filter(|path| path.exists()) {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
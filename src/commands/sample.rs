@@ -0,0 +1,107 @@
+use num_bigint::BigUint;
+use std::collections::HashSet;
+
+use crate::commands::bench::XorShift;
+use crate::db::DbHandle;
+use crate::output::{RecordEntry, SampleSummary};
+use crate::record::RecordDecoder;
+
+/// Seeks per requested record before giving up on finding more distinct ones
+const SEEKS_PER_RECORD: usize = 8;
+
+/// Left-aligns `key` in `width` bytes, so keys compare as the big-endian numbers they become
+fn to_number(key: &[u8], width: usize) -> BigUint {
+    let mut padded = key.to_vec();
+    padded.resize(width, 0);
+    BigUint::from_bytes_be(&padded)
+}
+
+/// Passes up to `count` distinct random records of a column family to `on_record`, returns how many
+///
+/// Each record is found by seeking to a position drawn uniformly between the first and the last
+/// key, without scanning the column family. Records are picked evenly when keys are spread evenly
+/// over the keyspace, as the hashes keying merkle_records and data_records are, otherwise records
+/// right after large gaps are picked more often. Fewer records are returned when the column family
+/// holds fewer.
+pub(crate) fn sample_records<F>(
+    db: &DbHandle,
+    target_cf: &str,
+    count: usize,
+    seed: u64,
+    mut on_record: F,
+) -> Result<usize, Box<dyn std::error::Error>>
+where
+    F: FnMut(&[u8], &[u8]) -> Result<(), Box<dyn std::error::Error>>,
+{
+    let cf = db.cf(target_cf)?;
+    let mut iter = db.raw_iterator_cf_opt(cf, db.read_options());
+
+    iter.seek_to_first();
+    let first = iter.key().map(<[u8]>::to_vec);
+    iter.seek_to_last();
+    let last = iter.key().map(<[u8]>::to_vec);
+    iter.status()?;
+
+    let mut sampled = HashSet::new();
+    if let (Some(first), Some(last)) = (first, last) {
+        let width = first.len().max(last.len());
+        let low = to_number(&first, width);
+        let span = to_number(&last, width) - &low + 1u32;
+        let mut rng = XorShift::new(seed);
+
+        for _ in 0..count.saturating_mul(SEEKS_PER_RECORD) {
+            if sampled.len() == count {
+                break;
+            }
+            // Extra random bytes keep the modulo bias negligible
+            let random: Vec<u8> =
+                (0..width / 8 + 2).flat_map(|_| rng.next().to_be_bytes()).collect();
+            let offset = BigUint::from_bytes_be(&random) % &span;
+            let target = (&low + offset).to_bytes_be();
+            let mut padded = vec![0; width.saturating_sub(target.len())];
+            padded.extend_from_slice(&target);
+
+            iter.seek(&padded);
+            if !iter.valid() {
+                // Between the last key and its zero padding, wrap around to the first
+                iter.seek_to_first();
+            }
+            iter.status()?;
+            if let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+                if sampled.insert(key.to_vec()) {
+                    on_record(key, value)?;
+                }
+            }
+        }
+    }
+    Ok(sampled.len())
+}
+
+/// Passes up to `count` distinct random records of a column family, decoded with `decoder`, to
+/// `on_record`, see [`sample_records`] for how they are picked
+pub fn sample<F>(
+    db: &DbHandle,
+    target_cf: &str,
+    count: usize,
+    decoder: &dyn RecordDecoder,
+    seed: u64,
+    mut on_record: F,
+) -> Result<SampleSummary, Box<dyn std::error::Error>>
+where
+    F: FnMut(RecordEntry) -> Result<(), Box<dyn std::error::Error>>,
+{
+    if count == 0 {
+        return Err("Sample size must be greater than 0".into());
+    }
+
+    let sampled = sample_records(db, target_cf, count, seed, |key, value| {
+        on_record(RecordEntry::decoded(decoder, key, value))
+    })?;
+
+    Ok(SampleSummary {
+        column_family: target_cf.to_string(),
+        requested: count,
+        sampled,
+        seed,
+    })
+}
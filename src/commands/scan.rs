@@ -0,0 +1,86 @@
+use crate::db::DbHandle;
+use crate::output::{RecordEntry, ScanSummary};
+use crate::paginate::{Pagination, Paginator, Step};
+use crate::range::{self, KeyRange};
+use crate::record::RecordDecoder;
+
+/// Iterates a column family within `range`, passing the pairs selected by `page` to `on_record`
+///
+/// Secondary instances catch up with their primary while scanning, see [`range::iter_range_following`].
+pub fn scan<F>(
+    db: &DbHandle,
+    target_cf: &str,
+    range: &KeyRange,
+    reverse: bool,
+    page: Pagination,
+    mut on_record: F,
+) -> Result<ScanSummary, Box<dyn std::error::Error>>
+where
+    F: FnMut(RecordEntry) -> Result<(), Box<dyn std::error::Error>>,
+{
+    let _span = tracing::info_span!("scan", cf = target_cf, reverse).entered();
+    let cf = db.cf(target_cf)?;
+
+    let iter = range::iter_range_following(db, cf, range, reverse);
+
+    let mut paginator = Paginator::new(page);
+    for item in iter {
+        let (key, value) = item?;
+        match paginator.step() {
+            Step::Skip => continue,
+            Step::List => on_record(RecordEntry::raw(&key, &value))?,
+            Step::Stop => break,
+        }
+    }
+    tracing::debug!(count = paginator.listed(), "scan finished");
+
+    Ok(summary(target_cf, &paginator))
+}
+
+/// Iterates the keys of a column family starting with `prefix`, passing the records selected by
+/// `page` decoded with `decoder` to `on_record`
+///
+/// The handle should be opened with `DbHandle::open_read_only_with_prefix` so RocksDB can use its prefix
+/// bloom filters, any other handle still returns correct results.
+pub fn prefix_scan<F>(
+    db: &DbHandle,
+    target_cf: &str,
+    prefix: &[u8],
+    decoder: &dyn RecordDecoder,
+    page: Pagination,
+    mut on_record: F,
+) -> Result<ScanSummary, Box<dyn std::error::Error>>
+where
+    F: FnMut(RecordEntry) -> Result<(), Box<dyn std::error::Error>>,
+{
+    if prefix.is_empty() {
+        return Err("Prefix must not be empty".into());
+    }
+
+    let _span = tracing::info_span!("prefix_scan", cf = target_cf).entered();
+    let cf = db.cf(target_cf)?;
+
+    let iter = range::iter_prefix(db, cf, prefix);
+
+    let mut paginator = Paginator::new(page);
+    for item in iter {
+        let (key, value) = item?;
+        match paginator.step() {
+            Step::Skip => continue,
+            Step::List => on_record(RecordEntry::decoded(decoder, &key, &value))?,
+            Step::Stop => break,
+        }
+    }
+    tracing::debug!(count = paginator.listed(), "scan finished");
+
+    Ok(summary(target_cf, &paginator))
+}
+
+fn summary(target_cf: &str, paginator: &Paginator) -> ScanSummary {
+    ScanSummary {
+        column_family: target_cf.to_string(),
+        count: paginator.listed(),
+        skipped: paginator.skipped(),
+        next_skip: paginator.next_skip(),
+    }
+}
@@ -0,0 +1,233 @@
+use rocksdb::WriteBatch;
+use std::path::Path;
+use zkwasm_host_circuits::host::{datahash::DataHashRecord, mongomerkle::MerkleRecord};
+
+use crate::commands::bench::XorShift;
+use crate::db::DbHandle;
+use crate::output::{to_hex, SelfTestMismatch, SelfTestSummary};
+use crate::{DATA_CF_NAME, DEFAULT_CF_NAMES, MERKLE_CF_NAME};
+
+/// Largest leaf index of the depth 32 trees mongomerkle stores
+const MAX_MERKLE_INDEX: u64 = (1 << 33) - 2;
+
+/// Settings of a self-test run
+pub struct SelfTestConfig {
+    /// Records generated of each type
+    pub records: usize,
+    /// Largest payload of the generated data records in bytes
+    pub max_data_len: usize,
+    /// Seed of the generated records, the same seed generates the same records
+    pub seed: u64,
+}
+
+fn random_hash(rng: &mut XorShift) -> [u8; 32] {
+    let mut hash = [0; 32];
+    for chunk in hash.chunks_mut(8) {
+        chunk.copy_from_slice(&rng.next().to_le_bytes());
+    }
+    hash
+}
+
+/// A leaf with a data hash, an inner node with two children or, rarely, a node missing fields
+fn random_merkle_record(rng: &mut XorShift) -> MerkleRecord {
+    let index = rng.next() % (MAX_MERKLE_INDEX + 1);
+    let hash = random_hash(rng);
+    let (left, right, data) = match rng.next() % 8 {
+        0..=3 => (None, None, Some(random_hash(rng))),
+        4..=6 => (Some(random_hash(rng)), Some(random_hash(rng)), None),
+        _ => (Some(random_hash(rng)), None, None),
+    };
+    MerkleRecord {
+        index,
+        hash,
+        left,
+        right,
+        data,
+    }
+}
+
+fn random_data_record(rng: &mut XorShift, max_data_len: usize) -> DataHashRecord {
+    let hash = random_hash(rng);
+    let len = (rng.next() % (max_data_len as u64 + 1)) as usize;
+    let mut data: Vec<u8> = (0..(len + 7) / 8)
+        .flat_map(|_| rng.next().to_le_bytes())
+        .collect();
+    data.truncate(len);
+    DataHashRecord { hash, data }
+}
+
+/// Name of the first field of `decoded` that differs from `expected`
+fn merkle_mismatch(expected: &MerkleRecord, decoded: &MerkleRecord) -> Option<&'static str> {
+    if decoded.index != expected.index {
+        Some("index")
+    } else if decoded.hash != expected.hash {
+        Some("hash")
+    } else if decoded.left != expected.left {
+        Some("left")
+    } else if decoded.right != expected.right {
+        Some("right")
+    } else if decoded.data != expected.data {
+        Some("data")
+    } else {
+        None
+    }
+}
+
+fn data_mismatch(expected: &DataHashRecord, decoded: &DataHashRecord) -> Option<&'static str> {
+    if decoded.hash != expected.hash {
+        Some("hash")
+    } else if decoded.data != expected.data {
+        Some("data")
+    } else {
+        None
+    }
+}
+
+/// Checks a decoded value against the record it was encoded from
+fn check_merkle(
+    stage: &'static str,
+    expected: &MerkleRecord,
+    value: &[u8],
+) -> Option<SelfTestMismatch> {
+    let error = match MerkleRecord::from_slice(value) {
+        Ok(decoded) => match merkle_mismatch(expected, &decoded) {
+            Some(field) => format!("field {} differs after decoding", field),
+            None if decoded.to_slice() != value => "encoding again gives other bytes".to_string(),
+            None => return None,
+        },
+        Err(e) => format!("failed to decode: {}", e),
+    };
+    Some(SelfTestMismatch {
+        record_type: "merkle",
+        stage,
+        key: to_hex(&expected.hash),
+        value: to_hex(value),
+        error,
+    })
+}
+
+fn check_data(
+    stage: &'static str,
+    expected: &DataHashRecord,
+    value: &[u8],
+) -> Option<SelfTestMismatch> {
+    let error = match DataHashRecord::from_slice(value) {
+        Ok(decoded) => match data_mismatch(expected, &decoded) {
+            Some(field) => format!("field {} differs after decoding", field),
+            None if decoded.to_slice() != value => "encoding again gives other bytes".to_string(),
+            None => return None,
+        },
+        Err(e) => format!("failed to decode: {}", e),
+    };
+    Some(SelfTestMismatch {
+        record_type: "data",
+        stage,
+        key: to_hex(&expected.hash),
+        value: to_hex(value),
+        error,
+    })
+}
+
+/// Round-trips random records through the record encoding and a scratch database at `scratch_path`
+///
+/// Each generated `MerkleRecord` and `DataHashRecord` is encoded, decoded and compared field by
+/// field, then all of them are written to merkle_records and data_records of the scratch database,
+/// flushed, and read back after reopening it. Any difference is passed to `on_mismatch`, so a run
+/// after upgrading zkwasm-host-circuits shows whether the stored format is still read the same
+/// way. The scratch database must not exist and is removed afterwards unless `keep` is set.
+pub fn self_test<F>(
+    scratch_path: &Path,
+    config: &SelfTestConfig,
+    keep: bool,
+    mut on_mismatch: F,
+) -> Result<SelfTestSummary, Box<dyn std::error::Error>>
+where
+    F: FnMut(SelfTestMismatch) -> Result<(), Box<dyn std::error::Error>>,
+{
+    if scratch_path.exists() {
+        return Err(format!("Scratch database {:?} already exists", scratch_path).into());
+    }
+
+    let mut rng = XorShift::new(config.seed);
+    let merkle_records: Vec<MerkleRecord> =
+        (0..config.records).map(|_| random_merkle_record(&mut rng)).collect();
+    let data_records: Vec<DataHashRecord> = (0..config.records)
+        .map(|_| random_data_record(&mut rng, config.max_data_len))
+        .collect();
+
+    let mut summary = SelfTestSummary {
+        path: scratch_path.display().to_string(),
+        seed: config.seed,
+        merkle_records: merkle_records.len(),
+        data_records: data_records.len(),
+        mismatches: 0,
+        kept: keep,
+    };
+    let mut report = |mismatch: Option<SelfTestMismatch>| match mismatch {
+        Some(mismatch) => {
+            summary.mismatches += 1;
+            on_mismatch(mismatch)
+        }
+        None => Ok(()),
+    };
+
+    // In memory
+    for record in &merkle_records {
+        report(check_merkle("encoding", record, &record.to_slice()))?;
+    }
+    for record in &data_records {
+        report(check_data("encoding", record, &record.to_slice()))?;
+    }
+
+    // Through the database, read back from SST files after reopening it
+    {
+        let db = DbHandle::open_read_write(scratch_path, &DEFAULT_CF_NAMES)?;
+        let merkle_cf = db.cf(MERKLE_CF_NAME)?;
+        let data_cf = db.cf(DATA_CF_NAME)?;
+        let mut batch = WriteBatch::default();
+        for record in &merkle_records {
+            batch.put_cf(merkle_cf, record.hash, record.to_slice());
+        }
+        for record in &data_records {
+            batch.put_cf(data_cf, record.hash, record.to_slice());
+        }
+        db.write(batch)?;
+        db.flush_cf(merkle_cf)?;
+        db.flush_cf(data_cf)?;
+    }
+    {
+        let db = DbHandle::open_read_only(scratch_path, &DEFAULT_CF_NAMES)?;
+        let merkle_cf = db.cf(MERKLE_CF_NAME)?;
+        let data_cf = db.cf(DATA_CF_NAME)?;
+        let read_opts = db.read_options();
+        for record in &merkle_records {
+            let mismatch = match db.get_pinned_cf_opt(merkle_cf, record.hash, &read_opts)? {
+                Some(value) => check_merkle("database", record, &value),
+                None => Some(missing("merkle", &record.hash)),
+            };
+            report(mismatch)?;
+        }
+        for record in &data_records {
+            let mismatch = match db.get_pinned_cf_opt(data_cf, record.hash, &read_opts)? {
+                Some(value) => check_data("database", record, &value),
+                None => Some(missing("data", &record.hash)),
+            };
+            report(mismatch)?;
+        }
+    }
+
+    if !keep {
+        rocksdb::DB::destroy(&rocksdb::Options::default(), scratch_path)?;
+    }
+    Ok(summary)
+}
+
+fn missing(record_type: &'static str, hash: &[u8; 32]) -> SelfTestMismatch {
+    SelfTestMismatch {
+        record_type,
+        stage: "database",
+        key: to_hex(hash),
+        value: String::new(),
+        error: "not found after reopening the database".to_string(),
+    }
+}
@@ -0,0 +1,128 @@
+use rocksdb::{IngestExternalFileOptions, Options, SstFileWriter};
+use std::path::{Path, PathBuf};
+
+use crate::db::DbHandle;
+use crate::output::{KeyView, SstExportSummary, SstFileInfo, SstIngestSummary};
+use crate::range::{self, KeyRange};
+
+/// Writes key-value pairs, which must come in ascending key order, into a new SST file
+///
+/// Returns the number of pairs and their total key and value bytes. RocksDB cannot finish an
+/// empty SST file, so nothing is created when `pairs` is empty.
+pub(crate) fn write_sst_file<I>(path: &Path, pairs: I) -> Result<(u64, u64), rocksdb::Error>
+where
+    I: IntoIterator<Item = Result<(Box<[u8]>, Box<[u8]>), rocksdb::Error>>,
+{
+    write_sst_file_with_options(path, &Options::default(), pairs)
+}
+
+/// [`write_sst_file`] with the table and compression settings of `opts`
+pub(crate) fn write_sst_file_with_options<I>(
+    path: &Path,
+    opts: &Options,
+    pairs: I,
+) -> Result<(u64, u64), rocksdb::Error>
+where
+    I: IntoIterator<Item = Result<(Box<[u8]>, Box<[u8]>), rocksdb::Error>>,
+{
+    let mut writer = SstFileWriter::create(opts);
+    let mut opened = false;
+    let mut records = 0u64;
+    let mut bytes = 0u64;
+    for item in pairs {
+        let (key, value) = item?;
+        if !opened {
+            writer.open(path)?;
+            opened = true;
+        }
+        writer.put(&key, &value)?;
+        records += 1;
+        bytes += (key.len() + value.len()) as u64;
+    }
+    if opened {
+        writer.finish()?;
+    }
+    Ok((records, bytes))
+}
+
+/// Lists the live SST files, optionally restricted to one column family and to files overlapping `range`
+pub fn sst_info(
+    db: &DbHandle,
+    target_cf: Option<&str>,
+    range: &KeyRange,
+) -> Result<Vec<SstFileInfo>, Box<dyn std::error::Error>> {
+    let mut files: Vec<SstFileInfo> = db
+        .live_files()?
+        .into_iter()
+        .filter(|file| target_cf.map_or(true, |cf| file.column_family_name == cf))
+        .filter(|file| range.overlaps(file.start_key.as_deref(), file.end_key.as_deref()))
+        .map(|file| SstFileInfo {
+            column_family: file.column_family_name,
+            name: file.name,
+            level: file.level,
+            size: file.size as u64,
+            num_entries: file.num_entries,
+            num_deletions: file.num_deletions,
+            smallest_key: file.start_key.as_deref().map(KeyView::new),
+            largest_key: file.end_key.as_deref().map(KeyView::new),
+        })
+        .collect();
+
+    files.sort_by(|a, b| {
+        (&a.column_family, a.level, &a.smallest_key).cmp(&(&b.column_family, b.level, &b.smallest_key))
+    });
+    Ok(files)
+}
+
+/// Writes the pairs of a column family within `range` into a new SST file at `file`
+pub fn export_sst(
+    db: &DbHandle,
+    target_cf: &str,
+    range: &KeyRange,
+    file: &Path,
+) -> Result<SstExportSummary, Box<dyn std::error::Error>> {
+    if file.exists() {
+        return Err(format!("{} already exists", file.display()).into());
+    }
+    let cf = db.cf(target_cf)?;
+
+    let (records, bytes) = write_sst_file(file, range::iter_range(db, cf, range, false))?;
+    if records == 0 {
+        return Err(format!("No records in column family '{}' to export", target_cf).into());
+    }
+
+    Ok(SstExportSummary {
+        column_family: target_cf.to_string(),
+        file: file.display().to_string(),
+        records,
+        bytes,
+    })
+}
+
+/// Bulk-loads SST files into a column family with `ingest_external_file`
+///
+/// The files are copied into the database unless `move_files` is set, in which case they are
+/// hard-linked or moved and must not be used afterwards.
+pub fn ingest_sst(
+    db: &DbHandle,
+    target_cf: &str,
+    files: &[PathBuf],
+    move_files: bool,
+) -> Result<SstIngestSummary, Box<dyn std::error::Error>> {
+    let cf = db.cf(target_cf)?;
+
+    let mut size = 0;
+    for file in files {
+        size += std::fs::metadata(file)?.len();
+    }
+
+    let mut opts = IngestExternalFileOptions::default();
+    opts.set_move_files(move_files);
+    db.ingest_external_file_cf_opts(cf, &opts, files.to_vec())?;
+
+    Ok(SstIngestSummary {
+        column_family: target_cf.to_string(),
+        files: files.iter().map(|file| file.display().to_string()).collect(),
+        size,
+    })
+}
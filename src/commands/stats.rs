@@ -0,0 +1,47 @@
+use rocksdb::{properties, ColumnFamily};
+
+use crate::db::DbHandle;
+use crate::output::{CfStats, StatsReport};
+
+/// Number of LSM levels RocksDB uses with default options
+pub const NUM_LEVELS: usize = 7;
+
+/// Number of SST files on each level of a column family
+pub(crate) fn files_per_level(
+    db: &DbHandle,
+    cf: &ColumnFamily,
+) -> Result<Vec<u64>, rocksdb::Error> {
+    let mut files_per_level = Vec::with_capacity(NUM_LEVELS);
+    for level in 0..NUM_LEVELS {
+        let files = db
+            .property_int_value_cf(cf, &*properties::num_files_at_level(level))?
+            .unwrap_or(0);
+        files_per_level.push(files);
+    }
+    Ok(files_per_level)
+}
+
+/// Collects size, file and memory statistics for every column family from RocksDB properties
+pub fn stats(db: &DbHandle) -> Result<StatsReport, Box<dyn std::error::Error>> {
+    let mut column_families = Vec::new();
+    for name in db.cf_names() {
+        let cf = db.cf(name)?;
+        let files_per_level = files_per_level(db, cf)?;
+
+        column_families.push(CfStats {
+            name: name.clone(),
+            estimated_keys: db.property_int_value_cf(cf, properties::ESTIMATE_NUM_KEYS)?,
+            sst_files_size: db.property_int_value_cf(cf, properties::TOTAL_SST_FILES_SIZE)?,
+            sst_files: files_per_level.iter().sum(),
+            files_per_level,
+            block_cache_usage: db.property_int_value_cf(cf, properties::BLOCK_CACHE_USAGE)?,
+            cur_mem_tables_size: db
+                .property_int_value_cf(cf, properties::CUR_SIZE_ALL_MEM_TABLES)?,
+            all_mem_tables_size: db.property_int_value_cf(cf, properties::SIZE_ALL_MEM_TABLES)?,
+        });
+    }
+    Ok(StatsReport {
+        column_families,
+        latest_sequence_number: db.latest_sequence_number(),
+    })
+}
@@ -0,0 +1,42 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::db::DbHandle;
+
+/// Follows a primary database through a secondary `db` and passes every key that appears in
+/// `target_cf` to `on_new_key`
+///
+/// Keys present when tailing starts are remembered but not reported. The set of seen keys is
+/// kept in memory, so this is meant for watching a prover write, not for very large column
+/// families. Runs until `on_new_key` returns an error.
+pub fn tail<F>(
+    db: &DbHandle,
+    target_cf: &str,
+    interval: Duration,
+    mut on_new_key: F,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: FnMut(&[u8], &[u8]) -> Result<(), Box<dyn std::error::Error>>,
+{
+    let cf = db.cf(target_cf)?;
+    let mut seen: HashSet<Box<[u8]>> = HashSet::new();
+    let mut initial = true;
+
+    loop {
+        db.try_catch_up_with_primary()?;
+
+        for item in db.iterator_cf_opt(cf, db.read_options(), rocksdb::IteratorMode::Start) {
+            let (key, value) = item?;
+            if seen.contains(&key) {
+                continue;
+            }
+            if !initial {
+                on_new_key(&key, &value)?;
+            }
+            seen.insert(key);
+        }
+
+        initial = false;
+        std::thread::sleep(interval);
+    }
+}
@@ -0,0 +1,323 @@
+use rayon::prelude::*;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use super::resume::{CheckpointFile, ScanCheckpoint};
+use crate::db::DbHandle;
+use crate::error::Error;
+use crate::export::{CsvLayout, ExportFormat, ExportReader, ExportWriter};
+use crate::output::{
+    to_hex, DumpVerifySummary, ExportPart, ExportSummary, ImportSummary, ParallelExportSummary,
+};
+use crate::range::{self, KeyRange};
+
+/// Streams every pair of a column family, optionally restricted to a key prefix, into `file`
+///
+/// CSV rows follow `csv`. `on_record` is called with the key and value size of every written pair.
+/// Full exports of a secondary instance catch up with the primary as they go. With `checkpoint`,
+/// the last written key and the length of `file` are saved periodically. A resumed export cuts
+/// `file` back to the saved length and appends the pairs after the saved key.
+#[allow(clippy::too_many_arguments)]
+pub fn export_file<F>(
+    db: &DbHandle,
+    target_cf: &str,
+    file: &Path,
+    format: ExportFormat,
+    csv: CsvLayout,
+    key_prefix: Option<&[u8]>,
+    checkpoint: Option<&CheckpointFile>,
+    mut on_record: F,
+) -> Result<ExportSummary, Box<dyn std::error::Error>>
+where
+    F: FnMut(usize),
+{
+    let cf = db.cf(target_cf)?;
+
+    let scan = format!(
+        "column family '{}' with prefix {} into {} as {}",
+        target_cf,
+        key_prefix.map_or_else(|| "none".to_string(), to_hex),
+        file.display(),
+        format.name()
+    );
+    let checkpoint = ScanCheckpoint::open(checkpoint, "export", scan)?;
+    let resume_from = checkpoint.position(target_cf).next_key()?;
+
+    let iter: Box<dyn Iterator<Item = Result<(Box<[u8]>, Box<[u8]>), rocksdb::Error>>> =
+        match (key_prefix, resume_from) {
+            (Some(prefix), None) => Box::new(range::iter_prefix(db, cf, prefix)),
+            (prefix, start) => {
+                let range = KeyRange::new(start, None);
+                let prefix = prefix.unwrap_or_default();
+                Box::new(
+                    range::iter_range_following(db, cf, &range, false).take_while(
+                        move |item| match item {
+                            Ok((key, _)) => key.starts_with(prefix),
+                            Err(_) => true,
+                        },
+                    ),
+                )
+            }
+        };
+
+    let mut writer = match checkpoint.output_len() {
+        Some(len) => {
+            let out = OpenOptions::new().write(true).open(file)?;
+            out.set_len(len)?;
+            let mut out = BufWriter::new(out);
+            out.seek(SeekFrom::End(0))?;
+            ExportWriter::resume(format, out, csv, checkpoint.resumed_from())
+        }
+        None => {
+            let out = BufWriter::new(File::create(file)?);
+            ExportWriter::for_column_family(format, out, target_cf, csv)?
+        }
+    };
+    let mut count = checkpoint.resumed_from();
+    for item in iter {
+        let (key, value) = item?;
+        writer.write_record(&key, &value)?;
+        count += 1;
+        on_record(key.len() + value.len());
+
+        if checkpoint.due() {
+            let len = writer.flush()?.get_ref().metadata()?.len();
+            checkpoint.set_totals(Some(len), 0);
+            checkpoint.advance(target_cf, &key, count)?;
+        }
+    }
+    writer.finish()?;
+    let resumed_from = checkpoint.resumed_from();
+    checkpoint.complete()?;
+
+    Ok(ExportSummary {
+        column_family: target_cf.to_string(),
+        file: file.display().to_string(),
+        count,
+        resumed_from,
+    })
+}
+
+/// Exports a column family as up to `parts` part files written in parallel on a pool of `threads`
+/// threads, and lists them in a JSON manifest at `manifest`
+///
+/// The keyspace is split at SST file boundaries so that every part covers about the same number
+/// of bytes on disk, fewer parts are written when the column family has fewer files. Part `i` is
+/// written next to the manifest as `<manifest>.part-<i>`, each a complete export in `format` that
+/// can be imported on its own. `on_record` is called with the key and value size of every written
+/// pair.
+#[allow(clippy::too_many_arguments)]
+pub fn export_parallel<F>(
+    db: &DbHandle,
+    target_cf: &str,
+    manifest: &Path,
+    format: ExportFormat,
+    csv: CsvLayout,
+    parts: usize,
+    threads: usize,
+    on_record: F,
+) -> Result<ParallelExportSummary, Box<dyn std::error::Error>>
+where
+    F: Fn(usize) + Sync,
+{
+    if parts == 0 {
+        return Err("Number of parts must be greater than zero".into());
+    }
+    db.cf(target_cf)?;
+
+    let splits = sst_split_keys(db, target_cf, parts)?;
+    let mut ranges = Vec::with_capacity(splits.len() + 1);
+    let mut start = None;
+    for split in splits {
+        ranges.push((start, Some(split.clone())));
+        start = Some(split);
+    }
+    ranges.push((start, None));
+
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build()?;
+    let written = pool.install(|| {
+        ranges
+            .into_par_iter()
+            .enumerate()
+            .map(|(index, (start, end))| {
+                let file = part_path(manifest, index);
+                export_part(db, target_cf, &file, format, csv.clone(), start, end, &on_record)
+            })
+            .collect::<Result<Vec<_>, Error>>()
+    })?;
+
+    let summary = ParallelExportSummary {
+        column_family: target_cf.to_string(),
+        format: format.name().to_string(),
+        manifest: manifest.display().to_string(),
+        records: written.iter().map(|part| part.records).sum(),
+        parts: written,
+    };
+    serde_json::to_writer_pretty(BufWriter::new(File::create(manifest)?), &summary)?;
+    Ok(summary)
+}
+
+/// Picks up to `parts - 1` keys splitting the live SST files of a column family into runs of
+/// about equal size
+fn sst_split_keys(
+    db: &DbHandle,
+    target_cf: &str,
+    parts: usize,
+) -> Result<Vec<Vec<u8>>, Box<dyn std::error::Error>> {
+    let mut files: Vec<_> = db
+        .live_files()?
+        .into_iter()
+        .filter(|file| file.column_family_name == target_cf)
+        .filter_map(|file| file.start_key.map(|key| (key, file.size as u64)))
+        .collect();
+    files.sort();
+
+    let total: u64 = files.iter().map(|(_, size)| size).sum();
+    let mut splits: Vec<Vec<u8>> = Vec::new();
+    let mut seen = 0u64;
+    for (start_key, size) in files {
+        // A file starts a new part once the files before it fill the parts so far
+        let due = seen * parts as u64 / total.max(1);
+        if due > splits.len() as u64 && splits.last() != Some(&start_key) && seen > 0 {
+            splits.push(start_key);
+        }
+        seen += size;
+    }
+    Ok(splits)
+}
+
+/// Path of part `index` of the parallel export listed in `manifest`
+fn part_path(manifest: &Path, index: usize) -> PathBuf {
+    let mut path = manifest.as_os_str().to_owned();
+    path.push(format!(".part-{:04}", index));
+    PathBuf::from(path)
+}
+
+/// Exports the keys from `start` up to but excluding `end` into `file`
+#[allow(clippy::too_many_arguments)]
+fn export_part<F: Fn(usize)>(
+    db: &DbHandle,
+    target_cf: &str,
+    file: &Path,
+    format: ExportFormat,
+    csv: CsvLayout,
+    start: Option<Vec<u8>>,
+    end: Option<Vec<u8>>,
+    on_record: &F,
+) -> Result<ExportPart, Error> {
+    let cf = db.cf(target_cf)?;
+    let mut opts = db.read_options();
+    if let Some(start) = &start {
+        opts.set_iterate_lower_bound(start.clone());
+    }
+    if let Some(end) = &end {
+        opts.set_iterate_upper_bound(end.clone());
+    }
+
+    let out = BufWriter::new(File::create(file)?);
+    let mut writer = ExportWriter::for_column_family(format, out, target_cf, csv)?;
+    let mut records = 0;
+    for item in db.iterator_cf_opt(cf, opts, rocksdb::IteratorMode::Start) {
+        let (key, value) = item?;
+        writer.write_record(&key, &value)?;
+        records += 1;
+        on_record(key.len() + value.len());
+    }
+    writer.finish()?;
+
+    Ok(ExportPart {
+        file: file.display().to_string(),
+        start_key: start.as_deref().map(to_hex),
+        end_key: end.as_deref().map(to_hex),
+        records,
+        bytes: std::fs::metadata(file)?.len(),
+    })
+}
+
+/// Writes the pairs of an export file into a column family in batches of `batch_size`
+///
+/// Without a database handle the file is only parsed and counted, which is how dry runs are done.
+pub fn import_file(
+    db: Option<&DbHandle>,
+    target_cf: &str,
+    file: &Path,
+    format: ExportFormat,
+    batch_size: usize,
+    any_cf: bool,
+) -> Result<ImportSummary, Box<dyn std::error::Error>> {
+    if batch_size == 0 {
+        return Err("Batch size must be greater than zero".into());
+    }
+
+    let mut reader = ExportReader::new(format, BufReader::new(File::open(file)?));
+    // Dumps name the column family they were exported from, importing one into another is
+    // almost always a mistake
+    if matches!(format, ExportFormat::Dump) {
+        let dump_cf = &reader.dump_header()?.column_family;
+        if dump_cf != target_cf && !any_cf {
+            return Err(format!(
+                "{} is a dump of column family '{}', not '{}', pass --any-cf to import it anyway",
+                file.display(),
+                dump_cf,
+                target_cf
+            )
+            .into());
+        }
+    }
+
+    let mut count = 0;
+    match db {
+        None => {
+            for item in reader {
+                item?;
+                count += 1;
+            }
+        }
+        Some(db) => {
+            let cf = db.cf(target_cf)?;
+
+            let mut batch = rocksdb::WriteBatch::default();
+            for item in reader {
+                let (key, value) = item?;
+                batch.put_cf(cf, key, value);
+                count += 1;
+                if batch.len() >= batch_size {
+                    db.write(std::mem::take(&mut batch))?;
+                }
+            }
+            if !batch.is_empty() {
+                db.write(batch)?;
+            }
+        }
+    }
+
+    Ok(ImportSummary {
+        column_family: target_cf.to_string(),
+        file: file.display().to_string(),
+        count,
+        dry_run: db.is_none(),
+    })
+}
+
+/// Reads a dump end to end, checking its header, every record checksum and the trailer
+pub fn verify_dump(file: &Path) -> Result<DumpVerifySummary, Box<dyn std::error::Error>> {
+    let mut reader = ExportReader::new(ExportFormat::Dump, BufReader::new(File::open(file)?));
+    let header = reader.dump_header()?.clone();
+
+    let mut records = 0;
+    let mut bytes = 0;
+    for item in reader {
+        let (key, value) = item?;
+        records += 1;
+        bytes += (key.len() + value.len()) as u64;
+    }
+
+    Ok(DumpVerifySummary {
+        file: file.display().to_string(),
+        version: header.version,
+        column_family: header.column_family,
+        records,
+        bytes,
+    })
+}
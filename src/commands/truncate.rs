@@ -0,0 +1,85 @@
+use clap::ValueEnum;
+use rocksdb::properties;
+
+use crate::db::DbHandle;
+use crate::output::TruncateSummary;
+
+/// How a column family is emptied
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum TruncateMethod {
+    /// Write one range tombstone over all keys and compact it away, keeps the column family options
+    DeleteRange,
+    /// Drop the column family and create it again with default options
+    Recreate,
+}
+
+impl TruncateMethod {
+    fn name(&self) -> &'static str {
+        match self {
+            TruncateMethod::DeleteRange => "delete-range",
+            TruncateMethod::Recreate => "recreate",
+        }
+    }
+}
+
+/// Removes every key of a column family without iterating over them
+///
+/// Dry runs count the keys exactly by iterating, real runs report RocksDB's estimate of the key
+/// count before truncating, since counting a large column family takes longer than emptying it.
+pub fn truncate(
+    db: &mut DbHandle,
+    target_cf: &str,
+    method: TruncateMethod,
+    dry_run: bool,
+) -> Result<TruncateSummary, Box<dyn std::error::Error>> {
+    let cf = db.cf(target_cf)?;
+    if method == TruncateMethod::Recreate && target_cf == rocksdb::DEFAULT_COLUMN_FAMILY_NAME {
+        return Err("The default column family cannot be dropped, use --method delete-range".into());
+    }
+
+    let mut summary = TruncateSummary {
+        column_family: target_cf.to_string(),
+        method: method.name().to_string(),
+        keys: 0,
+        approximate: !dry_run,
+        dry_run,
+    };
+    if dry_run {
+        let mut iter = db.raw_iterator_cf_opt(cf, db.read_options());
+        iter.seek_to_first();
+        while iter.valid() {
+            summary.keys += 1;
+            iter.next();
+        }
+        iter.status()?;
+        return Ok(summary);
+    }
+    summary.keys = db
+        .property_int_value_cf(cf, properties::ESTIMATE_NUM_KEYS)?
+        .unwrap_or(0);
+
+    match method {
+        TruncateMethod::DeleteRange => {
+            let mut iter = db.raw_iterator_cf_opt(cf, db.read_options());
+            iter.seek_to_first();
+            let first = iter.key().map(<[u8]>::to_vec);
+            iter.seek_to_last();
+            let last = iter.key().map(<[u8]>::to_vec);
+            iter.status()?;
+            drop(iter);
+
+            if let (Some(first), Some(mut end)) = (first, last) {
+                // The smallest key after the last one is the last one followed by a zero byte
+                end.push(0);
+                db.delete_range_cf(cf, &first, &end)?;
+                // Compacting the range drops the files the tombstone covers
+                db.compact_range_cf(cf, Some(&first), Some(&end));
+            }
+        },
+        TruncateMethod::Recreate => {
+            db.drop_cf(target_cf)?;
+            db.create_cf(target_cf)?;
+        },
+    }
+    Ok(summary)
+}
@@ -0,0 +1,142 @@
+use rocksdb::WriteBatch;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::analyze::{bucket_bounds, log2_bucket};
+use crate::db::{DbHandle, DbOptions};
+use crate::output::{CfCopy, HistogramBucket, RecordAgeReport, SetTtlSummary};
+use crate::range::{self, KeyRange};
+
+/// Number of records written per WriteBatch into the TTL database
+const TTL_BATCH_SIZE: usize = 10_000;
+
+/// Length of the write time RocksDB appends to the values of a TTL database, u32 LE unix seconds
+const TIMESTAMP_LEN: usize = 4;
+
+/// Earliest write time RocksDB accepts as a TTL timestamp, older ones mark a value without one
+const MIN_TIMESTAMP: u64 = 1_368_146_402;
+
+/// Copies column families into a new TTL database whose records expire `ttl` after the copy
+///
+/// `destination` must not exist yet. Records are written through WriteBatches, since RocksDB
+/// only adds the write time to values written through a TTL handle, so all of them expire
+/// together. Expired records are dropped by compactions of a TTL handle, see
+/// [`DbOptions::with_ttl`].
+pub fn set_ttl<N: AsRef<str>>(
+    db: &DbHandle,
+    destination: &Path,
+    cf_names: &[N],
+    ttl: Duration,
+) -> Result<SetTtlSummary, Box<dyn std::error::Error>> {
+    if destination.exists() {
+        return Err(format!("Destination {} already exists", destination.display()).into());
+    }
+    // Fail on a missing column family before anything is written
+    for name in cf_names {
+        db.cf(name.as_ref())?;
+    }
+
+    let target = DbHandle::open_read_write_with_options(
+        destination,
+        cf_names,
+        DbOptions::default().with_ttl(ttl),
+    )?;
+
+    let mut column_families = Vec::with_capacity(cf_names.len());
+    for name in cf_names {
+        let name = name.as_ref();
+        let target_cf = target.cf(name)?;
+        let mut records = 0u64;
+        let mut bytes = 0u64;
+        let mut batch = WriteBatch::default();
+        for item in range::iter_range(db, db.cf(name)?, &KeyRange::default(), false) {
+            let (key, value) = item?;
+            records += 1;
+            bytes += (key.len() + value.len()) as u64;
+            batch.put_cf(target_cf, key, value);
+            if batch.len() >= TTL_BATCH_SIZE {
+                target.write(std::mem::take(&mut batch))?;
+            }
+        }
+        if !batch.is_empty() {
+            target.write(batch)?;
+        }
+
+        column_families.push(CfCopy {
+            column_family: name.to_string(),
+            records,
+            bytes,
+        });
+    }
+
+    Ok(SetTtlSummary {
+        destination: destination.display().to_string(),
+        ttl_secs: ttl.as_secs(),
+        column_families,
+    })
+}
+
+/// Reports how long ago the records of a column family of a TTL database were written
+///
+/// The database has to be opened without a TTL so that values still end with the write time.
+/// Values too short to hold one or whose last bytes are not a plausible time are counted as
+/// untimestamped. With `expire_after` the records a TTL of that many seconds would drop are
+/// counted.
+pub fn record_ages(
+    db: &DbHandle,
+    target_cf: &str,
+    expire_after: Option<u64>,
+) -> Result<RecordAgeReport, Box<dyn std::error::Error>> {
+    let cf = db.cf(target_cf)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+    let mut report = RecordAgeReport {
+        column_family: target_cf.to_string(),
+        records: 0,
+        timestamped: 0,
+        oldest_write: None,
+        newest_write: None,
+        expire_after,
+        expired: expire_after.map(|_| 0),
+        ages: Vec::new(),
+    };
+    let mut age_buckets: BTreeMap<u32, u64> = BTreeMap::new();
+    for item in db.iterator_cf_opt(cf, db.read_options(), rocksdb::IteratorMode::Start) {
+        let (_, value) = item?;
+        report.records += 1;
+        let written = match write_time(&value, now) {
+            Some(written) => written,
+            None => continue,
+        };
+        report.timestamped += 1;
+        report.oldest_write = Some(report.oldest_write.map_or(written, |t: u64| t.min(written)));
+        report.newest_write = Some(report.newest_write.map_or(written, |t: u64| t.max(written)));
+
+        let age = now.saturating_sub(written);
+        *age_buckets.entry(log2_bucket(age as usize)).or_default() += 1;
+        if let (Some(ttl), Some(expired)) = (expire_after, report.expired.as_mut()) {
+            if age > ttl {
+                *expired += 1;
+            }
+        }
+    }
+
+    report.ages = age_buckets
+        .into_iter()
+        .map(|(bucket, count)| {
+            let (min, max) = bucket_bounds(bucket);
+            HistogramBucket { min, max, count }
+        })
+        .collect();
+    Ok(report)
+}
+
+/// Write time at the end of a TTL database value, unless it is not a plausible time before `now`
+fn write_time(value: &[u8], now: u64) -> Option<u64> {
+    let suffix = value.len().checked_sub(TIMESTAMP_LEN).map(|start| &value[start..])?;
+    let written = u32::from_le_bytes(suffix.try_into().ok()?) as u64;
+    // Clocks of the writer may be a little ahead
+    const CLOCK_SKEW: u64 = 24 * 60 * 60;
+    (MIN_TIMESTAMP..=now + CLOCK_SKEW).contains(&written).then_some(written)
+}
@@ -0,0 +1,49 @@
+use crate::db::DbHandle;
+use crate::error::Error;
+use crate::output::{to_hex, InvalidRecord, ValidateSummary};
+use crate::range::{self, KeyRange};
+use crate::record::RecordDecoder;
+
+/// Decodes every value of a column family and passes the keys whose values fail to `on_invalid`
+///
+/// `on_progress` is called with the value size of every record read.
+pub fn validate_records<F, P>(
+    db: &DbHandle,
+    target_cf: &str,
+    decoder: &dyn RecordDecoder,
+    mut on_invalid: F,
+    mut on_progress: P,
+) -> Result<ValidateSummary, Box<dyn std::error::Error>>
+where
+    F: FnMut(InvalidRecord) -> Result<(), Box<dyn std::error::Error>>,
+    P: FnMut(usize),
+{
+    let cf = db.cf(target_cf)?;
+
+    let mut summary = ValidateSummary {
+        column_family: target_cf.to_string(),
+        decoder: decoder.name().to_string(),
+        records: 0,
+        invalid: 0,
+    };
+    for item in range::iter_range_following(db, cf, &KeyRange::default(), false) {
+        let (key, value) = item?;
+        summary.records += 1;
+        on_progress(value.len());
+
+        match decoder.decode(&value) {
+            Ok(_) => {}
+            Err(Error::Decode { message, .. }) => {
+                summary.invalid += 1;
+                on_invalid(InvalidRecord {
+                    key: to_hex(&key),
+                    value_len: value.len(),
+                    error: message,
+                })?;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(summary)
+}
@@ -0,0 +1,217 @@
+use crate::db::DbHandle;
+use crate::output::{
+    to_hex, ChangeBatch, ChangeOperation, ChangesSummary, WalOperation, WalSummary,
+};
+
+/// Number of value bytes shown in hex for each operation
+const VALUE_PREFIX_LEN: usize = 16;
+
+/// Reads the write-ahead log through `get_updates_since`, passing every operation of the logged
+/// write batches with a sequence number of at least `since` to `on_operation`, up to `limit` batches
+///
+/// The WAL iterator yields raw write batches, which are decoded here because the batch iteration
+/// rust-rocksdb offers skips operations on column families other than the default one. Column
+/// families are reported by id, 0 is the default column family.
+pub fn wal_dump<F>(
+    db: &DbHandle,
+    since: u64,
+    limit: Option<usize>,
+    mut on_operation: F,
+) -> Result<WalSummary, Box<dyn std::error::Error>>
+where
+    F: FnMut(WalOperation) -> Result<(), Box<dyn std::error::Error>>,
+{
+    let mut summary = WalSummary {
+        batches: 0,
+        operations: 0,
+        first_sequence: None,
+        last_sequence: None,
+    };
+    for item in db.get_updates_since(since)?.take(limit.unwrap_or(usize::MAX)) {
+        let (sequence, batch) = item?;
+        summary.batches += 1;
+        summary.first_sequence.get_or_insert(sequence);
+
+        let operations = decode_batch(batch.data())
+            .map_err(|e| format!("Malformed write batch at sequence {}: {}", sequence, e))?;
+        let mut op_sequence = sequence;
+        for operation in operations {
+            on_operation(WalOperation {
+                sequence: op_sequence,
+                batch_sequence: sequence,
+                op: operation.op,
+                column_family_id: operation.column_family_id,
+                key: to_hex(operation.key),
+                end_key: operation.end_key.map(to_hex),
+                value_len: operation.value.map(<[u8]>::len),
+                value_prefix: operation
+                    .value
+                    .map(|value| to_hex(&value[..value.len().min(VALUE_PREFIX_LEN)])),
+            })?;
+            summary.operations += 1;
+            summary.last_sequence = Some(op_sequence);
+            if operation.consumes_sequence {
+                op_sequence += 1;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Streams the write batches logged after sequence number `since` to `on_batch`, with every
+/// operation and its full key and value, up to `limit` batches
+///
+/// Operations at or before `since` are left out of the first batch, so passing the
+/// `last_sequence` of one run as `since` of the next continues where it stopped. This is the
+/// building block for incremental replication, the summary also reports the latest sequence
+/// number of the database. Only changes still in the WAL files can be streamed.
+pub fn changes<F>(
+    db: &DbHandle,
+    since: u64,
+    limit: Option<usize>,
+    mut on_batch: F,
+) -> Result<ChangesSummary, Box<dyn std::error::Error>>
+where
+    F: FnMut(ChangeBatch) -> Result<(), Box<dyn std::error::Error>>,
+{
+    let mut summary = ChangesSummary {
+        since,
+        batches: 0,
+        operations: 0,
+        last_sequence: None,
+        latest_sequence: db.latest_sequence_number(),
+    };
+    let batches = db.get_updates_since(since.saturating_add(1))?;
+    for item in batches.take(limit.unwrap_or(usize::MAX)) {
+        let (sequence, batch) = item?;
+        let operations = decode_batch(batch.data())
+            .map_err(|e| format!("Malformed write batch at sequence {}: {}", sequence, e))?;
+
+        let mut change = ChangeBatch {
+            sequence,
+            operations: Vec::new(),
+        };
+        let mut op_sequence = sequence;
+        for operation in operations {
+            if op_sequence > since {
+                change.operations.push(ChangeOperation {
+                    sequence: op_sequence,
+                    op: operation.op,
+                    column_family_id: operation.column_family_id,
+                    key: to_hex(operation.key),
+                    end_key: operation.end_key.map(to_hex),
+                    value: operation.value.map(to_hex),
+                });
+            }
+            if operation.consumes_sequence {
+                op_sequence += 1;
+            }
+        }
+        if change.operations.is_empty() {
+            continue;
+        }
+
+        summary.batches += 1;
+        summary.operations += change.operations.len() as u64;
+        summary.last_sequence = change.operations.last().map(|operation| operation.sequence);
+        on_batch(change)?;
+    }
+
+    Ok(summary)
+}
+
+/// One operation of a write batch, borrowing from the batch representation
+struct BatchOperation<'a> {
+    op: &'static str,
+    column_family_id: u32,
+    key: &'a [u8],
+    end_key: Option<&'a [u8]>,
+    value: Option<&'a [u8]>,
+    /// Whether RocksDB assigns the operation its own sequence number, log data does not
+    consumes_sequence: bool,
+}
+
+/// Size of the sequence number and count header of a write batch
+const BATCH_HEADER_LEN: usize = 12;
+
+// Record tags of the write batch format, from RocksDB's db/dbformat.h
+const TAG_DELETION: u8 = 0x0;
+const TAG_VALUE: u8 = 0x1;
+const TAG_MERGE: u8 = 0x2;
+const TAG_LOG_DATA: u8 = 0x3;
+const TAG_CF_DELETION: u8 = 0x4;
+const TAG_CF_VALUE: u8 = 0x5;
+const TAG_CF_MERGE: u8 = 0x6;
+const TAG_SINGLE_DELETION: u8 = 0x7;
+const TAG_CF_SINGLE_DELETION: u8 = 0x8;
+const TAG_NOOP: u8 = 0xD;
+const TAG_CF_RANGE_DELETION: u8 = 0xE;
+const TAG_RANGE_DELETION: u8 = 0xF;
+
+/// Decodes the operations of a write batch representation
+fn decode_batch(data: &[u8]) -> Result<Vec<BatchOperation<'_>>, String> {
+    let mut input = data
+        .get(BATCH_HEADER_LEN..)
+        .ok_or("batch shorter than its header")?;
+
+    let mut operations = Vec::new();
+    while let Some((&tag, rest)) = input.split_first() {
+        input = rest;
+        let column_family_id = match tag {
+            TAG_CF_DELETION | TAG_CF_VALUE | TAG_CF_MERGE | TAG_CF_SINGLE_DELETION
+            | TAG_CF_RANGE_DELETION => read_varint32(&mut input)?,
+            _ => 0,
+        };
+        let (op, has_value) = match tag {
+            TAG_VALUE | TAG_CF_VALUE => ("put", true),
+            TAG_MERGE | TAG_CF_MERGE => ("merge", true),
+            TAG_DELETION | TAG_CF_DELETION => ("delete", false),
+            TAG_SINGLE_DELETION | TAG_CF_SINGLE_DELETION => ("single_delete", false),
+            TAG_RANGE_DELETION | TAG_CF_RANGE_DELETION => ("delete_range", false),
+            TAG_LOG_DATA => ("log_data", false),
+            TAG_NOOP => continue,
+            tag => return Err(format!("unsupported record tag 0x{:02x}", tag)),
+        };
+
+        let key = read_slice(&mut input)?;
+        let mut operation = BatchOperation {
+            op,
+            column_family_id,
+            key,
+            end_key: None,
+            value: None,
+            consumes_sequence: tag != TAG_LOG_DATA,
+        };
+        if op == "delete_range" {
+            operation.end_key = Some(read_slice(&mut input)?);
+        } else if has_value {
+            operation.value = Some(read_slice(&mut input)?);
+        }
+        operations.push(operation);
+    }
+    Ok(operations)
+}
+
+fn read_varint32(input: &mut &[u8]) -> Result<u32, String> {
+    let mut value = 0u32;
+    for shift in (0..35).step_by(7) {
+        let (&byte, rest) = input.split_first().ok_or("truncated varint")?;
+        *input = rest;
+        value |= u32::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("varint longer than 5 bytes".to_string())
+}
+
+fn read_slice<'a>(input: &mut &'a [u8]) -> Result<&'a [u8], String> {
+    let len = read_varint32(input)? as usize;
+    if input.len() < len {
+        return Err("truncated record".to_string());
+    }
+    let (slice, rest) = input.split_at(len);
+    *input = rest;
+    Ok(slice)
+}
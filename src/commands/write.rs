@@ -0,0 +1,89 @@
+use crate::db::DbHandle;
+use crate::output::{to_hex, DeleteSummary, PutResult};
+use crate::range::{self, KeyRange};
+
+/// Writes a single key-value pair, returning the value it replaced if any
+pub fn put(
+    db: &DbHandle,
+    target_cf: &str,
+    key: &[u8],
+    value: &[u8],
+) -> Result<PutResult, Box<dyn std::error::Error>> {
+    let cf = db.cf(target_cf)?;
+
+    let previous_value = db.get_cf_opt(cf, key, &db.read_options())?;
+    db.put_cf(cf, key, value)?;
+
+    Ok(PutResult {
+        column_family: target_cf.to_string(),
+        key: to_hex(key),
+        value_len: value.len(),
+        previous_value: previous_value.map(|value| to_hex(&value)),
+    })
+}
+
+/// Deletes a single key, returning whether it existed; nothing is written when `dry_run` is set
+pub fn delete(
+    db: &DbHandle,
+    target_cf: &str,
+    key: &[u8],
+    dry_run: bool,
+) -> Result<DeleteSummary, Box<dyn std::error::Error>> {
+    let cf = db.cf(target_cf)?;
+
+    let existed = db.get_cf_opt(cf, key, &db.read_options())?.is_some();
+    if existed && !dry_run {
+        db.delete_cf(cf, key)?;
+    }
+
+    Ok(DeleteSummary {
+        column_family: target_cf.to_string(),
+        count: existed as u64,
+        dry_run,
+    })
+}
+
+/// Deletes every key within `range`, passing each affected key to `on_key` first
+///
+/// Keys are counted by iterating the range, the deletion itself is a single range tombstone.
+pub fn delete_range<F>(
+    db: &DbHandle,
+    target_cf: &str,
+    range: &KeyRange,
+    dry_run: bool,
+    mut on_key: F,
+) -> Result<DeleteSummary, Box<dyn std::error::Error>>
+where
+    F: FnMut(&[u8]) -> Result<(), Box<dyn std::error::Error>>,
+{
+    let cf = db.cf(target_cf)?;
+
+    let mut count = 0;
+    let mut first_key = None;
+    let mut last_key = None;
+    for item in range::iter_range(db, cf, range, false) {
+        let (key, _) = item?;
+        on_key(&key)?;
+        if first_key.is_none() {
+            first_key = Some(key.to_vec());
+        }
+        last_key = Some(key);
+        count += 1;
+    }
+
+    if let (Some(first), Some(last), false) = (first_key, last_key, dry_run) {
+        let from = range.start.clone().unwrap_or(first);
+        let to = range.exclusive_end().unwrap_or_else(|| {
+            let mut upper = last.to_vec();
+            upper.push(0);
+            upper
+        });
+        db.delete_range_cf(cf, from, to)?;
+    }
+
+    Ok(DeleteSummary {
+        column_family: target_cf.to_string(),
+        count,
+        dry_run,
+    })
+}
@@ -0,0 +1,152 @@
+//! Shell completion scripts, completing column family names from the database being typed
+
+use clap_complete::Shell;
+use regex::Regex;
+use std::io::{self, Write};
+
+/// Hidden subcommand the scripts run to list the column families of the `--db-path` typed so far
+pub const COMPLETE_CF_COMMAND: &str = "complete-cf";
+
+/// Writes the completion script of `shell` for `cmd`
+///
+/// Bash, zsh and fish scripts complete the values of `--cf` and `--target-cf` with the column
+/// families of the database named by `--db-path` earlier on the line, other shells get the
+/// static completions of clap_complete.
+pub fn write_completions(
+    shell: Shell,
+    cmd: &mut clap::Command,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    let bin = cmd.get_name().to_string();
+    let mut script = Vec::new();
+    clap_complete::generate(shell, cmd, &bin, &mut script);
+    let script = String::from_utf8_lossy(&script);
+
+    let script = match shell {
+        Shell::Bash => bash(&bin, &script),
+        Shell::Zsh => zsh(&bin, &script),
+        Shell::Fish => fish(&bin, &script),
+        _ => script.into_owned(),
+    };
+    out.write_all(script.as_bytes())
+}
+
+/// Name of a shell function helping `bin`, without characters some shells reject
+fn helper_name(bin: &str, suffix: &str) -> String {
+    format!("_{}_{}", bin.replace('-', "_"), suffix)
+}
+
+/// Registers a wrapper completing column families before handing over to the generated function
+fn bash(bin: &str, script: &str) -> String {
+    let wrapper = helper_name(bin, "with_cfs");
+    format!(
+        r#"{script}
+{wrapper}() {{
+    local prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    if [[ "$prev" == "--cf" || "$prev" == "--target-cf" || "$prev" == "-t" ]]; then
+        local i db_path=""
+        for (( i = 1; i < COMP_CWORD; i++ )); do
+            case "${{COMP_WORDS[i]}}" in
+                --db-path|-d) db_path="${{COMP_WORDS[i+1]}}" ;;
+                --db-path=*) db_path="${{COMP_WORDS[i]#--db-path=}}" ;;
+            esac
+        done
+        if [[ -n "$db_path" ]]; then
+            db_path="${{db_path/#\~/$HOME}}"
+            local cfs
+            cfs="$({bin} {command} --db-path "$db_path" 2>/dev/null)"
+            COMPREPLY=( $(compgen -W "$cfs" -- "${{COMP_WORDS[COMP_CWORD]}}") )
+            return 0
+        fi
+    fi
+    _{bin} "$@"
+}}
+
+complete -F {wrapper} -o bashdefault -o default {bin}
+"#,
+        script = script,
+        wrapper = wrapper,
+        bin = bin,
+        command = COMPLETE_CF_COMMAND,
+    )
+}
+
+/// Replaces the empty value action of the column family options with a helper function
+fn zsh(bin: &str, script: &str) -> String {
+    let helper = helper_name(bin, "cfs");
+    let option = Regex::new(r"^\s*'(\*?--cf=|-t\+|--target-cf=)\[").unwrap();
+    let action = Regex::new(r"(:[A-Z_]+:)[^']*'( \\)$").unwrap();
+    let mut lines = script.lines().map(|line| {
+        if option.is_match(line) {
+            action
+                .replace(line, format!("${{1}}{}'${{2}}", helper).as_str())
+                .into_owned()
+        } else {
+            line.to_string()
+        }
+    });
+
+    // The #compdef line has to stay first, the helper must exist before the script runs
+    let mut out = lines.next().unwrap_or_default();
+    out.push_str(&format!(
+        r#"
+
+{helper}() {{
+    local i db_path
+    for (( i = 1; i < CURRENT; i++ )); do
+        case "${{words[i]}}" in
+            --db-path|-d) db_path="${{words[i+1]}}" ;;
+            --db-path=*) db_path="${{words[i]#--db-path=}}" ;;
+        esac
+    done
+    [[ -n "$db_path" ]] || return 1
+    local -a cfs
+    cfs=(${{(f)"$({bin} {command} --db-path "${{~db_path}}" 2>/dev/null)"}})
+    _describe 'column family' cfs
+}}
+"#,
+        helper = helper,
+        bin = bin,
+        command = COMPLETE_CF_COMMAND,
+    ));
+    for line in lines {
+        out.push('\n');
+        out.push_str(&line);
+    }
+    out.push('\n');
+    out
+}
+
+/// Adds the helper function as the argument source of the column family options
+fn fish(bin: &str, script: &str) -> String {
+    let helper = helper_name(bin, "cfs");
+    let option = Regex::new(r" -l (cf|target-cf) ").unwrap();
+    let mut out = format!(
+        r#"function {helper}
+    set -l words (commandline -opc)
+    set -l db_path
+    for i in (seq (count $words))
+        switch $words[$i]
+            case --db-path -d
+                set db_path $words[(math $i + 1)]
+            case '--db-path=*'
+                set db_path (string replace -- --db-path= '' $words[$i])
+        end
+    end
+    test -n "$db_path"; and {bin} {command} --db-path $db_path 2>/dev/null
+end
+
+"#,
+        helper = helper,
+        bin = bin,
+        command = COMPLETE_CF_COMMAND,
+    );
+    for line in script.lines() {
+        out.push_str(line);
+        if option.is_match(line) {
+            out.push_str(&format!(" -f -a \"({})\"", helper));
+        }
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,64 @@
+//! Named profiles in the config file, holding the flags operators pass on every invocation
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+
+/// Path of the config file below the user's config directory
+const CONFIG_FILE: &str = "rocksdb-tool/config.toml";
+
+/// Flags filled in by a profile when the command line leaves them out
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// `--db-path` of every command taking one
+    pub db_path: Option<PathBuf>,
+    /// `--cf` of commands taking a list of column families, and `--target-cf` when it holds one
+    #[serde(default)]
+    pub column_families: Vec<String>,
+    /// Global `--output` format, `text` or `json`
+    pub output: Option<String>,
+    /// Global `--options-file`
+    pub options_file: Option<PathBuf>,
+}
+
+/// Contents of `config.toml`, one `[profiles.<name>]` table per profile
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/rocksdb-tool/config.toml`, falling back to `~/.config`
+    pub fn default_path() -> Option<PathBuf> {
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(config_home.join(CONFIG_FILE))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| Error::Config {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })?;
+        toml::from_str(&text).map_err(|e| Error::Config {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })
+    }
+
+    /// Looks up a profile by name, `path` is the file the config was loaded from
+    pub fn profile(&self, name: &str, path: &Path) -> Result<&Profile> {
+        self.profiles.get(name).ok_or_else(|| Error::UnknownProfile {
+            name: name.to_string(),
+            path: path.to_path_buf(),
+            expected: self.profiles.keys().cloned().collect::<Vec<_>>().join(", "),
+        })
+    }
+}
@@ -0,0 +1,810 @@
+use clap::ValueEnum;
+use rocksdb::{
+    AsColumnFamilyRef, BlockBasedOptions, Cache, ColumnFamily, ColumnFamilyDescriptor,
+    DBCompressionType, DBIteratorWithThreadMode, Env, IteratorMode, Options, ReadOptions,
+    SliceTransform, SnapshotWithThreadMode, DB,
+};
+use std::fmt;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+
+/// How a database is opened
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OpenMode {
+    /// Read-only, writes are refused
+    #[default]
+    ReadOnly,
+    /// Read-write, creating the database and missing column families
+    ReadWrite,
+    /// Secondary instance reading a database another process has open, writes are refused
+    Secondary,
+}
+
+impl OpenMode {
+    /// Fails with [`Error::WriteRefused`] unless the mode allows writes
+    pub fn ensure_writable(self) -> Result<()> {
+        match self {
+            OpenMode::ReadWrite => Ok(()),
+            mode => Err(Error::WriteRefused { mode }),
+        }
+    }
+}
+
+impl fmt::Display for OpenMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OpenMode::ReadOnly => "read-only",
+            OpenMode::ReadWrite => "read-write",
+            OpenMode::Secondary => "secondary",
+        })
+    }
+}
+
+/// Directory for the files of a secondary instance opened by this process, under the system temp dir
+pub fn default_secondary_path() -> PathBuf {
+    std::env::temp_dir().join(format!("playground-rocksdb-tool-secondary-{}", std::process::id()))
+}
+
+/// An open RocksDB database together with the column families it was opened with
+pub struct DbHandle {
+    db: Database,
+    cf_names: Vec<String>,
+    secondary: bool,
+    read: ReadSettings,
+    /// Whether reads keep the view of the database at open, see [`DbOptions::with_snapshot`]
+    pinned: bool,
+}
+
+/// The database of a [`DbHandle`], with the snapshot its reads are made at if it holds one
+enum Database {
+    Live(DB),
+    Snapshot(DbSnapshot),
+}
+
+type Snapshot<'a> = SnapshotWithThreadMode<'a, DB>;
+
+self_cell::self_cell!(
+    /// A database owning a snapshot of itself, released before the database closes
+    struct DbSnapshot {
+        owner: DB,
+
+        #[covariant]
+        dependent: Snapshot,
+    }
+);
+
+/// Read options applied to every read made through a [`DbHandle`]
+#[derive(Clone, Debug)]
+pub struct ReadSettings {
+    /// Bytes iterators read ahead, RocksDB's automatic readahead when `None`
+    pub readahead_size: Option<usize>,
+    /// Whether blocks read are added to the block cache, full scans should not evict the hot blocks
+    pub fill_cache: bool,
+    /// Whether block checksums are verified on every read
+    pub verify_checksums: bool,
+    /// Caps the bytes iterated per second, shared by every handle opened with these settings
+    pub throttle: Option<Arc<ReadThrottle>>,
+    /// Whether scans yield the CPU every [`BACKGROUND_YIELD_INTERVAL`] records, to run alongside
+    /// the prover
+    pub background: bool,
+}
+
+/// Records a background scan reads between yielding the CPU
+pub const BACKGROUND_YIELD_INTERVAL: u64 = 1024;
+
+/// Readahead of background runs unless set explicitly, small enough not to compete with the prover
+pub const BACKGROUND_READAHEAD: usize = 32 << 10;
+
+impl Default for ReadSettings {
+    fn default() -> Self {
+        // RocksDB's own defaults
+        ReadSettings {
+            readahead_size: None,
+            fill_cache: true,
+            verify_checksums: true,
+            throttle: None,
+            background: false,
+        }
+    }
+}
+
+/// Debt allowed to build up before [`ReadThrottle::consume`] sleeps, so it sleeps in a few longer
+/// pauses rather than after every record
+const THROTTLE_SLACK: Duration = Duration::from_millis(50);
+
+/// Limits the bytes read per second across the threads sharing it, to leave disk bandwidth to
+/// other processes
+#[derive(Debug)]
+pub struct ReadThrottle {
+    bytes_per_sec: u64,
+    /// When the bytes consumed so far are paid off at the allowed rate
+    next_free: Mutex<Instant>,
+}
+
+impl ReadThrottle {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        ReadThrottle {
+            bytes_per_sec: bytes_per_sec.max(1),
+            next_free: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Accounts for `bytes` read, sleeping once more than [`THROTTLE_SLACK`] is owed
+    pub fn consume(&self, bytes: usize) {
+        let cost = Duration::from_secs_f64(bytes as f64 / self.bytes_per_sec as f64);
+        let wait = {
+            let mut next_free = self.next_free.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            // Time spent idle is not saved up for later bursts
+            *next_free = (*next_free).max(now) + cost;
+            next_free.saturating_duration_since(now)
+        };
+        if wait > THROTTLE_SLACK {
+            std::thread::sleep(wait);
+        }
+    }
+}
+
+/// Iterator returned by [`DbHandle::iterator_cf_opt`]
+pub struct ThrottledIter<'a> {
+    inner: DBIteratorWithThreadMode<'a, DB>,
+    read: &'a ReadSettings,
+    records: u64,
+}
+
+impl Iterator for ThrottledIter<'_> {
+    type Item = std::result::Result<(Box<[u8]>, Box<[u8]>), rocksdb::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.inner.next()?;
+        if let Ok((key, value)) = &item {
+            self.records += 1;
+            self.read.pace(key.len() + value.len(), self.records);
+        }
+        Some(item)
+    }
+}
+
+impl ReadSettings {
+    /// Waits as the throttle requires after the `records`-th record of a scan, `bytes` long, and
+    /// yields the CPU every [`BACKGROUND_YIELD_INTERVAL`] records in background mode
+    pub fn pace(&self, bytes: usize, records: u64) {
+        if let Some(throttle) = &self.throttle {
+            throttle.consume(bytes);
+        }
+        if self.background && records % BACKGROUND_YIELD_INTERVAL == 0 {
+            std::thread::yield_now();
+        }
+    }
+
+    /// Read options with these settings and no iterate bounds
+    pub fn read_options(&self) -> ReadOptions {
+        let mut opts = ReadOptions::default();
+        if let Some(readahead_size) = self.readahead_size {
+            opts.set_readahead_size(readahead_size);
+        }
+        opts.fill_cache(self.fill_cache);
+        opts.set_verify_checksums(self.verify_checksums);
+        opts
+    }
+}
+
+/// Capacity of the block cache shared by the column families of loaded options
+const OPTIONS_BLOCK_CACHE_SIZE: usize = 64 << 20;
+
+/// Compression codec of new SST files
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum CompressionCodec {
+    None,
+    Snappy,
+    Zlib,
+    Bz2,
+    Lz4,
+    Lz4hc,
+    Zstd,
+}
+
+impl CompressionCodec {
+    /// Name of the codec as given on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompressionCodec::None => "none",
+            CompressionCodec::Snappy => "snappy",
+            CompressionCodec::Zlib => "zlib",
+            CompressionCodec::Bz2 => "bz2",
+            CompressionCodec::Lz4 => "lz4",
+            CompressionCodec::Lz4hc => "lz4hc",
+            CompressionCodec::Zstd => "zstd",
+        }
+    }
+
+    fn to_rocksdb(self) -> DBCompressionType {
+        match self {
+            CompressionCodec::None => DBCompressionType::None,
+            CompressionCodec::Snappy => DBCompressionType::Snappy,
+            CompressionCodec::Zlib => DBCompressionType::Zlib,
+            CompressionCodec::Bz2 => DBCompressionType::Bz2,
+            CompressionCodec::Lz4 => DBCompressionType::Lz4,
+            CompressionCodec::Lz4hc => DBCompressionType::Lz4hc,
+            CompressionCodec::Zstd => DBCompressionType::Zstd,
+        }
+    }
+}
+
+/// Compresses every level of `opts` with `codec`, at the codec's default level unless `level` is
+/// given
+pub fn set_compression(opts: &mut Options, codec: CompressionCodec, level: Option<i32>) {
+    // RocksDB's default window bits, and its marker for the codec's default level
+    const WINDOW_BITS: i32 = -14;
+    const DEFAULT_LEVEL: i32 = 32767;
+    let level = level.unwrap_or(DEFAULT_LEVEL);
+    opts.set_compression_type(codec.to_rocksdb());
+    opts.set_compression_options(WINDOW_BITS, level, 0, 0);
+    opts.set_bottommost_compression_type(codec.to_rocksdb());
+    opts.set_bottommost_compression_options(WINDOW_BITS, level, 0, 0, true);
+}
+
+/// Database and column family options to open a database with, defaults unless loaded from an OPTIONS file
+///
+/// Databases created with non-default options, a prefix extractor in particular, should be opened
+/// with the same options or reads may behave differently.
+#[derive(Default)]
+pub struct DbOptions {
+    db: Options,
+    cf_descriptors: Vec<ColumnFamilyDescriptor>,
+    secondary_path: Option<PathBuf>,
+    block_cache: Option<Cache>,
+    read: ReadSettings,
+    compression: Option<(CompressionCodec, Option<i32>)>,
+    ttl: Option<Duration>,
+    snapshot: bool,
+    prefix_len: Option<usize>,
+}
+
+impl DbOptions {
+    /// Loads the options from the latest OPTIONS file RocksDB wrote in the database directory
+    pub fn load_latest<P: AsRef<Path>>(db_path: P) -> Result<Self> {
+        Self::load_latest_in(db_path.as_ref(), db_path.as_ref())
+    }
+
+    /// Loads the options the database was last opened with, defaults when it has no OPTIONS file
+    pub fn detect<P: AsRef<Path>>(db_path: P) -> Result<Self> {
+        match latest_options_file(&db_path)? {
+            Some(_) => Self::load_latest(db_path),
+            None => Ok(DbOptions::default()),
+        }
+    }
+
+    /// Loads the options from a RocksDB OPTIONS file, for example one copied from a production database
+    pub fn from_file<P: AsRef<Path>>(options_file: P) -> Result<Self> {
+        // RocksDB only loads the latest OPTIONS-<number> file of a directory, removed on drop
+        // whether or not loading succeeds
+        let dir = tempfile::Builder::new()
+            .prefix("playground-rocksdb-tool-options-")
+            .tempdir()?;
+        std::fs::copy(&options_file, dir.path().join("OPTIONS-000001"))?;
+        let options = Self::load_latest_in(dir.path(), options_file.as_ref());
+        dir.close()?;
+        options
+    }
+
+    fn load_latest_in(dir: &Path, reported_path: &Path) -> Result<Self> {
+        let env = Env::new()?;
+        let cache = Cache::new_lru_cache(OPTIONS_BLOCK_CACHE_SIZE);
+        let (db, cf_descriptors) = Options::load_latest(dir, env, true, cache.clone())
+            .map_err(open_error(reported_path))?;
+        Ok(DbOptions {
+            db,
+            cf_descriptors,
+            secondary_path: None,
+            block_cache: Some(cache),
+            read: ReadSettings::default(),
+            compression: None,
+            ttl: None,
+            snapshot: false,
+            prefix_len: None,
+        })
+    }
+
+    /// Sets the capacity of the block cache shared by all column families, in bytes
+    ///
+    /// Loaded options already share a cache of [`OPTIONS_BLOCK_CACHE_SIZE`] bytes which is resized,
+    /// otherwise a new cache replaces RocksDB's small per column family default.
+    pub fn with_block_cache_size(mut self, capacity: usize) -> Self {
+        match &mut self.block_cache {
+            Some(cache) => cache.set_capacity(capacity),
+            None => {
+                let cache = Cache::new_lru_cache(capacity);
+                self.db.set_block_based_table_factory(&block_based_options(&cache));
+                self.block_cache = Some(cache);
+            },
+        }
+        self
+    }
+
+    /// Read options the opened handle applies to all of its reads
+    pub fn with_read_settings(mut self, read: ReadSettings) -> Self {
+        self.read = read;
+        self
+    }
+
+    /// Compresses column families the options do not cover with `codec`, see [`set_compression`]
+    pub fn with_compression(mut self, codec: CompressionCodec, level: Option<i32>) -> Self {
+        set_compression(&mut self.db, codec, level);
+        self.compression = Some((codec, level));
+        self
+    }
+
+    /// Opens the database as a TTL database whose records expire `ttl` after they were written
+    ///
+    /// RocksDB appends the write time to every value of a TTL database and strips it on reads of a
+    /// TTL handle, expired records are dropped by compactions. Only read-write opens support it.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Gives column families the options do not cover a fixed-length prefix extractor of
+    /// `prefix_len` bytes, so prefix scans of databases opened with default options can use it
+    pub fn with_prefix_extractor(mut self, prefix_len: usize) -> Self {
+        self.prefix_len = Some(prefix_len);
+        self
+    }
+
+    /// Makes every read of the opened handle see the database as it was when it was opened
+    ///
+    /// Read-only and read-write opens take a RocksDB snapshot, so a scan and the counts and
+    /// verifications after it agree even while records are written. Secondary instances do not
+    /// support snapshots, they stop catching up with the primary instead.
+    pub fn with_snapshot(mut self) -> Self {
+        self.snapshot = true;
+        self
+    }
+
+    /// Directory [`DbHandle::open`] keeps the files of a secondary instance in, instead of [`default_secondary_path`]
+    pub fn with_secondary_path<P: AsRef<Path>>(mut self, secondary_path: P) -> Self {
+        self.secondary_path = Some(secondary_path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Column family descriptors for `cf_names`, with default options for column families the options do not cover
+    ///
+    /// Those column families still share the configured block cache.
+    fn into_descriptors(
+        self,
+        cf_names: &[String],
+    ) -> (Options, Vec<ColumnFamilyDescriptor>, ReadSettings) {
+        let mut descriptors = self.cf_descriptors;
+        descriptors.retain(|descriptor| cf_names.iter().any(|name| name == descriptor.name()));
+        for name in cf_names {
+            if !descriptors.iter().any(|descriptor| descriptor.name() == name) {
+                let mut cf_opts = Options::default();
+                if let Some(cache) = &self.block_cache {
+                    cf_opts.set_block_based_table_factory(&block_based_options(cache));
+                }
+                if let Some((codec, level)) = self.compression {
+                    set_compression(&mut cf_opts, codec, level);
+                }
+                if let Some(prefix_len) = self.prefix_len {
+                    cf_opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(prefix_len));
+                }
+                descriptors.push(ColumnFamilyDescriptor::new(name, cf_opts));
+            }
+        }
+        (self.db, descriptors, self.read)
+    }
+}
+
+fn block_based_options(cache: &Cache) -> BlockBasedOptions {
+    let mut table_opts = BlockBasedOptions::default();
+    table_opts.set_block_cache(cache);
+    table_opts
+}
+
+/// The OPTIONS-<number> file with the highest number in a database directory, RocksDB writes one on every open
+pub fn latest_options_file<P: AsRef<Path>>(db_path: P) -> Result<Option<PathBuf>> {
+    // Databases opened read-write may not have been created yet
+    if !db_path.as_ref().exists() {
+        return Ok(None);
+    }
+
+    let mut latest: Option<(u64, PathBuf)> = None;
+    for entry in std::fs::read_dir(&db_path)? {
+        let entry = entry?;
+        let number = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.strip_prefix("OPTIONS-"))
+            .and_then(|number| number.parse::<u64>().ok());
+        if let Some(number) = number {
+            if latest.as_ref().map_or(true, |(max, _)| number > *max) {
+                latest = Some((number, entry.path()));
+            }
+        }
+    }
+    Ok(latest.map(|(_, path)| path))
+}
+
+fn open_error<P: AsRef<Path>>(path: P) -> impl FnOnce(rocksdb::Error) -> Error {
+    let path = path.as_ref().to_path_buf();
+    move |source| {
+        // RocksDB reports a held lock as an IO error naming the LOCK file
+        if source.as_ref().contains("lock file") {
+            let holder = describe_lock_holder(&path);
+            Error::Locked {
+                path,
+                holder,
+                source,
+            }
+        } else {
+            Error::Open { path, source }
+        }
+    }
+}
+
+/// Describes who holds the LOCK file of the database at `db_path`, for error messages
+///
+/// The LOCK file itself is empty, the holder is looked up in /proc/locks by the file's inode on Linux.
+/// The IDENTITY file names the database so the holder can be matched to the right instance.
+fn describe_lock_holder(db_path: &Path) -> String {
+    let holder = lock_holder_pid(&db_path.join("LOCK")).map_or_else(
+        || "another process".to_string(),
+        |pid| match process_command(pid) {
+            Some(command) => format!("process {} ({})", pid, command),
+            None => format!("process {}", pid),
+        },
+    );
+    match std::fs::read_to_string(db_path.join("IDENTITY")) {
+        Ok(identity) if !identity.trim().is_empty() => {
+            format!("{} (database id {})", holder, identity.trim())
+        }
+        _ => holder,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn lock_holder_pid(lock_file: &Path) -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = std::fs::metadata(lock_file).ok()?;
+    let dev = metadata.dev();
+    let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+    let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+    // Lines look like "1: POSIX  ADVISORY  WRITE 1234 fd:01:5678 0 EOF", the device numbers in hex
+    let file_id = format!("{:02x}:{:02x}:{}", major, minor, metadata.ino());
+
+    let locks = std::fs::read_to_string("/proc/locks").ok()?;
+    locks.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.get(5) != Some(&file_id.as_str()) {
+            return None;
+        }
+        // Open file description locks are not owned by a process and report a pid of -1
+        fields.get(4)?.parse().ok()
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn lock_holder_pid(_lock_file: &Path) -> Option<u32> {
+    None
+}
+
+fn process_command(pid: u32) -> Option<String> {
+    let command = std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+    Some(command.trim().to_string()).filter(|command| !command.is_empty())
+}
+
+/// Delay between attempts of [`retry_while_locked`]
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Calls `open` until it stops failing with [`Error::Locked`] or `timeout` has passed, once when it is `None`
+///
+/// Lets commands wait for another process, such as a prover, to release a database instead of failing.
+pub fn retry_while_locked<T>(
+    timeout: Option<Duration>,
+    mut open: impl FnMut() -> Result<T>,
+) -> Result<T> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    loop {
+        match open() {
+            Err(Error::Locked { .. }) if deadline.map_or(false, |deadline| Instant::now() < deadline) => {
+                std::thread::sleep(LOCK_RETRY_INTERVAL);
+            },
+            result => return result,
+        }
+    }
+}
+
+/// Span around opening a database, logged with its duration when it closes
+fn open_span<P: AsRef<Path>>(path: P, mode: OpenMode) -> tracing::Span {
+    tracing::info_span!("open_db", path = %path.as_ref().display(), %mode)
+}
+
+fn to_owned_names<N: AsRef<str>>(cf_names: &[N]) -> Vec<String> {
+    cf_names.iter().map(|name| name.as_ref().to_string()).collect()
+}
+
+impl DbHandle {
+    /// Wraps an opened database, pinning reads to its current view when `snapshot` is set, see
+    /// [`DbOptions::with_snapshot`]
+    fn new(
+        db: DB,
+        cf_names: Vec<String>,
+        secondary: bool,
+        read: ReadSettings,
+        snapshot: bool,
+    ) -> Self {
+        // Secondary instances do not support snapshots, they stop catching up instead
+        let db = if snapshot && !secondary {
+            Database::Snapshot(DbSnapshot::new(db, |db| db.snapshot()))
+        } else {
+            Database::Live(db)
+        };
+        DbHandle {
+            db,
+            cf_names,
+            secondary,
+            read,
+            pinned: snapshot,
+        }
+    }
+
+    fn db(&self) -> &DB {
+        match &self.db {
+            Database::Live(db) => db,
+            Database::Snapshot(snapshot) => snapshot.borrow_owner(),
+        }
+    }
+
+    /// The database for changing its column families, which a held snapshot does not allow
+    fn db_mut(&mut self) -> Result<&mut DB> {
+        match &mut self.db {
+            Database::Live(db) => Ok(db),
+            Database::Snapshot(_) => Err(Error::SnapshotHeld),
+        }
+    }
+
+    /// Lists the column families stored in the database at `path`
+    pub fn list_cf_names<P: AsRef<Path>>(path: P) -> Result<Vec<String>> {
+        DB::list_cf(&Options::default(), &path).map_err(open_error(&path))
+    }
+
+    /// Opens a RocksDB database in read-only mode
+    pub fn open_read_only<P: AsRef<Path>, N: AsRef<str>>(
+        path: P,
+        cf_names: &[N],
+    ) -> Result<Self> {
+        Self::open_read_only_with_options(path, cf_names, DbOptions::default())
+    }
+
+    /// Opens a RocksDB database in read-only mode with options loaded from an OPTIONS file
+    pub fn open_read_only_with_options<P: AsRef<Path>, N: AsRef<str>>(
+        path: P,
+        cf_names: &[N],
+        options: DbOptions,
+    ) -> Result<Self> {
+        if options.ttl.is_some() {
+            return Err(Error::TtlNotWritable);
+        }
+        let _span = open_span(&path, OpenMode::ReadOnly).entered();
+        let cf_names = to_owned_names(cf_names);
+        let snapshot = options.snapshot;
+        let (opts, cf_descriptors, read) = options.into_descriptors(&cf_names);
+        let db = DB::open_cf_descriptors_read_only(&opts, &path, cf_descriptors, false)
+            .map_err(open_error(&path))?;
+        Ok(DbHandle::new(db, cf_names, false, read, snapshot))
+    }
+
+    /// Opens a RocksDB database in `mode` with every column family it contains
+    ///
+    /// Secondary instances keep their files in the directory set with [`DbOptions::with_secondary_path`],
+    /// [`default_secondary_path`] if none is set.
+    pub fn open<P: AsRef<Path>>(path: P, mode: OpenMode, options: DbOptions) -> Result<Self> {
+        let cf_names = Self::list_cf_names(&path)?;
+        match mode {
+            OpenMode::ReadOnly => Self::open_read_only_with_options(path, &cf_names, options),
+            OpenMode::ReadWrite => Self::open_read_write_with_options(path, &cf_names, options),
+            OpenMode::Secondary => {
+                let secondary_path = options
+                    .secondary_path
+                    .clone()
+                    .unwrap_or_else(default_secondary_path);
+                Self::open_secondary_with_options(path, secondary_path, &cf_names, options)
+            },
+        }
+    }
+
+    /// Opens a RocksDB database for a command that writes to it, refused unless `mode` is read-write
+    ///
+    /// The database and the column families in `cf_names` are created if missing.
+    pub fn open_writable<P: AsRef<Path>, N: AsRef<str>>(
+        path: P,
+        mode: OpenMode,
+        cf_names: &[N],
+        options: DbOptions,
+    ) -> Result<Self> {
+        mode.ensure_writable()?;
+        Self::open_read_write_with_options(path, cf_names, options)
+    }
+
+    /// Opens a RocksDB database in read-only mode with a fixed-length prefix extractor on every column family
+    pub fn open_read_only_with_prefix<P: AsRef<Path>, N: AsRef<str>>(
+        path: P,
+        cf_names: &[N],
+        prefix_len: usize,
+    ) -> Result<Self> {
+        let options = DbOptions::default().with_prefix_extractor(prefix_len);
+        Self::open_read_only_with_options(path, cf_names, options)
+    }
+
+    /// Opens a RocksDB database in read-write mode, creating the database and any missing column families
+    pub fn open_read_write<P: AsRef<Path>, N: AsRef<str>>(
+        path: P,
+        cf_names: &[N],
+    ) -> Result<Self> {
+        Self::open_read_write_with_options(path, cf_names, DbOptions::default())
+    }
+
+    /// Opens a RocksDB database in read-write mode with options loaded from an OPTIONS file
+    pub fn open_read_write_with_options<P: AsRef<Path>, N: AsRef<str>>(
+        path: P,
+        cf_names: &[N],
+        options: DbOptions,
+    ) -> Result<Self> {
+        let _span = open_span(&path, OpenMode::ReadWrite).entered();
+        // Every existing column family has to be opened in read-write mode
+        let mut all_cf_names = DB::list_cf(&Options::default(), &path).unwrap_or_default();
+        for name in cf_names {
+            if !all_cf_names.iter().any(|existing| existing == name.as_ref()) {
+                all_cf_names.push(name.as_ref().to_string());
+            }
+        }
+
+        let (ttl, snapshot) = (options.ttl, options.snapshot);
+        let (mut opts, cf_descriptors, read) = options.into_descriptors(&all_cf_names);
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+
+        let db = match ttl {
+            Some(ttl) => DB::open_cf_descriptors_with_ttl(&opts, &path, cf_descriptors, ttl),
+            None => DB::open_cf_descriptors(&opts, &path, cf_descriptors),
+        }
+        .map_err(open_error(&path))?;
+        Ok(DbHandle::new(db, all_cf_names, false, read, snapshot))
+    }
+
+    /// Opens a RocksDB database as a secondary instance that can follow a running primary
+    ///
+    /// The secondary keeps its own info log and metadata in `secondary_path`, call
+    /// `try_catch_up_with_primary` to see writes made by the primary since opening.
+    pub fn open_secondary<P: AsRef<Path>, S: AsRef<Path>, N: AsRef<str>>(
+        path: P,
+        secondary_path: S,
+        cf_names: &[N],
+    ) -> Result<Self> {
+        Self::open_secondary_with_options(path, secondary_path, cf_names, DbOptions::default())
+    }
+
+    /// Opens a RocksDB database as a secondary instance with options loaded from an OPTIONS file
+    pub fn open_secondary_with_options<P: AsRef<Path>, S: AsRef<Path>, N: AsRef<str>>(
+        path: P,
+        secondary_path: S,
+        cf_names: &[N],
+        options: DbOptions,
+    ) -> Result<Self> {
+        if options.ttl.is_some() {
+            return Err(Error::TtlNotWritable);
+        }
+        let _span = open_span(&path, OpenMode::Secondary).entered();
+        let cf_names = to_owned_names(cf_names);
+        let snapshot = options.snapshot;
+        let (mut opts, cf_descriptors, read) = options.into_descriptors(&cf_names);
+        // Secondary instances have to keep every table file open to follow the primary
+        opts.set_max_open_files(-1);
+
+        let db = DB::open_cf_descriptors_as_secondary(
+            &opts,
+            path.as_ref(),
+            secondary_path.as_ref(),
+            cf_descriptors,
+        )
+        .map_err(open_error(&path))?;
+        Ok(DbHandle::new(db, cf_names, true, read, snapshot))
+    }
+
+    /// Read options with the handle's [`ReadSettings`], for every read made through it
+    ///
+    /// Reads of a handle opened with [`DbOptions::with_snapshot`] are made at its snapshot.
+    pub fn read_options(&self) -> ReadOptions {
+        let mut opts = self.read.read_options();
+        if let Database::Snapshot(snapshot) = &self.db {
+            opts.set_snapshot(snapshot.borrow_dependent());
+        }
+        opts
+    }
+
+    /// Iterates a column family like [`DB::iterator_cf_opt`], throttled by the read settings
+    pub fn iterator_cf_opt<'a>(
+        &'a self,
+        cf: &impl AsColumnFamilyRef,
+        opts: ReadOptions,
+        mode: IteratorMode,
+    ) -> ThrottledIter<'a> {
+        ThrottledIter {
+            inner: self.db().iterator_cf_opt(cf, opts, mode),
+            read: &self.read,
+            records: 0,
+        }
+    }
+
+    /// Paces the `records`-th record of a scan made without [`DbHandle::iterator_cf_opt`], such as
+    /// through raw iterators, see [`ReadSettings::pace`]
+    pub fn pace(&self, bytes: usize, records: u64) {
+        self.read.pace(bytes, records);
+    }
+
+    /// Whether the database is open as a secondary instance following a primary
+    pub fn is_secondary(&self) -> bool {
+        self.secondary
+    }
+
+    /// Whether the handle sees the writes of a primary as it catches up with it, a secondary
+    /// instance not pinned to a snapshot
+    pub fn follows_primary(&self) -> bool {
+        self.secondary && !self.pinned
+    }
+
+    /// Makes writes the primary has made since opening or the last catch-up visible to new iterators
+    ///
+    /// Does nothing unless the database is open as a secondary instance, or when it is pinned to
+    /// the view at open.
+    pub fn catch_up(&self) -> Result<()> {
+        if self.follows_primary() {
+            self.db().try_catch_up_with_primary()?;
+        }
+        Ok(())
+    }
+
+    /// Creates an empty column family with default options, the database must be open in
+    /// read-write mode
+    ///
+    /// Fails with [`Error::SnapshotHeld`] when reads are made at a snapshot.
+    pub fn create_cf(&mut self, name: &str) -> Result<()> {
+        self.db_mut()?.create_cf(name, &Options::default())?;
+        self.cf_names.push(name.to_string());
+        Ok(())
+    }
+
+    /// Drops a column family and all of its data, the database must be open in read-write mode
+    ///
+    /// Fails with [`Error::SnapshotHeld`] when reads are made at a snapshot.
+    pub fn drop_cf(&mut self, name: &str) -> Result<()> {
+        self.db_mut()?.drop_cf(name)?;
+        self.cf_names.retain(|existing| existing != name);
+        Ok(())
+    }
+
+    /// Names of the column families the database was opened with
+    pub fn cf_names(&self) -> &[String] {
+        &self.cf_names
+    }
+
+    /// Returns the handle of a column family the database was opened with
+    pub fn cf(&self, name: &str) -> Result<&ColumnFamily> {
+        self.db()
+            .cf_handle(name)
+            .ok_or_else(|| Error::MissingColumnFamily(name.to_string()))
+    }
+}
+
+impl Deref for DbHandle {
+    type Target = DB;
+
+    fn deref(&self) -> &DB {
+        self.db()
+    }
+}
@@ -0,0 +1,119 @@
+//! Errors returned by the library, each kind maps to a process exit code of the CLI
+
+use std::path::PathBuf;
+
+use crate::db::OpenMode;
+
+/// Exit code of errors without a more specific code
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit code when a looked up key is not stored in the column family
+pub const EXIT_KEY_NOT_FOUND: i32 = 2;
+/// Exit code when a stored value cannot be decoded
+pub const EXIT_DECODE_ERROR: i32 = 3;
+/// Exit code when the database cannot be opened
+pub const EXIT_OPEN_ERROR: i32 = 4;
+/// Exit code when a column family does not exist
+pub const EXIT_MISSING_CF: i32 = 5;
+/// Exit code when a key or hash given on the command line cannot be parsed
+pub const EXIT_KEY_PARSE_ERROR: i32 = 6;
+/// Exit code when a command would write to a database not opened in read-write mode
+pub const EXIT_WRITE_REFUSED: i32 = 7;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Failed to open database at {path:?}: {source}")]
+    Open {
+        path: PathBuf,
+        source: rocksdb::Error,
+    },
+
+    #[error(
+        "Database at {path:?} is locked by {holder}, open it with --mode secondary to read it while \
+         the other process runs or pass --wait-for-lock to wait for the lock: {source}"
+    )]
+    Locked {
+        path: PathBuf,
+        holder: String,
+        source: rocksdb::Error,
+    },
+
+    #[error("Column family '{0}' not found")]
+    MissingColumnFamily(String),
+
+    #[error("Key {key} not found in column family '{column_family}'")]
+    KeyNotFound { column_family: String, key: String },
+
+    #[error("Failed to decode value as {decoder}: {message}")]
+    Decode {
+        decoder: &'static str,
+        message: String,
+    },
+
+    #[error("Unknown decoder '{name}', expected one of: {expected}")]
+    UnknownDecoder { name: String, expected: String },
+
+    #[error("{0}")]
+    KeyParse(String),
+
+    #[error("Refusing to write to a database opened in {mode} mode, pass --mode read-write to allow it")]
+    WriteRefused { mode: OpenMode },
+
+    #[error("TTL databases can only be opened in read-write mode, pass --mode read-write")]
+    TtlNotWritable,
+
+    #[error("Column families cannot change while reads use a snapshot, run without --snapshot")]
+    SnapshotHeld,
+
+    #[error("Invalid config file {path:?}: {message}")]
+    Config { path: PathBuf, message: String },
+
+    #[error("Unknown profile '{name}' in {path:?}, expected one of: {expected}")]
+    UnknownProfile {
+        name: String,
+        path: PathBuf,
+        expected: String,
+    },
+
+    #[error("Cannot resume from checkpoint file {path:?}: {message}")]
+    Resume { path: PathBuf, message: String },
+
+    #[error(transparent)]
+    RocksDb(#[from] rocksdb::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// Process exit code scripts can use to tell failures apart
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::KeyNotFound { .. } => EXIT_KEY_NOT_FOUND,
+            Error::Decode { .. } => EXIT_DECODE_ERROR,
+            Error::Open { .. } | Error::Locked { .. } | Error::TtlNotWritable => EXIT_OPEN_ERROR,
+            Error::MissingColumnFamily(_) => EXIT_MISSING_CF,
+            Error::KeyParse(_) => EXIT_KEY_PARSE_ERROR,
+            Error::WriteRefused { .. } => EXIT_WRITE_REFUSED,
+            Error::UnknownDecoder { .. }
+            | Error::SnapshotHeld
+            | Error::Config { .. }
+            | Error::UnknownProfile { .. }
+            | Error::Resume { .. }
+            | Error::RocksDb(_)
+            | Error::Io(_)
+            | Error::Json(_) => EXIT_FAILURE,
+        }
+    }
+}
+
+/// Exit code for any error returned by a command, `EXIT_FAILURE` unless it is an [`Error`]
+pub fn exit_code(error: &(dyn std::error::Error + 'static)) -> i32 {
+    error
+        .downcast_ref::<Error>()
+        .map_or(EXIT_FAILURE, Error::exit_code)
+}
@@ -0,0 +1,622 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::io::{self, BufRead, Read, Write};
+use zkwasm_host_circuits::host::{datahash::DataHashRecord, mongomerkle::MerkleRecord};
+
+use crate::field;
+use crate::output::{to_hex, RecordEntry};
+
+/// File format of a column family dump
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// One `{"key": "0x..", "value": "0x.."}` object per line
+    Ndjson,
+    /// `key,value` rows with hex encoded fields
+    Csv,
+    /// Length-prefixed raw bytes: u32 LE key length, key, u32 LE value length, value
+    Binary,
+    /// Archival dump: a versioned header with the column family name, a CRC32 per record and a
+    /// trailer with the record count, see [`DUMP_MAGIC`]
+    Dump,
+}
+
+impl ExportFormat {
+    /// Name of the format as given on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Binary => "binary",
+            ExportFormat::Dump => "dump",
+        }
+    }
+}
+
+/// First bytes of a dump file
+///
+/// A dump is the magic, the u16 LE format version, the u16 LE length and bytes of the column
+/// family name, then per record the u32 LE key length, key, u32 LE value length, value and the
+/// u32 LE CRC32 of these four fields, and finally a `DUMP_END` length followed by the u64 LE
+/// record count.
+pub const DUMP_MAGIC: &[u8; 8] = b"RDBDUMP\0";
+/// Version of the dump layout written by this build
+pub const DUMP_VERSION: u16 = 1;
+/// Key length marking the trailer, records cannot be that large
+const DUMP_END: u32 = u32::MAX;
+
+/// A column of a CSV export, decoded columns fail on values that are not of that record type
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "snake_case")]
+pub enum CsvColumn {
+    /// Key as 0x-prefixed hex
+    KeyHex,
+    /// 32-byte key as four little-endian u64 limbs, empty for other key lengths
+    #[clap(name = "key_u64x4")]
+    KeyU64x4,
+    /// Value as 0x-prefixed hex
+    ValueHex,
+    /// Value length in bytes
+    ValueLen,
+    /// `MerkleRecord` index
+    MerkleIndex,
+    /// `MerkleRecord` hash
+    MerkleHash,
+    /// `MerkleRecord` left child hash, empty for leaves
+    MerkleLeft,
+    /// `MerkleRecord` right child hash, empty for leaves
+    MerkleRight,
+    /// `MerkleRecord` leaf data, empty for inner nodes
+    MerkleData,
+    /// `DataHashRecord` hash
+    DataHash,
+    /// `DataHashRecord` data
+    Data,
+}
+
+impl CsvColumn {
+    fn name(self) -> &'static str {
+        match self {
+            CsvColumn::KeyHex => "key_hex",
+            CsvColumn::KeyU64x4 => "key_u64x4",
+            CsvColumn::ValueHex => "value_hex",
+            CsvColumn::ValueLen => "value_len",
+            CsvColumn::MerkleIndex => "merkle_index",
+            CsvColumn::MerkleHash => "merkle_hash",
+            CsvColumn::MerkleLeft => "merkle_left",
+            CsvColumn::MerkleRight => "merkle_right",
+            CsvColumn::MerkleData => "merkle_data",
+            CsvColumn::DataHash => "data_hash",
+            CsvColumn::Data => "data",
+        }
+    }
+
+    fn is_merkle(self) -> bool {
+        matches!(
+            self,
+            CsvColumn::MerkleIndex
+                | CsvColumn::MerkleHash
+                | CsvColumn::MerkleLeft
+                | CsvColumn::MerkleRight
+                | CsvColumn::MerkleData
+        )
+    }
+
+    fn is_data(self) -> bool {
+        matches!(self, CsvColumn::DataHash | CsvColumn::Data)
+    }
+}
+
+/// Columns and header of CSV exports, `key,value` hex pairs with a header by default
+///
+/// Only the default layout can be imported back.
+#[derive(Clone, Debug)]
+pub struct CsvLayout {
+    pub columns: Vec<CsvColumn>,
+    pub header: bool,
+}
+
+impl Default for CsvLayout {
+    fn default() -> Self {
+        CsvLayout {
+            columns: vec![CsvColumn::KeyHex, CsvColumn::ValueHex],
+            header: true,
+        }
+    }
+}
+
+impl CsvLayout {
+    fn is_default(&self) -> bool {
+        self.columns == [CsvColumn::KeyHex, CsvColumn::ValueHex]
+    }
+}
+
+/// Streams key-value pairs into a writer in the selected format
+pub struct ExportWriter<W: Write> {
+    format: ExportFormat,
+    inner: W,
+    csv: CsvLayout,
+    records: u64,
+}
+
+impl<W: Write> ExportWriter<W> {
+    pub fn new(format: ExportFormat, inner: W) -> io::Result<Self> {
+        Self::for_column_family(format, inner, "", CsvLayout::default())
+    }
+
+    /// A writer for the records of `column_family`, named in dump headers, with CSV rows
+    /// following `csv`
+    pub fn for_column_family(
+        format: ExportFormat,
+        mut inner: W,
+        column_family: &str,
+        csv: CsvLayout,
+    ) -> io::Result<Self> {
+        match format {
+            ExportFormat::Csv if csv.header => {
+                // Imports recognize the `key,value` header of the default layout
+                let names: Vec<&str> = if csv.is_default() {
+                    vec!["key", "value"]
+                } else {
+                    csv.columns.iter().map(|column| column.name()).collect()
+                };
+                writeln!(inner, "{}", names.join(","))?;
+            }
+            ExportFormat::Dump => {
+                let name_len = u16::try_from(column_family.len()).map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "column family name too long")
+                })?;
+                inner.write_all(DUMP_MAGIC)?;
+                inner.write_all(&DUMP_VERSION.to_le_bytes())?;
+                inner.write_all(&name_len.to_le_bytes())?;
+                inner.write_all(column_family.as_bytes())?;
+            }
+            _ => {}
+        }
+        Ok(ExportWriter {
+            format,
+            inner,
+            csv,
+            records: 0,
+        })
+    }
+
+    /// A writer appending to an export cut short after `records` records, the header is already
+    /// written
+    pub fn resume(format: ExportFormat, inner: W, csv: CsvLayout, records: u64) -> Self {
+        ExportWriter {
+            format,
+            inner,
+            csv,
+            records,
+        }
+    }
+
+    pub fn write_record(&mut self, key: &[u8], value: &[u8]) -> io::Result<()> {
+        match self.format {
+            ExportFormat::Ndjson => {
+                serde_json::to_writer(&mut self.inner, &RecordEntry::raw(key, value))?;
+                writeln!(self.inner)
+            }
+            ExportFormat::Csv => {
+                let row = csv_row(&self.csv.columns, key, value)?;
+                writeln!(self.inner, "{}", row)
+            }
+            ExportFormat::Binary => {
+                write_len_prefixed(&mut self.inner, key)?;
+                write_len_prefixed(&mut self.inner, value)
+            }
+            ExportFormat::Dump => {
+                let mut record = Vec::with_capacity(key.len() + value.len() + 8);
+                write_len_prefixed(&mut record, key)?;
+                write_len_prefixed(&mut record, value)?;
+                self.inner.write_all(&record)?;
+                self.inner.write_all(&crc32fast::hash(&record).to_le_bytes())?;
+                self.records += 1;
+                Ok(())
+            }
+        }
+    }
+
+    /// Flushes buffered output and returns the underlying writer, which then holds every record
+    pub fn flush(&mut self) -> io::Result<&W> {
+        self.inner.flush()?;
+        Ok(&self.inner)
+    }
+
+    /// Writes the dump trailer, flushes buffered output and returns the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.format == ExportFormat::Dump {
+            self.inner.write_all(&DUMP_END.to_le_bytes())?;
+            self.inner.write_all(&self.records.to_le_bytes())?;
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+/// Formats the CSV fields of a record, decoding the value once if a decoded column is selected
+fn csv_row(columns: &[CsvColumn], key: &[u8], value: &[u8]) -> io::Result<String> {
+    let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+    let merkle = if columns.iter().any(|column| column.is_merkle()) {
+        Some(MerkleRecord::from_slice(value).map_err(|e| {
+            invalid(format!("value of key {} is not a MerkleRecord: {}", to_hex(key), e))
+        })?)
+    } else {
+        None
+    };
+    let data = if columns.iter().any(|column| column.is_data()) {
+        Some(DataHashRecord::from_slice(value).map_err(|e| {
+            invalid(format!("value of key {} is not a DataHashRecord: {}", to_hex(key), e))
+        })?)
+    } else {
+        None
+    };
+
+    let optional_hex = |bytes: Option<&[u8; 32]>| bytes.map(|bytes| to_hex(bytes)).unwrap_or_default();
+    let fields: Vec<String> = columns
+        .iter()
+        .map(|column| match (column, &merkle, &data) {
+            (CsvColumn::KeyHex, _, _) => to_hex(key),
+            (CsvColumn::KeyU64x4, _, _) => <&[u8; 32]>::try_from(key)
+                .map(|hash| {
+                    let limbs = field::to_u64_limbs(hash);
+                    // Quoted, the limbs are separated by commas
+                    format!("\"[{},{},{},{}]\"", limbs[0], limbs[1], limbs[2], limbs[3])
+                })
+                .unwrap_or_default(),
+            (CsvColumn::ValueHex, _, _) => to_hex(value),
+            (CsvColumn::ValueLen, _, _) => value.len().to_string(),
+            (CsvColumn::MerkleIndex, Some(record), _) => record.index.to_string(),
+            (CsvColumn::MerkleHash, Some(record), _) => to_hex(&record.hash),
+            (CsvColumn::MerkleLeft, Some(record), _) => optional_hex(record.left.as_ref()),
+            (CsvColumn::MerkleRight, Some(record), _) => optional_hex(record.right.as_ref()),
+            (CsvColumn::MerkleData, Some(record), _) => optional_hex(record.data.as_ref()),
+            (CsvColumn::DataHash, _, Some(record)) => to_hex(&record.hash),
+            (CsvColumn::Data, _, Some(record)) => to_hex(&record.data),
+            // Decoded columns always have their record decoded above
+            _ => String::new(),
+        })
+        .collect();
+    Ok(fields.join(","))
+}
+
+pub(crate) fn write_len_prefixed<W: Write>(writer: &mut W, bytes: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(bytes.len())
+        .ok()
+        .filter(|&len| len != DUMP_END)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "record larger than 4GiB"))?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(bytes)
+}
+
+pub(crate) fn read_array<R: Read, const N: usize>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Reads `len` bytes into a buffer grown as they arrive, so a corrupt length read from a file
+/// cannot make it allocate more than the file holds
+fn read_vec<R: Read>(reader: &mut R, len: u32) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.by_ref().take(u64::from(len)).read_to_end(&mut bytes)?;
+    if bytes.len() != len as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("expected {} bytes, the input ends after {}", len, bytes.len()),
+        ));
+    }
+    Ok(bytes)
+}
+
+/// Reads a length-prefixed byte string, `None` when the input ends before its length
+///
+/// Input ending within the length prefix is a truncated file, not a clean end.
+fn read_len_prefixed<R: Read>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut len_bytes = [0u8; 4];
+    let mut filled = 0;
+    while filled < len_bytes.len() {
+        match reader.read(&mut len_bytes[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "input ends within a length prefix",
+                ))
+            }
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    read_vec(reader, u32::from_le_bytes(len_bytes)).map(Some)
+}
+
+fn invalid_data<E: std::fmt::Display>(line: usize, e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line, e))
+}
+
+fn decode_hex_field(field: &str) -> Result<Vec<u8>, hex::FromHexError> {
+    hex::decode(field.trim().trim_start_matches("0x"))
+}
+
+#[derive(Deserialize)]
+struct NdjsonRecord {
+    key: String,
+    value: String,
+}
+
+/// Header of a dump file
+#[derive(Clone, Debug)]
+pub struct DumpHeader {
+    pub version: u16,
+    pub column_family: String,
+}
+
+/// Streams key-value pairs back out of a dump written by `ExportWriter`
+///
+/// Dump records are checked against their CRC32 and the trailer against the number of records
+/// read, a dump without trailer is reported as truncated and bytes after the trailer are invalid.
+pub struct ExportReader<R: BufRead> {
+    format: ExportFormat,
+    inner: R,
+    /// Line number, or record number in dumps
+    line: usize,
+    dump_header: Option<DumpHeader>,
+    finished: bool,
+}
+
+impl<R: BufRead> ExportReader<R> {
+    pub fn new(format: ExportFormat, inner: R) -> Self {
+        ExportReader {
+            format,
+            inner,
+            line: 0,
+            dump_header: None,
+            finished: false,
+        }
+    }
+
+    /// Reads the dump header, done by the first read otherwise
+    pub fn dump_header(&mut self) -> io::Result<&DumpHeader> {
+        if self.dump_header.is_none() {
+            let mut magic = [0u8; 8];
+            self.inner.read_exact(&mut magic)?;
+            if &magic != DUMP_MAGIC {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "not a dump file"));
+            }
+            let version = u16::from_le_bytes(read_array(&mut self.inner)?);
+            if version != DUMP_VERSION {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unsupported dump version {}, expected {}", version, DUMP_VERSION),
+                ));
+            }
+            let name_len = u16::from_le_bytes(read_array(&mut self.inner)?);
+            let mut name = vec![0u8; name_len as usize];
+            self.inner.read_exact(&mut name)?;
+            let column_family = String::from_utf8(name)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            self.dump_header = Some(DumpHeader {
+                version,
+                column_family,
+            });
+        }
+        Ok(self.dump_header.as_ref().expect("header was just read"))
+    }
+
+    fn read_dump_record(&mut self) -> io::Result<Option<(Vec<u8>, Vec<u8>)>> {
+        self.dump_header()?;
+        if self.finished {
+            return Ok(None);
+        }
+        let record_number = self.line + 1;
+        // Only the input ending early means a truncated dump, other errors are passed on as is
+        let truncated = |e: io::Error| {
+            if e.kind() != io::ErrorKind::UnexpectedEof {
+                return e;
+            }
+            io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("dump truncated at record {}", record_number),
+            )
+        };
+
+        let key_len: [u8; 4] = read_array(&mut self.inner).map_err(truncated)?;
+        if u32::from_le_bytes(key_len) == DUMP_END {
+            let count = u64::from_le_bytes(read_array(&mut self.inner).map_err(truncated)?);
+            if count != self.line as u64 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("dump trailer counts {} records, read {}", count, self.line),
+                ));
+            }
+            if !self.inner.fill_buf()?.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "unexpected bytes after the dump trailer",
+                ));
+            }
+            self.finished = true;
+            return Ok(None);
+        }
+
+        // Lengths are not covered by the checksum until the record is read, so buffers grow with
+        // the bytes actually read
+        let key = read_vec(&mut self.inner, u32::from_le_bytes(key_len)).map_err(truncated)?;
+        let value_len: [u8; 4] = read_array(&mut self.inner).map_err(truncated)?;
+        let value = read_vec(&mut self.inner, u32::from_le_bytes(value_len)).map_err(truncated)?;
+        let checksum = u32::from_le_bytes(read_array(&mut self.inner).map_err(truncated)?);
+
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&key_len);
+        hasher.update(&key);
+        hasher.update(&value_len);
+        hasher.update(&value);
+        if hasher.finalize() != checksum {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("checksum mismatch in record {}", record_number),
+            ));
+        }
+
+        self.line = record_number;
+        Ok(Some((key, value)))
+    }
+
+    fn next_line(&mut self) -> io::Result<Option<String>> {
+        loop {
+            let mut line = String::new();
+            if self.inner.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            self.line += 1;
+            let line = line.trim();
+            if !line.is_empty() {
+                return Ok(Some(line.to_string()));
+            }
+        }
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<(Vec<u8>, Vec<u8>)>> {
+        match self.format {
+            ExportFormat::Ndjson => {
+                let line = match self.next_line()? {
+                    Some(line) => line,
+                    None => return Ok(None),
+                };
+                let record: NdjsonRecord =
+                    serde_json::from_str(&line).map_err(|e| invalid_data(self.line, e))?;
+                let key = decode_hex_field(&record.key).map_err(|e| invalid_data(self.line, e))?;
+                let value =
+                    decode_hex_field(&record.value).map_err(|e| invalid_data(self.line, e))?;
+                Ok(Some((key, value)))
+            }
+            ExportFormat::Csv => {
+                let mut line = match self.next_line()? {
+                    Some(line) => line,
+                    None => return Ok(None),
+                };
+                if self.line == 1 && line == "key,value" {
+                    line = match self.next_line()? {
+                        Some(line) => line,
+                        None => return Ok(None),
+                    };
+                }
+                let (key, value) = line
+                    .split_once(',')
+                    .ok_or_else(|| invalid_data(self.line, "expected `key,value`"))?;
+                let key = decode_hex_field(key).map_err(|e| invalid_data(self.line, e))?;
+                let value = decode_hex_field(value).map_err(|e| invalid_data(self.line, e))?;
+                Ok(Some((key, value)))
+            }
+            ExportFormat::Binary => {
+                let key = match read_len_prefixed(&mut self.inner)? {
+                    Some(key) => key,
+                    None => return Ok(None),
+                };
+                let value = read_len_prefixed(&mut self.inner)?.ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::UnexpectedEof, "truncated record value")
+                })?;
+                Ok(Some((key, value)))
+            }
+            ExportFormat::Dump => self.read_dump_record(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for ExportReader<R> {
+    type Item = io::Result<(Vec<u8>, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECORDS: [(&[u8], &[u8]); 3] = [(b"a", b"one"), (b"bb", b""), (&[0; 32], &[7; 64])];
+
+    fn export(format: ExportFormat) -> Vec<u8> {
+        let csv = CsvLayout::default();
+        let mut writer =
+            ExportWriter::for_column_family(format, Vec::new(), "merkle_records", csv).unwrap();
+        for (key, value) in RECORDS {
+            writer.write_record(key, value).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    fn import(format: ExportFormat, bytes: &[u8]) -> io::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        ExportReader::new(format, bytes).collect()
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let expected: Vec<(Vec<u8>, Vec<u8>)> =
+            RECORDS.iter().map(|(key, value)| (key.to_vec(), value.to_vec())).collect();
+        for format in [
+            ExportFormat::Ndjson,
+            ExportFormat::Csv,
+            ExportFormat::Binary,
+            ExportFormat::Dump,
+        ] {
+            let bytes = export(format);
+            assert_eq!(import(format, &bytes).unwrap(), expected, "{}", format.name());
+        }
+    }
+
+    #[test]
+    fn dump_header_names_the_column_family() {
+        let bytes = export(ExportFormat::Dump);
+        let mut reader = ExportReader::new(ExportFormat::Dump, &bytes[..]);
+        let header = reader.dump_header().unwrap();
+        assert_eq!(header.version, DUMP_VERSION);
+        assert_eq!(header.column_family, "merkle_records");
+    }
+
+    #[test]
+    fn truncated_dump_is_unexpected_eof() {
+        let bytes = export(ExportFormat::Dump);
+        // Within a record and within the trailer
+        for len in [bytes.len() - 40, bytes.len() - 3] {
+            let error = import(ExportFormat::Dump, &bytes[..len]).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        }
+    }
+
+    #[test]
+    fn dump_with_trailing_bytes_is_invalid() {
+        let mut bytes = export(ExportFormat::Dump);
+        bytes.push(0);
+        let error = import(ExportFormat::Dump, &bytes).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn corrupt_dump_record_fails_its_checksum() {
+        let mut bytes = export(ExportFormat::Dump);
+        // The last byte of the last value, before its checksum and the trailer
+        let last_value_byte = bytes.len() - 12 - 4 - 1;
+        bytes[last_value_byte] ^= 1;
+        let error = import(ExportFormat::Dump, &bytes).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn corrupt_length_does_not_allocate_it() {
+        let mut bytes = export(ExportFormat::Binary);
+        bytes[..4].copy_from_slice(&(u32::MAX - 1).to_le_bytes());
+        let error = import(ExportFormat::Binary, &bytes).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn binary_cut_within_a_length_prefix_is_unexpected_eof() {
+        let bytes = export(ExportFormat::Binary);
+        let error = import(ExportFormat::Binary, &bytes[..bytes.len() - 66]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,268 @@
+//! C interface for linking the checks of this crate into other programs, declared in
+//! `include/rocksdb_tool.h`
+//!
+//! Functions return 0 on success and the CLI exit code of the error otherwise, the message of the
+//! last error on the calling thread is read with [`rdbt_last_error`]. JSON strings returned through
+//! `out_json` are the objects printed by `--output json`, owned by the caller and freed with
+//! [`rdbt_free_string`].
+
+use serde::Serialize;
+use serde_json::json;
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::commands;
+use crate::db::{self, DbHandle, DbOptions, OpenMode};
+use crate::error::{self, EXIT_FAILURE, EXIT_KEY_NOT_FOUND};
+use crate::interpret::ValueType;
+use crate::merkle;
+use crate::range::KeyRange;
+use crate::record::DecoderRegistry;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+/// Secondary instances opened by this process, numbering their scratch directories
+static SECONDARY_COUNT: AtomicU64 = AtomicU64::new(0);
+
+type FfiResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+/// A database opened by [`rdbt_open`], opaque to C callers
+pub struct RdbtHandle {
+    db: DbHandle,
+    decoders: DecoderRegistry,
+    /// Scratch directory of a secondary instance, removed on close
+    secondary_path: Option<PathBuf>,
+}
+
+impl RdbtHandle {
+    /// The database, caught up with the primary first when it is a secondary instance
+    fn db(&self) -> FfiResult<&DbHandle> {
+        self.db.catch_up()?;
+        Ok(&self.db)
+    }
+}
+
+fn set_last_error(message: &str) {
+    // Interior NUL bytes cannot be passed on in a C string
+    let message = CString::new(message.replace('\0', " ")).ok();
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+}
+
+/// Runs `f`, turning its errors and panics into an exit code and the last error message
+///
+/// A panic must not unwind into the C caller.
+fn run<F: FnOnce() -> FfiResult<c_int>>(f: F) -> c_int {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(code)) => code,
+        Ok(Err(e)) => {
+            set_last_error(&e.to_string());
+            error::exit_code(e.as_ref())
+        }
+        Err(_) => {
+            set_last_error("panicked, see stderr");
+            EXIT_FAILURE
+        }
+    }
+}
+
+/// # Safety
+///
+/// `ptr` must be null or point to a NUL-terminated string that outlives `'a`.
+unsafe fn str_arg<'a>(ptr: *const c_char, name: &str) -> FfiResult<&'a str> {
+    if ptr.is_null() {
+        return Err(format!("{} is null", name).into());
+    }
+    Ok(CStr::from_ptr(ptr).to_str()?)
+}
+
+/// # Safety
+///
+/// `ptr` must be null or point to `len` readable bytes that outlive `'a`.
+unsafe fn bytes_arg<'a>(ptr: *const u8, len: usize, name: &str) -> FfiResult<&'a [u8]> {
+    if ptr.is_null() {
+        return Err(format!("{} is null", name).into());
+    }
+    Ok(std::slice::from_raw_parts(ptr, len))
+}
+
+/// # Safety
+///
+/// `handle` must be null or returned by [`rdbt_open`] and not closed yet.
+unsafe fn handle_arg<'a>(handle: *const RdbtHandle) -> FfiResult<&'a RdbtHandle> {
+    handle.as_ref().ok_or_else(|| "handle is null".into())
+}
+
+/// # Safety
+///
+/// `out_json` must be null or point to writable storage for a pointer.
+unsafe fn write_json<T: Serialize>(out_json: *mut *mut c_char, value: &T) -> FfiResult<()> {
+    if !out_json.is_null() {
+        *out_json = CString::new(serde_json::to_string(value)?)?.into_raw();
+    }
+    Ok(())
+}
+
+/// Opens the database at `db_path` with all its column families, read-only or, when `secondary`
+/// is non-zero, as a secondary instance following a running primary
+///
+/// Every call on a secondary handle first catches up with the primary, so it sees the writes made
+/// until then. Each secondary handle keeps its files in its own scratch directory under the
+/// system temp dir, removed by [`rdbt_close`]. Returns null on failure, see [`rdbt_last_error`].
+///
+/// # Safety
+///
+/// `db_path` must be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn rdbt_open(db_path: *const c_char, secondary: c_int) -> *mut RdbtHandle {
+    let mut handle = ptr::null_mut();
+    run(|| {
+        let path = str_arg(db_path, "db_path")?;
+        let (mode, secondary_path) = if secondary != 0 {
+            // Handles sharing a scratch directory would overwrite each other's files
+            let number = SECONDARY_COUNT.fetch_add(1, Ordering::Relaxed);
+            let mut secondary_path = db::default_secondary_path().into_os_string();
+            secondary_path.push(format!("-{}", number));
+            (OpenMode::Secondary, Some(PathBuf::from(secondary_path)))
+        } else {
+            (OpenMode::ReadOnly, None)
+        };
+        let mut options = DbOptions::default();
+        if let Some(secondary_path) = &secondary_path {
+            options = options.with_secondary_path(secondary_path);
+        }
+        let db = DbHandle::open(path, mode, options)?;
+        handle = Box::into_raw(Box::new(RdbtHandle {
+            db,
+            decoders: DecoderRegistry::default(),
+            secondary_path,
+        }));
+        Ok(0)
+    });
+    handle
+}
+
+/// Closes a database opened by [`rdbt_open`], null is ignored
+///
+/// # Safety
+///
+/// `handle` must be null or returned by [`rdbt_open`], it must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rdbt_close(handle: *mut RdbtHandle) {
+    if !handle.is_null() {
+        let handle = Box::from_raw(handle);
+        let secondary_path = handle.secondary_path.clone();
+        drop(handle);
+        if let Some(secondary_path) = secondary_path {
+            // Only the info logs of the secondary instance are kept there
+            let _ = std::fs::remove_dir_all(secondary_path);
+        }
+    }
+}
+
+/// Looks up `key` in column family `cf` and writes the decoded result to `out_json`
+///
+/// Returns 2 when the key is not stored, the result is written then too.
+///
+/// # Safety
+///
+/// `handle` must be open, `cf` a NUL-terminated string, `key` must point to `key_len` bytes and
+/// `out_json` must be null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn rdbt_check(
+    handle: *const RdbtHandle,
+    cf: *const c_char,
+    key: *const u8,
+    key_len: usize,
+    out_json: *mut *mut c_char,
+) -> c_int {
+    run(|| {
+        let handle = handle_arg(handle)?;
+        let cf = str_arg(cf, "cf")?;
+        let key = bytes_arg(key, key_len, "key")?;
+        let decoder = handle.decoders.resolve(cf, None)?;
+        let result = commands::check(handle.db()?, cf, key, decoder, ValueType::Auto)?;
+        write_json(out_json, &result)?;
+        Ok(if result.found { 0 } else { EXIT_KEY_NOT_FOUND })
+    })
+}
+
+/// Counts the records of column family `cf` into `out_count`, iterating over all of them on as
+/// many threads as there are CPUs
+///
+/// # Safety
+///
+/// `handle` must be open, `cf` a NUL-terminated string and `out_count` must be null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn rdbt_count(
+    handle: *const RdbtHandle,
+    cf: *const c_char,
+    out_count: *mut u64,
+) -> c_int {
+    run(|| {
+        let handle = handle_arg(handle)?;
+        let cf = str_arg(cf, "cf")?;
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let range = KeyRange::default();
+        let result = commands::count(handle.db()?, cf, &range, threads, None, |_| {})?;
+        if !out_count.is_null() {
+            *out_count = result.count;
+        }
+        Ok(0)
+    })
+}
+
+/// Walks the merkle tree below the 32-byte `root` and writes `{"issues": [...], "summary": {...}}`
+/// to `out_json` and the number of issues to `out_issues`
+///
+/// Returns 0 when the tree could be walked, whether or not it has issues.
+///
+/// # Safety
+///
+/// `handle` must be open, `root` must point to 32 bytes and `out_issues` and `out_json` must be
+/// null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn rdbt_verify_merkle(
+    handle: *const RdbtHandle,
+    root: *const u8,
+    include_defaults: c_int,
+    out_issues: *mut u64,
+    out_json: *mut *mut c_char,
+) -> c_int {
+    run(|| {
+        let handle = handle_arg(handle)?;
+        let root = merkle::to_hash(bytes_arg(root, 32, "root")?)?;
+        let (issues, summary) =
+            commands::verify_merkle(handle.db()?, root, include_defaults != 0, |_| {})?;
+        if !out_issues.is_null() {
+            *out_issues = summary.issues;
+        }
+        write_json(out_json, &json!({ "issues": issues, "summary": summary }))?;
+        Ok(0)
+    })
+}
+
+/// Message of the last failed call on this thread, null if none failed
+///
+/// The string stays valid until the next call on this thread.
+#[no_mangle]
+pub extern "C" fn rdbt_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+/// Frees a string returned through `out_json`, null is ignored
+///
+/// # Safety
+///
+/// `json` must be null or returned through `out_json`, it must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rdbt_free_string(json: *mut c_char) {
+    if !json.is_null() {
+        drop(CString::from_raw(json));
+    }
+}
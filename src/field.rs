@@ -0,0 +1,72 @@
+//! Conversions between 32-byte hashes and the u64 limb and field element forms circuits log them in
+
+use num_bigint::BigUint;
+
+/// Modulus of the bn254 scalar field `Fr`, in decimal
+pub const BN254_FR_MODULUS: &str =
+    "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+
+fn modulus() -> BigUint {
+    BigUint::parse_bytes(BN254_FR_MODULUS.as_bytes(), 10)
+        .expect("BN254_FR_MODULUS is a valid decimal integer")
+}
+
+/// Splits a 32-byte hash into its four little-endian u64 limbs
+pub fn to_u64_limbs(bytes: &[u8; 32]) -> [u64; 4] {
+    let mut limbs = [0u64; 4];
+    for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks_exact(8)) {
+        *limb = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    limbs
+}
+
+/// Reads a 32-byte hash as the little-endian representation of a field element, printed in decimal
+pub fn to_field_decimal(bytes: &[u8; 32]) -> String {
+    BigUint::from_bytes_le(bytes).to_string()
+}
+
+/// Whether the little-endian integer in `bytes` is below the bn254 `Fr` modulus
+pub fn is_field_element(bytes: &[u8; 32]) -> bool {
+    BigUint::from_bytes_le(bytes) < modulus()
+}
+
+/// Encodes a decimal big integer as 32 bytes little-endian, the inverse of `to_field_decimal`
+pub fn from_decimal(decimal: &str) -> Result<[u8; 32], String> {
+    let value = BigUint::parse_bytes(decimal.as_bytes(), 10)
+        .ok_or_else(|| format!("Failed to parse decimal integer: {}", decimal))?;
+    let le_bytes = value.to_bytes_le();
+    if le_bytes.len() > 32 {
+        return Err(format!("Decimal integer {} does not fit in 32 bytes", decimal));
+    }
+
+    let mut bytes = [0u8; 32];
+    bytes[..le_bytes.len()].copy_from_slice(&le_bytes);
+    Ok(bytes)
+}
+
+/// Encodes a bn254 `Fr` element into the 32-byte little-endian layout merkle hashes are stored in
+///
+/// Accepts decimal, or `0x` hex as printed by `Fr`'s `Debug` impl (big-endian), optionally wrapped
+/// in `Fr(..)`. Values not below the field modulus are rejected.
+pub fn from_fr_str(fr: &str) -> Result<[u8; 32], String> {
+    let inner = fr
+        .trim()
+        .strip_prefix("Fr(")
+        .and_then(|rest| rest.strip_suffix(')'))
+        .unwrap_or(fr.trim());
+
+    let value = match inner.strip_prefix("0x") {
+        Some(hex) => BigUint::parse_bytes(hex.as_bytes(), 16),
+        None => BigUint::parse_bytes(inner.as_bytes(), 10),
+    }
+    .ok_or_else(|| format!("Failed to parse field element: {}", fr))?;
+
+    if value >= modulus() {
+        return Err(format!("{} is not below the bn254 Fr modulus", fr));
+    }
+
+    let le_bytes = value.to_bytes_le();
+    let mut bytes = [0u8; 32];
+    bytes[..le_bytes.len()].copy_from_slice(&le_bytes);
+    Ok(bytes)
+}
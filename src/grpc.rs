@@ -0,0 +1,250 @@
+//! Read-only gRPC API over a database, defined in `proto/rocksdb_tool.proto`
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+use crate::commands;
+use crate::db::DbHandle;
+use crate::error::Error;
+use crate::merkle;
+use crate::paginate::{Pagination, Paginator, Step};
+use crate::range::{self, KeyRange};
+use crate::record::{DecoderRegistry, RecordDecoder};
+
+/// Messages and service generated from `proto/rocksdb_tool.proto`
+pub mod proto {
+    tonic::include_proto!("rocksdb_tool.v1");
+}
+
+use proto::rocks_db_tool_server::{RocksDbTool, RocksDbToolServer};
+use proto::verify_merkle_event::Event;
+
+/// Responses buffered per stream before the reading thread waits for the client
+const STREAM_BUFFER: usize = 256;
+/// Keys looked up per `batched_multi_get_cf_opt` call of a MultiGet
+const MULTI_GET_BATCH: usize = 1024;
+
+/// The status a failed command is answered with
+fn status(e: &(dyn std::error::Error + 'static)) -> Status {
+    match e.downcast_ref::<Error>() {
+        Some(Error::KeyNotFound { .. } | Error::MissingColumnFamily(_)) => {
+            Status::not_found(e.to_string())
+        }
+        Some(Error::KeyParse(_) | Error::UnknownDecoder { .. }) => {
+            Status::invalid_argument(e.to_string())
+        }
+        _ => Status::internal(e.to_string()),
+    }
+}
+
+/// A decoder name left empty selects the column family default
+fn decode_as(name: &str) -> Option<&str> {
+    Some(name).filter(|name| !name.is_empty())
+}
+
+fn record(decoder: &dyn RecordDecoder, key: &[u8], value: Option<&[u8]>) -> proto::Record {
+    let mut record = proto::Record {
+        key: key.to_vec(),
+        found: value.is_some(),
+        ..proto::Record::default()
+    };
+    if let Some(value) = value {
+        record.value = value.to_vec();
+        match decoder.decode(value) {
+            Ok(decoded) => {
+                record.decoded_json = decoded
+                    .and_then(|decoded| serde_json::to_string(&decoded).ok())
+                    .unwrap_or_default();
+            }
+            Err(e) => record.decode_error = e.to_string(),
+        }
+    }
+    record
+}
+
+struct Service {
+    db: Arc<DbHandle>,
+    decoders: Arc<DecoderRegistry>,
+}
+
+impl Service {
+    /// Runs `f` on the blocking thread pool, RocksDB calls must not block the async workers
+    async fn blocking<T, F>(&self, f: F) -> Result<T, Status>
+    where
+        T: Send + 'static,
+        F: FnOnce(&DbHandle, &DecoderRegistry) -> Result<T, Box<dyn std::error::Error>>
+            + Send
+            + 'static,
+    {
+        let (db, decoders) = (self.db.clone(), self.decoders.clone());
+        tokio::task::spawn_blocking(move || f(&db, &decoders).map_err(|e| status(e.as_ref())))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+    }
+
+    /// Streams the items `f` sends on the blocking thread pool
+    ///
+    /// `f` sees a closed channel once the client cancels the call and should stop then, an error it
+    /// returns ends the stream with that status.
+    fn stream<T, F>(&self, f: F) -> ReceiverStream<Result<T, Status>>
+    where
+        T: Send + 'static,
+        F: FnOnce(
+                &DbHandle,
+                &DecoderRegistry,
+                &mpsc::Sender<Result<T, Status>>,
+            ) -> Result<(), Box<dyn std::error::Error>>
+            + Send
+            + 'static,
+    {
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+        let (db, decoders) = (self.db.clone(), self.decoders.clone());
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = f(&db, &decoders, &tx) {
+                let _ = tx.blocking_send(Err(status(e.as_ref())));
+            }
+        });
+        ReceiverStream::new(rx)
+    }
+}
+
+#[tonic::async_trait]
+impl RocksDbTool for Service {
+    async fn get(
+        &self,
+        request: Request<proto::GetRequest>,
+    ) -> Result<Response<proto::Record>, Status> {
+        let request = request.into_inner();
+        let record = self
+            .blocking(move |db, decoders| {
+                let cf = db.cf(&request.column_family)?;
+                let decoder =
+                    decoders.resolve(&request.column_family, decode_as(&request.decode_as))?;
+                let value = db.get_pinned_cf_opt(cf, &request.key, &db.read_options())?;
+                Ok(record(decoder, &request.key, value.as_deref()))
+            })
+            .await?;
+        Ok(Response::new(record))
+    }
+
+    type MultiGetStream = ReceiverStream<Result<proto::Record, Status>>;
+
+    async fn multi_get(
+        &self,
+        request: Request<proto::MultiGetRequest>,
+    ) -> Result<Response<Self::MultiGetStream>, Status> {
+        let request = request.into_inner();
+        Ok(Response::new(self.stream(move |db, decoders, tx| {
+            let cf = db.cf(&request.column_family)?;
+            let decoder =
+                decoders.resolve(&request.column_family, decode_as(&request.decode_as))?;
+            let read_opts = db.read_options();
+            for batch in request.keys.chunks(MULTI_GET_BATCH) {
+                let values = db.batched_multi_get_cf_opt(cf, batch, false, &read_opts);
+                for (key, value) in batch.iter().zip(values) {
+                    let value = value?;
+                    if tx.blocking_send(Ok(record(decoder, key, value.as_deref()))).is_err() {
+                        return Ok(());
+                    }
+                }
+            }
+            Ok(())
+        })))
+    }
+
+    type ScanStream = ReceiverStream<Result<proto::Record, Status>>;
+
+    async fn scan(
+        &self,
+        request: Request<proto::ScanRequest>,
+    ) -> Result<Response<Self::ScanStream>, Status> {
+        let request = request.into_inner();
+        Ok(Response::new(self.stream(move |db, decoders, tx| {
+            let cf = db.cf(&request.column_family)?;
+            let decoder =
+                decoders.resolve(&request.column_family, decode_as(&request.decode_as))?;
+            let range = KeyRange::new(request.start_key, request.end_key);
+            let mut paginator = Paginator::new(Pagination {
+                skip: request.skip,
+                limit: request.limit,
+                page_size: None,
+            });
+            for item in range::iter_range_following(db, cf, &range, request.reverse) {
+                let (key, value) = item?;
+                match paginator.step() {
+                    Step::Skip => continue,
+                    Step::List => {
+                        if tx.blocking_send(Ok(record(decoder, &key, Some(&*value)))).is_err() {
+                            break;
+                        }
+                    }
+                    Step::Stop => break,
+                }
+            }
+            Ok(())
+        })))
+    }
+
+    type VerifyMerkleStream = ReceiverStream<Result<proto::VerifyMerkleEvent, Status>>;
+
+    async fn verify_merkle(
+        &self,
+        request: Request<proto::VerifyMerkleRequest>,
+    ) -> Result<Response<Self::VerifyMerkleStream>, Status> {
+        let request = request.into_inner();
+        let root = merkle::to_hash(&request.root).map_err(|e| status(&e))?;
+        Ok(Response::new(self.stream(move |db, _, tx| {
+            let (issues, summary) =
+                commands::verify_merkle(db, root, request.include_defaults, |_| {})?;
+            let events = issues
+                .iter()
+                .map(|issue| {
+                    Event::Issue(proto::MerkleIssue {
+                        description: issue.to_string(),
+                        json: serde_json::to_string(issue).unwrap_or_default(),
+                    })
+                })
+                .chain(std::iter::once(Event::Summary(proto::VerifyMerkleSummary {
+                    root: root.to_vec(),
+                    nodes_visited: summary.nodes_visited,
+                    leaves: summary.leaves,
+                    default_nodes: summary.default_nodes,
+                    defaults_included: summary.defaults_included,
+                    issues: summary.issues,
+                })));
+            for event in events {
+                let event = proto::VerifyMerkleEvent { event: Some(event) };
+                if tx.blocking_send(Ok(event)).is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        })))
+    }
+}
+
+/// Serves the gRPC API on `addr` until the process is stopped
+///
+/// RPCs:
+/// - `Get`, a missing key is answered with `found = false`
+/// - `MultiGet`, streaming one record per key in request order
+/// - `Scan`, streaming the records within a key range with `skip` and `limit`
+/// - `VerifyMerkle`, streaming every issue found below a root and then the summary
+pub async fn serve(
+    db: DbHandle,
+    decoders: DecoderRegistry,
+    addr: SocketAddr,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let service = Service {
+        db: Arc::new(db),
+        decoders: Arc::new(decoders),
+    };
+    tonic::transport::Server::builder()
+        .add_service(RocksDbToolServer::new(service))
+        .serve(addr)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,160 @@
+//! Readings of raw value bytes as integers, text, encodings and field elements
+
+use base64::Engine;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fmt;
+
+use crate::error::{Error, Result};
+use crate::field;
+use crate::output::to_hex;
+
+/// How to read the bytes of a value
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ValueType {
+    /// Every reading that fits the value, see [`interpret`]
+    Auto,
+    U16Le,
+    U16Be,
+    U32Le,
+    U32Be,
+    U64Le,
+    U64Be,
+    I64Le,
+    I64Be,
+    /// UTF-8 text
+    Utf8,
+    /// 0x-prefixed hex
+    Hex,
+    /// Standard base64
+    Base64,
+    /// 32 bytes as four little-endian u64 limbs
+    #[clap(name = "u64x4")]
+    U64x4,
+    /// 32 bytes as a little-endian bn254 field element, printed in decimal
+    FieldElement,
+}
+
+impl ValueType {
+    /// Name of the type as given on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            ValueType::Auto => "auto",
+            ValueType::U16Le => "u16-le",
+            ValueType::U16Be => "u16-be",
+            ValueType::U32Le => "u32-le",
+            ValueType::U32Be => "u32-be",
+            ValueType::U64Le => "u64-le",
+            ValueType::U64Be => "u64-be",
+            ValueType::I64Le => "i64-le",
+            ValueType::I64Be => "i64-be",
+            ValueType::Utf8 => "utf8",
+            ValueType::Hex => "hex",
+            ValueType::Base64 => "base64",
+            ValueType::U64x4 => "u64x4",
+            ValueType::FieldElement => "field-element",
+        }
+    }
+}
+
+/// Types tried by [`ValueType::Auto`], hex and base64 read any value and are left out
+const AUTO_TYPES: [ValueType; 11] = [
+    ValueType::U16Le,
+    ValueType::U16Be,
+    ValueType::U32Le,
+    ValueType::U32Be,
+    ValueType::U64Le,
+    ValueType::U64Be,
+    ValueType::I64Le,
+    ValueType::I64Be,
+    ValueType::Utf8,
+    ValueType::U64x4,
+    ValueType::FieldElement,
+];
+
+/// A value read as one type
+#[derive(Serialize)]
+pub struct Interpretation {
+    #[serde(rename = "as")]
+    pub value_type: &'static str,
+    pub value: String,
+}
+
+impl fmt::Display for Interpretation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Value (as {}): {}", self.value_type, self.value)
+    }
+}
+
+fn array<const N: usize>(bytes: &[u8], value_type: ValueType) -> Result<[u8; N]> {
+    bytes.try_into().map_err(|_| Error::Decode {
+        decoder: value_type.name(),
+        message: format!("expected {} bytes, got {}", N, bytes.len()),
+    })
+}
+
+fn read(bytes: &[u8], value_type: ValueType) -> Result<String> {
+    let value = match value_type {
+        ValueType::Auto => unreachable!("auto is expanded by interpret"),
+        ValueType::U16Le => u16::from_le_bytes(array(bytes, value_type)?).to_string(),
+        ValueType::U16Be => u16::from_be_bytes(array(bytes, value_type)?).to_string(),
+        ValueType::U32Le => u32::from_le_bytes(array(bytes, value_type)?).to_string(),
+        ValueType::U32Be => u32::from_be_bytes(array(bytes, value_type)?).to_string(),
+        ValueType::U64Le => u64::from_le_bytes(array(bytes, value_type)?).to_string(),
+        ValueType::U64Be => u64::from_be_bytes(array(bytes, value_type)?).to_string(),
+        ValueType::I64Le => i64::from_le_bytes(array(bytes, value_type)?).to_string(),
+        ValueType::I64Be => i64::from_be_bytes(array(bytes, value_type)?).to_string(),
+        ValueType::Utf8 => std::str::from_utf8(bytes)
+            .map_err(|e| Error::Decode {
+                decoder: value_type.name(),
+                message: e.to_string(),
+            })?
+            .to_string(),
+        ValueType::Hex => to_hex(bytes),
+        ValueType::Base64 => base64::engine::general_purpose::STANDARD.encode(bytes),
+        ValueType::U64x4 => format!("{:?}", field::to_u64_limbs(&array(bytes, value_type)?)),
+        ValueType::FieldElement => {
+            let bytes = array(bytes, value_type)?;
+            if !field::is_field_element(&bytes) {
+                return Err(Error::Decode {
+                    decoder: value_type.name(),
+                    message: "value is not below the bn254 Fr modulus".to_string(),
+                });
+            }
+            field::to_field_decimal(&bytes)
+        }
+    };
+    Ok(value)
+}
+
+fn is_text(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(|c| !c.is_control() || c.is_ascii_whitespace())
+}
+
+/// Reads `bytes` as `value_type`
+///
+/// With [`ValueType::Auto`] every plausible reading is returned: the integers matching the value
+/// length, UTF-8 when the value is valid text without control characters, and the u64 limbs and
+/// field element of 32-byte values. Any other type fails when the bytes do not fit it.
+pub fn interpret(bytes: &[u8], value_type: ValueType) -> Result<Vec<Interpretation>> {
+    if value_type != ValueType::Auto {
+        return Ok(vec![Interpretation {
+            value_type: value_type.name(),
+            value: read(bytes, value_type)?,
+        }]);
+    }
+
+    Ok(AUTO_TYPES
+        .iter()
+        .filter_map(|&value_type| {
+            let value = read(bytes, value_type).ok()?;
+            if value_type == ValueType::Utf8 && !is_text(&value) {
+                return None;
+            }
+            Some(Interpretation {
+                value_type: value_type.name(),
+                value,
+            })
+        })
+        .collect())
+}
@@ -0,0 +1,200 @@
+use base64::Engine;
+use clap::ValueEnum;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::field;
+
+/// How key arguments are interpreted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum KeyFormat {
+    /// `[..]` arrays as in `Array`, anything else as hex
+    #[default]
+    Auto,
+    /// Hex string with optional 0x prefix
+    Hex,
+    /// `[u64; 4]` limbs or u8 array, e.g. "[10,27,44]"
+    Array,
+    /// Standard base64
+    B64,
+    /// Decimal big integer, encoded as 32 bytes little-endian
+    Decimal,
+    /// bn254 `Fr` element in decimal or `0x` big-endian hex, encoded as merkle hashes are stored
+    Fr,
+}
+
+/// Parses a key string into a vector of bytes
+/// Accepts hex strings (e.g., "0x0A1B2C") or array strings (e.g., "[10,27,44]")
+pub fn parse_key(key_str: &str) -> Result<Vec<u8>> {
+    parse_key_as(key_str, KeyFormat::Auto, true)
+}
+
+/// Same as `parse_key` without printing how the input was interpreted, for parsing many keys
+pub fn parse_key_quiet(key_str: &str) -> Result<Vec<u8>> {
+    parse_key_as(key_str, KeyFormat::Auto, false)
+}
+
+/// Parses a key in the given format, with `notes` set the interpretation is printed to stderr
+pub fn parse_key_as(key_str: &str, format: KeyFormat, notes: bool) -> Result<Vec<u8>> {
+    parse_key_inner(key_str, format, notes).map_err(Error::KeyParse)
+}
+
+/// Reads a file with one key per line, blank lines and lines starting with `#` are skipped
+pub fn parse_key_file<P: AsRef<Path>>(path: P, format: KeyFormat) -> Result<Vec<Vec<u8>>> {
+    read_key_file(path, format)?.collect()
+}
+
+/// Like [`parse_key_file`], but parses the keys lazily as the returned iterator is advanced
+pub fn read_key_file<P: AsRef<Path>>(
+    path: P,
+    format: KeyFormat,
+) -> Result<impl Iterator<Item = Result<Vec<u8>>> + Send> {
+    let file = File::open(&path).map_err(|e| {
+        Error::KeyParse(format!("Failed to open key file {:?}: {}", path.as_ref(), e))
+    })?;
+
+    let keys = BufReader::new(file)
+        .lines()
+        .enumerate()
+        .filter_map(move |(line_no, line)| {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(Error::KeyParse(format!("Failed to read key file: {}", e)))),
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+            Some(
+                parse_key_inner(line, format, false)
+                    .map_err(|e| Error::KeyParse(format!("line {}: {}", line_no + 1, e))),
+            )
+        });
+    Ok(keys)
+}
+
+/// Reads a file with one `INDEX HASH` pair per line, separated by whitespace or a comma
+///
+/// Hashes are parsed in `format`, blank lines and lines starting with `#` are skipped.
+pub fn parse_leaf_file<P: AsRef<Path>>(path: P, format: KeyFormat) -> Result<Vec<(u64, Vec<u8>)>> {
+    let file = File::open(&path).map_err(|e| {
+        Error::KeyParse(format!("Failed to open leaf file {:?}: {}", path.as_ref(), e))
+    })?;
+
+    let mut leaves = Vec::new();
+    for (line_no, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| Error::KeyParse(format!("Failed to read leaf file: {}", e)))?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parse_error =
+            |message: String| Error::KeyParse(format!("line {}: {}", line_no + 1, message));
+        let (index, hash) = line
+            .split_once(|c: char| c.is_whitespace() || c == ',')
+            .ok_or_else(|| parse_error("expected an index and a hash".to_string()))?;
+        let index = index
+            .parse::<u64>()
+            .map_err(|e| parse_error(format!("invalid index '{}': {}", index, e)))?;
+        let hash = parse_key_inner(hash.trim(), format, false).map_err(parse_error)?;
+        leaves.push((index, hash));
+    }
+    Ok(leaves)
+}
+
+fn parse_key_inner(
+    key_str: &str,
+    format: KeyFormat,
+    notes: bool,
+) -> std::result::Result<Vec<u8>, String> {
+    match format {
+        KeyFormat::Auto if key_str.starts_with('[') && key_str.ends_with(']') => {
+            parse_array(key_str, notes)
+        }
+        KeyFormat::Auto | KeyFormat::Hex => parse_hex(key_str, notes),
+        KeyFormat::Array => parse_array(key_str, notes),
+        KeyFormat::B64 => base64::engine::general_purpose::STANDARD
+            .decode(key_str.trim())
+            .map_err(|e| format!("Failed to parse base64 string: {}", e)),
+        KeyFormat::Decimal => field::from_decimal(key_str.trim()).map(|bytes| bytes.to_vec()),
+        KeyFormat::Fr => field::from_fr_str(key_str).map(|bytes| bytes.to_vec()),
+    }
+}
+
+fn parse_array(key_str: &str, notes: bool) -> std::result::Result<Vec<u8>, String> {
+    if !(key_str.starts_with('[') && key_str.ends_with(']')) {
+        return Err(format!("Expected an array like \"[10,27,44]\", got \"{}\"", key_str));
+    }
+
+    // Parse array format: [10,27,44]
+    let contents = &key_str[1..key_str.len() - 1];
+
+    // Count how many elements are in the array
+    let element_count = contents.split(',').count();
+
+    if element_count == 4 {
+        // Parse 4 length array as u64 (could be [u64; 4])
+        let values: std::result::Result<Vec<u64>, _> = contents
+            .split(',')
+            .map(|s| {
+                let s = s.trim().replace("_u64", "");
+                s.parse::<u64>()
+            })
+            .collect();
+
+        if let Ok(u64_values) = values {
+            if notes {
+                tracing::info!("Parsed input as [u64; 4]");
+            }
+            let mut bytes = Vec::with_capacity(u64_values.len() * 8);
+            for val in u64_values {
+                bytes.extend_from_slice(&val.to_le_bytes());
+            }
+            return Ok(bytes);
+        }
+    }
+
+    if element_count == 32 && notes {
+        tracing::info!("Parsed input as [u8; 32]");
+    }
+
+    // Parse as regular u8 array
+    let values: std::result::Result<Vec<u8>, _> = contents
+        .split(',')
+        .map(|s| s.trim().parse::<u8>())
+        .collect();
+
+    values.map_err(|e| format!("Failed to parse array format: {}", e))
+}
+
+fn parse_hex(key_str: &str, notes: bool) -> std::result::Result<Vec<u8>, String> {
+    // Parse hex string format
+    let hex_str = if key_str.starts_with("0x") {
+        // Remove "0x" prefix
+        &key_str[2..]
+    } else {
+        key_str
+    };
+
+    let bytes =
+        hex::decode(hex_str).map_err(|e| format!("Failed to parse hex string: {}", e))?;
+
+    if !notes {
+        return Ok(bytes);
+    }
+
+    // If the byte length is 32, check if this might be a [u8; 32] or [u64; 4]
+    if bytes.len() == 32 {
+        tracing::info!("Detected 32-byte key (compatible with [u8; 32] or [u64; 4])");
+    } else if bytes.len() % 8 == 0 && bytes.len() > 0 {
+        tracing::info!(
+            "Detected {}-byte key ({} u64 values)",
+            bytes.len(),
+            bytes.len() / 8
+        );
+    }
+
+    Ok(bytes)
+}
@@ -0,0 +1,48 @@
+//! Inspection tooling for the RocksDB stores written by zkwasm-host-circuits.
+//!
+//! The `playground-rocksdb-tool` binary is a thin CLI over this crate, the same
+//! commands can be called directly through [`commands`] with a [`DbHandle`].
+
+#[cfg(feature = "tui")]
+pub mod browse;
+pub mod commands;
+pub mod completions;
+pub mod config;
+pub mod db;
+pub mod error;
+pub mod export;
+#[cfg(feature = "cdylib")]
+pub mod ffi;
+pub mod field;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod interpret;
+pub mod key;
+pub mod logging;
+pub mod merkle;
+#[cfg(feature = "serve")]
+pub mod metrics;
+#[cfg(feature = "mongo")]
+pub mod mongo;
+pub mod output;
+pub mod paginate;
+pub mod patch;
+pub mod priority;
+pub mod progress;
+pub mod range;
+pub mod record;
+pub mod rpc;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod timing;
+
+pub use db::{DbHandle, OpenMode};
+pub use error::{Error, Result};
+pub use key::{parse_key, parse_key_file, parse_leaf_file, read_key_file};
+pub use record::{DecodedRecord, DecoderRegistry, RecordDecoder};
+
+pub const MERKLE_CF_NAME: &str = "merkle_records";
+pub const DATA_CF_NAME: &str = "data_records";
+
+/// Column families written by mongomerkle and datahash
+pub const DEFAULT_CF_NAMES: [&str; 2] = [MERKLE_CF_NAME, DATA_CF_NAME];
@@ -0,0 +1,39 @@
+//! Diagnostic logging to stderr, command results stay on stdout
+
+use clap::ValueEnum;
+use tracing::Level;
+use tracing_subscriber::fmt::format::FmtSpan;
+
+/// Format of the log lines written to stderr
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Human readable lines
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// Most detailed level logged after `-v` is given `verbosity` times
+pub fn level(verbosity: u8) -> Level {
+    match verbosity {
+        0 => Level::WARN,
+        1 => Level::INFO,
+        2 => Level::DEBUG,
+        _ => Level::TRACE,
+    }
+}
+
+/// Installs the global logger, does nothing when one is already installed
+///
+/// Spans are logged with their duration when they close, which times database opens and scans
+/// from `-v` on and the decoding of every value with `-vvv`.
+pub fn init(verbosity: u8, format: LogFormat) {
+    let builder = tracing_subscriber::fmt()
+        .with_max_level(level(verbosity))
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(std::io::stderr);
+    let _ = match format {
+        LogFormat::Text => builder.try_init(),
+        LogFormat::Json => builder.json().try_init(),
+    };
+}
@@ -1,11 +1,136 @@
-use clap::{Parser, Subcommand};
-use hex;
-use std::path::PathBuf;
-use zkwasm_host_circuits::host::{datahash::DataHashRecord, mongomerkle::MerkleRecord};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use playground_rocksdb_tool::completions::write_completions;
+use playground_rocksdb_tool::config::{Config, Profile};
+use clap_complete::Shell;
+use playground_rocksdb_tool::commands::{
+    self, CheckpointFile, ConflictPolicy, CopyMethod, LeafSelector, RecordFields, RecordType,
+    TruncateMethod, ValuePattern, WriteDurability,
+};
+use playground_rocksdb_tool::db::{
+    self, CompressionCodec, DbOptions, OpenMode, ReadSettings, ReadThrottle,
+};
+use playground_rocksdb_tool::error;
+use playground_rocksdb_tool::export::{CsvColumn, CsvLayout, ExportFormat};
+use playground_rocksdb_tool::interpret::ValueType;
+use playground_rocksdb_tool::key::{parse_key_as, KeyFormat};
+use playground_rocksdb_tool::logging::{self, LogFormat};
+use playground_rocksdb_tool::merkle;
+#[cfg(feature = "mongo")]
+use playground_rocksdb_tool::mongo::{MongoRecordType, MongoSource};
+use playground_rocksdb_tool::output::{to_hex, KeyEntry, Output, OutputFormat, RecordEntry};
+use playground_rocksdb_tool::paginate::Pagination;
+use playground_rocksdb_tool::patch::PatchReader;
+use playground_rocksdb_tool::priority;
+use playground_rocksdb_tool::progress::Progress;
+use playground_rocksdb_tool::range::KeyRange;
+use playground_rocksdb_tool::timing::Timer;
+use playground_rocksdb_tool::{
+    parse_key_file, parse_leaf_file, read_key_file, DbHandle, DecodedRecord, DecoderRegistry, Error,
+    DEFAULT_CF_NAMES, MERKLE_CF_NAME,
+};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{BufReader, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[clap(author, version, about = "CLI tool to check RocksDB key-value pairs")]
 struct Cli {
+    /// Output format of command results
+    #[clap(long, global = true, value_enum, default_value = "text")]
+    output: OutputFormat,
+
+    /// Log more to stderr: -v status messages and open/scan timings, -vv debug, -vvv every decode
+    #[clap(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Format of the log lines written to stderr
+    #[clap(long, global = true, value_enum, default_value = "text")]
+    log_format: LogFormat,
+
+    /// How key, prefix and hash arguments are written: auto detects arrays and otherwise reads hex
+    #[clap(long, global = true, value_enum, default_value = "auto")]
+    key_format: KeyFormat,
+
+    /// How databases are opened, commands that write refuse to run unless this is read-write
+    #[clap(long, global = true, value_enum, default_value = "read-only")]
+    mode: OpenMode,
+
+    /// Open databases as a secondary instance keeping its files in this scratch directory, to read while the prover runs
+    #[clap(long, global = true, value_name = "SCRATCH_DIR", conflicts_with = "mode")]
+    secondary: Option<PathBuf>,
+
+    /// RocksDB OPTIONS file to open databases with, instead of the database's own latest one
+    #[clap(long, global = true)]
+    options_file: Option<PathBuf>,
+
+    /// Open databases with default options instead of those in their latest OPTIONS file
+    #[clap(long, global = true, conflicts_with = "options_file")]
+    default_options: bool,
+
+    /// Read every database at a snapshot taken when it is opened, so all reads of the command, or
+    /// of a serve or rpc session, see one consistent view while others write to it
+    #[clap(long, global = true)]
+    snapshot: bool,
+
+    /// Seconds to keep retrying when another process holds the database lock, instead of failing at once
+    #[clap(long, global = true, value_name = "SECS")]
+    wait_for_lock: Option<u64>,
+
+    /// Open databases created with a TTL of this many seconds, stripping the write times RocksDB
+    /// appends to their values and expiring older records, needs --mode read-write
+    #[clap(long, global = true, value_name = "SECS")]
+    ttl: Option<u64>,
+
+    /// Capacity of the block cache shared by all column families, in MiB
+    #[clap(long, global = true)]
+    block_cache_mb: Option<usize>,
+
+    /// Bytes iterators read ahead, in KiB, larger values speed up scans on spinning disks
+    #[clap(long, global = true)]
+    readahead_kb: Option<usize>,
+
+    /// MiB of keys and values scans and exports may read per second, to leave disk bandwidth to
+    /// the prover
+    #[clap(long, global = true, value_name = "MB")]
+    rate_limit_mb: Option<u64>,
+
+    /// Run at the lowest CPU and idle IO priority with a small readahead, yielding the CPU during
+    /// scans, for long audits alongside the prover
+    #[clap(long, global = true)]
+    background: bool,
+
+    /// Whether blocks read are added to the block cache, pass false for full scans
+    #[clap(long, global = true, action = clap::ArgAction::Set, default_value = "true")]
+    fill_cache: bool,
+
+    /// Whether block checksums are verified on every read
+    #[clap(long, global = true, action = clap::ArgAction::Set, default_value = "true")]
+    verify_checksums: bool,
+
+    /// Do not draw progress bars on stderr, for CI logs
+    #[clap(long, global = true)]
+    no_progress: bool,
+
+    /// Print the wall-clock time and RocksDB perf counters of the command on stderr
+    #[clap(long, global = true)]
+    timing: bool,
+
+    /// Print every RocksDB perf context counter of the command on stderr, timing mutex waits too
+    #[clap(long, global = true)]
+    perf: bool,
+
+    /// Config file profile filling in --db-path, --cf, --output and --options-file when not given
+    #[clap(long, global = true)]
+    profile: Option<String>,
+
+    /// Config file holding the profiles, defaults to ~/.config/rocksdb-tool/config.toml
+    #[clap(long, global = true)]
+    config: Option<PathBuf>,
+
     #[clap(subcommand)]
     command: Commands,
 }
@@ -18,191 +143,3237 @@ enum Commands {
         #[clap(short, long)]
         db_path: PathBuf,
 
-        /// Target column family to look up in the database, should either be "merkle_records" or "data_records"
+        /// Target column family to look up in the database, any column family, "merkle_records" and "data_records" are decoded automatically
         #[clap(short, long)]
         target_cf: String,
 
         /// Key to look up in the database (hex string like "0A1B2C" or array format like "[10,27,44]")
         #[clap(short, long)]
         key: String,
+
+        /// Decoder for the value ("merkle", "data" or "raw"), defaults to the one registered for the column family
+        #[clap(long)]
+        decode_as: Option<String>,
+
+        /// Print each field of the decoded record on its own line as hex, u64 limbs and field element
+        #[clap(long)]
+        pretty: bool,
+
+        /// Write the data payload of a DataHashRecord to this file instead of printing it
+        #[clap(long)]
+        dump_data: Option<PathBuf>,
+
+        /// Print the value as an xxd-style hexdump with offsets and an ASCII column
+        #[clap(long)]
+        hexdump: bool,
+
+        /// Read the value as this type, "auto" prints every reading that fits the value
+        #[clap(long = "as", value_enum, default_value = "auto")]
+        value_type: ValueType,
+
+        /// Print nothing, only report the outcome through the exit code (0 found, 2 not found, other codes on errors)
+        #[clap(short, long)]
+        quiet: bool,
+    },
+    /// Decode a value pasted from logs or read from a file as a MerkleRecord or DataHashRecord
+    Decode {
+        /// Value to decode (hex string like "0A1B2C" or array format like "[10,27,44]")
+        #[clap(long, required_unless_present = "file", conflicts_with = "file")]
+        value: Option<String>,
+
+        /// File holding the raw value bytes
+        #[clap(long)]
+        file: Option<PathBuf>,
+
+        /// Decoder for the value ("merkle" or "data"), every decoder is tried when it is not given
+        #[clap(long)]
+        decode_as: Option<String>,
+
+        /// Print each field of the decoded record on its own line as hex, u64 limbs and field element
+        #[clap(long)]
+        pretty: bool,
+    },
+    /// Serialize a MerkleRecord or DataHashRecord from its fields and print its key and value bytes
+    Encode {
+        /// Type of the record given by the field flags
+        #[clap(long, value_enum, required_unless_present = "json_file")]
+        record_type: Option<RecordType>,
+
+        /// Leaf index of a merkle record
+        #[clap(long)]
+        index: Option<u64>,
+
+        /// Hash of the record, which is also its key
+        #[clap(long)]
+        hash: Option<String>,
+
+        /// Left child hash of a merkle record
+        #[clap(long)]
+        left: Option<String>,
+
+        /// Right child hash of a merkle record
+        #[clap(long)]
+        right: Option<String>,
+
+        /// Data hash of a merkle leaf, or the payload bytes of a data record
+        #[clap(long)]
+        data: Option<String>,
+
+        /// JSON file holding a decoded record object as printed by `--output json`
+        #[clap(
+            long,
+            conflicts_with_all = &["record_type", "index", "hash", "left", "right", "data"]
+        )]
+        json_file: Option<PathBuf>,
     },
+    /// Count the records of a column family
     CountRocksDb {
         /// Path to the RocksDB database directory
         #[clap(short, long)]
         db_path: PathBuf,
 
-        /// Target column family to look up in the database, should either be "merkle_records" or "data_records"
+        /// Target column family to look up in the database, any column family, "merkle_records" and "data_records" are decoded automatically
         #[clap(short, long)]
         target_cf: String,
+
+        /// Number of threads counting keyspace shards in parallel, defaults to the number of CPUs
+        #[clap(long)]
+        threads: Option<usize>,
+
+        /// Report RocksDB's estimate-num-keys property instead of iterating
+        #[clap(long, conflicts_with_all = &["start_key", "end_key"])]
+        approximate: bool,
+
+        /// First key to count (inclusive), counts from the beginning if omitted
+        #[clap(long)]
+        start_key: Option<String>,
+
+        /// Last key to count (inclusive), counts to the end if omitted
+        #[clap(long)]
+        end_key: Option<String>,
+
+        /// File the position of the scan is saved to periodically, see --resume
+        #[clap(long, conflicts_with = "approximate")]
+        checkpoint_file: Option<PathBuf>,
+
+        /// Continue after the position saved in --checkpoint-file instead of starting over
+        #[clap(long, requires = "checkpoint_file")]
+        resume: bool,
     },
-}
+    /// Scan key-value pairs of a column family within a key range
+    Scan {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
 
-/// Opens a RocksDB database in read-only mode
-fn create_read_only_db_handler(rocksdb_path: PathBuf, cf_names: Vec<&str>) -> rocksdb::DB {
-    rocksdb::DB::open_cf_for_read_only(&rocksdb::Options::default(), rocksdb_path, cf_names, false)
-        .expect("Should be able to open db")
-}
+        /// Target column family to look up in the database, any column family, "merkle_records" and "data_records" are decoded automatically
+        #[clap(short, long)]
+        target_cf: String,
 
-/// Parses a key string into a vector of bytes
-/// Accepts hex strings (e.g., "0x0A1B2C") or array strings (e.g., "[10,27,44]")
-fn parse_key(key_str: &str) -> Result<Vec<u8>, String> {
-    if key_str.starts_with('[') && key_str.ends_with(']') {
-        // Parse array format: [10,27,44]
-        let contents = &key_str[1..key_str.len() - 1];
-
-        // Count how many elements are in the array
-        let element_count = contents.split(',').count();
-
-        if element_count == 4 {
-            // Parse 4 length array as u64 (could be [u64; 4])
-            let values: Result<Vec<u64>, _> = contents
-                .split(',')
-                .map(|s| {
-                    let s = s.trim().replace("_u64", "");
-                    s.parse::<u64>()
-                })
-                .collect();
-
-            if let Ok(u64_values) = values {
-                println!("Parsed input as [u64; 4]");
-                let mut bytes = Vec::with_capacity(u64_values.len() * 8);
-                for val in u64_values {
-                    bytes.extend_from_slice(&val.to_le_bytes());
-                }
-                return Ok(bytes);
-            }
-        }
+        /// First key of the range (inclusive), scans from the beginning if omitted
+        #[clap(long)]
+        start_key: Option<String>,
 
-        if element_count == 32 {
-            println!("Parsed input as [u8; 32]");
-        }
+        /// Last key of the range (inclusive), scans to the end if omitted
+        #[clap(long)]
+        end_key: Option<String>,
 
-        // Parse as regular u8 array
-        let values: Result<Vec<u8>, _> = contents
-            .split(',')
-            .map(|s| s.trim().parse::<u8>())
-            .collect();
+        /// Maximum number of records to print
+        #[clap(short, long)]
+        limit: Option<u64>,
 
-        values.map_err(|e| format!("Failed to parse array format: {}", e))
-    } else {
-        // Parse hex string format
-        let hex_str = if key_str.starts_with("0x") {
-            // Remove "0x" prefix
-            &key_str[2..]
-        } else {
-            key_str
-        };
+        /// Number of records to skip before the first printed one
+        #[clap(long, default_value = "0")]
+        skip: u64,
 
-        let bytes =
-            hex::decode(hex_str).map_err(|e| format!("Failed to parse hex string: {}", e))?;
-
-        // If the byte length is 32, check if this might be a [u8; 32] or [u64; 4]
-        if bytes.len() == 32 {
-            println!("Detected 32-byte key (compatible with [u8; 32] or [u64; 4])");
-        } else if bytes.len() % 8 == 0 && bytes.len() > 0 {
-            println!(
-                "Detected {}-byte key ({} u64 values)",
-                bytes.len(),
-                bytes.len() / 8
-            );
-        }
+        /// Print a page break with the --skip value of the next page after every this many records
+        #[clap(long)]
+        page_size: Option<u64>,
 
-        Ok(bytes)
-    }
-}
+        /// Iterate from the end of the range towards the start
+        #[clap(short, long)]
+        reverse: bool,
+    },
+    /// Scan all keys of a column family that start with a given prefix
+    PrefixScan {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
 
-const MERKLE_CF_NAME: &str = "merkle_records";
-const DATA_CF_NAME: &str = "data_records";
+        /// Target column family to look up in the database, any column family, "merkle_records" and "data_records" are decoded automatically
+        #[clap(short, long)]
+        target_cf: String,
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let cli = Cli::parse();
+        /// Key prefix to match (hex string like "0A1B2C" or array format like "[10,27,44]")
+        #[clap(short, long)]
+        prefix: String,
 
-    match &cli.command {
-        Commands::CheckRocksDb {
-            db_path,
-            target_cf,
-            key,
-        } => {
-            println!("Checking RocksDB at path: {:?}", db_path);
+        /// Decoder for the values ("merkle", "data" or "raw"), defaults to the one registered for the column family
+        #[clap(long)]
+        decode_as: Option<String>,
 
-            // Parse the key
-            let key_bytes = parse_key(key).map_err(|e| {
-                eprintln!("Error parsing key: {}", e);
-                e
-            })?;
+        /// Maximum number of records to print
+        #[clap(short, long)]
+        limit: Option<u64>,
 
-            println!("Looking for key (bytes): {:?}", key_bytes);
-            let cf_names = vec![MERKLE_CF_NAME, DATA_CF_NAME];
-            // Open the database
-            let db = create_read_only_db_handler(db_path.clone(), cf_names);
+        /// Number of records to skip before the first printed one
+        #[clap(long, default_value = "0")]
+        skip: u64,
 
-            let cf = db
-                .cf_handle(target_cf)
-                .expect("Should be able to get cf handle");
-            // Try to get the value
-            match db.get_cf(cf, &key_bytes) {
-                Ok(Some(value)) => {
-                    println!("Key found!");
-                    println!("Value (bytes): {:?}", value);
+        /// Print a page break with the --skip value of the next page after every this many records
+        #[clap(long)]
+        page_size: Option<u64>,
+    },
+    /// Check sampled records against the record layout this build reads, warning on mismatches
+    ProbeFormat {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
 
-                    // Try to display the value in different formats for convenience
-                    if target_cf == MERKLE_CF_NAME {
-                        let record = MerkleRecord::from_slice(&value)?;
-                        println!("Value (as MerkleRecord): {:?}", record);
-                    }
+        /// Column family to probe, repeat to probe several, defaults to all of them
+        #[clap(long = "cf")]
+        column_families: Vec<String>,
 
-                    if target_cf == DATA_CF_NAME {
-                        let record = DataHashRecord::from_slice(&value)?;
-                        println!("Value (as DataRecord): {:?}", record);
-                    }
+        /// Number of records sampled per column family
+        #[clap(long, default_value = "1000")]
+        sample_size: usize,
 
-                    // Try to interpret as u32 or u64 if appropriate length
-                    if value.len() == 4 {
-                        let val_u32 = u32::from_le_bytes([value[0], value[1], value[2], value[3]]);
-                        println!("Value (as u32, little-endian): {}", val_u32);
-                    }
-                    if value.len() == 8 {
-                        let val_u64 = u64::from_le_bytes([
-                            value[0], value[1], value[2], value[3], value[4], value[5], value[6],
-                            value[7],
-                        ]);
-                        println!("Value (as u64, little-endian): {}", val_u64);
-                    }
+        /// Seed of the random positions
+        #[clap(long, default_value = "42")]
+        seed: u64,
+    },
+    /// Print random records of a column family, found by random seeks instead of a full scan
+    Sample {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
 
-                    // Try to interpret as UTF-8 string
-                    match std::str::from_utf8(&value) {
-                        Ok(s) => println!("Value (as UTF-8): {}", s),
-                        Err(_) => println!("Value is not valid UTF-8"),
-                    }
-                }
-                Ok(None) => {
-                    println!("Key not found in the database");
-                }
-                Err(e) => {
-                    eprintln!("Error reading from database: {}", e);
-                    return Err(Box::new(std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        format!("Database error: {}", e),
-                    )));
-                }
-            }
-        },
-        Commands::CountRocksDb { db_path, target_cf } => {
-            println!("Counting RocksDB at path: {:?}", db_path);
+        /// Target column family to sample, "merkle_records" and "data_records" are decoded automatically
+        #[clap(short, long)]
+        target_cf: String,
 
-            let cf_names = vec![MERKLE_CF_NAME, DATA_CF_NAME];
-            // Open the database
-            let db = create_read_only_db_handler(db_path.clone(), cf_names);
+        /// Number of distinct records to print
+        #[clap(short = 'n', long, default_value = "10")]
+        count: usize,
+
+        /// Decoder for the values ("merkle", "data" or "raw"), defaults to the one registered for the column family
+        #[clap(long)]
+        decode_as: Option<String>,
+
+        /// Seed of the random positions, pass the seed of an earlier run to print the same records again
+        #[clap(long)]
+        seed: Option<u64>,
+    },
+    /// Export all key-value pairs of a column family to a file
+    Export {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to export, any column family, "merkle_records" and "data_records" are decoded automatically
+        #[clap(short, long)]
+        target_cf: String,
+
+        /// Path of the file to write
+        #[clap(short, long)]
+        file: PathBuf,
+
+        /// Format of the exported file
+        #[clap(long, value_enum, default_value = "ndjson")]
+        format: ExportFormat,
+
+        /// Only export keys starting with this prefix (hex string or array format)
+        #[clap(long)]
+        key_prefix: Option<String>,
+
+        /// Comma-separated CSV columns, defaults to key_hex,value_hex which import can read back
+        #[clap(long, value_enum, value_delimiter = ',')]
+        columns: Vec<CsvColumn>,
+
+        /// Leave out the CSV header row
+        #[clap(long)]
+        no_header: bool,
+
+        /// Split the export at SST file boundaries into this many part files written in parallel,
+        /// --file then names the JSON manifest listing them
+        #[clap(long, conflicts_with = "key_prefix")]
+        parts: Option<usize>,
+
+        /// Number of threads writing parts in parallel, defaults to the number of CPUs
+        #[clap(long, requires = "parts")]
+        threads: Option<usize>,
+
+        /// File the position of the scan is saved to periodically, see --resume
+        #[clap(long, conflicts_with = "parts")]
+        checkpoint_file: Option<PathBuf>,
+
+        /// Continue after the position saved in --checkpoint-file instead of starting over
+        #[clap(long, requires = "checkpoint_file")]
+        resume: bool,
+    },
+    /// Import key-value pairs from an export file into a column family
+    Import {
+        /// Path to the RocksDB database directory, created if it does not exist
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to write, created if it does not exist
+        #[clap(short, long)]
+        target_cf: String,
 
-            let cf = db
-                .cf_handle(target_cf)
-                .expect("Should be able to get cf handle");
+        /// Path of the export file to read
+        #[clap(short, long)]
+        file: PathBuf,
+
+        /// Format of the export file
+        #[clap(long, value_enum, default_value = "ndjson")]
+        format: ExportFormat,
+
+        /// Number of records written per WriteBatch
+        #[clap(long, default_value = "10000")]
+        batch_size: usize,
+
+        /// Parse the file and report what would be imported without opening the database
+        #[clap(long)]
+        dry_run: bool,
+
+        /// Import a dump into --target-cf even when it was exported from another column family
+        #[clap(long)]
+        any_cf: bool,
+    },
+    /// Write the puts and deletes turning a column family of one database into another's to a patch
+    MakePatch {
+        /// Path to the database the patch will be applied to
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Path to the database holding the column family as it should become
+        #[clap(long)]
+        new_db_path: PathBuf,
+
+        /// Column family to compare
+        #[clap(short, long)]
+        target_cf: String,
+
+        /// Path of the patch file to write, must not exist yet
+        #[clap(short, long)]
+        file: PathBuf,
+    },
+    /// Apply a patch written by make-patch to a column family
+    ApplyPatch {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Column family to write, defaults to the one the patch was made from
+        #[clap(short, long)]
+        target_cf: Option<String>,
+
+        /// Path of the patch file
+        #[clap(short, long)]
+        file: PathBuf,
+
+        /// Number of operations written per WriteBatch
+        #[clap(long, default_value = "10000")]
+        batch_size: usize,
+
+        /// Check the patch and report what would be applied without opening the database
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Check the header, record checksums and trailer of a dump written with --format dump
+    VerifyDump {
+        /// Path of the dump file
+        #[clap(short, long)]
+        file: PathBuf,
+    },
+    /// Verify the merkle tree stored in merkle_records starting from a root hash
+    VerifyMerkle {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Root hash of the tree to verify (hex string or array format)
+        #[clap(short, long)]
+        root: String,
+
+        /// Count unstored empty subtrees toward the visited nodes and leaves
+        #[clap(long)]
+        include_defaults: bool,
+    },
+    /// Report node counts per level, leaves and shape anomalies of the merkle tree below a root
+    MerkleStats {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Root hash of the tree to inspect (hex string or array format)
+        #[clap(short, long)]
+        root: String,
+
+        /// Count unstored empty subtrees toward the nodes, leaves and depth
+        #[clap(long)]
+        include_defaults: bool,
+    },
+    /// Report merkle_records entries not reachable from any of the given roots
+    FindOrphans {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Root hash of a live tree, repeat for several roots
+        #[clap(short, long = "root")]
+        roots: Vec<String>,
+
+        /// File with one root hash per line, lines starting with '#' are ignored
+        #[clap(long)]
+        root_file: Option<PathBuf>,
+
+        /// Delete the orphaned records instead of only listing them
+        #[clap(long)]
+        delete_orphans: bool,
+    },
+    /// Scan merkle_records for the nodes whose left or right child is a given hash
+    FindParent {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Hash of the child node
+        #[clap(long)]
+        hash: String,
+    },
+    /// Copy the merkle nodes below a root and the data records of its leaves into a new database
+    ExportSubtree {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Hash of the node the subtree is rooted at
+        #[clap(short, long)]
+        root: String,
+
+        /// Directory of the new database, must not exist yet
+        #[clap(long)]
+        destination: PathBuf,
+    },
+    /// Graft a subtree written by export-subtree into a database, optionally at another index
+    ImportSubtree {
+        /// Path to the RocksDB database directory to write, created if it does not exist
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Path of the database holding the subtree
+        #[clap(long)]
+        source: PathBuf,
+
+        /// Hash of the subtree root, defaults to the node with the lowest index in the source
+        #[clap(short, long)]
+        root: Option<String>,
+
+        /// Move the subtree root to this heap index, which must be at the same depth
+        #[clap(long)]
+        new_root_index: Option<u64>,
+    },
+    /// Recompute a merkle root from its leaves and report where it diverges from the stored tree
+    ComputeRoot {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Hash of the stored root to compare with
+        #[clap(short, long)]
+        root: String,
+
+        /// File with one "INDEX HASH" leaf per line to compute from, instead of the stored leaves
+        #[clap(long)]
+        leaf_file: Option<PathBuf>,
+    },
+    /// Check merkle leaves against data_records and report dangling references in either direction
+    CrossCheck {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+    },
+    /// Print the authentication path of a leaf in the merkle tree stored in merkle_records
+    MerklePath {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Root hash of the tree to walk (hex string or array format)
+        #[clap(short, long)]
+        root: String,
+
+        /// Index of the leaf in the tree
+        #[clap(long)]
+        leaf_index: Option<u64>,
+
+        /// Hash of the leaf, its index is read from the leaf record
+        #[clap(long)]
+        leaf_hash: Option<String>,
+
+        /// Root hash the recomputed root is compared against
+        #[clap(long)]
+        expected_root: Option<String>,
+    },
+    /// Look up a merkle leaf by its index below a root, along with the data record it points to
+    GetLeaf {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Root hash of the tree to walk (hex string or array format)
+        #[clap(short, long)]
+        root: String,
+
+        /// Heap index of the leaf in the tree
+        #[clap(short, long)]
+        index: u64,
+
+        /// Print each field of the decoded records on its own line as hex, limbs and field element
+        #[clap(long)]
+        pretty: bool,
+    },
+    /// List the leaf indices and data hashes of the merkle tree below a root, a page at a time
+    ListLeaves {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Root hash of the tree to walk (hex string or array format)
+        #[clap(short, long)]
+        root: String,
+
+        /// Lowest leaf index to list (inclusive)
+        #[clap(long)]
+        start_index: Option<u64>,
+
+        /// Highest leaf index to list (inclusive)
+        #[clap(long)]
+        end_index: Option<u64>,
+
+        /// Number of matching leaves to skip
+        #[clap(long, alias = "offset", default_value = "0")]
+        skip: u64,
+
+        /// Maximum number of leaves to list
+        #[clap(short, long, default_value = "1000")]
+        limit: u64,
+
+        /// Print a page break with the --skip value of the next page after every this many leaves
+        #[clap(long)]
+        page_size: Option<u64>,
+    },
+    /// List the column families of a RocksDB database with estimated key counts and sizes
+    ListCf {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+    },
+    /// Create empty column families, creating the database if it does not exist
+    CreateCf {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Column family to create, repeat to create several
+        #[clap(long = "cf", required = true)]
+        column_families: Vec<String>,
+
+        /// Create them without asking for confirmation
+        #[clap(short, long)]
+        yes: bool,
+    },
+    /// Drop column families with all of their data
+    DropCf {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Column family to drop, repeat to drop several
+        #[clap(long = "cf", required = true)]
+        column_families: Vec<String>,
+
+        /// Drop them without asking for confirmation
+        #[clap(short, long)]
+        yes: bool,
+    },
+    /// Print the database and column family options from the latest OPTIONS file of a database
+    ShowOptions {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
 
-            let iter = db.iterator_cf(cf, rocksdb::IteratorMode::Start);
-            
-            let count = iter.count();
+        /// Only print the options of this column family
+        #[clap(short, long)]
+        target_cf: Option<String>,
+    },
+    /// Write a key-value pair, overwriting any existing value
+    Put {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to write
+        #[clap(short, long)]
+        target_cf: String,
+
+        /// Key to write (hex string like "0A1B2C" or array format like "[10,27,44]")
+        #[clap(short, long)]
+        key: String,
+
+        /// Value to write, in the same formats as the key
+        #[clap(long)]
+        value: Option<String>,
+
+        /// Read the raw value bytes from this file instead of --value
+        #[clap(long)]
+        value_file: Option<PathBuf>,
+
+        /// Confirm that the database should be modified
+        #[clap(long)]
+        i_know_what_im_doing: bool,
+    },
+    /// Delete a single key
+    Delete {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to delete from
+        #[clap(short, long)]
+        target_cf: String,
+
+        /// Key to delete (hex string like "0A1B2C" or array format like "[10,27,44]")
+        #[clap(short, long)]
+        key: String,
+
+        /// Report whether the key exists without deleting it
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Delete every key within a key range
+    DeleteRange {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to delete from
+        #[clap(short, long)]
+        target_cf: String,
+
+        /// First key of the range (inclusive), deletes from the beginning if omitted
+        #[clap(long)]
+        start_key: Option<String>,
+
+        /// Last key of the range (inclusive), deletes to the end if omitted
+        #[clap(long)]
+        end_key: Option<String>,
+
+        /// List the keys that would be deleted without deleting them
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Remove every key of a column family without iterating over them
+    Truncate {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to empty
+        #[clap(short, long)]
+        target_cf: String,
+
+        /// Write a range tombstone over all keys, or drop and recreate the column family
+        #[clap(long, value_enum, default_value = "delete-range")]
+        method: TruncateMethod,
+
+        /// Count the keys that would be removed without removing them
+        #[clap(long)]
+        dry_run: bool,
+
+        /// Truncate without asking for confirmation
+        #[clap(short, long)]
+        yes: bool,
+    },
+    /// Print size, file, cache and memtable statistics of every column family
+    Stats {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+    },
+    /// Print every rocksdb.* integer and string property of the column families
+    Properties {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Column family to inspect, repeat to inspect several, defaults to all of them
+        #[clap(long = "cf")]
+        column_families: Vec<String>,
+
+        /// Print the properties again every SECS seconds until interrupted
+        #[clap(long, value_name = "SECS")]
+        watch: Option<u64>,
+    },
+    /// Print the first and last key of each column family and the key range of its SST files
+    Bounds {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Column family to inspect, repeat to inspect several, defaults to all of them
+        #[clap(long = "cf")]
+        column_families: Vec<String>,
+    },
+    /// List live SST files with their level, size, entry count and key range
+    SstInfo {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Only list files of this column family
+        #[clap(short, long)]
+        target_cf: Option<String>,
+
+        /// Only list files whose key range ends at or after this key
+        #[clap(long)]
+        start_key: Option<String>,
+
+        /// Only list files whose key range starts at or before this key
+        #[clap(long)]
+        end_key: Option<String>,
+    },
+    /// Write the records of a column family, optionally only a key range, to an SST file
+    ExportSst {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to export
+        #[clap(short, long)]
+        target_cf: String,
+
+        /// Path of the SST file to write, must not exist yet
+        #[clap(short, long)]
+        file: PathBuf,
+
+        /// First key to export (inclusive), exports from the beginning if omitted
+        #[clap(long)]
+        start_key: Option<String>,
+
+        /// Last key to export (inclusive), exports to the end if omitted
+        #[clap(long)]
+        end_key: Option<String>,
+    },
+    /// Bulk-load externally written SST files into a column family
+    IngestSst {
+        /// Path to the RocksDB database directory, created if it does not exist
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to load into, created if it does not exist
+        #[clap(short, long)]
+        target_cf: String,
+
+        /// SST file to ingest, repeat to ingest several
+        #[clap(short, long = "file", required = true)]
+        files: Vec<PathBuf>,
+
+        /// Move or hard-link the files into the database instead of copying them
+        #[clap(long)]
+        move_files: bool,
+    },
+    /// Print the keys whose values match a byte pattern or a regex
+    Grep {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to search
+        #[clap(short, long)]
+        target_cf: String,
+
+        /// Byte sequence the value must contain (hex string or array format)
+        #[clap(long, conflicts_with = "regex")]
+        hex: Option<String>,
+
+        /// Regex matched against the UTF-8 interpretation of the value
+        #[clap(long)]
+        regex: Option<String>,
+
+        /// Stop after this many matches
+        #[clap(short, long)]
+        limit: Option<u64>,
+
+        /// Number of matches to skip before the first printed one, stable across runs only with
+        /// --threads 1
+        #[clap(long, default_value = "0")]
+        skip: u64,
+
+        /// Print a page break with the --skip value of the next page after every this many matches
+        #[clap(long)]
+        page_size: Option<u64>,
+
+        /// Number of threads scanning keyspace shards in parallel, defaults to the number of CPUs
+        #[clap(long)]
+        threads: Option<usize>,
+    },
+    /// Report key length, value length and leading key byte distributions of a column family
+    Analyze {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to analyze
+        #[clap(short, long)]
+        target_cf: String,
+    },
+    /// Report records and bytes per range of one u64 limb of the keys, to plan sharding
+    Partition {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to partition
+        #[clap(short, long)]
+        target_cf: String,
+
+        /// Which of the four little-endian u64 limbs of 32-byte keys to bucket by
+        #[clap(long, default_value = "0")]
+        limb: usize,
+
+        /// Number of equal ranges the limb values are split into
+        #[clap(short, long, default_value = "16")]
+        buckets: usize,
+    },
+    /// Report the keys with the largest values in a column family
+    LargestValues {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to scan
+        #[clap(short, long)]
+        target_cf: String,
+
+        /// Number of keys to report
+        #[clap(short, long, default_value = "20")]
+        n: usize,
+    },
+    /// Find keys of a column family that store identical values
+    FindDuplicates {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to scan
+        #[clap(short, long)]
+        target_cf: String,
+
+        /// Maximum number of groups to print, largest potential savings first
+        #[clap(short, long)]
+        limit: Option<usize>,
+    },
+    /// Decode every value of a column family and report the keys whose values fail to decode
+    ValidateRecords {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to validate
+        #[clap(short, long)]
+        target_cf: String,
+
+        /// Decoder for the values ("merkle" or "data"), defaults to the one registered for the column family
+        #[clap(long)]
+        decode_as: Option<String>,
+    },
+    /// Read every block of the database with checksum verification and report corrupted files
+    VerifyChecksums {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Column family to verify, repeat to verify several, defaults to all of them
+        #[clap(long = "cf")]
+        column_families: Vec<String>,
+
+        /// File the position of the scan is saved to periodically, see --resume
+        #[clap(long)]
+        checkpoint_file: Option<PathBuf>,
+
+        /// Continue after the position saved in --checkpoint-file instead of starting over
+        #[clap(long, requires = "checkpoint_file")]
+        resume: bool,
+    },
+    /// Check many keys read from a file, one key per line
+    CheckBatch {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to look up in the database, any column family, "merkle_records" and "data_records" are decoded automatically
+        #[clap(short, long)]
+        target_cf: String,
+
+        /// File with one key per line (hex string or array format), lines starting with '#' are ignored
+        #[clap(short, long)]
+        key_file: PathBuf,
+
+        /// Decoder for the values ("merkle", "data" or "raw"), defaults to the one registered for the column family
+        #[clap(long)]
+        decode_as: Option<String>,
+
+        /// Number of keys looked up per multi-get call
+        #[clap(long, default_value = "1024")]
+        batch_size: usize,
+    },
+    /// Check which keys of a key file are stored, ruling out missing keys with Bloom filters first
+    Exists {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to look up in the database
+        #[clap(short, long)]
+        target_cf: String,
+
+        /// File with one key per line (hex string or array format), lines starting with '#' are ignored
+        #[clap(short, long)]
+        key_file: PathBuf,
+    },
+    /// Measure random get latency, sequential scan throughput and multi-get throughput of a column family
+    Bench {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to read
+        #[clap(short, long)]
+        target_cf: String,
+
+        /// Number of keys sampled at random positions for the random reads
+        #[clap(long, default_value = "10000")]
+        sample_size: usize,
+
+        /// Seconds each phase runs
+        #[clap(long, default_value = "10")]
+        duration_secs: u64,
+
+        /// Threads issuing random gets and multi-gets, defaults to the number of CPUs
+        #[clap(long)]
+        threads: Option<usize>,
+
+        /// Number of keys per multi-get call
+        #[clap(long, default_value = "1024")]
+        batch_size: usize,
+
+        /// Seed of the key sampling, keep it fixed to compare machines on the same keys
+        #[clap(long, default_value = "42")]
+        seed: u64,
+    },
+    /// Measure put and WriteBatch throughput on a scratch database for several value sizes and sync settings
+    BenchWrite {
+        /// Path of the scratch database, must not exist yet
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Sizes of the written values in bytes
+        #[clap(long, value_delimiter = ',', default_value = "32,128,1024")]
+        value_sizes: Vec<usize>,
+
+        /// Durability settings to run: no-wal, async (WAL not synced) and sync (WAL synced on every write)
+        #[clap(long, value_enum, value_delimiter = ',', default_value = "no-wal,async,sync")]
+        durability: Vec<WriteDurability>,
+
+        /// Records per WriteBatch
+        #[clap(long, default_value = "1000")]
+        batch_size: usize,
+
+        /// Seconds each case runs
+        #[clap(long, default_value = "5")]
+        duration_secs: u64,
+
+        /// Seed of the random keys and values
+        #[clap(long, default_value = "42")]
+        seed: u64,
+
+        /// Keep the scratch database instead of removing it afterwards
+        #[clap(long)]
+        keep: bool,
+    },
+    /// Round-trip random records through their encoding and a scratch database, as an upgrade canary
+    SelfTest {
+        /// Path of the scratch database, must not exist yet
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Number of records generated of each type
+        #[clap(long, default_value = "10000")]
+        records: usize,
+
+        /// Largest payload of the generated data records in bytes
+        #[clap(long, default_value = "1024")]
+        max_data_len: usize,
+
+        /// Seed of the generated records, pass the seed of a failed run to repeat it
+        #[clap(long)]
+        seed: Option<u64>,
+
+        /// Keep the scratch database instead of removing it afterwards
+        #[clap(long)]
+        keep: bool,
+    },
+    /// Follow a database while another process writes to it and print newly written keys
+    Tail {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to watch
+        #[clap(short, long)]
+        target_cf: String,
+
+        /// Directory for the secondary instance files, defaults to a directory under the system temp dir
+        #[clap(long)]
+        secondary_path: Option<PathBuf>,
+
+        /// Milliseconds between catching up with the primary
+        #[clap(long, default_value = "1000")]
+        interval_ms: u64,
+
+        /// Decoder for the values ("merkle", "data" or "raw"), defaults to the one registered for the column family
+        #[clap(long)]
+        decode_as: Option<String>,
+    },
+    /// Print the operations logged in the write-ahead log, for post-mortem analysis after a crash
+    WalDump {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// First sequence number to print, the oldest logged one if it is no longer in the log
+        #[clap(long, default_value = "0")]
+        since: u64,
+
+        /// Maximum number of write batches to print
+        #[clap(short, long)]
+        limit: Option<usize>,
+    },
+    /// Stream the write batches logged after a sequence number with full keys and values, for incremental replication
+    Changes {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Stream the operations after this sequence number, pass the last_sequence of the previous run to continue
+        #[clap(long, default_value = "0")]
+        since: u64,
+
+        /// Maximum number of write batches to stream
+        #[clap(short, long)]
+        limit: Option<usize>,
+    },
+    /// Summarize flushes, compactions and write stalls from RocksDB's LOG file as a timeline
+    DbLog {
+        /// Path to the RocksDB database directory, its LOG file is read
+        #[clap(short, long, required_unless_present = "log_file")]
+        db_path: Option<PathBuf>,
+
+        /// LOG file to read instead of the one in the database directory, for example a rotated LOG.old file
+        #[clap(long)]
+        log_file: Option<PathBuf>,
+
+        /// Minutes per timeline bucket
+        #[clap(long, default_value = "60")]
+        bucket_mins: u64,
+    },
+    /// Run a manual compaction over a column family or a key range of it
+    Compact {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to compact
+        #[clap(short, long)]
+        target_cf: String,
+
+        /// First key of the range (inclusive), compacts from the beginning if omitted
+        #[clap(long)]
+        start_key: Option<String>,
+
+        /// Last key of the range (inclusive), compacts to the end if omitted
+        #[clap(long)]
+        end_key: Option<String>,
+
+        /// Also rewrite the bottommost level, dropping obsolete data stored there
+        #[clap(long)]
+        bottommost: bool,
+    },
+    /// Back up a damaged database directory, then repair it in place
+    Repair {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Directory to copy the database to before repairing, must not exist yet
+        #[clap(long)]
+        backup_path: PathBuf,
+    },
+    /// Copy column families, optionally only a key range, into a new database
+    Copy {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Directory of the new database, must not exist yet
+        #[clap(long)]
+        destination: PathBuf,
+
+        /// Column family to copy, repeat to copy several, defaults to all of them
+        #[clap(long = "cf")]
+        column_families: Vec<String>,
+
+        /// First key to copy (inclusive), copies from the beginning if omitted
+        #[clap(long)]
+        start_key: Option<String>,
+
+        /// Last key to copy (inclusive), copies to the end if omitted
+        #[clap(long)]
+        end_key: Option<String>,
+
+        /// Write SST files and ingest them, or write through WriteBatches
+        #[clap(long, value_enum, default_value = "sst")]
+        method: CopyMethod,
+    },
+    /// Report the compression codec and compressed and estimated raw size per level
+    Compression {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Column family to report, repeat to report several, defaults to all of them
+        #[clap(long = "cf")]
+        column_families: Vec<String>,
+    },
+    /// Rewrite column families into a new database compressed with another codec
+    Recompress {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Directory of the new database, must not exist yet
+        #[clap(long)]
+        destination: PathBuf,
+
+        /// Column family to rewrite, repeat to rewrite several, defaults to all of them
+        #[clap(long = "cf")]
+        column_families: Vec<String>,
+
+        /// Codec of the new database
+        #[clap(long, value_enum, default_value = "zstd")]
+        codec: CompressionCodec,
+
+        /// Compression level of the codec, its default level if omitted
+        #[clap(long)]
+        level: Option<i32>,
+    },
+    /// Copy column families into a new TTL database whose records expire after a lifetime
+    SetTtl {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Directory of the new TTL database, must not exist yet
+        #[clap(long)]
+        destination: PathBuf,
+
+        /// Column family to copy, repeat to copy several, defaults to all of them
+        #[clap(long = "cf")]
+        column_families: Vec<String>,
+
+        /// Seconds the copied records live, counted from the copy
+        #[clap(long)]
+        lifetime: u64,
+    },
+    /// Report how long ago the records of a TTL database were written, open it without --ttl
+    RecordAge {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to scan
+        #[clap(short, long)]
+        target_cf: String,
+
+        /// Also count the records a TTL of this many seconds would expire
+        #[clap(long)]
+        expire_after: Option<u64>,
+    },
+    /// Write the union of the column families of several databases into a new database
+    Merge {
+        /// Path of a database to merge, repeat for each, conflicts are resolved in this order
+        #[clap(long = "source", required = true)]
+        sources: Vec<PathBuf>,
+
+        /// Directory of the new database, must not exist yet
+        #[clap(long)]
+        destination: PathBuf,
+
+        /// Column family to merge, repeat to merge several, defaults to those of all sources
+        #[clap(long = "cf")]
+        column_families: Vec<String>,
+
+        /// Value kept for keys stored with different values in several sources
+        #[clap(long, value_enum, default_value = "first-wins")]
+        on_conflict: ConflictPolicy,
+
+        /// Conflict policy of one column family as CF=POLICY, repeat for several
+        #[clap(long = "cf-policy")]
+        cf_policies: Vec<String>,
+    },
+    /// Create a consistent point-in-time copy of a database in a new directory
+    Checkpoint {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Directory to create the checkpoint in, must not exist yet
+        #[clap(long)]
+        checkpoint_path: PathBuf,
+
+        /// Keep only these column families in the checkpoint, repeat to keep several
+        #[clap(long = "cf")]
+        column_families: Vec<String>,
+    },
+    /// Add an incremental backup of a database to a backup directory
+    Backup {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Directory holding the backups, created if missing
+        #[clap(long)]
+        backup_dir: PathBuf,
+
+        /// Remove the oldest backups so that at most this many are kept
+        #[clap(long)]
+        keep_last: Option<usize>,
+    },
+    /// List the backups in a backup directory
+    ListBackups {
+        /// Directory holding the backups
+        #[clap(long)]
+        backup_dir: PathBuf,
+
+        /// Check the files of every backup against their recorded sizes and checksums
+        #[clap(long)]
+        verify: bool,
+    },
+    /// Restore a backup into an empty database directory
+    RestoreBackup {
+        /// Directory holding the backups
+        #[clap(long)]
+        backup_dir: PathBuf,
+
+        /// Directory to restore the database into, must be empty or not exist
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Backup to restore, defaults to the latest one
+        #[clap(long)]
+        backup_id: Option<u32>,
+    },
+    /// Diff a MongoDB collection against a column family to validate a migration
+    #[cfg(feature = "mongo")]
+    CompareMongo {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to compare
+        #[clap(short, long)]
+        target_cf: String,
+
+        /// MongoDB connection string
+        #[clap(long, default_value = "mongodb://localhost:27017")]
+        mongo_uri: String,
+
+        /// MongoDB database holding the collection
+        #[clap(long, default_value = "zkwasmkvpair")]
+        database: String,
+
+        /// Collection to compare, defaults to the column family name
+        #[clap(long)]
+        collection: Option<String>,
+
+        /// Type of the records in the collection, inferred from the column family if omitted
+        #[clap(long, value_enum)]
+        record_type: Option<MongoRecordType>,
+    },
+    /// Copy a MongoDB collection into a column family, resumable and verified by record count
+    #[cfg(feature = "mongo")]
+    MigrateFromMongo {
+        /// Path to the RocksDB database directory, created if it does not exist
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Target column family to write, created if it does not exist
+        #[clap(short, long)]
+        target_cf: String,
+
+        /// MongoDB connection string
+        #[clap(long, default_value = "mongodb://localhost:27017")]
+        mongo_uri: String,
+
+        /// MongoDB database holding the collection
+        #[clap(long, default_value = "zkwasmkvpair")]
+        database: String,
+
+        /// Collection to migrate, defaults to the column family name
+        #[clap(long)]
+        collection: Option<String>,
+
+        /// Type of the records in the collection, inferred from the column family if omitted
+        #[clap(long, value_enum)]
+        record_type: Option<MongoRecordType>,
+
+        /// Number of records written per WriteBatch
+        #[clap(long, default_value = "10000")]
+        batch_size: usize,
+
+        /// File recording the progress after every batch, an existing one resumes the migration
+        #[clap(long)]
+        checkpoint_file: Option<PathBuf>,
+    },
+    /// Serve a read-only JSON HTTP API for key lookups, counts, prefix scans and stats
+    #[cfg(feature = "serve")]
+    Serve {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Address to listen on
+        #[clap(long, default_value = "127.0.0.1:3000")]
+        listen: std::net::SocketAddr,
+    },
+    /// Answer JSON-RPC 2.0 requests, one per line, on stdin/stdout or a unix socket with the
+    /// database kept open
+    Rpc {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Listen on a unix socket at this path instead of reading stdin
+        #[clap(long)]
+        socket: Option<PathBuf>,
+    },
+    /// Serve a read-only gRPC API with Get, MultiGet, Scan and VerifyMerkle RPCs
+    #[cfg(feature = "grpc")]
+    ServeGrpc {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Address to listen on
+        #[clap(long, default_value = "127.0.0.1:50051")]
+        listen: std::net::SocketAddr,
+    },
+    /// Export key estimates, sizes, level file counts and cache stats as Prometheus metrics
+    #[cfg(feature = "serve")]
+    Metrics {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Address to listen on
+        #[clap(long, default_value = "127.0.0.1:9400")]
+        listen: std::net::SocketAddr,
+
+        /// Column family to export, repeat to export several, defaults to all of them
+        #[clap(long = "cf")]
+        column_families: Vec<String>,
+    },
+    /// Print a shell completion script, bash, zsh and fish ones also complete column families
+    Completions {
+        /// Shell to complete in
+        #[clap(value_enum)]
+        shell: Shell,
+    },
+    /// Print the column family names of a database, one per line, for the completion scripts
+    #[clap(hide = true)]
+    CompleteCf {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+    },
+    /// Browse the column families of a database in an interactive terminal UI
+    #[cfg(feature = "tui")]
+    Browse {
+        /// Path to the RocksDB database directory
+        #[clap(short, long)]
+        db_path: PathBuf,
+
+        /// Number of keys loaded per page
+        #[clap(long, default_value = "100")]
+        page_size: usize,
+    },
+}
+
+/// Asks on stderr whether to go ahead, `yes` answers for scripts
+///
+/// Without a terminal to ask on, only `yes` confirms.
+fn confirm(question: &str, yes: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if yes {
+        return Ok(());
+    }
+    if !std::io::stdin().is_terminal() {
+        return Err(format!("{} Pass --yes to confirm without a terminal", question).into());
+    }
+    eprint!("{} [y/N] ", question);
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    match answer.trim() {
+        "y" | "Y" | "yes" => Ok(()),
+        _ => Err("Aborted".into()),
+    }
+}
+
+fn num_cpus() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Seed for commands run without `--seed`, printed with their results so a run can be repeated
+fn random_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+}
+
+impl Commands {
+    /// Whether the command was asked to print nothing, errors included
+    fn is_quiet(&self) -> bool {
+        matches!(self, Commands::CheckRocksDb { quiet: true, .. })
+    }
+}
+
+/// Value of a `--long value` or `--long=value` option anywhere on the command line
+fn option_value(args: &[OsString], long: &str) -> Option<OsString> {
+    let flag = format!("--{}", long);
+    let prefix = format!("--{}=", long);
+    args.iter().enumerate().find_map(|(i, arg)| {
+        let arg = arg.to_str()?;
+        if arg == flag {
+            args.get(i + 1).cloned()
+        } else {
+            arg.strip_prefix(&prefix).map(OsString::from)
+        }
+    })
+}
+
+/// Whether `arg` is given on the command line, by its long or its short name
+fn is_given(args: &[OsString], arg: &clap::Arg) -> bool {
+    args.iter().filter_map(|given| given.to_str()).any(|given| {
+        let long = arg.get_long().map_or(false, |long| {
+            given.strip_prefix("--").map_or(false, |given| {
+                given == long || given.starts_with(&format!("{}=", long))
+            })
+        });
+        let short = arg.get_short().map_or(false, |short| {
+            !given.starts_with("--")
+                && given.strip_prefix('-').and_then(|given| given.chars().next()) == Some(short)
+        });
+        long || short
+    })
+}
+
+/// The subcommand named on the command line, skipping the values of global options
+fn find_subcommand<'a>(
+    cli: &'a clap::Command<'a>,
+    args: &[OsString],
+) -> Option<&'a clap::Command<'a>> {
+    let mut args = args.iter().skip(1).filter_map(|arg| arg.to_str());
+    while let Some(arg) = args.next() {
+        if let Some(long) = arg.strip_prefix("--") {
+            let takes_value = cli
+                .get_arguments()
+                .any(|global| global.get_long() == Some(long) && global.is_takes_value_set());
+            if takes_value {
+                args.next();
+            }
+        } else if let Some(subcommand) = cli.find_subcommand(arg) {
+            return Some(subcommand);
+        }
+    }
+    None
+}
+
+/// Adds the flags the profile sets and the command line leaves out
+///
+/// Global flags go right after the program name, flags of the subcommand at the end. `--db-path`
+/// and `--cf` are only added to subcommands taking them, and a profile listing a single column
+/// family also fills in `--target-cf`.
+fn apply_profile(args: Vec<OsString>, profile: &Profile) -> Vec<OsString> {
+    let cli = Cli::command();
+    let global = |long: &str| cli.get_arguments().find(|arg| arg.get_long() == Some(long));
+    let mut global_flags: Vec<OsString> = Vec::new();
+    if let Some(output) = &profile.output {
+        if global("output").map_or(false, |arg| !is_given(&args, arg)) {
+            global_flags.extend(["--output".into(), output.into()]);
+        }
+    }
+    if let Some(options_file) = &profile.options_file {
+        let overridden = ["options-file", "default-options"]
+            .iter()
+            .filter_map(|long| global(long))
+            .any(|arg| is_given(&args, arg));
+        if !overridden {
+            global_flags.extend(["--options-file".into(), options_file.into()]);
+        }
+    }
+
+    let mut command_flags: Vec<OsString> = Vec::new();
+    if let Some(subcommand) = find_subcommand(&cli, &args) {
+        let accepts = |long: &str| {
+            subcommand
+                .get_arguments()
+                .find(|arg| arg.get_long() == Some(long))
+                .filter(|arg| !is_given(&args, arg))
+        };
+        if let (Some(db_path), Some(_)) = (&profile.db_path, accepts("db-path")) {
+            command_flags.extend(["--db-path".into(), db_path.into()]);
+        }
+        if accepts("cf").is_some() {
+            for cf in &profile.column_families {
+                command_flags.extend(["--cf".into(), cf.into()]);
+            }
+        } else if let (Some(_), [cf]) = (accepts("target-cf"), profile.column_families.as_slice()) {
+            command_flags.extend(["--target-cf".into(), cf.into()]);
+        }
+    }
+
+    let mut args = args.into_iter();
+    args.next()
+        .into_iter()
+        .chain(global_flags)
+        .chain(args)
+        .chain(command_flags)
+        .collect()
+}
+
+/// The command line with the flags of the `--profile` it names filled in
+fn profile_args(args: Vec<OsString>) -> Result<Vec<OsString>, Error> {
+    let name = match option_value(&args, "profile") {
+        Some(name) => name,
+        None => return Ok(args),
+    };
+    let path = match option_value(&args, "config") {
+        Some(path) => PathBuf::from(path),
+        None => Config::default_path().ok_or_else(|| Error::Config {
+            path: PathBuf::from("~/.config/rocksdb-tool/config.toml"),
+            message: "neither XDG_CONFIG_HOME nor HOME is set".to_string(),
+        })?,
+    };
+    let config = Config::load(&path)?;
+    let profile = config.profile(&name.to_string_lossy(), &path)?;
+    Ok(apply_profile(args, profile))
+}
+
+fn main() {
+    let args = profile_args(std::env::args_os().collect()).unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        std::process::exit(e.exit_code());
+    });
+    let cli = Cli::parse_from(args);
+    logging::init(cli.verbose, cli.log_format);
+    if cli.background {
+        // Before any database or thread pool spawns threads, which inherit the priorities
+        if let Err(e) = priority::lower_priority() {
+            tracing::warn!("Failed to lower the process priority: {}", e);
+        }
+    }
+    let timer = match (cli.perf, cli.timing) {
+        (true, _) => Some(Timer::start_detailed()),
+        (false, true) => Some(Timer::start()),
+        (false, false) => None,
+    };
+    let result = run(&cli);
+    if let Some(timer) = timer {
+        let out = Output::new(cli.output);
+        if cli.perf {
+            let _ = out.emit_stderr(&timer.perf_counters());
+        }
+        let report = timer.finish();
+        if cli.timing {
+            let _ = out.emit_stderr(&report);
+        }
+    }
+    if let Err(e) = result {
+        if !cli.command.is_quiet() {
+            eprintln!("Error: {}", e);
+        }
+        std::process::exit(error::exit_code(e.as_ref()));
+    }
+}
+
+fn run(cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let out = Output::new(cli.output);
+    // Every key, prefix and hash argument is read in the --key-format format
+    let parse_key = |key: &str| parse_key_as(key, cli.key_format, true);
+    // Reads of every command use the --readahead-kb, --fill-cache, --verify-checksums,
+    // --rate-limit-mb and --background settings
+    let readahead_size = match (cli.readahead_kb, cli.background) {
+        (Some(kb), _) => Some(kb << 10),
+        (None, true) => Some(db::BACKGROUND_READAHEAD),
+        (None, false) => None,
+    };
+    let read_settings = ReadSettings {
+        readahead_size,
+        fill_cache: cli.fill_cache,
+        verify_checksums: cli.verify_checksums,
+        throttle: cli.rate_limit_mb.map(|mb| Arc::new(ReadThrottle::new(mb << 20))),
+        background: cli.background,
+    };
+    // Databases are opened with the options they were last opened with unless told otherwise
+    let db_options = |db_path: &Path| {
+        let mut options = match (&cli.options_file, cli.default_options) {
+            (Some(options_file), _) => DbOptions::from_file(options_file)?,
+            (None, true) => DbOptions::default(),
+            (None, false) => DbOptions::detect(db_path)?,
+        }
+        .with_read_settings(read_settings.clone());
+        if let Some(block_cache_mb) = cli.block_cache_mb {
+            options = options.with_block_cache_size(block_cache_mb << 20);
+        }
+        if let Some(secondary_path) = &cli.secondary {
+            options = options.with_secondary_path(secondary_path);
+        }
+        if let Some(ttl) = cli.ttl {
+            options = options.with_ttl(Duration::from_secs(ttl));
+        }
+        if cli.snapshot {
+            options = options.with_snapshot();
+        }
+        Ok::<_, Error>(options)
+    };
+    // --secondary opens every database as a secondary instance in the given directory
+    let mode = if cli.secondary.is_some() {
+        OpenMode::Secondary
+    } else {
+        cli.mode
+    };
+    // Opens retry while another process holds the database lock, for up to --wait-for-lock seconds
+    let lock_wait = cli.wait_for_lock.map(Duration::from_secs);
+    let open_db = |db_path: &Path| {
+        db::retry_while_locked(lock_wait, || DbHandle::open(db_path, mode, db_options(db_path)?))
+    };
+    let decoders = DecoderRegistry::default();
+    // Long scans draw a progress bar on stderr and log a summary
+    let show_progress = !cli.no_progress;
+
+    match &cli.command {
+        Commands::CheckRocksDb {
+            db_path,
+            target_cf,
+            key,
+            decode_as,
+            pretty,
+            dump_data,
+            hexdump,
+            value_type,
+            quiet,
+        } => {
+            if !quiet {
+                out.info(&format!("Checking RocksDB at path: {:?}", db_path));
+            }
+
+            // Parse the key
+            let key_bytes = if *quiet {
+                parse_key_as(key, cli.key_format, false)?
+            } else {
+                parse_key(key)?
+            };
+
+            if !quiet {
+                out.info(&format!("Looking for key (bytes): {:?}", key_bytes));
+            }
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let decoder = decoders.resolve(target_cf, decode_as.as_deref())?;
+            let mut result = commands::check(&db, target_cf, &key_bytes, decoder, *value_type)?;
+            result.pretty = *pretty;
+            result.hexdump = *hexdump;
+            if let Some(path) = dump_data {
+                match &result.decoded {
+                    Some(DecodedRecord::Data(record)) => {
+                        result.data_dump = Some(commands::dump_record_data(record, path)?);
+                    }
+                    Some(_) => return Err("--dump-data requires a value decoded as a DataHashRecord".into()),
+                    None => {}
+                }
+            }
+            if !quiet {
+                out.emit(&result)?;
+            }
+            if !result.found {
+                return Err(Error::KeyNotFound {
+                    column_family: result.column_family,
+                    key: result.key,
+                }
+                .into());
+            }
+        },
+        Commands::Decode {
+            value,
+            file,
+            decode_as,
+            pretty,
+        } => {
+            let bytes = match (value, file) {
+                (Some(value), _) => parse_key(value)?,
+                (None, Some(file)) => std::fs::read(file)?,
+                (None, None) => return Err("Pass --value or --file".into()),
+            };
+
+            let selected = match decode_as {
+                Some(name) => vec![decoders.resolve("", Some(name))?],
+                None => decoders
+                    .names()
+                    .into_iter()
+                    .filter_map(|name| decoders.by_name(name))
+                    .filter(|decoder| decoder.name() != "raw")
+                    .collect(),
+            };
+            let mut result = commands::decode(&bytes, &selected)?;
+            result.pretty = *pretty;
+            out.emit(&result)?;
+        },
+        Commands::Encode {
+            record_type,
+            index,
+            hash,
+            left,
+            right,
+            data,
+            json_file,
+        } => {
+            let fields = match (json_file, record_type) {
+                (Some(json_file), _) => RecordFields::from_json_file(json_file)?,
+                (None, Some(RecordType::Merkle)) => RecordFields::MerkleRecord {
+                    index: index.ok_or("A merkle record needs --index")?,
+                    hash: hash.clone().ok_or("A merkle record needs --hash")?,
+                    left: left.clone(),
+                    right: right.clone(),
+                    data: data.clone(),
+                },
+                (None, Some(RecordType::Data)) => RecordFields::DataHashRecord {
+                    hash: hash.clone().ok_or("A data record needs --hash")?,
+                    data: data.clone().ok_or("A data record needs --data")?,
+                },
+                (None, None) => return Err("Pass --record-type or --json-file".into()),
+            };
+
+            out.emit(&commands::encode(fields.into_record(cli.key_format)?)?)?;
+        },
+        Commands::CountRocksDb {
+            db_path,
+            target_cf,
+            threads,
+            approximate,
+            start_key,
+            end_key,
+            checkpoint_file,
+            resume,
+        } => {
+            out.info(&format!("Counting RocksDB at path: {:?}", db_path));
+
+            let start = start_key.as_deref().map(parse_key).transpose()?;
+            let end = end_key.as_deref().map(parse_key).transpose()?;
+            let range = KeyRange::new(start, end);
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            if *approximate {
+                out.emit(&commands::count_approximate(&db, target_cf)?)?;
+                return Ok(());
+            }
+
+            // The key estimate covers the whole column family, a range only gets a spinner
+            let progress = if range.is_full() {
+                Progress::for_cf(show_progress, &db, target_cf)?
+            } else {
+                Progress::new(show_progress, None, "keys")
+            };
+
+            let threads = threads.unwrap_or_else(num_cpus);
+            let checkpoint = checkpoint_file.as_ref().map(|path| CheckpointFile {
+                path: path.clone(),
+                resume: *resume,
+            });
+            let result = commands::count(
+                &db,
+                target_cf,
+                &range,
+                threads,
+                checkpoint.as_ref(),
+                |n| progress.inc(n),
+            )?;
+            progress.finish();
+
+            out.emit(&result)?;
+        },
+        Commands::Scan {
+            db_path,
+            target_cf,
+            start_key,
+            end_key,
+            limit,
+            skip,
+            page_size,
+            reverse,
+        } => {
+            out.info(&format!("Scanning RocksDB at path: {:?}", db_path));
+
+            let start = start_key.as_deref().map(parse_key).transpose()?;
+            let end = end_key.as_deref().map(parse_key).transpose()?;
+            let range = KeyRange::new(start, end);
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let page = Pagination {
+                skip: *skip,
+                limit: *limit,
+                page_size: *page_size,
+            };
+            let mut emit = out.paged(page);
+            let summary = commands::scan(&db, target_cf, &range, *reverse, page, |entry| {
+                Ok(emit(&entry)?)
+            })?;
+            out.emit(&summary)?;
+        },
+        Commands::PrefixScan {
+            db_path,
+            target_cf,
+            prefix,
+            decode_as,
+            limit,
+            skip,
+            page_size,
+        } => {
+            out.info(&format!("Prefix scanning RocksDB at path: {:?}", db_path));
+
+            let prefix_bytes = parse_key(prefix)?;
+
+            // Open the database, with the prefix extractor it was created with unless told otherwise
+            let db = db::retry_while_locked(lock_wait, || {
+                let mut options = db_options(db_path)?;
+                if cli.default_options {
+                    options = options.with_prefix_extractor(prefix_bytes.len().max(1));
+                }
+                DbHandle::open(db_path, mode, options)
+            })?;
+
+            let decoder = decoders.resolve(target_cf, decode_as.as_deref())?;
+            let page = Pagination {
+                skip: *skip,
+                limit: *limit,
+                page_size: *page_size,
+            };
+            let mut emit = out.paged(page);
+            let summary =
+                commands::prefix_scan(&db, target_cf, &prefix_bytes, decoder, page, |entry| {
+                    Ok(emit(&entry)?)
+                })?;
+            out.emit(&summary)?;
+        },
+        Commands::ProbeFormat {
+            db_path,
+            column_families,
+            sample_size,
+            seed,
+        } => {
+            out.info(&format!("Probing record formats of RocksDB at path: {:?}", db_path));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let cf_names = if column_families.is_empty() {
+                db.cf_names().to_vec()
+            } else {
+                column_families.clone()
+            };
+            commands::probe_format(&db, &cf_names, &decoders, *sample_size, *seed, |probe| {
+                Ok(out.emit(&probe)?)
+            })?;
+        },
+        Commands::Sample {
+            db_path,
+            target_cf,
+            count,
+            decode_as,
+            seed,
+        } => {
+            out.info(&format!("Sampling RocksDB at path: {:?}", db_path));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let decoder = decoders.resolve(target_cf, decode_as.as_deref())?;
+            let seed = seed.unwrap_or_else(random_seed);
+            let summary = commands::sample(&db, target_cf, *count, decoder, seed, |entry| {
+                Ok(out.emit(&entry)?)
+            })?;
+            out.emit(&summary)?;
+        },
+        Commands::Export {
+            db_path,
+            target_cf,
+            file,
+            format,
+            key_prefix,
+            columns,
+            no_header,
+            parts,
+            threads,
+            checkpoint_file,
+            resume,
+        } => {
+            out.info(&format!("Exporting RocksDB at path: {:?}", db_path));
+
+            let prefix_bytes = key_prefix.as_deref().map(parse_key).transpose()?;
+
+            if (!columns.is_empty() || *no_header) && *format != ExportFormat::Csv {
+                return Err("--columns and --no-header only apply to --format csv".into());
+            }
+            let mut csv = CsvLayout::default();
+            if !columns.is_empty() {
+                csv.columns = columns.clone();
+            }
+            csv.header = !no_header;
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            if let Some(parts) = parts {
+                let progress = Progress::for_cf(show_progress, &db, target_cf)?.with_bytes();
+                let summary = commands::export_parallel(
+                    &db,
+                    target_cf,
+                    file,
+                    *format,
+                    csv,
+                    *parts,
+                    threads.unwrap_or_else(num_cpus),
+                    |bytes| {
+                        progress.inc(1);
+                        progress.inc_bytes(bytes as u64);
+                    },
+                )?;
+                progress.finish();
+
+                out.emit(&summary)?;
+                return Ok(());
+            }
+
+            // The key estimate covers the whole column family, a prefix only gets a spinner
+            let progress = match prefix_bytes {
+                Some(_) => Progress::new(show_progress, None, "keys"),
+                None => Progress::for_cf(show_progress, &db, target_cf)?,
+            }
+            .with_bytes();
+            let checkpoint = checkpoint_file.as_ref().map(|path| CheckpointFile {
+                path: path.clone(),
+                resume: *resume,
+            });
+            let summary = commands::export_file(
+                &db,
+                target_cf,
+                file,
+                *format,
+                csv,
+                prefix_bytes.as_deref(),
+                checkpoint.as_ref(),
+                |bytes| {
+                    progress.inc(1);
+                    progress.inc_bytes(bytes as u64);
+                },
+            )?;
+            progress.finish();
+
+            out.emit(&summary)?;
+        },
+        Commands::Import {
+            db_path,
+            target_cf,
+            file,
+            format,
+            batch_size,
+            dry_run,
+            any_cf,
+        } => {
+            out.info(&format!("Importing {:?} into RocksDB at path: {:?}", file, db_path));
+
+            let db = if *dry_run {
+                None
+            } else {
+                Some(db::retry_while_locked(lock_wait, || {
+                    DbHandle::open_writable(db_path, mode, &[target_cf.as_str()], db_options(db_path)?)
+                })?)
+            };
+
+            out.emit(&commands::import_file(
+                db.as_ref(),
+                target_cf,
+                file,
+                *format,
+                *batch_size,
+                *any_cf,
+            )?)?;
+        },
+        Commands::MakePatch {
+            db_path,
+            new_db_path,
+            target_cf,
+            file,
+        } => {
+            out.info(&format!(
+                "Writing patch from RocksDB at path: {:?} to RocksDB at path: {:?}",
+                db_path, new_db_path
+            ));
+
+            // Open both databases
+            let old = open_db(db_path)?;
+            let new = open_db(new_db_path)?;
+
+            let progress = Progress::new(show_progress, None, "records").with_bytes();
+            let summary = commands::make_patch(&old, &new, target_cf, file, |bytes| {
+                progress.inc(1);
+                progress.inc_bytes(bytes as u64);
+            })?;
+            progress.finish();
+
+            out.emit(&summary)?;
+        },
+        Commands::ApplyPatch {
+            db_path,
+            target_cf,
+            file,
+            batch_size,
+            dry_run,
+        } => {
+            out.info(&format!("Applying patch {:?} to RocksDB at path: {:?}", file, db_path));
+
+            let db = if *dry_run {
+                None
+            } else {
+                // The column family to open is named in the patch header unless given
+                let cf = match target_cf {
+                    Some(cf) => cf.clone(),
+                    None => PatchReader::new(BufReader::new(File::open(file)?))?
+                        .column_family()
+                        .to_string(),
+                };
+                Some(db::retry_while_locked(lock_wait, || {
+                    DbHandle::open_writable(db_path, mode, &[cf.as_str()], db_options(db_path)?)
+                })?)
+            };
+
+            out.emit(&commands::apply_patch(
+                db.as_ref(),
+                target_cf.as_deref(),
+                file,
+                *batch_size,
+            )?)?;
+        },
+        Commands::VerifyDump { file } => {
+            out.info(&format!("Verifying dump {:?}", file));
+
+            out.emit(&commands::verify_dump(file)?)?;
+        },
+        Commands::VerifyMerkle {
+            db_path,
+            root,
+            include_defaults,
+        } => {
+            out.info(&format!("Verifying merkle tree in RocksDB at path: {:?}", db_path));
+
+            let root_hash = merkle::to_hash(&parse_key(root)?)?;
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let progress = Progress::new(show_progress, None, "nodes").with_bytes();
+            let (issues, summary) =
+                commands::verify_merkle(&db, root_hash, *include_defaults, |bytes| {
+                    progress.inc(1);
+                    progress.inc_bytes(bytes as u64);
+                })?;
+            progress.finish();
+            for issue in &issues {
+                out.emit(issue)?;
+            }
+            out.emit(&summary)?;
+        },
+        Commands::MerkleStats {
+            db_path,
+            root,
+            include_defaults,
+        } => {
+            out.info(&format!("Collecting merkle tree statistics in RocksDB at path: {:?}", db_path));
+
+            let root_hash = merkle::to_hash(&parse_key(root)?)?;
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            out.emit(&commands::merkle_stats(&db, root_hash, *include_defaults)?)?;
+        },
+        Commands::FindOrphans {
+            db_path,
+            roots,
+            root_file,
+            delete_orphans,
+        } => {
+            out.info(&format!("Finding orphan merkle nodes in RocksDB at path: {:?}", db_path));
+
+            let mut root_hashes = Vec::new();
+            for root in roots {
+                root_hashes.push(merkle::to_hash(&parse_key(root)?)?);
+            }
+            if let Some(root_file) = root_file {
+                for root in parse_key_file(root_file, cli.key_format)? {
+                    root_hashes.push(merkle::to_hash(&root)?);
+                }
+            }
+            if root_hashes.is_empty() {
+                return Err("At least one --root or a --root-file is required".into());
+            }
+
+            // Open the database
+            let db = if *delete_orphans {
+                db::retry_while_locked(lock_wait, || {
+                    DbHandle::open_writable(db_path, mode, &[MERKLE_CF_NAME], db_options(db_path)?)
+                })?
+            } else {
+                open_db(db_path)?
+            };
+
+            let summary = commands::find_orphans(&db, &root_hashes, *delete_orphans, |key| {
+                Ok(out.emit(&KeyEntry { key: to_hex(key) })?)
+            })?;
+            out.emit(&summary)?;
+        },
+        Commands::FindParent { db_path, hash } => {
+            out.info(&format!("Finding parents of a node in RocksDB at path: {:?}", db_path));
+
+            let hash = merkle::to_hash(&parse_key(hash)?)?;
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let summary = commands::find_parents(&db, hash, |parent| Ok(out.emit(&parent)?))?;
+            out.emit(&summary)?;
+        },
+        Commands::ExportSubtree {
+            db_path,
+            root,
+            destination,
+        } => {
+            out.info(&format!("Exporting a subtree of RocksDB at path: {:?}", db_path));
+
+            let root = merkle::to_hash(&parse_key(root)?)?;
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            out.emit(&commands::export_subtree(&db, root, destination)?)?;
+        },
+        Commands::ImportSubtree {
+            db_path,
+            source,
+            root,
+            new_root_index,
+        } => {
+            out.info(&format!(
+                "Importing subtree {:?} into RocksDB at path: {:?}",
+                source, db_path
+            ));
+
+            let root = match root {
+                Some(root) => Some(merkle::to_hash(&parse_key(root)?)?),
+                None => None,
+            };
+
+            // Open both databases
+            let source = open_db(source)?;
+            let db = db::retry_while_locked(lock_wait, || {
+                DbHandle::open_writable(db_path, mode, &DEFAULT_CF_NAMES, db_options(db_path)?)
+            })?;
+
+            out.emit(&commands::import_subtree(&source, &db, root, *new_root_index)?)?;
+        },
+        Commands::ComputeRoot {
+            db_path,
+            root,
+            leaf_file,
+        } => {
+            out.info(&format!("Recomputing merkle root in RocksDB at path: {:?}", db_path));
+
+            let root = merkle::to_hash(&parse_key(root)?)?;
+            let leaves = match leaf_file {
+                Some(leaf_file) => {
+                    let mut leaves = Vec::new();
+                    for (index, hash) in parse_leaf_file(leaf_file, cli.key_format)? {
+                        leaves.push((index, merkle::to_hash(&hash)?));
+                    }
+                    Some(leaves)
+                },
+                None => None,
+            };
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let report = commands::recompute_root(&db, root, leaves)?;
+            let matches = report.matches;
+            out.emit(&report)?;
+            if !matches {
+                return Err("Computed root differs from the stored root".into());
+            }
+        },
+        Commands::CrossCheck { db_path } => {
+            out.info(&format!("Cross-checking merkle leaves and data records in RocksDB at path: {:?}", db_path));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let summary = commands::cross_check(&db, |issue| Ok(out.emit(&issue)?))?;
+            out.emit(&summary)?;
+        },
+        Commands::MerklePath {
+            db_path,
+            root,
+            leaf_index,
+            leaf_hash,
+            expected_root,
+        } => {
+            out.info(&format!("Extracting merkle path from RocksDB at path: {:?}", db_path));
+
+            let root_hash = merkle::to_hash(&parse_key(root)?)?;
+            let expected_root = expected_root
+                .as_deref()
+                .map(|root| parse_key(root).and_then(|bytes| merkle::to_hash(&bytes)))
+                .transpose()?;
+            let leaf = match (leaf_index, leaf_hash) {
+                (Some(index), None) => LeafSelector::Index(*index),
+                (None, Some(hash)) => LeafSelector::Hash(merkle::to_hash(&parse_key(hash)?)?),
+                _ => return Err("Exactly one of --leaf-index or --leaf-hash is required".into()),
+            };
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let (steps, summary) = commands::merkle_path(&db, root_hash, leaf, expected_root)?;
+            for step in &steps {
+                out.emit(step)?;
+            }
+            out.emit(&summary)?;
+        },
+        Commands::ShowOptions { db_path, target_cf } => {
+            out.info(&format!("Reading options of RocksDB at path: {:?}", db_path));
+
+            out.emit(&commands::show_options(db_path, target_cf.as_deref())?)?;
+        },
+        Commands::GetLeaf {
+            db_path,
+            root,
+            index,
+            pretty,
+        } => {
+            out.info(&format!("Looking up merkle leaf in RocksDB at path: {:?}", db_path));
+
+            let root = merkle::to_hash(&parse_key(root)?)?;
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let (mut leaf, data) = commands::get_leaf(&db, root, *index)?;
+            leaf.pretty = *pretty;
+            out.emit(&leaf)?;
+            if let Some(mut data) = data {
+                data.pretty = *pretty;
+                out.emit(&data)?;
+            }
+            if !leaf.found {
+                return Err(Error::KeyNotFound {
+                    column_family: leaf.column_family,
+                    key: leaf.key,
+                }
+                .into());
+            }
+        },
+        Commands::ListLeaves {
+            db_path,
+            root,
+            start_index,
+            end_index,
+            skip,
+            limit,
+            page_size,
+        } => {
+            out.info(&format!("Listing merkle leaves in RocksDB at path: {:?}", db_path));
+
+            let root = merkle::to_hash(&parse_key(root)?)?;
+            let leaves = merkle::leaf_index_range();
+            let indices =
+                start_index.unwrap_or(*leaves.start())..=end_index.unwrap_or(*leaves.end());
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let page = Pagination {
+                skip: *skip,
+                limit: Some(*limit),
+                page_size: *page_size,
+            };
+            let mut emit = out.paged(page);
+            let summary =
+                commands::list_leaves(&db, root, indices, page, |leaf| Ok(emit(&leaf)?))?;
+            out.emit(&summary)?;
+        },
+        Commands::ListCf { db_path } => {
+            out.info(&format!("Listing column families of RocksDB at path: {:?}", db_path));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            for info in commands::list_cf(&db)? {
+                out.emit(&info)?;
+            }
+        },
+        Commands::Truncate {
+            db_path,
+            target_cf,
+            method,
+            dry_run,
+            yes,
+        } => {
+            let mut db = if *dry_run {
+                open_db(db_path)?
+            } else {
+                confirm(
+                    &format!("Remove every key of column family '{}' in {:?}?", target_cf, db_path),
+                    *yes,
+                )?;
+                db::retry_while_locked(lock_wait, || {
+                    DbHandle::open_writable(db_path, mode, &[target_cf.as_str()], db_options(db_path)?)
+                })?
+            };
+
+            out.info(&format!("Truncating column family in RocksDB at path: {:?}", db_path));
+
+            out.emit(&commands::truncate(&mut db, target_cf, *method, *dry_run)?)?;
+        },
+        Commands::CreateCf {
+            db_path,
+            column_families,
+            yes,
+        } => {
+            confirm(
+                &format!("Create column families {} in {:?}?", column_families.join(", "), db_path),
+                *yes,
+            )?;
+
+            out.info(&format!("Creating column families in RocksDB at path: {:?}", db_path));
+
+            let mut db = db::retry_while_locked(lock_wait, || {
+                DbHandle::open_writable(db_path, mode, &[] as &[&str], db_options(db_path)?)
+            })?;
+
+            out.emit(&commands::create_cf(&mut db, column_families)?)?;
+        },
+        Commands::DropCf {
+            db_path,
+            column_families,
+            yes,
+        } => {
+            confirm(
+                &format!(
+                    "Drop column families {} from {:?} with all of their data?",
+                    column_families.join(", "),
+                    db_path
+                ),
+                *yes,
+            )?;
+
+            out.info(&format!("Dropping column families in RocksDB at path: {:?}", db_path));
+
+            let mut db = db::retry_while_locked(lock_wait, || {
+                DbHandle::open_writable(db_path, mode, &[] as &[&str], db_options(db_path)?)
+            })?;
+
+            out.emit(&commands::drop_cf(&mut db, column_families)?)?;
+        },
+        Commands::Put {
+            db_path,
+            target_cf,
+            key,
+            value,
+            value_file,
+            i_know_what_im_doing,
+        } => {
+            if !i_know_what_im_doing {
+                return Err("Put modifies the database, pass --i-know-what-im-doing to confirm".into());
+            }
+
+            let key_bytes = parse_key(key)?;
+            let value_bytes = match (value, value_file) {
+                (Some(value), None) => parse_key(value)?,
+                (None, Some(path)) => std::fs::read(path)?,
+                _ => return Err("Exactly one of --value or --value-file is required".into()),
+            };
+
+            out.info(&format!("Writing to RocksDB at path: {:?}", db_path));
+
+            let db = db::retry_while_locked(lock_wait, || {
+                DbHandle::open_writable(db_path, mode, &[target_cf.as_str()], db_options(db_path)?)
+            })?;
+
+            out.emit(&commands::put(&db, target_cf, &key_bytes, &value_bytes)?)?;
+        },
+        Commands::Delete {
+            db_path,
+            target_cf,
+            key,
+            dry_run,
+        } => {
+            out.info(&format!("Deleting from RocksDB at path: {:?}", db_path));
+
+            let key_bytes = parse_key(key)?;
+
+            let db = if *dry_run {
+                open_db(db_path)?
+            } else {
+                db::retry_while_locked(lock_wait, || {
+                    DbHandle::open_writable(db_path, mode, &[target_cf.as_str()], db_options(db_path)?)
+                })?
+            };
+
+            out.emit(&commands::delete(&db, target_cf, &key_bytes, *dry_run)?)?;
+        },
+        Commands::DeleteRange {
+            db_path,
+            target_cf,
+            start_key,
+            end_key,
+            dry_run,
+        } => {
+            out.info(&format!("Deleting range from RocksDB at path: {:?}", db_path));
+
+            let start = start_key.as_deref().map(parse_key).transpose()?;
+            let end = end_key.as_deref().map(parse_key).transpose()?;
+            let range = KeyRange::new(start, end);
+
+            let db = if *dry_run {
+                open_db(db_path)?
+            } else {
+                db::retry_while_locked(lock_wait, || {
+                    DbHandle::open_writable(db_path, mode, &[target_cf.as_str()], db_options(db_path)?)
+                })?
+            };
+
+            let summary = commands::delete_range(&db, target_cf, &range, *dry_run, |key| {
+                if *dry_run {
+                    out.emit(&KeyEntry { key: to_hex(key) })?;
+                }
+                Ok(())
+            })?;
+            out.emit(&summary)?;
+        },
+        Commands::Stats { db_path } => {
+            out.info(&format!("Collecting statistics of RocksDB at path: {:?}", db_path));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            out.emit(&commands::stats(&db)?)?;
+        },
+        Commands::Properties { db_path, column_families, watch } => {
+            out.info(&format!("Reading properties of RocksDB at path: {:?}", db_path));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let cf_names = if column_families.is_empty() {
+                db.cf_names().to_vec()
+            } else {
+                column_families.clone()
+            };
+            match watch {
+                Some(secs) => {
+                    let interval = Duration::from_secs((*secs).max(1));
+                    commands::watch_properties(&db, &cf_names, interval, |report| {
+                        Ok(out.emit(&report)?)
+                    })?;
+                },
+                None => out.emit(&commands::properties(&db, &cf_names)?)?,
+            }
+        },
+        Commands::Bounds { db_path, column_families } => {
+            out.info(&format!("Reading key ranges of RocksDB at path: {:?}", db_path));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let cf_names = if column_families.is_empty() {
+                db.cf_names().to_vec()
+            } else {
+                column_families.clone()
+            };
+            out.emit(&commands::bounds(&db, &cf_names)?)?;
+        },
+        Commands::SstInfo {
+            db_path,
+            target_cf,
+            start_key,
+            end_key,
+        } => {
+            out.info(&format!("Listing SST files of RocksDB at path: {:?}", db_path));
+
+            let start = start_key.as_deref().map(parse_key).transpose()?;
+            let end = end_key.as_deref().map(parse_key).transpose()?;
+            let range = KeyRange::new(start, end);
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            for file in commands::sst_info(&db, target_cf.as_deref(), &range)? {
+                out.emit(&file)?;
+            }
+        },
+        Commands::ExportSst {
+            db_path,
+            target_cf,
+            file,
+            start_key,
+            end_key,
+        } => {
+            out.info(&format!("Exporting SST file from RocksDB at path: {:?}", db_path));
+
+            let start = start_key.as_deref().map(parse_key).transpose()?;
+            let end = end_key.as_deref().map(parse_key).transpose()?;
+            let range = KeyRange::new(start, end);
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            out.emit(&commands::export_sst(&db, target_cf, &range, file)?)?;
+        },
+        Commands::IngestSst {
+            db_path,
+            target_cf,
+            files,
+            move_files,
+        } => {
+            out.info(&format!("Ingesting SST files into RocksDB at path: {:?}", db_path));
+
+            let db = db::retry_while_locked(lock_wait, || {
+                DbHandle::open_writable(db_path, mode, &[target_cf.as_str()], db_options(db_path)?)
+            })?;
+
+            out.emit(&commands::ingest_sst(&db, target_cf, files, *move_files)?)?;
+        },
+        Commands::Grep {
+            db_path,
+            target_cf,
+            hex,
+            regex,
+            limit,
+            skip,
+            page_size,
+            threads,
+        } => {
+            out.info(&format!("Searching values in RocksDB at path: {:?}", db_path));
+
+            let pattern = match (hex, regex) {
+                (Some(hex), None) => ValuePattern::Bytes(parse_key(hex)?),
+                (None, Some(regex)) => ValuePattern::Regex(regex::Regex::new(regex)?),
+                _ => return Err("Exactly one of --hex or --regex is required".into()),
+            };
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let threads = threads.unwrap_or_else(num_cpus);
+            let page = Pagination {
+                skip: *skip,
+                limit: *limit,
+                page_size: *page_size,
+            };
+            let mut emit = out.paged(page);
+            let summary = commands::grep(&db, target_cf, &pattern, threads, page, |key, _| {
+                Ok(emit(&KeyEntry { key: to_hex(key) })?)
+            })?;
+            out.emit(&summary)?;
+        },
+        Commands::Analyze { db_path, target_cf } => {
+            out.info(&format!("Analyzing RocksDB at path: {:?}", db_path));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            out.emit(&commands::analyze(&db, target_cf)?)?;
+        },
+        Commands::Partition {
+            db_path,
+            target_cf,
+            limb,
+            buckets,
+        } => {
+            out.info(&format!("Partitioning keys of RocksDB at path: {:?}", db_path));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            out.emit(&commands::partition(&db, target_cf, *limb, *buckets)?)?;
+        },
+        Commands::LargestValues {
+            db_path,
+            target_cf,
+            n,
+        } => {
+            out.info(&format!("Finding largest values in RocksDB at path: {:?}", db_path));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            out.emit(&commands::largest_values(&db, target_cf, *n)?)?;
+        },
+        Commands::FindDuplicates {
+            db_path,
+            target_cf,
+            limit,
+        } => {
+            out.info(&format!("Finding duplicate values in RocksDB at path: {:?}", db_path));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let summary = commands::find_duplicates(&db, target_cf, *limit, |group| {
+                Ok(out.emit(&group)?)
+            })?;
+            out.emit(&summary)?;
+        },
+        Commands::ValidateRecords {
+            db_path,
+            target_cf,
+            decode_as,
+        } => {
+            out.info(&format!("Validating records in RocksDB at path: {:?}", db_path));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let decoder = decoders.resolve(target_cf, decode_as.as_deref())?;
+            if decoder.name() == "raw" {
+                return Err(format!(
+                    "No decoder registered for column family '{}', pass --decode-as",
+                    target_cf
+                )
+                .into());
+            }
+
+            let progress = Progress::for_cf(show_progress, &db, target_cf)?.with_bytes();
+            let summary = commands::validate_records(
+                &db,
+                target_cf,
+                decoder,
+                |record| Ok(out.emit(&record)?),
+                |bytes| {
+                    progress.inc(1);
+                    progress.inc_bytes(bytes as u64);
+                },
+            )?;
+            progress.finish();
+
+            out.emit(&summary)?;
+        },
+        Commands::VerifyChecksums {
+            db_path,
+            column_families,
+            checkpoint_file,
+            resume,
+        } => {
+            out.info(&format!("Verifying checksums of RocksDB at path: {:?}", db_path));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let cf_names = if column_families.is_empty() {
+                db.cf_names().to_vec()
+            } else {
+                column_families.clone()
+            };
+            let progress = Progress::new(show_progress, None, "records").with_bytes();
+            let checkpoint = checkpoint_file.as_ref().map(|path| CheckpointFile {
+                path: path.clone(),
+                resume: *resume,
+            });
+            let summary = commands::verify_checksums(
+                &db,
+                &cf_names,
+                checkpoint.as_ref(),
+                |corruption| Ok(out.emit(&corruption)?),
+                |bytes| {
+                    progress.inc(1);
+                    progress.inc_bytes(bytes as u64);
+                },
+            )?;
+            progress.finish();
+
+            out.emit(&summary)?;
+        },
+        Commands::CheckBatch {
+            db_path,
+            target_cf,
+            key_file,
+            decode_as,
+            batch_size,
+        } => {
+            out.info(&format!("Checking keys from {:?} in RocksDB at path: {:?}", key_file, db_path));
+
+            // Keys are parsed while the previous batches are looked up
+            let keys = read_key_file(key_file, cli.key_format)?;
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let decoder = decoders.resolve(target_cf, decode_as.as_deref())?;
+            let progress = Progress::new(show_progress, None, "keys");
+            let summary = commands::check_batch(&db, target_cf, keys, *batch_size, decoder, |result| {
+                progress.inc(1);
+                Ok(out.emit(&result)?)
+            })?;
+            progress.finish();
+            out.emit(&summary)?;
+        },
+        Commands::Exists {
+            db_path,
+            target_cf,
+            key_file,
+        } => {
+            out.info(&format!("Checking keys from {:?} in RocksDB at path: {:?}", key_file, db_path));
+
+            let keys = parse_key_file(key_file, cli.key_format)?;
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            // Only the missing keys are printed, the summary counts the rest
+            let summary = commands::exists(&db, target_cf, &keys, |key| {
+                Ok(out.emit(&KeyEntry { key: to_hex(key) })?)
+            })?;
+            out.emit(&summary)?;
+        },
+        Commands::Bench {
+            db_path,
+            target_cf,
+            sample_size,
+            duration_secs,
+            threads,
+            batch_size,
+            seed,
+        } => {
+            out.info(&format!("Benchmarking RocksDB at path: {:?}", db_path));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let config = commands::BenchConfig {
+                sample_size: *sample_size,
+                duration: Duration::from_secs(*duration_secs),
+                threads: threads.unwrap_or_else(num_cpus),
+                batch_size: *batch_size,
+                seed: *seed,
+            };
+            out.emit(&commands::bench(&db, target_cf, &config)?)?;
+        },
+        Commands::BenchWrite {
+            db_path,
+            value_sizes,
+            durability,
+            batch_size,
+            duration_secs,
+            seed,
+            keep,
+        } => {
+            out.info(&format!("Benchmarking writes to scratch RocksDB at path: {:?}", db_path));
+
+            let config = commands::WriteBenchConfig {
+                value_sizes: value_sizes.clone(),
+                durabilities: durability.clone(),
+                batch_size: *batch_size,
+                duration: Duration::from_secs(*duration_secs),
+                seed: *seed,
+            };
+            let summary = commands::bench_write(db_path, &config, *keep, |case| Ok(out.emit(&case)?))?;
+            out.emit(&summary)?;
+        },
+        Commands::SelfTest {
+            db_path,
+            records,
+            max_data_len,
+            seed,
+            keep,
+        } => {
+            out.info(&format!("Self-testing records in scratch RocksDB at path: {:?}", db_path));
+
+            let config = commands::SelfTestConfig {
+                records: *records,
+                max_data_len: *max_data_len,
+                seed: seed.unwrap_or_else(random_seed),
+            };
+            let summary = commands::self_test(db_path, &config, *keep, |mismatch| {
+                Ok(out.emit(&mismatch)?)
+            })?;
+            out.emit(&summary)?;
+            if summary.mismatches > 0 {
+                return Err(format!("{} records did not round-trip", summary.mismatches).into());
+            }
+        },
+        Commands::Tail {
+            db_path,
+            target_cf,
+            secondary_path,
+            interval_ms,
+            decode_as,
+        } => {
+            out.info(&format!("Tailing RocksDB at path: {:?}", db_path));
+
+            let secondary_path = secondary_path
+                .clone()
+                .or_else(|| cli.secondary.clone())
+                .unwrap_or_else(db::default_secondary_path);
+
+            // Open the database as a secondary instance
+            let cf_names = DbHandle::list_cf_names(db_path)?;
+            let db = DbHandle::open_secondary_with_options(
+                db_path,
+                &secondary_path,
+                &cf_names,
+                db_options(db_path)?,
+            )?;
+
+            let decoder = decoders.resolve(target_cf, decode_as.as_deref())?;
+            let interval = Duration::from_millis(*interval_ms);
+            commands::tail(&db, target_cf, interval, |key, value| {
+                Ok(out.emit(&RecordEntry::decoded(decoder, key, value))?)
+            })?;
+        },
+        Commands::WalDump {
+            db_path,
+            since,
+            limit,
+        } => {
+            out.info(&format!("Reading the write-ahead log of RocksDB at path: {:?}", db_path));
+
+            // Open the database, read-only so the log is replayed without being flushed and removed
+            let db = open_db(db_path)?;
+
+            let summary = commands::wal_dump(&db, *since, *limit, |operation| {
+                Ok(out.emit(&operation)?)
+            })?;
+            out.emit(&summary)?;
+        },
+        Commands::Changes {
+            db_path,
+            since,
+            limit,
+        } => {
+            out.info(&format!("Streaming changes of RocksDB at path: {:?}", db_path));
+
+            // Open the database, read-only so the log is replayed without being flushed and removed
+            let db = open_db(db_path)?;
+
+            let summary = commands::changes(&db, *since, *limit, |batch| Ok(out.emit(&batch)?))?;
+            out.emit(&summary)?;
+        },
+        Commands::DbLog {
+            db_path,
+            log_file,
+            bucket_mins,
+        } => {
+            let log_file = match (log_file, db_path) {
+                (Some(log_file), _) => log_file.clone(),
+                (None, Some(db_path)) => db_path.join("LOG"),
+                (None, None) => return Err("Pass --db-path or --log-file".into()),
+            };
+            out.info(&format!("Analyzing RocksDB log file: {:?}", log_file));
+
+            out.emit(&commands::analyze_log(&log_file, bucket_mins * 60)?)?;
+        },
+        Commands::Compact {
+            db_path,
+            target_cf,
+            start_key,
+            end_key,
+            bottommost,
+        } => {
+            out.info(&format!("Compacting RocksDB at path: {:?}", db_path));
+
+            let start = start_key.as_deref().map(parse_key).transpose()?;
+            let end = end_key.as_deref().map(parse_key).transpose()?;
+            let range = KeyRange::new(start, end);
+
+            let cf_names = DbHandle::list_cf_names(db_path)?;
+            let db = db::retry_while_locked(lock_wait, || {
+                DbHandle::open_writable(db_path, mode, &cf_names, db_options(db_path)?)
+            })?;
+
+            out.emit(&commands::compact(&db, target_cf, &range, *bottommost)?)?;
+        },
+        Commands::Repair {
+            db_path,
+            backup_path,
+        } => {
+            out.info(&format!("Repairing RocksDB at path: {:?}", db_path));
+
+            mode.ensure_writable()?;
+            out.emit(&commands::repair(db_path, backup_path)?)?;
+        },
+        Commands::Copy {
+            db_path,
+            destination,
+            column_families,
+            start_key,
+            end_key,
+            method,
+        } => {
+            out.info(&format!("Copying RocksDB at path: {:?} to {:?}", db_path, destination));
+
+            let start = start_key.as_deref().map(parse_key).transpose()?;
+            let end = end_key.as_deref().map(parse_key).transpose()?;
+            let range = KeyRange::new(start, end);
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let cf_names = if column_families.is_empty() {
+                db.cf_names().to_vec()
+            } else {
+                column_families.clone()
+            };
+            out.emit(&commands::copy(&db, destination, &cf_names, &range, *method)?)?;
+        },
+        Commands::Compression {
+            db_path,
+            column_families,
+        } => {
+            out.info(&format!("Reporting compression of RocksDB at path: {:?}", db_path));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let cf_names = if column_families.is_empty() {
+                db.cf_names().to_vec()
+            } else {
+                column_families.clone()
+            };
+            out.emit(&commands::compression_report(&db, db_path, &cf_names)?)?;
+        },
+        Commands::Recompress {
+            db_path,
+            destination,
+            column_families,
+            codec,
+            level,
+        } => {
+            out.info(&format!(
+                "Recompressing RocksDB at path: {:?} into {:?}",
+                db_path, destination
+            ));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let cf_names = if column_families.is_empty() {
+                db.cf_names().to_vec()
+            } else {
+                column_families.clone()
+            };
+            out.emit(&commands::recompress(&db, destination, &cf_names, *codec, *level)?)?;
+        },
+        Commands::SetTtl {
+            db_path,
+            destination,
+            column_families,
+            lifetime,
+        } => {
+            out.info(&format!(
+                "Copying RocksDB at path: {:?} to TTL database {:?}",
+                db_path, destination
+            ));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let cf_names = if column_families.is_empty() {
+                db.cf_names().to_vec()
+            } else {
+                column_families.clone()
+            };
+            let ttl = Duration::from_secs(*lifetime);
+            out.emit(&commands::set_ttl(&db, destination, &cf_names, ttl)?)?;
+        },
+        Commands::RecordAge {
+            db_path,
+            target_cf,
+            expire_after,
+        } => {
+            out.info(&format!("Reading record ages in RocksDB at path: {:?}", db_path));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            out.emit(&commands::record_ages(&db, target_cf, *expire_after)?)?;
+        },
+        Commands::Merge {
+            sources,
+            destination,
+            column_families,
+            on_conflict,
+            cf_policies,
+        } => {
+            out.info(&format!("Merging {} databases into {:?}", sources.len(), destination));
+
+            let mut policies = BTreeMap::new();
+            for entry in cf_policies {
+                let (cf, policy) = entry
+                    .split_once('=')
+                    .ok_or_else(|| format!("Expected CF=POLICY, got '{}'", entry))?;
+                policies.insert(cf.to_string(), ConflictPolicy::from_str(policy, true)?);
+            }
+
+            // Open every source
+            let mut dbs = Vec::with_capacity(sources.len());
+            for source in sources {
+                dbs.push((source.display().to_string(), open_db(source)?));
+            }
+
+            let cf_names = if column_families.is_empty() {
+                let mut names: Vec<String> = Vec::new();
+                for (_, db) in &dbs {
+                    for name in db.cf_names() {
+                        if !names.contains(name) {
+                            names.push(name.clone());
+                        }
+                    }
+                }
+                names
+            } else {
+                column_families.clone()
+            };
+            out.emit(&commands::merge(&dbs, destination, &cf_names, *on_conflict, &policies)?)?;
+        },
+        Commands::Checkpoint {
+            db_path,
+            checkpoint_path,
+            column_families,
+        } => {
+            out.info(&format!("Creating checkpoint of RocksDB at path: {:?}", db_path));
+
+            // Checkpoints are taken from a read-write handle, the database must not be open elsewhere
+            let cf_names = DbHandle::list_cf_names(db_path)?;
+            let db = db::retry_while_locked(lock_wait, || {
+                DbHandle::open_read_write_with_options(db_path, &cf_names, db_options(db_path)?)
+            })?;
+
+            let keep_cfs = if column_families.is_empty() {
+                None
+            } else {
+                Some(column_families.as_slice())
+            };
+            out.emit(&commands::checkpoint(&db, checkpoint_path, keep_cfs)?)?;
+        },
+        Commands::Backup {
+            db_path,
+            backup_dir,
+            keep_last,
+        } => {
+            out.info(&format!("Backing up RocksDB at path: {:?}", db_path));
+
+            // Backups are taken from a read-write handle, the database must not be open elsewhere
+            let cf_names = DbHandle::list_cf_names(db_path)?;
+            let db = db::retry_while_locked(lock_wait, || {
+                DbHandle::open_read_write_with_options(db_path, &cf_names, db_options(db_path)?)
+            })?;
+
+            out.emit(&commands::backup(&db, backup_dir, *keep_last)?)?;
+        },
+        Commands::ListBackups { backup_dir, verify } => {
+            out.info(&format!("Listing backups in: {:?}", backup_dir));
+
+            for info in commands::list_backups(backup_dir, *verify)? {
+                out.emit(&info)?;
+            }
+        },
+        Commands::RestoreBackup {
+            backup_dir,
+            db_path,
+            backup_id,
+        } => {
+            out.info(&format!("Restoring backup from {:?} to path: {:?}", backup_dir, db_path));
+
+            out.emit(&commands::restore_backup(backup_dir, db_path, *backup_id)?)?;
+        },
+        #[cfg(feature = "mongo")]
+        Commands::CompareMongo {
+            db_path,
+            target_cf,
+            mongo_uri,
+            database,
+            collection,
+            record_type,
+        } => {
+            out.info(&format!("Comparing MongoDB at {} with RocksDB at path: {:?}", mongo_uri, db_path));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let source = MongoSource {
+                uri: mongo_uri,
+                database,
+                collection: collection.as_deref().unwrap_or(target_cf),
+                record_type: record_type.unwrap_or_else(|| MongoRecordType::for_cf(target_cf)),
+            };
+            let progress = Progress::new(show_progress, None, "records");
+            let summary = commands::compare_mongo(
+                &db,
+                target_cf,
+                &source,
+                |diff| Ok(out.emit(&diff)?),
+                |processed| progress.set_position(processed),
+            )?;
+            progress.finish();
+
+            out.emit(&summary)?;
+        },
+        #[cfg(feature = "mongo")]
+        Commands::MigrateFromMongo {
+            db_path,
+            target_cf,
+            mongo_uri,
+            database,
+            collection,
+            record_type,
+            batch_size,
+            checkpoint_file,
+        } => {
+            out.info(&format!("Migrating MongoDB at {} into RocksDB at path: {:?}", mongo_uri, db_path));
+
+            let db = db::retry_while_locked(lock_wait, || {
+                DbHandle::open_writable(db_path, mode, &[target_cf.as_str()], db_options(db_path)?)
+            })?;
+
+            let source = MongoSource {
+                uri: mongo_uri,
+                database,
+                collection: collection.as_deref().unwrap_or(target_cf),
+                record_type: record_type.unwrap_or_else(|| MongoRecordType::for_cf(target_cf)),
+            };
+            let progress = Progress::new(show_progress, Some(0), "documents");
+            let summary = commands::migrate_from_mongo(
+                &db,
+                target_cf,
+                &source,
+                *batch_size,
+                checkpoint_file.as_deref(),
+                |migrated, total| {
+                    progress.set_total(total);
+                    progress.set_position(migrated);
+                },
+            )?;
+            progress.finish();
+
+            out.emit(&summary)?;
+        },
+        #[cfg(feature = "serve")]
+        Commands::Serve { db_path, listen } => {
+            out.info(&format!("Serving RocksDB at path: {:?} on http://{}", db_path, listen));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let runtime = tokio::runtime::Runtime::new()?;
+            runtime.block_on(playground_rocksdb_tool::serve::serve(db, decoders, *listen))?;
+        },
+        Commands::Rpc { db_path, socket } => {
+            out.info(&format!("Answering JSON-RPC requests for RocksDB at path: {:?}", db_path));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            match socket {
+                #[cfg(unix)]
+                Some(path) => playground_rocksdb_tool::rpc::serve_socket(&db, &decoders, path)?,
+                #[cfg(not(unix))]
+                Some(_) => return Err("--socket needs unix domain sockets".into()),
+                None => playground_rocksdb_tool::rpc::serve_stdio(&db, &decoders)?,
+            }
+        },
+        #[cfg(feature = "grpc")]
+        Commands::ServeGrpc { db_path, listen } => {
+            out.info(&format!("Serving RocksDB at path: {:?} over gRPC on {}", db_path, listen));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let runtime = tokio::runtime::Runtime::new()?;
+            runtime.block_on(playground_rocksdb_tool::grpc::serve(db, decoders, *listen))?;
+        },
+        #[cfg(feature = "serve")]
+        Commands::Metrics { db_path, listen, column_families } => {
+            out.info(&format!(
+                "Exporting metrics of RocksDB at path: {:?} on http://{}/metrics",
+                db_path, listen
+            ));
+
+            // Open the database
+            let db = open_db(db_path)?;
+
+            let cf_names = if column_families.is_empty() {
+                db.cf_names().to_vec()
+            } else {
+                column_families.clone()
+            };
+            for name in &cf_names {
+                db.cf(name)?;
+            }
+            let runtime = tokio::runtime::Runtime::new()?;
+            runtime.block_on(playground_rocksdb_tool::metrics::serve_metrics(
+                db, cf_names, *listen,
+            ))?;
+        },
+        Commands::Completions { shell } => {
+            let mut stdout = std::io::stdout();
+            write_completions(*shell, &mut Cli::command(), &mut stdout)?;
+        },
+        Commands::CompleteCf { db_path } => {
+            for name in DbHandle::list_cf_names(db_path)? {
+                println!("{}", name);
+            }
+        },
+        #[cfg(feature = "tui")]
+        Commands::Browse { db_path, page_size } => {
+            // Open the database
+            let db = open_db(db_path)?;
 
-            println!("Total number of records in column family '{}': {}", target_cf, count);
+            playground_rocksdb_tool::browse::run(&db, &decoders, *page_size)?;
         },
     }
 
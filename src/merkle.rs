@@ -0,0 +1,504 @@
+use rocksdb::ColumnFamily;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use zkwasm_host_circuits::host::merkle::MerkleTree;
+use zkwasm_host_circuits::host::mongomerkle::{MerkleRecord, MongoMerkle};
+
+use crate::db::DbHandle;
+use crate::error::Error;
+use crate::output::to_hex;
+
+/// Depth of the merkle trees written by the zkwasm prover
+pub const MERKLE_DEPTH: usize = 32;
+
+/// Hashes two child nodes into their parent the same way mongomerkle does
+pub fn hash_children(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    <MongoMerkle<MERKLE_DEPTH> as MerkleTree<[u8; 32], MERKLE_DEPTH>>::hash(left, right)
+}
+
+/// Hashes of empty subtrees as mongomerkle fills them in, indexed by node depth with the root at
+/// depth 0 and leaves at depth `MERKLE_DEPTH`
+pub fn default_hashes() -> Result<Vec<[u8; 32]>, Box<dyn std::error::Error>> {
+    (0..=MERKLE_DEPTH)
+        .map(|depth| {
+            MongoMerkle::<MERKLE_DEPTH>::get_default_hash(depth)
+                .map_err(|e| format!("No default hash for depth {}: {:?}", depth, e).into())
+        })
+        .collect()
+}
+
+/// Whether `hash` is the empty subtree hash for the depth of heap index `index`
+pub fn is_default(defaults: &[[u8; 32]], index: u64, hash: &[u8; 32]) -> bool {
+    defaults.get(depth_of(index)) == Some(hash)
+}
+
+pub fn left_child_index(index: u64) -> u64 {
+    index * 2 + 1
+}
+
+pub fn right_child_index(index: u64) -> u64 {
+    index * 2 + 2
+}
+
+/// Heap indices of the leaves of a tree of depth `MERKLE_DEPTH`
+pub fn leaf_index_range() -> std::ops::RangeInclusive<u64> {
+    let first = (1u64 << MERKLE_DEPTH) - 1;
+    first..=(first * 2)
+}
+
+/// Indices of the nodes from the root down to `index`, both included
+pub fn ancestors(index: u64) -> Vec<u64> {
+    let mut indices = vec![index];
+    let mut current = index;
+    while current > 0 {
+        current = (current - 1) / 2;
+        indices.push(current);
+    }
+    indices.reverse();
+    indices
+}
+
+/// Converts a parsed key into a node hash
+pub fn to_hash(bytes: &[u8]) -> Result<[u8; 32], Error> {
+    bytes.try_into().map_err(|_| {
+        Error::KeyParse(format!("Expected a 32-byte hash, got {} bytes", bytes.len()))
+    })
+}
+
+/// Reads and decodes the merkle record stored under `hash`
+pub fn get_record(
+    db: &DbHandle,
+    cf: &ColumnFamily,
+    hash: &[u8; 32],
+) -> Result<Option<MerkleRecord>, Box<dyn std::error::Error>> {
+    match db.get_cf_opt(cf, hash, &db.read_options())? {
+        Some(value) => Ok(Some(MerkleRecord::from_slice(&value)?)),
+        None => Ok(None),
+    }
+}
+
+/// One level of an authentication path
+pub struct PathStep {
+    /// Index of the node on the path at this level
+    pub index: u64,
+    pub hash: [u8; 32],
+    pub sibling_index: u64,
+    pub sibling_hash: [u8; 32],
+    /// Whether the sibling is the left child of the shared parent
+    pub sibling_is_left: bool,
+}
+
+/// Walks down from `root` to the leaf at `leaf_index`, returns the steps ordered from the leaf up to the root
+///
+/// mongomerkle does not store empty subtrees, a node missing from `cf` whose hash is in `defaults`
+/// for its depth is taken to have default children.
+pub fn authentication_path(
+    db: &DbHandle,
+    cf: &ColumnFamily,
+    defaults: &[[u8; 32]],
+    root: [u8; 32],
+    leaf_index: u64,
+) -> Result<Vec<PathStep>, Box<dyn std::error::Error>> {
+    let mut steps = Vec::new();
+    let mut hash = root;
+    let indices = ancestors(leaf_index);
+
+    for pair in indices.windows(2) {
+        let (index, next) = (pair[0], pair[1]);
+        let (left, right) = match get_record(db, cf, &hash)? {
+            Some(MerkleRecord {
+                left: Some(left),
+                right: Some(right),
+                ..
+            }) => (left, right),
+            None if is_default(defaults, index, &hash) => {
+                let child = defaults[depth_of(index) + 1];
+                (child, child)
+            }
+            None => {
+                return Err(format!("Missing node at index {}: {}", index, to_hex(&hash)).into())
+            }
+            Some(_) => {
+                return Err(format!(
+                    "Node at index {} ({}) has no children, cannot descend to index {}",
+                    index,
+                    to_hex(&hash),
+                    leaf_index
+                )
+                .into())
+            }
+        };
+
+        let step = if next == left_child_index(index) {
+            PathStep {
+                index: next,
+                hash: left,
+                sibling_index: right_child_index(index),
+                sibling_hash: right,
+                sibling_is_left: false,
+            }
+        } else {
+            PathStep {
+                index: next,
+                hash: right,
+                sibling_index: left_child_index(index),
+                sibling_hash: left,
+                sibling_is_left: true,
+            }
+        };
+        hash = step.hash;
+        steps.push(step);
+    }
+
+    steps.reverse();
+    Ok(steps)
+}
+
+/// Folds a leaf hash with the siblings of an authentication path into a root hash
+pub fn compute_root(leaf_hash: &[u8; 32], steps: &[PathStep]) -> [u8; 32] {
+    steps.iter().fold(*leaf_hash, |acc, step| {
+        if step.sibling_is_left {
+            hash_children(&step.sibling_hash, &acc)
+        } else {
+            hash_children(&acc, &step.sibling_hash)
+        }
+    })
+}
+
+/// A problem found while walking a merkle tree
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MerkleIssue {
+    /// A referenced node is not stored in the column family
+    MissingNode { index: u64, hash: String },
+    /// A stored value could not be decoded as a `MerkleRecord`
+    DecodeError { index: u64, hash: String, error: String },
+    /// The record stored under a key carries a different hash
+    KeyMismatch { index: u64, key: String, record_hash: String },
+    /// The record sits at a different index than its parent points to
+    IndexMismatch { expected: u64, actual: u64, hash: String },
+    /// The node hash does not equal the hash of its children
+    HashMismatch { index: u64, stored: String, computed: String },
+    /// Only one of the two children is set
+    BrokenLink { index: u64, hash: String },
+    /// The node sits below `MERKLE_DEPTH` or sits at it with children, its subtree is not walked
+    TooDeep { index: u64, hash: String },
+}
+
+impl fmt::Display for MerkleIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MerkleIssue::MissingNode { index, hash } => {
+                write!(f, "Missing node at index {}: {}", index, hash)
+            }
+            MerkleIssue::DecodeError { index, hash, error } => {
+                write!(f, "Failed to decode node at index {} ({}): {}", index, hash, error)
+            }
+            MerkleIssue::KeyMismatch {
+                index,
+                key,
+                record_hash,
+            } => write!(
+                f,
+                "Node at index {} is stored under key {} but has hash {}",
+                index, key, record_hash
+            ),
+            MerkleIssue::IndexMismatch {
+                expected,
+                actual,
+                hash,
+            } => write!(
+                f,
+                "Node {} has index {} but its parent expects index {}",
+                hash, actual, expected
+            ),
+            MerkleIssue::HashMismatch {
+                index,
+                stored,
+                computed,
+            } => write!(
+                f,
+                "Hash mismatch at index {}: stored {} computed {}",
+                index, stored, computed
+            ),
+            MerkleIssue::BrokenLink { index, hash } => {
+                write!(f, "Node at index {} ({}) has only one child", index, hash)
+            }
+            MerkleIssue::TooDeep { index, hash } => write!(
+                f,
+                "Node at index {} ({}) has children below depth {}",
+                index, hash, MERKLE_DEPTH
+            ),
+        }
+    }
+}
+
+/// Outcome of walking a merkle tree from a root
+#[derive(Default)]
+pub struct VerifyReport {
+    pub nodes_visited: u64,
+    pub leaves: u64,
+    /// Unstored nodes holding the empty subtree hash of their depth
+    pub default_nodes: u64,
+    /// Default nodes at leaf depth
+    pub default_leaves: u64,
+    pub issues: Vec<MerkleIssue>,
+}
+
+/// Walks the tree below `root`, checking every node hash against the hash of its children
+///
+/// Unstored nodes whose hash is in `defaults` for their depth are counted as default nodes rather
+/// than reported missing. `on_node` is called with the value size of every node read. A stored
+/// node is walked once even when several parents reference it, and nothing below `MERKLE_DEPTH` is
+/// walked, so corrupt child hashes pointing back up the tree cannot make the walk loop.
+pub fn verify_tree<F>(
+    db: &DbHandle,
+    cf: &ColumnFamily,
+    defaults: &[[u8; 32]],
+    root: [u8; 32],
+    mut on_node: F,
+) -> Result<VerifyReport, Box<dyn std::error::Error>>
+where
+    F: FnMut(usize),
+{
+    let read_opts = db.read_options();
+    let mut report = VerifyReport::default();
+    let mut visited = HashSet::new();
+    // (hash, expected index), the root index is taken from the root record itself
+    let mut stack: Vec<([u8; 32], Option<u64>)> = vec![(root, None)];
+
+    while let Some((hash, expected_index)) = stack.pop() {
+        let index = expected_index.unwrap_or(0);
+        let record = match db.get_cf_opt(cf, hash, &read_opts)? {
+            Some(value) => {
+                if !visited.insert(hash) {
+                    continue;
+                }
+                on_node(value.len());
+                match MerkleRecord::from_slice(&value) {
+                    Ok(record) => record,
+                    Err(e) => {
+                        report.issues.push(MerkleIssue::DecodeError {
+                            index,
+                            hash: to_hex(&hash),
+                            error: e.to_string(),
+                        });
+                        continue;
+                    }
+                }
+            }
+            None if is_default(defaults, index, &hash) => {
+                report.default_nodes += 1;
+                if depth_of(index) == MERKLE_DEPTH {
+                    report.default_leaves += 1;
+                }
+                continue;
+            }
+            None => {
+                report.issues.push(MerkleIssue::MissingNode {
+                    index,
+                    hash: to_hex(&hash),
+                });
+                continue;
+            }
+        };
+        report.nodes_visited += 1;
+        // Children are placed below the position the parent implies, a corrupt stored index is
+        // reported as a mismatch but not followed
+        let index = expected_index.unwrap_or(record.index);
+
+        if record.hash != hash {
+            report.issues.push(MerkleIssue::KeyMismatch {
+                index: record.index,
+                key: to_hex(&hash),
+                record_hash: to_hex(&record.hash),
+            });
+        }
+
+        if let Some(expected) = expected_index {
+            if record.index != expected {
+                report.issues.push(MerkleIssue::IndexMismatch {
+                    expected,
+                    actual: record.index,
+                    hash: to_hex(&hash),
+                });
+            }
+        }
+
+        match (record.left, record.right) {
+            (Some(left), Some(right)) => {
+                let computed = hash_children(&left, &right);
+                if computed != record.hash {
+                    report.issues.push(MerkleIssue::HashMismatch {
+                        index: record.index,
+                        stored: to_hex(&record.hash),
+                        computed: to_hex(&computed),
+                    });
+                }
+                if index >= *leaf_index_range().start() {
+                    report.issues.push(MerkleIssue::TooDeep {
+                        index,
+                        hash: to_hex(&hash),
+                    });
+                } else {
+                    stack.push((right, Some(right_child_index(index))));
+                    stack.push((left, Some(left_child_index(index))));
+                }
+            }
+            (None, None) => report.leaves += 1,
+            _ => report.issues.push(MerkleIssue::BrokenLink {
+                index: record.index,
+                hash: to_hex(&hash),
+            }),
+        }
+    }
+
+    Ok(report)
+}
+
+/// Depth of the node at heap index `index`, the root is at depth 0
+pub fn depth_of(index: u64) -> usize {
+    // Widened so that a corrupt index of u64::MAX does not overflow
+    (u128::BITS - 1 - (u128::from(index) + 1).leading_zeros()) as usize
+}
+
+/// First and last leaf index below the node at heap index `index`
+pub fn leaf_span(index: u64) -> (u64, u64) {
+    let levels_below = MERKLE_DEPTH - depth_of(index);
+    (((index + 1) << levels_below) - 1, ((index + 2) << levels_below) - 2)
+}
+
+/// Node counts per level and structural anomalies of a stored tree
+#[derive(Default)]
+pub struct TreeShape {
+    pub nodes: u64,
+    pub leaves: u64,
+    pub max_depth: usize,
+    /// Stored nodes per depth, index 0 is the root
+    pub nodes_per_level: Vec<u64>,
+    /// Children referenced but not stored per depth, other than default subtrees
+    pub unstored_per_level: Vec<u64>,
+    /// Unstored children holding the empty subtree hash of their depth, mongomerkle leaves those
+    /// unstored
+    pub default_per_level: Vec<u64>,
+    /// Nodes with exactly one child set
+    pub single_child_nodes: u64,
+    /// Nodes without children above `MERKLE_DEPTH`
+    pub shallow_leaves: u64,
+    /// Nodes below `MERKLE_DEPTH`
+    pub too_deep_nodes: u64,
+    /// Nodes whose stored index differs from the position their parent implies
+    pub index_mismatches: u64,
+    pub undecodable_nodes: u64,
+}
+
+fn bump(levels: &mut Vec<u64>, depth: usize) {
+    if levels.len() <= depth {
+        levels.resize(depth + 1, 0);
+    }
+    levels[depth] += 1;
+}
+
+/// Walks the tree below `root` and records how many nodes sit on each level and where it deviates
+/// from a complete binary tree of depth `MERKLE_DEPTH`
+///
+/// Nodes below `MERKLE_DEPTH` are counted as too deep, their children are not walked.
+pub fn tree_shape(
+    db: &DbHandle,
+    cf: &ColumnFamily,
+    defaults: &[[u8; 32]],
+    root: [u8; 32],
+) -> Result<TreeShape, Box<dyn std::error::Error>> {
+    let read_opts = db.read_options();
+    let mut shape = TreeShape::default();
+    // (hash, expected index), the root index is taken from the root record itself
+    let mut stack: Vec<([u8; 32], Option<u64>)> = vec![(root, None)];
+
+    while let Some((hash, expected_index)) = stack.pop() {
+        let value = match db.get_cf_opt(cf, hash, &read_opts)? {
+            Some(value) => value,
+            None => {
+                let index = expected_index.unwrap_or(0);
+                if is_default(defaults, index, &hash) {
+                    bump(&mut shape.default_per_level, depth_of(index));
+                } else {
+                    bump(&mut shape.unstored_per_level, depth_of(index));
+                }
+                continue;
+            }
+        };
+        let record = match MerkleRecord::from_slice(&value) {
+            Ok(record) => record,
+            Err(_) => {
+                shape.undecodable_nodes += 1;
+                continue;
+            }
+        };
+
+        if expected_index.map_or(false, |expected| expected != record.index) {
+            shape.index_mismatches += 1;
+        }
+        let index = expected_index.unwrap_or(record.index);
+        let depth = depth_of(index);
+        shape.nodes += 1;
+        shape.max_depth = shape.max_depth.max(depth);
+        bump(&mut shape.nodes_per_level, depth);
+        if depth > MERKLE_DEPTH {
+            shape.too_deep_nodes += 1;
+            // Nothing below a too deep node is walked, corrupt child hashes pointing back up the
+            // tree would otherwise be followed forever
+            continue;
+        }
+
+        match (record.left, record.right) {
+            (Some(left), Some(right)) => {
+                stack.push((right, Some(right_child_index(index))));
+                stack.push((left, Some(left_child_index(index))));
+            }
+            (None, None) => {
+                shape.leaves += 1;
+                if depth < MERKLE_DEPTH {
+                    shape.shallow_leaves += 1;
+                }
+            }
+            _ => shape.single_child_nodes += 1,
+        }
+    }
+
+    Ok(shape)
+}
+
+/// Collects the hashes of every stored node reachable from any of `roots`
+///
+/// Referenced nodes that are not stored are skipped, as are the children of nodes that fail to
+/// decode, so the set only holds keys present in the column family.
+pub fn reachable_hashes(
+    db: &DbHandle,
+    cf: &ColumnFamily,
+    roots: &[[u8; 32]],
+) -> Result<HashSet<[u8; 32]>, Box<dyn std::error::Error>> {
+    let read_opts = db.read_options();
+    let mut reachable = HashSet::new();
+    let mut stack: Vec<[u8; 32]> = roots.to_vec();
+
+    while let Some(hash) = stack.pop() {
+        if reachable.contains(&hash) {
+            continue;
+        }
+        let value = match db.get_cf_opt(cf, hash, &read_opts)? {
+            Some(value) => value,
+            None => continue,
+        };
+        reachable.insert(hash);
+
+        if let Ok(record) = MerkleRecord::from_slice(&value) {
+            if let (Some(left), Some(right)) = (record.left, record.right) {
+                stack.push(right);
+                stack.push(left);
+            }
+        }
+    }
+
+    Ok(reachable)
+}
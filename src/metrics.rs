@@ -0,0 +1,198 @@
+//! Prometheus exporter publishing the health of a database for continuous scraping
+
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use rocksdb::properties;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use crate::commands::NUM_LEVELS;
+use crate::db::DbHandle;
+
+/// Content type of the Prometheus text exposition format
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Gauges read from an integer property of every column family: name, property and help text
+const CF_GAUGES: &[(&str, &str, &str)] = &[
+    (
+        "rocksdb_estimated_keys",
+        "rocksdb.estimate-num-keys",
+        "Estimated number of keys in the column family",
+    ),
+    (
+        "rocksdb_sst_files_bytes",
+        "rocksdb.total-sst-files-size",
+        "Total size of all SST files of the column family",
+    ),
+    (
+        "rocksdb_live_sst_files_bytes",
+        "rocksdb.live-sst-files-size",
+        "Size of the SST files belonging to the latest version",
+    ),
+    (
+        "rocksdb_estimated_live_data_bytes",
+        "rocksdb.estimate-live-data-size",
+        "Estimated amount of live data",
+    ),
+    (
+        "rocksdb_memtables_bytes",
+        "rocksdb.size-all-mem-tables",
+        "Size of the active, unflushed immutable and pinned memtables",
+    ),
+    (
+        "rocksdb_immutable_memtables",
+        "rocksdb.num-immutable-mem-table",
+        "Number of immutable memtables not yet flushed",
+    ),
+    (
+        "rocksdb_compaction_pending",
+        "rocksdb.compaction-pending",
+        "1 when at least one compaction is pending",
+    ),
+    (
+        "rocksdb_pending_compaction_bytes",
+        "rocksdb.estimate-pending-compaction-bytes",
+        "Estimated bytes compaction needs to rewrite to bring all levels under their target size",
+    ),
+    (
+        "rocksdb_running_compactions",
+        "rocksdb.num-running-compactions",
+        "Number of compactions currently running",
+    ),
+    (
+        "rocksdb_background_errors",
+        "rocksdb.background-errors",
+        "Accumulated number of background errors",
+    ),
+    (
+        "rocksdb_write_stopped",
+        "rocksdb.is-write-stopped",
+        "1 when writes are stopped",
+    ),
+    (
+        "rocksdb_delayed_write_rate",
+        "rocksdb.actual-delayed-write-rate",
+        "Current delayed write rate in bytes per second, 0 when writes are not delayed",
+    ),
+    (
+        "rocksdb_block_cache_capacity_bytes",
+        "rocksdb.block-cache-capacity",
+        "Capacity of the block cache used by the column family",
+    ),
+    (
+        "rocksdb_block_cache_usage_bytes",
+        "rocksdb.block-cache-usage",
+        "Memory used by entries in the block cache",
+    ),
+    (
+        "rocksdb_block_cache_pinned_usage_bytes",
+        "rocksdb.block-cache-pinned-usage",
+        "Memory used by pinned entries in the block cache",
+    ),
+];
+
+/// Escapes a label value as the exposition format requires
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn write_header(text: &mut String, name: &str, help: &str) {
+    let _ = writeln!(text, "# HELP {} {}", name, help);
+    let _ = writeln!(text, "# TYPE {} gauge", name);
+}
+
+/// Renders the metrics of the column families in `cf_names` in the Prometheus text format
+///
+/// Properties a column family does not report are left out rather than exported as 0.
+pub fn render_metrics(
+    db: &DbHandle,
+    cf_names: &[String],
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut cfs = Vec::with_capacity(cf_names.len());
+    for name in cf_names {
+        cfs.push((escape_label(name), db.cf(name)?));
+    }
+
+    let mut text = String::new();
+    for (metric, property, help) in CF_GAUGES {
+        write_header(&mut text, metric, help);
+        for (label, cf) in &cfs {
+            if let Some(value) = db.property_int_value_cf(*cf, *property)? {
+                let _ = writeln!(text, "{}{{cf=\"{}\"}} {}", metric, label, value);
+            }
+        }
+    }
+
+    write_header(&mut text, "rocksdb_level_files", "Number of SST files on each level");
+    for (label, cf) in &cfs {
+        for level in 0..NUM_LEVELS {
+            let property = properties::num_files_at_level(level);
+            if let Some(files) = db.property_int_value_cf(*cf, &*property)? {
+                let _ = writeln!(
+                    text,
+                    "rocksdb_level_files{{cf=\"{}\",level=\"{}\"}} {}",
+                    label, level, files
+                );
+            }
+        }
+    }
+
+    write_header(
+        &mut text,
+        "rocksdb_latest_sequence_number",
+        "Sequence number of the most recent write",
+    );
+    let _ = writeln!(text, "rocksdb_latest_sequence_number {}", db.latest_sequence_number());
+    Ok(text)
+}
+
+#[derive(Clone)]
+struct AppState {
+    db: Arc<DbHandle>,
+    cf_names: Arc<Vec<String>>,
+}
+
+async fn get_metrics(State(state): State<AppState>) -> Response {
+    let rendered = tokio::task::spawn_blocking(move || {
+        state.db.catch_up().map_err(|e| e.to_string())?;
+        render_metrics(&state.db, &state.cf_names).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|result| result);
+
+    match rendered {
+        Ok(text) => ([(header::CONTENT_TYPE, CONTENT_TYPE)], text).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+/// Serves `GET /metrics` on `addr` until the process is stopped
+///
+/// Every scrape reads the properties anew, a secondary instance catches up with its primary
+/// first so the metrics follow a database another process keeps writing.
+pub async fn serve_metrics(
+    db: DbHandle,
+    cf_names: Vec<String>,
+    addr: SocketAddr,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let state = AppState {
+        db: Arc::new(db),
+        cf_names: Arc::new(cf_names),
+    };
+    let app = Router::new()
+        .route("/metrics", get(get_metrics))
+        .with_state(state);
+
+    axum::Server::bind(&addr)
+        .serve(app.into_make_service())
+        .await?;
+    Ok(())
+}
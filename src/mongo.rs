@@ -0,0 +1,66 @@
+//! Reading the MongoDB store of mongomerkle and datahash, the other backend the records live in
+
+use clap::ValueEnum;
+use mongodb::bson::{self, Document};
+use mongodb::sync::{Client, Collection};
+use zkwasm_host_circuits::host::{datahash::DataHashRecord, mongomerkle::MerkleRecord};
+
+/// Type of the documents stored in a collection
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum MongoRecordType {
+    /// `MerkleRecord` documents, as in merkle_records
+    Merkle,
+    /// `DataHashRecord` documents, as in data_records
+    Data,
+}
+
+impl MongoRecordType {
+    /// Record type stored in a RocksDB column family, merkle records unless it is data_records
+    pub fn for_cf(cf_name: &str) -> Self {
+        if cf_name == crate::DATA_CF_NAME {
+            MongoRecordType::Data
+        } else {
+            MongoRecordType::Merkle
+        }
+    }
+}
+
+/// A collection of records in a MongoDB deployment
+pub struct MongoSource<'a> {
+    pub uri: &'a str,
+    pub database: &'a str,
+    pub collection: &'a str,
+    pub record_type: MongoRecordType,
+}
+
+impl MongoSource<'_> {
+    /// Connects and opens the collection as raw documents
+    pub fn open(&self) -> Result<Collection<Document>, mongodb::error::Error> {
+        let client = Client::with_uri_str(self.uri)?;
+        Ok(client
+            .database(self.database)
+            .collection::<Document>(self.collection))
+    }
+
+    /// Converts a document into the RocksDB key and value the same record is stored under
+    pub fn to_key_value(&self, document: Document) -> Result<(Vec<u8>, Vec<u8>), bson::de::Error> {
+        to_key_value(self.record_type, document)
+    }
+}
+
+/// Converts a document into the RocksDB key and value the same record is stored under
+pub fn to_key_value(
+    record_type: MongoRecordType,
+    document: Document,
+) -> Result<(Vec<u8>, Vec<u8>), bson::de::Error> {
+    match record_type {
+        MongoRecordType::Merkle => {
+            let record: MerkleRecord = bson::from_document(document)?;
+            Ok((record.hash.to_vec(), record.to_slice()))
+        }
+        MongoRecordType::Data => {
+            let record: DataHashRecord = bson::from_document(document)?;
+            Ok((record.hash.to_vec(), record.to_slice()))
+        }
+    }
+}
@@ -0,0 +1,3016 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::field;
+use crate::interpret::{interpret, Interpretation, ValueType};
+use crate::paginate::Pagination;
+use crate::record::{DecodedRecord, RecordDecoder};
+
+/// Output format shared by all commands
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable text
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// Renders command results either as text or as JSON lines
+pub struct Output {
+    format: OutputFormat,
+}
+
+impl Output {
+    pub fn new(format: OutputFormat) -> Self {
+        Output { format }
+    }
+
+    pub fn is_json(&self) -> bool {
+        self.format == OutputFormat::Json
+    }
+
+    /// Logs a status message at info level, to stderr so stdout only holds results
+    pub fn info(&self, message: &str) {
+        tracing::info!("{}", message);
+    }
+
+    /// Prints a result using its `Display` impl in text mode or as a single JSON line
+    pub fn emit<T: Serialize + fmt::Display>(&self, value: &T) -> Result<(), serde_json::Error> {
+        match self.format {
+            OutputFormat::Text => println!("{}", value),
+            OutputFormat::Json => println!("{}", serde_json::to_string(value)?),
+        }
+        Ok(())
+    }
+
+    /// A callback emitting the records of a listing, followed by a page break after every full
+    /// page of `page`
+    pub fn paged<'a, T: Serialize + fmt::Display>(
+        &'a self,
+        page: Pagination,
+    ) -> impl FnMut(&T) -> Result<(), serde_json::Error> + 'a {
+        let mut listed = 0;
+        move |value| {
+            self.emit(value)?;
+            listed += 1;
+            match page.page_break(listed) {
+                Some(page_break) => self.emit(&page_break),
+                None => Ok(()),
+            }
+        }
+    }
+
+    /// Like [`Output::emit`] but on stderr, for reports about the command rather than its results
+    pub fn emit_stderr<T: Serialize + fmt::Display>(
+        &self,
+        value: &T,
+    ) -> Result<(), serde_json::Error> {
+        match self.format {
+            OutputFormat::Text => eprintln!("{}", value),
+            OutputFormat::Json => eprintln!("{}", serde_json::to_string(value)?),
+        }
+        Ok(())
+    }
+}
+
+/// Formats bytes as a 0x-prefixed hex string
+pub fn to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// A key rendered as hex, and as little-endian u64 limbs when it is a 32-byte hash
+#[derive(Serialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct KeyView {
+    pub hex: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub u64_limbs: Option<[u64; 4]>,
+}
+
+impl KeyView {
+    pub fn new(key: &[u8]) -> Self {
+        let u64_limbs = <[u8; 32]>::try_from(key)
+            .ok()
+            .map(|bytes| field::to_u64_limbs(&bytes));
+        KeyView {
+            hex: to_hex(key),
+            u64_limbs,
+        }
+    }
+}
+
+impl fmt::Display for KeyView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.u64_limbs {
+            Some(limbs) => write!(f, "{} {:?}", self.hex, limbs),
+            None => write!(f, "{}", self.hex),
+        }
+    }
+}
+
+/// A single key-value pair read from a column family
+#[derive(Serialize)]
+pub struct RecordEntry {
+    pub key: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decoded: Option<DecodedRecord>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decode_error: Option<String>,
+}
+
+impl RecordEntry {
+    /// Builds an entry with the raw bytes only
+    pub fn raw(key: &[u8], value: &[u8]) -> Self {
+        RecordEntry {
+            key: to_hex(key),
+            value: to_hex(value),
+            decoded: None,
+            decode_error: None,
+        }
+    }
+
+    /// Builds an entry and decodes the value with `decoder`
+    pub fn decoded(decoder: &dyn RecordDecoder, key: &[u8], value: &[u8]) -> Self {
+        let _span = tracing::trace_span!("decode", decoder = decoder.name()).entered();
+        let mut entry = Self::raw(key, value);
+        match decoder.decode(value) {
+            Ok(decoded) => entry.decoded = decoded,
+            Err(e) => entry.decode_error = Some(e.to_string()),
+        }
+        entry
+    }
+}
+
+impl fmt::Display for RecordEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Key: {}", self.key)?;
+        match (&self.decoded, &self.decode_error) {
+            (Some(record), _) => write!(f, "\n  {}", record),
+            (None, Some(e)) => write!(f, "\n  Failed to decode value {}: {}", self.value, e),
+            (None, None) => write!(f, " Value: {}", self.value),
+        }
+    }
+}
+
+/// Result of looking up a single key
+#[derive(Serialize)]
+pub struct CheckResult {
+    pub column_family: String,
+    pub key: String,
+    pub found: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decoded: Option<DecodedRecord>,
+    /// Readings of the value as integers, text or field elements
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub interpretations: Vec<Interpretation>,
+    /// Where the data payload of a `DataHashRecord` was written instead of being printed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_dump: Option<DataDump>,
+    /// Print decoded record fields one per line in text mode
+    #[serde(skip)]
+    pub pretty: bool,
+    /// Print the value as an xxd-style hexdump in text mode
+    #[serde(skip)]
+    pub hexdump: bool,
+}
+
+/// A record payload written to a file
+#[derive(Serialize)]
+pub struct DataDump {
+    pub path: String,
+    pub len: usize,
+    pub sha256: String,
+}
+
+impl CheckResult {
+    pub fn not_found(column_family: &str, key: &[u8]) -> Self {
+        CheckResult {
+            column_family: column_family.to_string(),
+            key: to_hex(key),
+            found: false,
+            value: None,
+            decoded: None,
+            interpretations: Vec::new(),
+            data_dump: None,
+            pretty: false,
+            hexdump: false,
+        }
+    }
+
+    pub fn found(
+        column_family: &str,
+        key: &[u8],
+        value: &[u8],
+        decoded: Option<DecodedRecord>,
+    ) -> Self {
+        CheckResult {
+            column_family: column_family.to_string(),
+            key: to_hex(key),
+            found: true,
+            value: Some(to_hex(value)),
+            decoded,
+            // Auto never fails, it only returns the readings that fit
+            interpretations: interpret(value, ValueType::Auto).unwrap_or_default(),
+            data_dump: None,
+            pretty: false,
+            hexdump: false,
+        }
+    }
+}
+
+impl fmt::Display for CheckResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.found {
+            return write!(f, "Key {} not found in the database", self.key);
+        }
+
+        writeln!(f, "Key {} found!", self.key)?;
+        if let (Some(dump), Some(DecodedRecord::Data(record))) = (&self.data_dump, &self.decoded) {
+            writeln!(f, "Value (as DataRecord) hash: {}", to_hex(&record.hash))?;
+            return write!(
+                f,
+                "Data payload ({} bytes, sha256 {}) written to {}",
+                dump.len, dump.sha256, dump.path
+            );
+        }
+        match &self.value {
+            Some(value) if self.hexdump => {
+                let bytes = hex::decode(value.trim_start_matches("0x")).map_err(|_| fmt::Error)?;
+                writeln!(f, "Value ({} bytes):", bytes.len())?;
+                write_hexdump(f, &bytes)?;
+            }
+            Some(value) => writeln!(f, "Value (bytes): {}", value)?,
+            None => {}
+        }
+        match &self.decoded {
+            Some(record) if self.pretty => write_pretty(f, record)?,
+            // The payload is part of the hexdump above
+            Some(DecodedRecord::Data(record)) if self.hexdump => writeln!(
+                f,
+                "Value (as DataRecord) hash: {}, data: {} bytes",
+                to_hex(&record.hash),
+                record.data.len()
+            )?,
+            Some(DecodedRecord::Merkle(record)) => writeln!(f, "Value (as MerkleRecord): {:?}", record)?,
+            Some(DecodedRecord::Data(record)) => writeln!(f, "Value (as DataRecord): {:?}", record)?,
+            None => {}
+        }
+        if self.interpretations.is_empty() {
+            return write!(f, "Value has no integer, text or field element reading");
+        }
+        let readings: Vec<String> = self.interpretations.iter().map(|i| i.to_string()).collect();
+        write!(f, "{}", readings.join("\n"))
+    }
+}
+
+const PRETTY_LABEL_WIDTH: usize = 6;
+
+/// Bytes per line of a hexdump
+const HEXDUMP_WIDTH: usize = 16;
+
+/// Writes `bytes` one line per 16 bytes the way xxd does: the offset, the bytes as hex in groups
+/// of two and the bytes as ASCII with unprintable ones shown as '.'
+fn write_hexdump(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    for (line, chunk) in bytes.chunks(HEXDUMP_WIDTH).enumerate() {
+        write!(f, "{:08x}:", line * HEXDUMP_WIDTH)?;
+        for i in 0..HEXDUMP_WIDTH {
+            if i % 2 == 0 {
+                write!(f, " ")?;
+            }
+            match chunk.get(i) {
+                Some(byte) => write!(f, "{:02x}", byte)?,
+                None => write!(f, "  ")?,
+            }
+        }
+        let ascii: String = chunk
+            .iter()
+            .map(|&byte| if byte == b' ' || byte.is_ascii_graphic() { byte as char } else { '.' })
+            .collect();
+        writeln!(f, "  {}", ascii)?;
+    }
+    Ok(())
+}
+
+fn write_pretty_hash(f: &mut fmt::Formatter<'_>, label: &str, hash: Option<&[u8; 32]>) -> fmt::Result {
+    let hash = match hash {
+        Some(hash) => hash,
+        None => return writeln!(f, "  {:<width$} : none", label, width = PRETTY_LABEL_WIDTH),
+    };
+    writeln!(f, "  {:<width$} : hex   {}", label, to_hex(hash), width = PRETTY_LABEL_WIDTH)?;
+    writeln!(
+        f,
+        "  {:<width$}   limbs {:?}",
+        "",
+        field::to_u64_limbs(hash),
+        width = PRETTY_LABEL_WIDTH
+    )?;
+    writeln!(
+        f,
+        "  {:<width$}   field {}",
+        "",
+        field::to_field_decimal(hash),
+        width = PRETTY_LABEL_WIDTH
+    )
+}
+
+/// Writes every field of a decoded record on its own line, hashes in hex, u64 limb and field element form
+fn write_pretty(f: &mut fmt::Formatter<'_>, record: &DecodedRecord) -> fmt::Result {
+    match record {
+        DecodedRecord::Merkle(record) => {
+            writeln!(f, "Value (as MerkleRecord):")?;
+            writeln!(f, "  {:<width$} : {}", "index", record.index, width = PRETTY_LABEL_WIDTH)?;
+            write_pretty_hash(f, "hash", Some(&record.hash))?;
+            write_pretty_hash(f, "left", record.left.as_ref())?;
+            write_pretty_hash(f, "right", record.right.as_ref())?;
+            write_pretty_hash(f, "data", record.data.as_ref())
+        }
+        DecodedRecord::Data(record) => {
+            writeln!(f, "Value (as DataRecord):")?;
+            write_pretty_hash(f, "hash", Some(&record.hash))?;
+            writeln!(
+                f,
+                "  {:<width$} : {} bytes {}",
+                "data",
+                record.data.len(),
+                to_hex(&record.data),
+                width = PRETTY_LABEL_WIDTH
+            )
+        }
+    }
+}
+
+/// Outcome of decoding a value with one decoder
+#[derive(Serialize)]
+pub struct DecodeAttempt {
+    pub decoder: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub record: Option<DecodedRecord>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A value decoded without a database, with the result of every decoder tried
+#[derive(Serialize)]
+pub struct DecodeResult {
+    pub value: String,
+    pub len: usize,
+    pub attempts: Vec<DecodeAttempt>,
+    /// Print decoded record fields one per line in text mode
+    #[serde(skip)]
+    pub pretty: bool,
+}
+
+impl DecodeResult {
+    pub fn new(value: &[u8], attempts: Vec<DecodeAttempt>) -> Self {
+        DecodeResult {
+            value: to_hex(value),
+            len: value.len(),
+            attempts,
+            pretty: false,
+        }
+    }
+}
+
+impl fmt::Display for DecodeResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Value ({} bytes): {}", self.len, self.value)?;
+        for attempt in &self.attempts {
+            writeln!(f)?;
+            match (&attempt.record, &attempt.error) {
+                (Some(record), _) if self.pretty => {
+                    write_pretty(f, record)?;
+                },
+                (Some(DecodedRecord::Merkle(record)), _) => {
+                    write!(f, "Value (as MerkleRecord): {:?}", record)?;
+                },
+                (Some(DecodedRecord::Data(record)), _) => {
+                    write!(f, "Value (as DataRecord): {:?}", record)?;
+                },
+                (None, Some(error)) => {
+                    write!(f, "Not decodable as {}: {}", attempt.decoder, error)?;
+                },
+                (None, None) => {
+                    write!(f, "No structure known to the {} decoder", attempt.decoder)?;
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A record serialized into the key and value it is stored under
+#[derive(Serialize)]
+pub struct EncodeResult {
+    pub key: String,
+    pub value: String,
+    pub len: usize,
+    /// Whether decoding the value and encoding it again gives the same bytes
+    pub round_trip: bool,
+    pub record: DecodedRecord,
+}
+
+impl fmt::Display for EncodeResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.record)?;
+        writeln!(f, "Key: {}", self.key)?;
+        writeln!(f, "Value ({} bytes): {}", self.len, self.value)?;
+        if self.round_trip {
+            write!(f, "Round trip: decoding and encoding again gives the same bytes")
+        } else {
+            write!(f, "Round trip MISMATCH: decoding and encoding again gives different bytes")
+        }
+    }
+}
+
+/// Number of records counted in a column family
+#[derive(Serialize)]
+pub struct CountResult {
+    pub column_family: String,
+    pub count: u64,
+    /// Inclusive bounds of the counted key range, `None` when unbounded
+    pub start_key: Option<String>,
+    pub end_key: Option<String>,
+    /// Whether `count` is RocksDB's estimate rather than an exact count
+    pub approximate: bool,
+    /// Records counted by the run the count resumed from, included in `count`
+    pub resumed_from: u64,
+    pub elapsed_secs: f64,
+    pub keys_per_sec: f64,
+}
+
+impl fmt::Display for CountResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.approximate {
+            return write!(
+                f,
+                "Estimated number of records in column family '{}': ~{}",
+                self.column_family, self.count
+            );
+        }
+
+        if self.start_key.is_none() && self.end_key.is_none() {
+            writeln!(
+                f,
+                "Total number of records in column family '{}': {}",
+                self.column_family, self.count
+            )?;
+        } else {
+            writeln!(
+                f,
+                "Number of records in column family '{}' from {} to {}: {}",
+                self.column_family,
+                self.start_key.as_deref().unwrap_or("the first key"),
+                self.end_key.as_deref().unwrap_or("the last key"),
+                self.count
+            )?;
+        }
+        write!(
+            f,
+            "Elapsed: {:.2}s ({:.0} keys/sec)",
+            self.elapsed_secs, self.keys_per_sec
+        )?;
+        if self.resumed_from > 0 {
+            write!(f, ", resumed after {}", self.resumed_from)?;
+        }
+        Ok(())
+    }
+}
+
+/// Summary line printed after a listing command
+#[derive(Serialize)]
+pub struct ScanSummary {
+    pub column_family: String,
+    /// Records listed, the skipped ones are not included
+    pub count: u64,
+    pub skipped: u64,
+    /// `--skip` value listing the next page, when more records follow
+    pub next_skip: Option<u64>,
+}
+
+impl fmt::Display for ScanSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Scanned {} records in column family '{}'",
+            self.count, self.column_family
+        )?;
+        write_pagination(f, self.skipped, self.next_skip)
+    }
+}
+
+/// Appends the records skipped before a listing and where the next page starts
+fn write_pagination(
+    f: &mut fmt::Formatter<'_>,
+    skipped: u64,
+    next_skip: Option<u64>,
+) -> fmt::Result {
+    if skipped > 0 {
+        write!(f, " after skipping {}", skipped)?;
+    }
+    if let Some(next_skip) = next_skip {
+        write!(f, ", more follow from --skip {}", next_skip)?;
+    }
+    Ok(())
+}
+
+/// Printed after every full page of a listing run with `--page-size`
+#[derive(Serialize)]
+pub struct PageBreak {
+    pub page: u64,
+    /// Records listed so far
+    pub listed: u64,
+    /// `--skip` value listing the next page
+    pub next_skip: u64,
+}
+
+impl fmt::Display for PageBreak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "--- end of page {} ({} records), next page from --skip {} ---",
+            self.page, self.listed, self.next_skip
+        )
+    }
+}
+
+/// Summary line printed after the records of a random sample
+#[derive(Serialize)]
+pub struct SampleSummary {
+    pub column_family: String,
+    pub requested: usize,
+    pub sampled: usize,
+    /// Seed of the sample, passing it again returns the same records from an unchanged database
+    pub seed: u64,
+}
+
+impl fmt::Display for SampleSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Sampled {} of {} requested records in column family '{}' (seed {})",
+            self.sampled, self.requested, self.column_family, self.seed
+        )
+    }
+}
+
+/// Summary of a column family export
+#[derive(Serialize)]
+pub struct ExportSummary {
+    pub column_family: String,
+    pub file: String,
+    /// Records exported, including those written by the run the export resumed from
+    pub count: u64,
+    pub resumed_from: u64,
+}
+
+impl fmt::Display for ExportSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Exported {} records from column family '{}' to {}",
+            self.count, self.column_family, self.file
+        )?;
+        if self.resumed_from > 0 {
+            write!(f, " (resumed after {})", self.resumed_from)?;
+        }
+        Ok(())
+    }
+}
+
+/// One part file of a parallel export, holding the keys from `start_key` up to but excluding
+/// `end_key`
+#[derive(Serialize)]
+pub struct ExportPart {
+    pub file: String,
+    pub start_key: Option<String>,
+    pub end_key: Option<String>,
+    pub records: u64,
+    pub bytes: u64,
+}
+
+/// Summary of a parallel export, also written as its JSON manifest
+#[derive(Serialize)]
+pub struct ParallelExportSummary {
+    pub column_family: String,
+    pub format: String,
+    pub manifest: String,
+    pub records: u64,
+    pub parts: Vec<ExportPart>,
+}
+
+impl fmt::Display for ParallelExportSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Exported {} records from column family '{}' to {} {} parts listed in {}",
+            self.records,
+            self.column_family,
+            self.parts.len(),
+            self.format,
+            self.manifest
+        )?;
+        for part in &self.parts {
+            write!(f, "\n  {}: {} records, {} bytes", part.file, part.records, part.bytes)?;
+        }
+        Ok(())
+    }
+}
+
+/// Summary of a column family import
+#[derive(Serialize)]
+pub struct ImportSummary {
+    pub column_family: String,
+    pub file: String,
+    pub count: u64,
+    pub dry_run: bool,
+}
+
+impl fmt::Display for ImportSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = if self.dry_run { "Would import" } else { "Imported" };
+        write!(
+            f,
+            "{} {} records from {} into column family '{}'",
+            verb, self.count, self.file, self.column_family
+        )
+    }
+}
+
+/// Result of writing the patch between two versions of a column family
+#[derive(Serialize)]
+pub struct MakePatchSummary {
+    pub column_family: String,
+    pub file: String,
+    pub puts: u64,
+    pub deletes: u64,
+    pub unchanged: u64,
+    /// Size of the patch file
+    pub bytes: u64,
+}
+
+impl fmt::Display for MakePatchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Wrote patch {} for column family '{}': {} puts, {} deletes, {} unchanged, {} bytes",
+            self.file, self.column_family, self.puts, self.deletes, self.unchanged, self.bytes
+        )
+    }
+}
+
+/// Result of applying a patch to a column family
+#[derive(Serialize)]
+pub struct ApplyPatchSummary {
+    pub column_family: String,
+    pub file: String,
+    pub puts: u64,
+    pub deletes: u64,
+    pub dry_run: bool,
+}
+
+impl fmt::Display for ApplyPatchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = if self.dry_run { "Would apply" } else { "Applied" };
+        write!(
+            f,
+            "{} {} puts and {} deletes from {} to column family '{}'",
+            verb, self.puts, self.deletes, self.file, self.column_family
+        )
+    }
+}
+
+/// Contents of a dump whose checksums and trailer all check out
+#[derive(Serialize)]
+pub struct DumpVerifySummary {
+    pub file: String,
+    pub version: u16,
+    pub column_family: String,
+    pub records: u64,
+    /// Total key and value bytes of the records
+    pub bytes: u64,
+}
+
+impl fmt::Display for DumpVerifySummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Dump {} is intact: version {}, column family '{}', {} records, {} bytes",
+            self.file, self.version, self.column_family, self.records, self.bytes
+        )
+    }
+}
+
+/// Summary of a merkle tree verification
+#[derive(Serialize)]
+pub struct VerifySummary {
+    pub root: String,
+    pub nodes_visited: u64,
+    pub leaves: u64,
+    /// Unstored empty subtrees reached, counted in the nodes and leaves when `defaults_included`
+    pub default_nodes: u64,
+    pub defaults_included: bool,
+    pub issues: u64,
+}
+
+impl fmt::Display for VerifySummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.issues == 0 {
+            write!(
+                f,
+                "Merkle tree at root {} is consistent: {} nodes visited, {} leaves",
+                self.root, self.nodes_visited, self.leaves
+            )?;
+        } else {
+            write!(
+                f,
+                "Merkle tree at root {} has {} issues: {} nodes visited, {} leaves",
+                self.root, self.issues, self.nodes_visited, self.leaves
+            )?;
+        }
+        if self.default_nodes > 0 {
+            let counted = if self.defaults_included { "included" } else { "not counted" };
+            write!(f, ", {} default subtrees ({})", self.default_nodes, counted)?;
+        }
+        Ok(())
+    }
+}
+
+/// Shape of the merkle tree below a root
+#[derive(Serialize)]
+pub struct MerkleStatsReport {
+    pub root: String,
+    pub expected_depth: usize,
+    pub max_depth: usize,
+    pub nodes: u64,
+    pub leaves: u64,
+    /// Whether the default subtrees count toward `nodes`, `leaves` and `max_depth`
+    pub defaults_included: bool,
+    pub nodes_per_level: Vec<u64>,
+    /// Unstored children holding the empty subtree hash of their depth
+    pub default_per_level: Vec<u64>,
+    pub unstored_per_level: Vec<u64>,
+    pub single_child_nodes: u64,
+    pub shallow_leaves: u64,
+    pub too_deep_nodes: u64,
+    pub index_mismatches: u64,
+    pub undecodable_nodes: u64,
+}
+
+impl fmt::Display for MerkleStatsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Merkle tree at root {}: {} nodes, {} leaves, depth {} (expected {})",
+            self.root, self.nodes, self.leaves, self.max_depth, self.expected_depth
+        )?;
+        if self.defaults_included {
+            write!(f, ", default subtrees included")?;
+        }
+        write!(f, "\n  {:>5} {:>12} {:>12} {:>12}", "level", "stored", "default", "unstored")?;
+        let levels = self
+            .nodes_per_level
+            .len()
+            .max(self.default_per_level.len())
+            .max(self.unstored_per_level.len());
+        for level in 0..levels {
+            write!(
+                f,
+                "\n  {:>5} {:>12} {:>12} {:>12}",
+                level,
+                self.nodes_per_level.get(level).copied().unwrap_or(0),
+                self.default_per_level.get(level).copied().unwrap_or(0),
+                self.unstored_per_level.get(level).copied().unwrap_or(0)
+            )?;
+        }
+        write!(
+            f,
+            "\nAnomalies: {} single-child nodes, {} leaves above depth {}, {} nodes below it, {} index mismatches, {} undecodable nodes",
+            self.single_child_nodes,
+            self.shallow_leaves,
+            self.expected_depth,
+            self.too_deep_nodes,
+            self.index_mismatches,
+            self.undecodable_nodes
+        )
+    }
+}
+
+/// One sibling on a merkle authentication path
+#[derive(Serialize)]
+pub struct PathStepEntry {
+    pub depth: u64,
+    pub index: u64,
+    pub hash: String,
+    pub sibling_index: u64,
+    pub sibling_hash: String,
+    pub sibling_side: &'static str,
+    /// Whether the sibling is the hash of an empty subtree
+    pub sibling_is_default: bool,
+}
+
+impl fmt::Display for PathStepEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Depth {:>2} index {} sibling ({}) index {}: ",
+            self.depth, self.index, self.sibling_side, self.sibling_index
+        )?;
+        if self.sibling_is_default {
+            write!(f, "DEFAULT(depth={})", self.depth)
+        } else {
+            write!(f, "{}", self.sibling_hash)
+        }
+    }
+}
+
+/// A leaf of a merkle tree
+#[derive(Serialize)]
+pub struct LeafEntry {
+    pub index: u64,
+    pub hash: String,
+    pub data_hash: Option<String>,
+}
+
+impl fmt::Display for LeafEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Leaf {} {} data {}",
+            self.index,
+            self.hash,
+            self.data_hash.as_deref().unwrap_or("none")
+        )
+    }
+}
+
+/// One page of the leaves of a merkle tree
+#[derive(Serialize)]
+pub struct LeafListSummary {
+    pub root: String,
+    pub listed: u64,
+    pub skipped: u64,
+    /// `--skip` value listing the next page, when more leaves follow
+    pub next_skip: Option<u64>,
+    /// Unstored empty subtrees in the index range, they hold no leaves
+    pub default_nodes: u64,
+    /// Referenced nodes that are not stored, other than empty subtrees
+    pub missing_nodes: u64,
+    /// Nodes that failed to decode, the leaves below them are not listed
+    pub undecodable_nodes: u64,
+    /// Nodes below `MERKLE_DEPTH`, nothing below them is listed
+    pub too_deep_nodes: u64,
+}
+
+impl fmt::Display for LeafListSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Listed {} leaves of {}", self.listed, self.root)?;
+        write_pagination(f, self.skipped, self.next_skip)?;
+        if self.default_nodes > 0 {
+            write!(f, "\nPassed {} default subtrees without leaves", self.default_nodes)?;
+        }
+        if self.missing_nodes + self.undecodable_nodes + self.too_deep_nodes > 0 {
+            write!(
+                f,
+                "\nSkipped {} unstored nodes, {} undecodable nodes and {} nodes below leaf depth",
+                self.missing_nodes, self.undecodable_nodes, self.too_deep_nodes
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Root recomputed from a merkle authentication path
+#[derive(Serialize)]
+pub struct PathSummary {
+    pub leaf_index: u64,
+    pub leaf_hash: String,
+    pub computed_root: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_root: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matches: Option<bool>,
+}
+
+impl fmt::Display for PathSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Leaf {} hash: {}", self.leaf_index, self.leaf_hash)?;
+        write!(f, "Computed root: {}", self.computed_root)?;
+        match (&self.expected_root, self.matches) {
+            (Some(expected), Some(true)) => write!(f, "\nMatches expected root {}", expected),
+            (Some(expected), _) => write!(f, "\nDOES NOT match expected root {}", expected),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Column family with its estimated size
+#[derive(Serialize)]
+pub struct CfInfo {
+    pub name: String,
+    pub estimated_keys: Option<u64>,
+    pub sst_files_size: Option<u64>,
+    pub live_data_size: Option<u64>,
+}
+
+fn or_unknown(value: Option<u64>) -> String {
+    value.map_or_else(|| "unknown".to_string(), |v| v.to_string())
+}
+
+impl fmt::Display for CfInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: ~{} keys, {} bytes in SST files, ~{} bytes of live data",
+            self.name,
+            or_unknown(self.estimated_keys),
+            or_unknown(self.sst_files_size),
+            or_unknown(self.live_data_size)
+        )
+    }
+}
+
+/// Column families created in a database
+#[derive(Serialize)]
+pub struct CreateCfSummary {
+    pub column_families: Vec<String>,
+}
+
+impl fmt::Display for CreateCfSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Created column families: {}", self.column_families.join(", "))
+    }
+}
+
+/// Column families dropped from a database, with their sizes just before
+#[derive(Serialize)]
+pub struct DropCfSummary {
+    pub column_families: Vec<CfInfo>,
+}
+
+impl fmt::Display for DropCfSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Dropped column families:")?;
+        for cf in &self.column_families {
+            write!(f, "\n  {}", cf)?;
+        }
+        Ok(())
+    }
+}
+
+/// Result of writing a single key
+#[derive(Serialize)]
+pub struct PutResult {
+    pub column_family: String,
+    pub key: String,
+    pub value_len: usize,
+    pub previous_value: Option<String>,
+}
+
+impl fmt::Display for PutResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Wrote {} bytes to key {} in column family '{}'",
+            self.value_len, self.key, self.column_family
+        )?;
+        match &self.previous_value {
+            Some(previous) => write!(f, "\nPrevious value: {}", previous),
+            None => write!(f, "\nKey did not exist before"),
+        }
+    }
+}
+
+/// Number of keys removed, or that would be removed in a dry run
+#[derive(Serialize)]
+pub struct DeleteSummary {
+    pub column_family: String,
+    pub count: u64,
+    pub dry_run: bool,
+}
+
+impl fmt::Display for DeleteSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = if self.dry_run { "Would delete" } else { "Deleted" };
+        write!(
+            f,
+            "{} {} keys from column family '{}'",
+            verb, self.count, self.column_family
+        )
+    }
+}
+
+/// Result of removing every key of a column family
+#[derive(Serialize)]
+pub struct TruncateSummary {
+    pub column_family: String,
+    pub method: String,
+    pub keys: u64,
+    /// Whether `keys` is RocksDB's estimate rather than an exact count
+    pub approximate: bool,
+    pub dry_run: bool,
+}
+
+impl fmt::Display for TruncateSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = if self.dry_run { "Would remove" } else { "Removed" };
+        let about = if self.approximate { "~" } else { "" };
+        write!(
+            f,
+            "{} {}{} keys from column family '{}' with {}",
+            verb, about, self.keys, self.column_family, self.method
+        )
+    }
+}
+
+/// Location and contents of a checkpoint directory
+#[derive(Serialize)]
+pub struct CheckpointSummary {
+    pub path: String,
+    pub column_families: Vec<String>,
+    pub dropped: Vec<String>,
+    /// Total size of the checkpoint files, most are hard links sharing storage with the source
+    pub files_size: u64,
+}
+
+impl fmt::Display for CheckpointSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Created checkpoint at {} ({} bytes) with column families: {}",
+            self.path,
+            self.files_size,
+            self.column_families.join(", ")
+        )?;
+        if !self.dropped.is_empty() {
+            write!(f, "\nDropped column families: {}", self.dropped.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Records copied from one column family
+#[derive(Serialize)]
+pub struct CfCopy {
+    pub column_family: String,
+    pub records: u64,
+    /// Total key and value bytes of the copied records
+    pub bytes: u64,
+}
+
+/// Column families copied into a new database
+#[derive(Serialize)]
+pub struct CopySummary {
+    pub destination: String,
+    pub column_families: Vec<CfCopy>,
+}
+
+impl fmt::Display for CopySummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Copied into {}:", self.destination)?;
+        for cf in &self.column_families {
+            write!(
+                f,
+                "\n  {}: {} records, {} bytes",
+                cf.column_family, cf.records, cf.bytes
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Codec and sizes of the live files of one level
+#[derive(Serialize)]
+pub struct LevelCompression {
+    pub level: usize,
+    /// Codec from the OPTIONS file, as RocksDB names it there
+    pub codec: Option<String>,
+    pub files: usize,
+    /// Size of the files on disk
+    pub size: u64,
+    /// Uncompressed over compressed size as sampled by RocksDB
+    pub ratio: Option<f64>,
+    pub estimated_raw_size: Option<u64>,
+}
+
+/// Compression of the levels of one column family
+#[derive(Serialize)]
+pub struct CfCompression {
+    pub name: String,
+    pub size: u64,
+    pub estimated_raw_size: u64,
+    pub levels: Vec<LevelCompression>,
+}
+
+/// Compression codecs and sizes per column family and level
+#[derive(Serialize)]
+pub struct CompressionReport {
+    pub column_families: Vec<CfCompression>,
+}
+
+impl fmt::Display for CompressionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, cf) in self.column_families.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "Column family '{}': {} bytes on disk, about {} bytes uncompressed",
+                cf.name, cf.size, cf.estimated_raw_size
+            )?;
+            for level in &cf.levels {
+                let ratio = level.ratio.map_or_else(|| "-".to_string(), |r| format!("{:.2}", r));
+                write!(
+                    f,
+                    "\n  L{}: {:<20} {:>6} files {:>16} bytes, ratio {}",
+                    level.level,
+                    level.codec.as_deref().unwrap_or("unknown"),
+                    level.files,
+                    level.size,
+                    ratio
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sizes of one column family before and after recompression
+#[derive(Serialize)]
+pub struct CfRecompression {
+    pub column_family: String,
+    pub records: u64,
+    /// Size of the live SST files in the source database
+    pub source_size: u64,
+    /// Size of the live SST files in the new database
+    pub size: u64,
+}
+
+/// Column families rewritten into a new database with another codec
+#[derive(Serialize)]
+pub struct RecompressSummary {
+    pub destination: String,
+    pub codec: String,
+    pub level: Option<i32>,
+    pub column_families: Vec<CfRecompression>,
+}
+
+impl fmt::Display for RecompressSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Recompressed into {} with {}", self.destination, self.codec)?;
+        if let Some(level) = self.level {
+            write!(f, " level {}", level)?;
+        }
+        write!(f, ":")?;
+        for cf in &self.column_families {
+            write!(
+                f,
+                "\n  {}: {} records, {} bytes -> {} bytes",
+                cf.column_family, cf.records, cf.source_size, cf.size
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Records merged into one column family
+#[derive(Serialize)]
+pub struct CfMerge {
+    pub column_family: String,
+    pub policy: String,
+    pub records: u64,
+    /// Keys stored with different values in several sources
+    pub conflicts: u64,
+}
+
+/// Column families of several databases merged into a new database
+#[derive(Serialize)]
+pub struct MergeSummary {
+    pub destination: String,
+    pub sources: Vec<String>,
+    pub column_families: Vec<CfMerge>,
+}
+
+impl fmt::Display for MergeSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Merged {} databases into {}:",
+            self.sources.len(),
+            self.destination
+        )?;
+        for cf in &self.column_families {
+            write!(
+                f,
+                "\n  {}: {} records, {} conflicts resolved with {}",
+                cf.column_family, cf.records, cf.conflicts, cf.policy
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Location and contents of an SST file written from a column family
+#[derive(Serialize)]
+pub struct SstExportSummary {
+    pub column_family: String,
+    pub file: String,
+    pub records: u64,
+    /// Total key and value bytes of the written records
+    pub bytes: u64,
+}
+
+impl fmt::Display for SstExportSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Exported {} records ({} bytes) from column family '{}' to {}",
+            self.records, self.bytes, self.column_family, self.file
+        )
+    }
+}
+
+/// SST files ingested into a column family
+#[derive(Serialize)]
+pub struct SstIngestSummary {
+    pub column_family: String,
+    pub files: Vec<String>,
+    /// Total size of the files before ingestion
+    pub size: u64,
+}
+
+impl fmt::Display for SstIngestSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Ingested {} SST files ({} bytes) into column family '{}': {}",
+            self.files.len(),
+            self.size,
+            self.column_family,
+            self.files.join(", ")
+        )
+    }
+}
+
+/// A backup stored by the backup engine
+#[derive(Serialize)]
+pub struct BackupInfo {
+    pub backup_id: u32,
+    /// Seconds since the Unix epoch when the backup was taken
+    pub timestamp: i64,
+    pub size: u64,
+    pub num_files: u32,
+    /// Result of checking the backup files, `None` when verification was not requested
+    pub verified: Option<bool>,
+    pub verify_error: Option<String>,
+}
+
+impl fmt::Display for BackupInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Backup {}: taken at {}, {} bytes in {} files",
+            self.backup_id, self.timestamp, self.size, self.num_files
+        )?;
+        match (self.verified, &self.verify_error) {
+            (Some(true), _) => write!(f, ", verified"),
+            (Some(false), Some(error)) => write!(f, ", verification FAILED: {}", error),
+            (Some(false), None) => write!(f, ", verification FAILED"),
+            (None, _) => Ok(()),
+        }
+    }
+}
+
+/// Outcome of creating a backup
+#[derive(Serialize)]
+pub struct BackupSummary {
+    pub backup_dir: String,
+    pub backup: BackupInfo,
+    /// Number of older backups removed to honour the retention limit
+    pub purged: usize,
+}
+
+impl fmt::Display for BackupSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Created in {}: {}", self.backup_dir, self.backup)?;
+        if self.purged > 0 {
+            write!(f, "\nRemoved {} old backups", self.purged)?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of restoring a backup
+#[derive(Serialize)]
+pub struct RestoreSummary {
+    pub backup_dir: String,
+    pub db_path: String,
+    pub backup_id: u32,
+}
+
+impl fmt::Display for RestoreSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Restored backup {} from {} to {}",
+            self.backup_id, self.backup_dir, self.db_path
+        )
+    }
+}
+
+/// What a repair kept, rebuilt and dropped
+#[derive(Serialize)]
+pub struct RepairSummary {
+    pub db_path: String,
+    pub backup_path: String,
+    pub sst_files_before: usize,
+    pub sst_bytes_before: u64,
+    pub sst_files_after: usize,
+    pub sst_bytes_after: u64,
+    /// Table files present before the repair that are no longer part of the database
+    pub dropped_files: Vec<String>,
+    /// Files RocksDB moved into the `lost` directory because they could not be recovered
+    pub lost_files: Vec<String>,
+    pub column_families: Vec<CfInfo>,
+}
+
+impl fmt::Display for RepairSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Repaired {}, original files backed up to {}",
+            self.db_path, self.backup_path
+        )?;
+        write!(
+            f,
+            "\nSST files before: {} ({} bytes), after: {} ({} bytes)",
+            self.sst_files_before, self.sst_bytes_before, self.sst_files_after, self.sst_bytes_after
+        )?;
+        if !self.dropped_files.is_empty() {
+            write!(f, "\nDropped table files: {}", self.dropped_files.join(", "))?;
+        }
+        if !self.lost_files.is_empty() {
+            write!(f, "\nUnrecoverable files moved to lost/: {}", self.lost_files.join(", "))?;
+        }
+        for cf in &self.column_families {
+            write!(f, "\n{}", cf)?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of an orphan node search in merkle_records
+#[derive(Serialize)]
+pub struct OrphanSummary {
+    pub roots: u64,
+    pub reachable: u64,
+    pub scanned: u64,
+    pub orphans: u64,
+    pub deleted: bool,
+}
+
+impl fmt::Display for OrphanSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} merkle records are reachable from {} roots, {} orphans {}",
+            self.reachable,
+            self.scanned,
+            self.roots,
+            self.orphans,
+            if self.deleted { "deleted" } else { "found" }
+        )
+    }
+}
+
+/// A merkle node with a given child
+#[derive(Serialize)]
+pub struct ParentEntry {
+    pub hash: String,
+    pub index: u64,
+    /// Which child of the parent the searched hash is
+    pub side: &'static str,
+}
+
+impl fmt::Display for ParentEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Parent {} at index {}, child on the {}",
+            self.hash, self.index, self.side
+        )
+    }
+}
+
+/// Outcome of a parent search in merkle_records
+#[derive(Serialize)]
+pub struct ParentSummary {
+    pub hash: String,
+    pub scanned: u64,
+    pub parents: u64,
+    pub undecodable_nodes: u64,
+}
+
+impl fmt::Display for ParentSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Found {} parents of {} among {} merkle records",
+            self.parents, self.hash, self.scanned
+        )?;
+        if self.undecodable_nodes > 0 {
+            write!(f, ", {} records could not be decoded", self.undecodable_nodes)?;
+        }
+        Ok(())
+    }
+}
+
+/// Contents of a database holding the subtree below one merkle node
+#[derive(Serialize)]
+pub struct SubtreeSummary {
+    pub root: String,
+    pub destination: String,
+    pub nodes: u64,
+    pub leaves: u64,
+    pub data_records: u64,
+    /// Total key and value bytes of the copied records
+    pub bytes: u64,
+    /// Unstored empty subtrees, mongomerkle leaves those unstored
+    pub default_nodes: u64,
+    pub missing_nodes: u64,
+    pub missing_data: u64,
+    pub undecodable_nodes: u64,
+}
+
+impl fmt::Display for SubtreeSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Exported the subtree below {} to {}: {} nodes, {} leaves, {} data records, {} bytes",
+            self.root, self.destination, self.nodes, self.leaves, self.data_records, self.bytes
+        )?;
+        if self.default_nodes > 0 {
+            write!(f, ", {} default subtrees", self.default_nodes)?;
+        }
+        if self.missing_nodes + self.missing_data + self.undecodable_nodes > 0 {
+            write!(
+                f,
+                "\n{} referenced nodes and {} data records are not stored, {} nodes could not be \
+                 decoded",
+                self.missing_nodes, self.missing_data, self.undecodable_nodes
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Records of a subtree grafted into a database
+#[derive(Serialize)]
+pub struct SubtreeImportSummary {
+    pub root: String,
+    pub root_index: u64,
+    pub new_root_index: u64,
+    pub nodes: u64,
+    pub data_records: u64,
+    /// Unstored empty subtrees, mongomerkle leaves those unstored
+    pub default_nodes: u64,
+    /// Referenced nodes that are not stored in the source, other than empty subtrees
+    pub missing_nodes: u64,
+    /// Nodes already stored in the target with another value
+    pub overwritten: u64,
+}
+
+impl fmt::Display for SubtreeImportSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Imported the subtree below {} with {} nodes and {} data records",
+            self.root, self.nodes, self.data_records
+        )?;
+        if self.new_root_index != self.root_index {
+            write!(
+                f,
+                ", moved from index {} to {}",
+                self.root_index, self.new_root_index
+            )?;
+        }
+        if self.default_nodes > 0 {
+            write!(f, ", {} default subtrees", self.default_nodes)?;
+        }
+        if self.missing_nodes > 0 {
+            write!(f, "\n{} referenced nodes are not stored", self.missing_nodes)?;
+        }
+        if self.overwritten > 0 {
+            write!(
+                f,
+                "\n{} nodes already stored with another value were overwritten",
+                self.overwritten
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A level of a merkle tree where recomputed hashes differ from the stored ones
+#[derive(Serialize)]
+pub struct LevelDivergence {
+    /// Depth of the level, the root is at depth 0
+    pub depth: usize,
+    /// Lowest index on the level whose hashes differ
+    pub first_index: u64,
+    pub stored: String,
+    pub computed: String,
+    pub mismatches: u64,
+}
+
+/// Root of a merkle tree recomputed from its leaves
+#[derive(Serialize)]
+pub struct ComputeRootReport {
+    pub root: String,
+    pub root_index: u64,
+    pub leaves: u64,
+    /// Unstored empty subtrees below the stored root
+    pub default_nodes: u64,
+    /// Nodes the stored tree references but does not store, other than empty subtrees
+    pub missing_nodes: u64,
+    pub computed_root: String,
+    pub matches: bool,
+    /// Levels with differing hashes, from the leaves up
+    pub divergent_levels: Vec<LevelDivergence>,
+}
+
+impl fmt::Display for ComputeRootReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Computed root {} from {} leaves, stored root {} {}",
+            self.computed_root,
+            self.leaves,
+            self.root,
+            if self.matches { "matches" } else { "differs" }
+        )?;
+        if self.default_nodes + self.missing_nodes > 0 {
+            write!(
+                f,
+                "\nThe stored tree has {} default subtrees and {} unstored nodes",
+                self.default_nodes, self.missing_nodes
+            )?;
+        }
+        if let Some(first) = self.divergent_levels.first() {
+            write!(
+                f,
+                "\nFirst divergence at depth {}, index {}: stored {} computed {}",
+                first.depth, first.first_index, first.stored, first.computed
+            )?;
+        }
+        for level in &self.divergent_levels {
+            write!(f, "\n  depth {}: {} mismatching nodes", level.depth, level.mismatches)?;
+        }
+        Ok(())
+    }
+}
+
+/// A key affected by a mutating command
+#[derive(Serialize)]
+pub struct KeyEntry {
+    pub key: String,
+}
+
+impl fmt::Display for KeyEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Key: {}", self.key)
+    }
+}
+
+/// Hit and miss counts of a batch lookup
+#[derive(Serialize)]
+pub struct BatchSummary {
+    pub column_family: String,
+    pub total: u64,
+    pub found: u64,
+    pub missing: u64,
+}
+
+impl fmt::Display for BatchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Checked {} keys in column family '{}': {} found, {} missing",
+            self.total, self.column_family, self.found, self.missing
+        )
+    }
+}
+
+/// Presence counts of a bulk existence check
+#[derive(Serialize)]
+pub struct ExistsSummary {
+    pub column_family: String,
+    pub total: u64,
+    pub found: u64,
+    /// Keys the Bloom filters ruled out without a read
+    pub definitely_missing: u64,
+    /// Keys the Bloom filters let through that a full get did not find
+    pub false_positives: u64,
+}
+
+impl fmt::Display for ExistsSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Checked {} keys in column family '{}': {} found, {} missing ({} ruled out by Bloom filters, {} false positives)",
+            self.total,
+            self.column_family,
+            self.found,
+            self.definitely_missing + self.false_positives,
+            self.definitely_missing,
+            self.false_positives
+        )
+    }
+}
+
+/// Latency of random point reads, percentiles in microseconds
+#[derive(Serialize)]
+pub struct GetLatency {
+    pub ops: u64,
+    pub ops_per_sec: f64,
+    pub p50_us: f64,
+    pub p95_us: f64,
+    pub p99_us: f64,
+    pub max_us: f64,
+}
+
+/// Throughput of a sequential scan from the first key
+#[derive(Serialize)]
+pub struct ScanThroughput {
+    pub records: u64,
+    pub bytes: u64,
+    pub elapsed_secs: f64,
+    pub records_per_sec: f64,
+    pub mib_per_sec: f64,
+}
+
+/// Throughput of random multi-gets
+#[derive(Serialize)]
+pub struct MultiGetThroughput {
+    pub keys: u64,
+    pub batch_size: usize,
+    pub keys_per_sec: f64,
+}
+
+/// Read benchmark results of a column family
+#[derive(Serialize)]
+pub struct BenchReport {
+    pub column_family: String,
+    pub sampled_keys: usize,
+    pub threads: usize,
+    /// Duration of each phase
+    pub duration_secs: f64,
+    pub seed: u64,
+    pub get: GetLatency,
+    pub scan: ScanThroughput,
+    pub multi_get: MultiGetThroughput,
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Benchmark of column family '{}': {} sampled keys, {} threads, {:.0}s per phase, seed {}",
+            self.column_family, self.sampled_keys, self.threads, self.duration_secs, self.seed
+        )?;
+        writeln!(
+            f,
+            "  random get: {} ops, {:.0} ops/s, p50 {:.1}us, p95 {:.1}us, p99 {:.1}us, max {:.1}us",
+            self.get.ops,
+            self.get.ops_per_sec,
+            self.get.p50_us,
+            self.get.p95_us,
+            self.get.p99_us,
+            self.get.max_us
+        )?;
+        writeln!(
+            f,
+            "  sequential scan: {} records, {} bytes in {:.2}s, {:.0} records/s, {:.1} MiB/s",
+            self.scan.records,
+            self.scan.bytes,
+            self.scan.elapsed_secs,
+            self.scan.records_per_sec,
+            self.scan.mib_per_sec
+        )?;
+        write!(
+            f,
+            "  multi-get: {} keys in batches of {}, {:.0} keys/s",
+            self.multi_get.keys, self.multi_get.batch_size, self.multi_get.keys_per_sec
+        )
+    }
+}
+
+/// Write throughput of one value size, durability and batch size
+#[derive(Serialize)]
+pub struct WriteBenchCase {
+    pub value_size: usize,
+    pub durability: &'static str,
+    /// Records per write, 1 for single puts
+    pub batch_size: usize,
+    pub records: u64,
+    pub elapsed_secs: f64,
+    pub records_per_sec: f64,
+    pub mib_per_sec: f64,
+}
+
+impl fmt::Display for WriteBenchCase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let method = if self.batch_size == 1 {
+            "put".to_string()
+        } else {
+            format!("batch of {}", self.batch_size)
+        };
+        write!(
+            f,
+            "{} byte values, {}, {}: {} records in {:.2}s, {:.0} records/s, {:.1} MiB/s",
+            self.value_size,
+            self.durability,
+            method,
+            self.records,
+            self.elapsed_secs,
+            self.records_per_sec,
+            self.mib_per_sec
+        )
+    }
+}
+
+/// Outcome of a write benchmark
+#[derive(Serialize)]
+pub struct BenchWriteSummary {
+    /// Scratch database written to
+    pub path: String,
+    pub cases: u64,
+    pub records: u64,
+    /// Whether the scratch database was kept instead of removed
+    pub kept: bool,
+}
+
+impl fmt::Display for BenchWriteSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Ran {} cases writing {} records to {}, {}",
+            self.cases,
+            self.records,
+            self.path,
+            if self.kept { "kept" } else { "removed" }
+        )
+    }
+}
+
+/// A dangling reference between merkle leaves and data_records
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CrossCheckIssue {
+    /// A leaf whose data hash is not stored in data_records
+    DanglingLeaf {
+        index: u64,
+        leaf_hash: String,
+        data_hash: String,
+    },
+    /// A data_records entry no leaf refers to
+    UnreferencedData { data_hash: String },
+}
+
+impl fmt::Display for CrossCheckIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CrossCheckIssue::DanglingLeaf {
+                index,
+                leaf_hash,
+                data_hash,
+            } => write!(
+                f,
+                "Leaf {} at index {} refers to data {} missing from data_records",
+                leaf_hash, index, data_hash
+            ),
+            CrossCheckIssue::UnreferencedData { data_hash } => {
+                write!(f, "Data record {} is not referenced by any leaf", data_hash)
+            }
+        }
+    }
+}
+
+/// Summary of checking merkle leaves against data_records
+#[derive(Serialize)]
+pub struct CrossCheckSummary {
+    pub leaves: u64,
+    pub data_records: u64,
+    pub undecodable_nodes: u64,
+    pub dangling_leaves: u64,
+    pub unreferenced_data: u64,
+}
+
+impl fmt::Display for CrossCheckSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Checked {} leaves against {} data records: {} dangling leaves, {} unreferenced data records",
+            self.leaves, self.data_records, self.dangling_leaves, self.unreferenced_data
+        )?;
+        if self.undecodable_nodes > 0 {
+            write!(f, ", {} undecodable merkle nodes skipped", self.undecodable_nodes)?;
+        }
+        Ok(())
+    }
+}
+
+/// A record whose value failed to decode
+#[derive(Serialize)]
+pub struct InvalidRecord {
+    pub key: String,
+    pub value_len: usize,
+    pub error: String,
+}
+
+impl fmt::Display for InvalidRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid record {} ({} bytes): {}", self.key, self.value_len, self.error)
+    }
+}
+
+/// Summary of decoding every value of a column family
+#[derive(Serialize)]
+pub struct ValidateSummary {
+    pub column_family: String,
+    pub decoder: String,
+    pub records: u64,
+    pub invalid: u64,
+}
+
+impl fmt::Display for ValidateSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Decoded {} records of column family '{}' as {}: {} invalid",
+            self.records, self.column_family, self.decoder, self.invalid
+        )
+    }
+}
+
+/// A read that failed checksum verification, with the damaged file when the error names it
+#[derive(Serialize)]
+pub struct ChecksumCorruption {
+    pub column_family: String,
+    pub file: Option<String>,
+    pub level: Option<i32>,
+    /// Key range of the damaged file, the records in it were skipped
+    pub smallest_key: Option<KeyView>,
+    pub largest_key: Option<KeyView>,
+    /// Last key read before the error
+    pub last_good_key: Option<String>,
+    pub error: String,
+}
+
+impl fmt::Display for ChecksumCorruption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Corruption in column family '{}'", self.column_family)?;
+        if let (Some(file), Some(level)) = (&self.file, self.level) {
+            write!(f, ", file {} at level {}", file, level)?;
+        }
+        if let (Some(smallest), Some(largest)) = (&self.smallest_key, &self.largest_key) {
+            write!(f, ", keys {} to {}", smallest, largest)?;
+        }
+        if let Some(last_good_key) = &self.last_good_key {
+            write!(f, ", after key {}", last_good_key)?;
+        }
+        write!(f, ": {}", self.error)
+    }
+}
+
+/// Outcome of a checksum verification pass
+#[derive(Serialize)]
+pub struct ChecksumSummary {
+    pub column_families: Vec<String>,
+    pub sst_files: u64,
+    /// Records read, including those read by the run the verification resumed from
+    pub records: u64,
+    pub corruptions: u64,
+    pub resumed_from: u64,
+}
+
+impl fmt::Display for ChecksumSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Verified checksums of {} records in {} SST files of {}: {} corruptions",
+            self.records,
+            self.sst_files,
+            self.column_families.join(", "),
+            self.corruptions
+        )?;
+        if self.resumed_from > 0 {
+            write!(f, " (resumed after {})", self.resumed_from)?;
+        }
+        Ok(())
+    }
+}
+
+/// How well the sampled values of a column family fit a record layout
+#[derive(Serialize)]
+pub struct FormatProbe {
+    pub column_family: String,
+    /// Record type the values were checked as, `merkle` or `data`
+    pub record_type: &'static str,
+    pub records: u64,
+    pub valid: u64,
+    /// Values that decode but whose fields or re-encoded bytes are off
+    pub suspicious: u64,
+    pub undecodable: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_problem: Option<String>,
+    /// Most frequent value lengths with their counts
+    pub value_lengths: Vec<(usize, u64)>,
+    /// `compatible`, `mixed`, `incompatible`, `unknown` when no record type fits, or `empty`
+    pub verdict: &'static str,
+}
+
+impl FormatProbe {
+    pub fn new(column_family: &str, record_type: &'static str, records: usize) -> Self {
+        FormatProbe {
+            column_family: column_family.to_string(),
+            record_type,
+            records: records as u64,
+            valid: 0,
+            suspicious: 0,
+            undecodable: 0,
+            first_problem: None,
+            value_lengths: Vec::new(),
+            verdict: "empty",
+        }
+    }
+}
+
+impl fmt::Display for FormatProbe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Column family '{}' as {} records: {} of {} sampled valid, {} suspicious, {} undecodable",
+            self.column_family,
+            self.record_type,
+            self.valid,
+            self.records,
+            self.suspicious,
+            self.undecodable
+        )?;
+        if !self.value_lengths.is_empty() {
+            let lengths: Vec<String> = self
+                .value_lengths
+                .iter()
+                .map(|(len, count)| format!("{} bytes x{}", len, count))
+                .collect();
+            write!(f, "\n  Value lengths: {}", lengths.join(", "))?;
+        }
+        if let Some(problem) = &self.first_problem {
+            write!(f, "\n  First problem: {}", problem)?;
+        }
+        match self.verdict {
+            "incompatible" => write!(
+                f,
+                "\n  WARNING: written by an incompatible zkwasm-host-circuits record format"
+            ),
+            "mixed" => write!(
+                f,
+                "\n  WARNING: some records do not match the current record format"
+            ),
+            "unknown" => write!(f, "\n  Not a merkle or data record column family"),
+            "empty" => write!(f, "\n  Empty"),
+            _ => write!(f, "\n  Matches the current record format"),
+        }
+    }
+}
+
+/// A generated record that did not survive a round trip unchanged
+#[derive(Serialize)]
+pub struct SelfTestMismatch {
+    pub record_type: &'static str,
+    /// `encoding` for the round trip through to_slice and from_slice, `database` for the scratch one
+    pub stage: &'static str,
+    pub key: String,
+    pub value: String,
+    pub error: String,
+}
+
+impl fmt::Display for SelfTestMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Mismatch of {} record {} in the {} round trip: {}",
+            self.record_type, self.key, self.stage, self.error
+        )?;
+        if !self.value.is_empty() {
+            write!(f, "\n  Value: {}", self.value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Result of round-tripping generated records
+#[derive(Serialize)]
+pub struct SelfTestSummary {
+    pub path: String,
+    pub seed: u64,
+    pub merkle_records: usize,
+    pub data_records: usize,
+    pub mismatches: u64,
+    pub kept: bool,
+}
+
+impl fmt::Display for SelfTestSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Round-tripped {} merkle and {} data records (seed {}): {} mismatches",
+            self.merkle_records, self.data_records, self.seed, self.mismatches
+        )?;
+        if self.kept {
+            write!(f, ", scratch database kept at {}", self.path)?;
+        }
+        Ok(())
+    }
+}
+
+/// An operation read from the write-ahead log
+#[derive(Serialize)]
+pub struct WalOperation {
+    pub sequence: u64,
+    /// Sequence number of the write batch the operation belongs to
+    pub batch_sequence: u64,
+    pub op: &'static str,
+    pub column_family_id: u32,
+    pub key: String,
+    /// Exclusive end of a range deletion
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_len: Option<usize>,
+    /// First bytes of the value in hex
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_prefix: Option<String>,
+}
+
+impl fmt::Display for WalOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{} {} cf {} key {}",
+            self.sequence, self.op, self.column_family_id, self.key
+        )?;
+        if let Some(end_key) = &self.end_key {
+            write!(f, " to {}", end_key)?;
+        }
+        if let (Some(value_len), Some(value_prefix)) = (self.value_len, &self.value_prefix) {
+            write!(f, " value {} bytes {}", value_len, value_prefix)?;
+            if value_prefix.len() < value_len * 2 {
+                write!(f, "...")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Totals of a write-ahead log dump
+#[derive(Serialize)]
+pub struct WalSummary {
+    pub batches: u64,
+    pub operations: u64,
+    pub first_sequence: Option<u64>,
+    pub last_sequence: Option<u64>,
+}
+
+impl fmt::Display for WalSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Read {} operations in {} write batches", self.operations, self.batches)?;
+        if let (Some(first), Some(last)) = (self.first_sequence, self.last_sequence) {
+            write!(f, ", sequence numbers {} to {}", first, last)?;
+        }
+        Ok(())
+    }
+}
+
+/// An operation of a streamed write batch, with its full key and value
+#[derive(Serialize)]
+pub struct ChangeOperation {
+    pub sequence: u64,
+    pub op: &'static str,
+    pub column_family_id: u32,
+    pub key: String,
+    /// Exclusive end of a range deletion
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+/// A write batch streamed from the write-ahead log
+#[derive(Serialize)]
+pub struct ChangeBatch {
+    pub sequence: u64,
+    pub operations: Vec<ChangeOperation>,
+}
+
+impl fmt::Display for ChangeBatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Batch #{} ({} operations)", self.sequence, self.operations.len())?;
+        for operation in &self.operations {
+            write!(
+                f,
+                "\n  #{} {} cf {} key {}",
+                operation.sequence, operation.op, operation.column_family_id, operation.key
+            )?;
+            if let Some(end_key) = &operation.end_key {
+                write!(f, " to {}", end_key)?;
+            }
+            if let Some(value) = &operation.value {
+                write!(f, " value {}", value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Totals of a change stream, `last_sequence` is where the next run continues from
+#[derive(Serialize)]
+pub struct ChangesSummary {
+    pub since: u64,
+    pub batches: u64,
+    pub operations: u64,
+    pub last_sequence: Option<u64>,
+    /// Latest sequence number of the database when the stream started
+    pub latest_sequence: u64,
+}
+
+impl fmt::Display for ChangesSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Streamed {} operations in {} batches after sequence number {}",
+            self.operations, self.batches, self.since
+        )?;
+        if let Some(last_sequence) = self.last_sequence {
+            write!(f, ", last sequence number {}", last_sequence)?;
+        }
+        write!(f, ", database at sequence number {}", self.latest_sequence)
+    }
+}
+
+/// Background work RocksDB logged during one time bucket
+#[derive(Serialize, Default)]
+pub struct LogBucket {
+    pub start: String,
+    pub flushes: u64,
+    pub compactions: u64,
+    pub compaction_secs: f64,
+    pub slowdowns: u64,
+    pub stops: u64,
+}
+
+/// Summary and timeline of a RocksDB info LOG file
+#[derive(Serialize)]
+pub struct DbLogReport {
+    pub file: String,
+    pub first_time: Option<String>,
+    pub last_time: Option<String>,
+    pub flushes: u64,
+    pub compactions: u64,
+    pub compaction_secs: f64,
+    pub compaction_output_bytes: u64,
+    /// "Stalling writes" warnings, writes were slowed down
+    pub slowdowns: u64,
+    /// "Stopping writes" warnings, writes were blocked
+    pub stops: u64,
+    pub stall_reasons: BTreeMap<String, u64>,
+    pub buckets: Vec<LogBucket>,
+}
+
+/// Width of the compaction time bar of the busiest bucket in the timeline
+const TIMELINE_BAR_WIDTH: f64 = 40.0;
+
+impl fmt::Display for DbLogReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Log {}", self.file)?;
+        if let (Some(first), Some(last)) = (&self.first_time, &self.last_time) {
+            write!(f, " from {} to {}", first, last)?;
+        }
+        write!(
+            f,
+            "\n{} flushes, {} compactions taking {:.1}s and writing {} bytes, \
+             {} write slowdowns, {} write stops",
+            self.flushes,
+            self.compactions,
+            self.compaction_secs,
+            self.compaction_output_bytes,
+            self.slowdowns,
+            self.stops
+        )?;
+        for (reason, count) in &self.stall_reasons {
+            write!(f, "\n  {} x {}", count, reason)?;
+        }
+
+        // One line per bucket, the bar shows the compaction time relative to the busiest bucket
+        let max_secs = self
+            .buckets
+            .iter()
+            .map(|bucket| bucket.compaction_secs)
+            .fold(0.0, f64::max);
+        write!(
+            f,
+            "\n{:<19}  {:>7}  {:>11}  {:>9}  {:>9}  {:>5}  compaction time",
+            "bucket start", "flushes", "compactions", "comp secs", "slowdowns", "stops"
+        )?;
+        for bucket in &self.buckets {
+            let bar_len = if max_secs > 0.0 {
+                (bucket.compaction_secs / max_secs * TIMELINE_BAR_WIDTH).round() as usize
+            } else {
+                0
+            };
+            write!(
+                f,
+                "\n{:<19}  {:>7}  {:>11}  {:>9.1}  {:>9}  {:>5}  {}",
+                bucket.start,
+                bucket.flushes,
+                bucket.compactions,
+                bucket.compaction_secs,
+                bucket.slowdowns,
+                bucket.stops,
+                "#".repeat(bar_len)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Size, file and memory statistics of one column family
+#[derive(Serialize)]
+pub struct CfStats {
+    pub name: String,
+    pub estimated_keys: Option<u64>,
+    pub sst_files_size: Option<u64>,
+    pub sst_files: u64,
+    pub files_per_level: Vec<u64>,
+    pub block_cache_usage: Option<u64>,
+    pub cur_mem_tables_size: Option<u64>,
+    pub all_mem_tables_size: Option<u64>,
+}
+
+/// SST file counts and sizes of a column family before and after a manual compaction
+#[derive(Serialize)]
+pub struct CompactSummary {
+    pub column_family: String,
+    pub bottommost: bool,
+    pub files_before: Vec<u64>,
+    pub files_after: Vec<u64>,
+    pub size_before: Option<u64>,
+    pub size_after: Option<u64>,
+    pub elapsed_secs: f64,
+}
+
+impl fmt::Display for CompactSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let levels = |files: &[u64]| {
+            files
+                .iter()
+                .map(|files| files.to_string())
+                .collect::<Vec<_>>()
+                .join("/")
+        };
+        write!(
+            f,
+            "Compacted column family '{}' in {:.2}s",
+            self.column_family, self.elapsed_secs
+        )?;
+        write!(
+            f,
+            "\nBefore: {} files ({}), {} bytes",
+            self.files_before.iter().sum::<u64>(),
+            levels(&self.files_before),
+            or_unknown(self.size_before)
+        )?;
+        write!(
+            f,
+            "\nAfter:  {} files ({}), {} bytes",
+            self.files_after.iter().sum::<u64>(),
+            levels(&self.files_after),
+            or_unknown(self.size_after)
+        )
+    }
+}
+
+/// Options of one column family as written in an OPTIONS file
+#[derive(Serialize)]
+pub struct CfOptionsInfo {
+    pub name: String,
+    pub options: BTreeMap<String, String>,
+    pub table_options: BTreeMap<String, String>,
+}
+
+/// Database and column family options read from an OPTIONS file
+#[derive(Serialize)]
+pub struct OptionsReport {
+    pub file: String,
+    pub db_options: BTreeMap<String, String>,
+    pub column_families: Vec<CfOptionsInfo>,
+}
+
+impl fmt::Display for OptionsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Options from {}", self.file)?;
+        write!(f, "\n[DBOptions]")?;
+        for (key, value) in &self.db_options {
+            write!(f, "\n  {} = {}", key, value)?;
+        }
+        for cf in &self.column_families {
+            write!(f, "\n[CFOptions \"{}\"]", cf.name)?;
+            for (key, value) in &cf.options {
+                write!(f, "\n  {} = {}", key, value)?;
+            }
+            write!(f, "\n[TableOptions \"{}\"]", cf.name)?;
+            for (key, value) in &cf.table_options {
+                write!(f, "\n  {} = {}", key, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Statistics of every column family of a database
+#[derive(Serialize)]
+pub struct StatsReport {
+    pub column_families: Vec<CfStats>,
+    pub latest_sequence_number: u64,
+}
+
+impl fmt::Display for StatsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name_width = self
+            .column_families
+            .iter()
+            .map(|cf| cf.name.len())
+            .max()
+            .unwrap_or(0)
+            .max("column family".len());
+
+        write!(
+            f,
+            "{:<name_width$}  {:>14}  {:>16}  {:>9}  {:<28}  {:>12}  {:>12}",
+            "column family",
+            "est. keys",
+            "sst bytes",
+            "sst files",
+            "files per level",
+            "block cache",
+            "memtables",
+            name_width = name_width
+        )?;
+        for cf in &self.column_families {
+            let levels = cf
+                .files_per_level
+                .iter()
+                .map(|files| files.to_string())
+                .collect::<Vec<_>>()
+                .join("/");
+            write!(
+                f,
+                "\n{:<name_width$}  {:>14}  {:>16}  {:>9}  {:<28}  {:>12}  {:>12}",
+                cf.name,
+                or_unknown(cf.estimated_keys),
+                or_unknown(cf.sst_files_size),
+                cf.sst_files,
+                levels,
+                or_unknown(cf.block_cache_usage),
+                or_unknown(cf.all_mem_tables_size),
+                name_width = name_width
+            )?;
+        }
+        write!(f, "\nLatest sequence number: {}", self.latest_sequence_number)
+    }
+}
+
+/// Every `rocksdb.*` property a column family reports
+#[derive(Serialize)]
+pub struct CfProperties {
+    pub name: String,
+    pub int_properties: BTreeMap<String, u64>,
+    pub string_properties: BTreeMap<String, String>,
+}
+
+/// RocksDB properties of the inspected column families
+#[derive(Serialize)]
+pub struct PropertiesReport {
+    pub column_families: Vec<CfProperties>,
+}
+
+impl fmt::Display for PropertiesReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, cf) in self.column_families.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "[{}]", cf.name)?;
+            let name_width = cf.int_properties.keys().map(|name| name.len()).max().unwrap_or(0);
+            for (name, value) in &cf.int_properties {
+                write!(f, "\n  {:<name_width$}  {}", name, value, name_width = name_width)?;
+            }
+            for (name, value) in &cf.string_properties {
+                let value = value.trim_end();
+                if value.contains('\n') {
+                    write!(f, "\n  {}:", name)?;
+                    for line in value.lines() {
+                        write!(f, "\n    {}", line)?;
+                    }
+                } else {
+                    write!(f, "\n  {:<name_width$}  {}", name, value, name_width = name_width)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Key range of a column family, as iterated and as recorded in its SST files
+#[derive(Serialize)]
+pub struct CfBounds {
+    pub name: String,
+    pub first_key: Option<KeyView>,
+    pub last_key: Option<KeyView>,
+    pub sst_files: usize,
+    pub sst_smallest_key: Option<KeyView>,
+    pub sst_largest_key: Option<KeyView>,
+}
+
+/// Key ranges of the inspected column families
+#[derive(Serialize)]
+pub struct BoundsReport {
+    pub column_families: Vec<CfBounds>,
+}
+
+impl fmt::Display for BoundsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, cf) in self.column_families.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "[{}]", cf.name)?;
+            match (&cf.first_key, &cf.last_key) {
+                (Some(first), Some(last)) => {
+                    write!(f, "\n  First key: {}", first)?;
+                    write!(f, "\n  Last key:  {}", last)?;
+                },
+                _ => write!(f, "\n  Empty")?,
+            }
+            write!(f, "\n  SST files: {}", cf.sst_files)?;
+            if cf.sst_files > 0 {
+                write!(f, "\n  SST smallest key: {}", key_or_unknown(&cf.sst_smallest_key))?;
+                write!(f, "\n  SST largest key:  {}", key_or_unknown(&cf.sst_largest_key))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A live SST file with its key range
+#[derive(Serialize)]
+pub struct SstFileInfo {
+    pub column_family: String,
+    pub name: String,
+    pub level: i32,
+    pub size: u64,
+    pub num_entries: u64,
+    pub num_deletions: u64,
+    pub smallest_key: Option<KeyView>,
+    pub largest_key: Option<KeyView>,
+}
+
+fn key_or_unknown(key: &Option<KeyView>) -> String {
+    key.as_ref()
+        .map_or_else(|| "unknown".to_string(), |key| key.to_string())
+}
+
+impl fmt::Display for SstFileInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} [{}] L{}: {} bytes, {} entries, {} deletions",
+            self.name, self.column_family, self.level, self.size, self.num_entries, self.num_deletions
+        )?;
+        writeln!(f, "  smallest: {}", key_or_unknown(&self.smallest_key))?;
+        write!(f, "  largest:  {}", key_or_unknown(&self.largest_key))
+    }
+}
+
+/// Number of values scanned and matched by a grep
+#[derive(Serialize)]
+pub struct GrepSummary {
+    pub column_family: String,
+    pub scanned: u64,
+    /// Matches listed, the skipped ones are not included
+    pub matches: u64,
+    pub skipped: u64,
+    /// `--skip` value listing the next page, when more matches follow
+    pub next_skip: Option<u64>,
+}
+
+impl fmt::Display for GrepSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Found {} matching values in column family '{}' ({} scanned)",
+            self.matches, self.column_family, self.scanned
+        )?;
+        write_pagination(f, self.skipped, self.next_skip)
+    }
+}
+
+/// Number of entries whose size falls within `[min, max]`
+#[derive(Serialize)]
+pub struct HistogramBucket {
+    pub min: u64,
+    pub max: u64,
+    pub count: u64,
+}
+
+/// Key and value size distribution of a column family
+#[derive(Serialize)]
+pub struct AnalyzeReport {
+    pub column_family: String,
+    pub records: u64,
+    pub key_bytes: u64,
+    pub value_bytes: u64,
+    pub key_lengths: Vec<HistogramBucket>,
+    pub value_lengths: Vec<HistogramBucket>,
+    /// Number of keys per leading byte, indexed by the byte value
+    pub first_bytes: Vec<u64>,
+}
+
+fn write_histogram(f: &mut fmt::Formatter<'_>, buckets: &[HistogramBucket], total: u64) -> fmt::Result {
+    const BAR_WIDTH: u64 = 40;
+    let max_count = buckets.iter().map(|b| b.count).max().unwrap_or(0).max(1);
+    for bucket in buckets {
+        let range = if bucket.min == bucket.max {
+            bucket.min.to_string()
+        } else {
+            format!("{}-{}", bucket.min, bucket.max)
+        };
+        write!(
+            f,
+            "\n  {:>21} {:>12} {:>6.2}% {}",
+            range,
+            bucket.count,
+            bucket.count as f64 * 100.0 / total.max(1) as f64,
+            "#".repeat((bucket.count * BAR_WIDTH / max_count) as usize)
+        )?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for AnalyzeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Column family '{}': {} records, {} key bytes, {} value bytes, {} bytes total",
+            self.column_family,
+            self.records,
+            self.key_bytes,
+            self.value_bytes,
+            self.key_bytes + self.value_bytes
+        )?;
+        write!(f, "\nKey length histogram:")?;
+        write_histogram(f, &self.key_lengths, self.records)?;
+        write!(f, "\nValue length histogram:")?;
+        write_histogram(f, &self.value_lengths, self.records)?;
+        write!(f, "\nFirst key byte distribution:")?;
+        for (row, counts) in self.first_bytes.chunks(16).enumerate() {
+            write!(f, "\n  {:02x}:", row * 16)?;
+            for count in counts {
+                write!(f, " {:>8}", count)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Records whose key limb falls in one range of a partition report
+#[derive(Serialize)]
+pub struct PartitionBucket {
+    pub index: usize,
+    pub first_limb: u64,
+    pub last_limb: u64,
+    pub records: u64,
+    pub key_bytes: u64,
+    pub value_bytes: u64,
+}
+
+/// Records and bytes of a column family per range of one u64 limb of its keys
+#[derive(Serialize)]
+pub struct PartitionReport {
+    pub column_family: String,
+    pub limb: usize,
+    pub records: u64,
+    pub bytes: u64,
+    /// Records whose key is not 32 bytes long and so has no limbs
+    pub other_keys: u64,
+    pub other_bytes: u64,
+    pub buckets: Vec<PartitionBucket>,
+}
+
+impl fmt::Display for PartitionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Column family '{}' partitioned by limb {} into {} buckets: {} records, {} bytes",
+            self.column_family,
+            self.limb,
+            self.buckets.len(),
+            self.records,
+            self.bytes
+        )?;
+        for bucket in &self.buckets {
+            let bytes = bucket.key_bytes + bucket.value_bytes;
+            write!(
+                f,
+                "\n  {:>4} {:016x}-{:016x} {:>12} records {:>16} bytes {:>6.2}%",
+                bucket.index,
+                bucket.first_limb,
+                bucket.last_limb,
+                bucket.records,
+                bytes,
+                bytes as f64 * 100.0 / self.bytes.max(1) as f64
+            )?;
+        }
+        if self.other_keys > 0 {
+            write!(
+                f,
+                "\n  {} records with keys other than 32 bytes, {} bytes, are in no bucket",
+                self.other_keys, self.other_bytes
+            )?;
+        }
+        // How far the fullest bucket is above an even split
+        let placed = self.bytes - self.other_bytes;
+        if placed > 0 {
+            let largest = self
+                .buckets
+                .iter()
+                .map(|b| b.key_bytes + b.value_bytes)
+                .max()
+                .unwrap_or(0);
+            let mean = placed as f64 / self.buckets.len() as f64;
+            write!(f, "\nLargest bucket holds {:.2}x the mean size", largest as f64 / mean)?;
+        }
+        Ok(())
+    }
+}
+
+/// Column families copied into a new TTL database
+#[derive(Serialize)]
+pub struct SetTtlSummary {
+    pub destination: String,
+    pub ttl_secs: u64,
+    pub column_families: Vec<CfCopy>,
+}
+
+impl fmt::Display for SetTtlSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Copied into {} with records expiring after {} seconds:",
+            self.destination, self.ttl_secs
+        )?;
+        for cf in &self.column_families {
+            write!(
+                f,
+                "\n  {}: {} records, {} bytes",
+                cf.column_family, cf.records, cf.bytes
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Write times of the records of a column family of a TTL database
+#[derive(Serialize)]
+pub struct RecordAgeReport {
+    pub column_family: String,
+    pub records: u64,
+    /// Records whose value ends with a plausible write time
+    pub timestamped: u64,
+    /// Unix seconds
+    pub oldest_write: Option<u64>,
+    pub newest_write: Option<u64>,
+    pub expire_after: Option<u64>,
+    /// Records older than `expire_after` seconds
+    pub expired: Option<u64>,
+    /// Number of records per age range in seconds
+    pub ages: Vec<HistogramBucket>,
+}
+
+impl fmt::Display for RecordAgeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Column family '{}': {} records, {} with a write time",
+            self.column_family, self.records, self.timestamped
+        )?;
+        if let (Some(oldest), Some(newest)) = (self.oldest_write, self.newest_write) {
+            write!(f, "\nOldest write at {}, newest at {} (unix seconds)", oldest, newest)?;
+        }
+        if let (Some(ttl), Some(expired)) = (self.expire_after, self.expired) {
+            write!(f, "\n{} records are older than {} seconds", expired, ttl)?;
+        }
+        write!(f, "\nAge histogram in seconds:")?;
+        write_histogram(f, &self.ages, self.timestamped)
+    }
+}
+
+/// A key with the size of its value
+#[derive(Serialize)]
+pub struct ValueSize {
+    pub key: String,
+    pub size: u64,
+}
+
+/// Number and total size of values whose size falls within `[min, max]`
+#[derive(Serialize)]
+pub struct SizeBucket {
+    pub min: u64,
+    pub max: u64,
+    pub count: u64,
+    pub bytes: u64,
+}
+
+/// The largest values of a column family and where the value bytes are concentrated
+#[derive(Serialize)]
+pub struct LargestValuesReport {
+    pub column_family: String,
+    pub records: u64,
+    pub value_bytes: u64,
+    /// Largest values first
+    pub largest: Vec<ValueSize>,
+    pub buckets: Vec<SizeBucket>,
+}
+
+impl fmt::Display for LargestValuesReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Column family '{}': {} records, {} value bytes",
+            self.column_family, self.records, self.value_bytes
+        )?;
+        write!(f, "\nLargest values:")?;
+        for entry in &self.largest {
+            write!(
+                f,
+                "\n  {:>12} bytes {:>6.2}%  {}",
+                entry.size,
+                entry.size as f64 * 100.0 / self.value_bytes.max(1) as f64,
+                entry.key
+            )?;
+        }
+        write!(f, "\nValue bytes per size bucket:")?;
+        for bucket in &self.buckets {
+            write!(
+                f,
+                "\n  {:>21} {:>12} values {:>16} bytes {:>6.2}%",
+                format!("{}-{}", bucket.min, bucket.max),
+                bucket.count,
+                bucket.bytes,
+                bucket.bytes as f64 * 100.0 / self.value_bytes.max(1) as f64
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Keys sharing an identical value
+#[derive(Serialize)]
+pub struct DuplicateGroup {
+    pub value_sha256: String,
+    pub value_size: u64,
+    pub count: u64,
+    /// The first keys holding the value, at most `commands::MAX_KEYS_PER_GROUP`
+    pub keys: Vec<String>,
+    /// Bytes saved if the value were stored once
+    pub savings: u64,
+}
+
+impl fmt::Display for DuplicateGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} keys share a {}-byte value (sha256 {}), {} bytes duplicated",
+            self.count, self.value_size, self.value_sha256, self.savings
+        )?;
+        for key in &self.keys {
+            write!(f, "\n  {}", key)?;
+        }
+        if self.count > self.keys.len() as u64 {
+            write!(f, "\n  ... {} more", self.count - self.keys.len() as u64)?;
+        }
+        Ok(())
+    }
+}
+
+/// Totals of a duplicate value search
+#[derive(Serialize)]
+pub struct DuplicatesSummary {
+    pub column_family: String,
+    pub records: u64,
+    pub distinct_values: u64,
+    pub duplicate_groups: u64,
+    /// Records whose value is also stored under another key
+    pub duplicate_records: u64,
+    pub potential_savings: u64,
+}
+
+impl fmt::Display for DuplicatesSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Column family '{}': {} records, {} distinct values, {} values stored more than once by {} records, {} bytes could be saved",
+            self.column_family,
+            self.records,
+            self.distinct_values,
+            self.duplicate_groups,
+            self.duplicate_records,
+            self.potential_savings
+        )
+    }
+}
+
+/// A record that differs between MongoDB and RocksDB
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MongoDiff {
+    /// Stored in MongoDB only
+    MissingInRocksDb { key: String },
+    /// Stored in RocksDB only
+    ExtraInRocksDb { key: String },
+    /// Stored in both with different values
+    ValueMismatch {
+        key: String,
+        mongo_value: String,
+        rocksdb_value: String,
+    },
+    /// A MongoDB document that could not be read as a record
+    InvalidDocument { id: String, error: String },
+}
+
+impl fmt::Display for MongoDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MongoDiff::MissingInRocksDb { key } => write!(f, "Missing in RocksDB: {}", key),
+            MongoDiff::ExtraInRocksDb { key } => write!(f, "Only in RocksDB: {}", key),
+            MongoDiff::ValueMismatch {
+                key,
+                mongo_value,
+                rocksdb_value,
+            } => write!(
+                f,
+                "Value mismatch for {}:\n  MongoDB: {}\n  RocksDB: {}",
+                key, mongo_value, rocksdb_value
+            ),
+            MongoDiff::InvalidDocument { id, error } => {
+                write!(f, "Invalid MongoDB document {}: {}", id, error)
+            }
+        }
+    }
+}
+
+/// Totals of a MongoDB to RocksDB comparison
+#[derive(Serialize)]
+pub struct CompareSummary {
+    pub column_family: String,
+    pub collection: String,
+    pub mongo_records: u64,
+    pub rocksdb_records: u64,
+    pub matching: u64,
+    pub missing_in_rocksdb: u64,
+    pub extra_in_rocksdb: u64,
+    pub value_mismatches: u64,
+    pub invalid_documents: u64,
+}
+
+impl fmt::Display for CompareSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Compared collection '{}' ({} records) with column family '{}' ({} records): {} matching, {} missing in RocksDB, {} only in RocksDB, {} differing, {} invalid documents",
+            self.collection,
+            self.mongo_records,
+            self.column_family,
+            self.rocksdb_records,
+            self.matching,
+            self.missing_in_rocksdb,
+            self.extra_in_rocksdb,
+            self.value_mismatches,
+            self.invalid_documents
+        )
+    }
+}
+
+/// Result of migrating a MongoDB collection into a column family
+#[derive(Serialize)]
+pub struct MigrationSummary {
+    pub collection: String,
+    pub column_family: String,
+    /// Documents written, including those written by the run the migration resumed from
+    pub migrated: u64,
+    pub resumed_from: u64,
+    pub mongo_documents: u64,
+    pub rocksdb_records: u64,
+    /// Whether the column family holds as many records as the collection has documents
+    pub verified: bool,
+}
+
+impl fmt::Display for MigrationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Migrated {} documents from collection '{}' into column family '{}'",
+            self.migrated, self.collection, self.column_family
+        )?;
+        if self.resumed_from > 0 {
+            write!(f, " (resumed after {})", self.resumed_from)?;
+        }
+        if self.verified {
+            write!(f, ", counts match: {} records", self.rocksdb_records)
+        } else {
+            write!(
+                f,
+                ", COUNT MISMATCH: {} documents in MongoDB, {} records in RocksDB",
+                self.mongo_documents, self.rocksdb_records
+            )
+        }
+    }
+}
+
+/// Wall-clock time and RocksDB perf context counters of a command, printed with `--timing`
+#[derive(Serialize)]
+pub struct TimingReport {
+    pub elapsed_ms: f64,
+    /// Blocks read from SST files, cache misses
+    pub block_reads: u64,
+    pub block_read_bytes: u64,
+    pub block_read_ms: f64,
+    pub block_cache_hits: u64,
+    /// Seeks issued to the memtable and SST file iterators
+    pub seeks: u64,
+    pub memtable_gets: u64,
+    /// Deleted and overwritten entries iterators stepped over
+    pub internal_keys_skipped: u64,
+}
+
+impl fmt::Display for TimingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lookups = self.block_reads + self.block_cache_hits;
+        let hit_rate = self.block_cache_hits as f64 * 100.0 / lookups.max(1) as f64;
+        write!(
+            f,
+            "Timing: {:.1} ms elapsed, {} block reads ({} bytes, {:.1} ms), \
+             {} block cache hits ({:.1}%), {} seeks, {} memtable gets, {} internal keys skipped",
+            self.elapsed_ms,
+            self.block_reads,
+            self.block_read_bytes,
+            self.block_read_ms,
+            self.block_cache_hits,
+            hit_rate,
+            self.seeks,
+            self.memtable_gets,
+            self.internal_keys_skipped
+        )
+    }
+}
+
+/// RocksDB perf context counters of a command, printed with `--perf`
+#[derive(Serialize)]
+pub struct PerfReport {
+    /// Counters by their RocksDB name, `_time` and `_nanos` counters are in nanoseconds
+    pub counters: BTreeMap<&'static str, u64>,
+}
+
+impl fmt::Display for PerfReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Perf context (times in nanoseconds):")?;
+        let width = self.counters.keys().map(|name| name.len()).max().unwrap_or(0);
+        let mut zero = 0;
+        for (name, value) in &self.counters {
+            if *value == 0 {
+                zero += 1;
+                continue;
+            }
+            write!(f, "\n  {:<width$} {:>14}", name, value, width = width)?;
+        }
+        if zero > 0 {
+            write!(f, "\n  {} other counters are zero", zero)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+//! Skipping, limiting and paging the records of listing commands
+
+use crate::output::PageBreak;
+
+/// Which records of a listing are passed on
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Pagination {
+    /// Records passed over before the first listed one
+    pub skip: u64,
+    /// Most records listed, all of them when `None`
+    pub limit: Option<u64>,
+    /// Records per page, a page break follows every full page
+    pub page_size: Option<u64>,
+}
+
+impl Pagination {
+    /// At most `limit` records from the first one
+    pub fn limit(limit: u64) -> Self {
+        Pagination {
+            limit: Some(limit),
+            ..Pagination::default()
+        }
+    }
+
+    /// The page break following the `listed`th listed record, when it completes a page
+    pub fn page_break(&self, listed: u64) -> Option<PageBreak> {
+        let page_size = self.page_size.filter(|&size| size > 0 && listed % size == 0)?;
+        Some(PageBreak {
+            page: listed / page_size,
+            listed,
+            next_skip: self.skip + listed,
+        })
+    }
+}
+
+/// What to do with the next record of a listing
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// Pass it over, it comes before the first listed record
+    Skip,
+    /// List it
+    List,
+    /// Stop the listing, the limit was reached before this record
+    Stop,
+}
+
+/// Counts the records of a listing against its [`Pagination`]
+pub struct Paginator {
+    pagination: Pagination,
+    skipped: u64,
+    listed: u64,
+    next_skip: Option<u64>,
+}
+
+impl Paginator {
+    pub fn new(pagination: Pagination) -> Self {
+        Paginator {
+            pagination,
+            skipped: 0,
+            listed: 0,
+            next_skip: None,
+        }
+    }
+
+    /// Decides on the next record, once [`Step::Stop`] is returned the listing should end
+    pub fn step(&mut self) -> Step {
+        if self.skipped < self.pagination.skip {
+            self.skipped += 1;
+            return Step::Skip;
+        }
+        if self.pagination.limit.map_or(false, |limit| self.listed >= limit) {
+            self.next_skip = Some(self.pagination.skip + self.listed);
+            return Step::Stop;
+        }
+        self.listed += 1;
+        Step::List
+    }
+
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    pub fn listed(&self) -> u64 {
+        self.listed
+    }
+
+    /// `--skip` value listing the records after the last listed one, when more follow
+    pub fn next_skip(&self) -> Option<u64> {
+        self.next_skip
+    }
+}
@@ -0,0 +1,190 @@
+//! Patch files holding the puts and deletes that turn one version of a column family into another
+
+use std::io::{self, BufRead, Write};
+
+use crate::export::{read_array, write_len_prefixed};
+
+/// First bytes of a patch file
+///
+/// A patch is the magic, the u16 LE format version, the u16 LE length and bytes of the column
+/// family name, then per operation a tag byte, the u32 LE key length and key, for puts the u32 LE
+/// value length and value, and the u32 LE CRC32 of these fields, and finally an `END_TAG` byte
+/// followed by the u64 LE operation count.
+pub const PATCH_MAGIC: &[u8; 8] = b"RDBPATCH";
+/// Version of the patch layout written by this build
+pub const PATCH_VERSION: u16 = 1;
+
+const PUT_TAG: u8 = 1;
+const DELETE_TAG: u8 = 2;
+const END_TAG: u8 = 0xff;
+
+/// An operation of a patch
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatchOp {
+    Put { key: Vec<u8>, value: Vec<u8> },
+    Delete { key: Vec<u8> },
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Streams operations into a patch file
+pub struct PatchWriter<W: Write> {
+    inner: W,
+    ops: u64,
+}
+
+impl<W: Write> PatchWriter<W> {
+    /// Writes the header of a patch for `column_family`
+    pub fn new(mut inner: W, column_family: &str) -> io::Result<Self> {
+        let name_len = u16::try_from(column_family.len()).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "column family name too long")
+        })?;
+        inner.write_all(PATCH_MAGIC)?;
+        inner.write_all(&PATCH_VERSION.to_le_bytes())?;
+        inner.write_all(&name_len.to_le_bytes())?;
+        inner.write_all(column_family.as_bytes())?;
+        Ok(PatchWriter { inner, ops: 0 })
+    }
+
+    pub fn put(&mut self, key: &[u8], value: &[u8]) -> io::Result<()> {
+        let mut op = vec![PUT_TAG];
+        write_len_prefixed(&mut op, key)?;
+        write_len_prefixed(&mut op, value)?;
+        self.write_op(&op)
+    }
+
+    pub fn delete(&mut self, key: &[u8]) -> io::Result<()> {
+        let mut op = vec![DELETE_TAG];
+        write_len_prefixed(&mut op, key)?;
+        self.write_op(&op)
+    }
+
+    fn write_op(&mut self, op: &[u8]) -> io::Result<()> {
+        self.inner.write_all(op)?;
+        self.inner.write_all(&crc32fast::hash(op).to_le_bytes())?;
+        self.ops += 1;
+        Ok(())
+    }
+
+    /// Writes the trailer, flushes buffered output and returns the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.inner.write_all(&[END_TAG])?;
+        self.inner.write_all(&self.ops.to_le_bytes())?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+/// Streams the operations of a patch file, checking every CRC32 and the trailer
+///
+/// A patch without trailer is reported as truncated, so a partial patch is never applied in full.
+pub struct PatchReader<R: BufRead> {
+    inner: R,
+    column_family: String,
+    ops: u64,
+    finished: bool,
+}
+
+impl<R: BufRead> PatchReader<R> {
+    /// Reads and checks the header
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let magic: [u8; 8] = read_array(&mut inner)?;
+        if &magic != PATCH_MAGIC {
+            return Err(invalid("not a patch file".to_string()));
+        }
+        let version = u16::from_le_bytes(read_array(&mut inner)?);
+        if version != PATCH_VERSION {
+            return Err(invalid(format!(
+                "unsupported patch version {}, expected {}",
+                version, PATCH_VERSION
+            )));
+        }
+        let name_len = u16::from_le_bytes(read_array(&mut inner)?);
+        let mut name = vec![0u8; name_len as usize];
+        inner.read_exact(&mut name)?;
+        let column_family = String::from_utf8(name).map_err(|e| invalid(e.to_string()))?;
+        Ok(PatchReader {
+            inner,
+            column_family,
+            ops: 0,
+            finished: false,
+        })
+    }
+
+    /// Column family the patch was made from
+    pub fn column_family(&self) -> &str {
+        &self.column_family
+    }
+
+    fn read_op(&mut self) -> io::Result<Option<PatchOp>> {
+        if self.finished {
+            return Ok(None);
+        }
+        let op_number = self.ops + 1;
+        let truncated = || {
+            io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("patch truncated at operation {}", op_number),
+            )
+        };
+
+        let [tag]: [u8; 1] = read_array(&mut self.inner).map_err(|_| truncated())?;
+        if tag == END_TAG {
+            let count = u64::from_le_bytes(read_array(&mut self.inner).map_err(|_| truncated())?);
+            if count != self.ops {
+                return Err(invalid(format!(
+                    "patch trailer counts {} operations, read {}",
+                    count, self.ops
+                )));
+            }
+            self.finished = true;
+            return Ok(None);
+        }
+        if tag != PUT_TAG && tag != DELETE_TAG {
+            return Err(invalid(format!("unknown tag {} of operation {}", tag, op_number)));
+        }
+
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&[tag]);
+        let mut read_field = |inner: &mut R| -> io::Result<Vec<u8>> {
+            let len: [u8; 4] = read_array(inner).map_err(|_| truncated())?;
+            let mut bytes = vec![0u8; u32::from_le_bytes(len) as usize];
+            inner.read_exact(&mut bytes).map_err(|_| truncated())?;
+            hasher.update(&len);
+            hasher.update(&bytes);
+            Ok(bytes)
+        };
+        let key = read_field(&mut self.inner)?;
+        let op = if tag == PUT_TAG {
+            let value = read_field(&mut self.inner)?;
+            PatchOp::Put { key, value }
+        } else {
+            PatchOp::Delete { key }
+        };
+
+        let checksum = u32::from_le_bytes(read_array(&mut self.inner).map_err(|_| truncated())?);
+        if hasher.finalize() != checksum {
+            return Err(invalid(format!("checksum mismatch in operation {}", op_number)));
+        }
+        self.ops = op_number;
+        Ok(Some(op))
+    }
+}
+
+impl<R: BufRead> Iterator for PatchReader<R> {
+    type Item = io::Result<PatchOp>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_op() {
+            Ok(Some(op)) => Some(Ok(op)),
+            Ok(None) => None,
+            Err(e) => {
+                // Stop after the first error instead of reading garbage
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
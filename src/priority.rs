@@ -0,0 +1,46 @@
+//! Lowering the CPU and IO priority of the process for `--background` runs
+
+use std::io;
+
+/// Niceness of background runs, the lowest CPU priority
+#[cfg(unix)]
+const BACKGROUND_NICE: libc::c_int = 19;
+
+/// Lowers the CPU priority of the calling thread to the lowest niceness and, on Linux, moves its IO
+/// to the idle class, served only while no other process uses the disk
+///
+/// Threads inherit the priorities of the thread creating them, so call this before opening
+/// databases or starting thread pools.
+#[cfg(unix)]
+pub fn lower_priority() -> io::Result<()> {
+    // SAFETY: setpriority only changes the scheduling of the calling thread
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, BACKGROUND_NICE) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+        const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+        const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+        // SAFETY: ioprio_set only changes the IO scheduling of the calling thread
+        let result = unsafe {
+            libc::syscall(
+                libc::SYS_ioprio_set,
+                IOPRIO_WHO_PROCESS,
+                0,
+                IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+            )
+        };
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Priorities cannot be lowered on this platform, background runs only pace their scans
+#[cfg(not(unix))]
+pub fn lower_priority() -> io::Result<()> {
+    Ok(())
+}
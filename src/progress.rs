@@ -0,0 +1,127 @@
+//! Progress bars for long scans, drawn on stderr so stdout stays parseable
+
+use indicatif::{HumanBytes, ProgressBar, ProgressDrawTarget, ProgressState, ProgressStyle};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::db::DbHandle;
+use crate::error::Result;
+
+/// Progress of a scan over keys, with an ETA when the total is estimated up front
+///
+/// A disabled progress draws nothing, for CI logs, its summary is still logged.
+pub struct Progress {
+    bar: ProgressBar,
+    bytes: Arc<AtomicU64>,
+    track_bytes: bool,
+    unit: &'static str,
+    started: Instant,
+}
+
+impl Progress {
+    /// A progress counting `unit`s, a bar towards `estimated_total` or a spinner without one
+    pub fn new(enabled: bool, estimated_total: Option<u64>, unit: &'static str) -> Self {
+        let bar = match estimated_total {
+            Some(total) => ProgressBar::new(total),
+            None => ProgressBar::new_spinner(),
+        };
+        if !enabled {
+            bar.set_draw_target(ProgressDrawTarget::hidden());
+        }
+        let progress = Progress {
+            bar,
+            bytes: Arc::new(AtomicU64::new(0)),
+            track_bytes: false,
+            unit,
+            started: Instant::now(),
+        };
+        progress.restyle(estimated_total.is_some());
+        progress
+    }
+
+    /// A progress over the keys of a column family, estimated with `rocksdb.estimate-num-keys`
+    pub fn for_cf(enabled: bool, db: &DbHandle, target_cf: &str) -> Result<Self> {
+        let cf = db.cf(target_cf)?;
+        let estimated_keys =
+            db.property_int_value_cf(cf, rocksdb::properties::ESTIMATE_NUM_KEYS)?;
+        Ok(Self::new(enabled, estimated_keys, "keys"))
+    }
+
+    /// Also shows the throughput of the bytes passed to [`Progress::inc_bytes`]
+    pub fn with_bytes(mut self) -> Self {
+        self.track_bytes = true;
+        self.restyle(self.bar.length().is_some());
+        self
+    }
+
+    fn restyle(&self, has_total: bool) {
+        let counts = if has_total {
+            format!("{{bar:40}} {{pos}}/~{{len}} {} ({{per_sec}}, ETA {{eta}})", self.unit)
+        } else {
+            format!("{{pos}} {} ({{per_sec}})", self.unit)
+        };
+        let bytes_per_sec = if self.track_bytes { " {bytes_per_sec}" } else { "" };
+        let template = format!("{{spinner}} [{{elapsed_precise}}] {}{}", counts, bytes_per_sec);
+
+        let bytes = Arc::clone(&self.bytes);
+        let style = ProgressStyle::with_template(&template)
+            .expect("progress templates are valid")
+            .with_key(
+                "bytes_per_sec",
+                move |state: &ProgressState, w: &mut dyn Write| {
+                    let seconds = state.elapsed().as_secs_f64().max(f64::EPSILON);
+                    let rate = bytes.load(Ordering::Relaxed) as f64 / seconds;
+                    let _ = write!(w, "{}/s", HumanBytes(rate as u64));
+                },
+            );
+        self.bar.set_style(style);
+    }
+
+    /// Updates the estimated total, for sources that only know it once started
+    pub fn set_total(&self, total: u64) {
+        if self.bar.length() != Some(total) {
+            self.bar.set_length(total);
+            self.restyle(true);
+        }
+    }
+
+    pub fn inc(&self, n: u64) {
+        self.bar.inc(n);
+    }
+
+    pub fn inc_bytes(&self, n: u64) {
+        self.bytes.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn set_position(&self, position: u64) {
+        self.bar.set_position(position);
+    }
+
+    /// Clears the bar and logs how much was processed and how fast at info level
+    pub fn finish(&self) {
+        self.bar.finish_and_clear();
+
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let count = self.bar.position();
+        let mut summary = format!(
+            "Processed {} {} in {:.1}s ({:.0} {}/s",
+            count,
+            self.unit,
+            elapsed,
+            count as f64 / elapsed.max(f64::EPSILON),
+            self.unit
+        );
+        if self.track_bytes {
+            let bytes = self.bytes.load(Ordering::Relaxed);
+            let _ = write!(
+                summary,
+                ", {} at {}/s",
+                HumanBytes(bytes),
+                HumanBytes((bytes as f64 / elapsed.max(f64::EPSILON)) as u64)
+            );
+        }
+        tracing::info!("{})", summary);
+    }
+}
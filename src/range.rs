@@ -0,0 +1,225 @@
+use rocksdb::{ColumnFamily, Direction, IteratorMode, ReadOptions};
+use std::time::{Duration, Instant};
+
+use crate::db::{DbHandle, ThrottledIter};
+
+/// A key range over a column family, both bounds are optional and inclusive
+#[derive(Clone, Debug, Default)]
+pub struct KeyRange {
+    pub start: Option<Vec<u8>>,
+    pub end: Option<Vec<u8>>,
+}
+
+impl KeyRange {
+    pub fn new(start: Option<Vec<u8>>, end: Option<Vec<u8>>) -> Self {
+        KeyRange { start, end }
+    }
+
+    /// Sets the iterate bounds of this range on `opts`
+    pub fn read_options(&self, mut opts: ReadOptions) -> ReadOptions {
+        if let Some(start) = &self.start {
+            opts.set_iterate_lower_bound(start.clone());
+        }
+        if let Some(upper) = self.exclusive_end() {
+            opts.set_iterate_upper_bound(upper);
+        }
+        opts
+    }
+
+    /// Whether neither bound is set
+    pub fn is_full(&self) -> bool {
+        self.start.is_none() && self.end.is_none()
+    }
+
+    /// Whether any key in `[smallest, largest]` falls within the range, missing bounds are unbounded
+    pub fn overlaps(&self, smallest: Option<&[u8]>, largest: Option<&[u8]>) -> bool {
+        let starts_before_end = match (smallest, &self.end) {
+            (Some(smallest), Some(end)) => smallest <= end.as_slice(),
+            _ => true,
+        };
+        let ends_after_start = match (largest, &self.start) {
+            (Some(largest), Some(start)) => largest >= start.as_slice(),
+            _ => true,
+        };
+        starts_before_end && ends_after_start
+    }
+
+    /// The end of the range as an exclusive bound, as RocksDB expects it
+    pub fn exclusive_end(&self) -> Option<Vec<u8>> {
+        // The smallest key greater than `end` is `end` followed by a zero byte
+        self.end.as_ref().map(|end| {
+            let mut upper = end.clone();
+            upper.push(0);
+            upper
+        })
+    }
+}
+
+/// Iterates the key-value pairs of a column family within `range`
+pub fn iter_range<'a>(
+    db: &'a DbHandle,
+    cf: &ColumnFamily,
+    range: &KeyRange,
+    reverse: bool,
+) -> ThrottledIter<'a> {
+    let mode = if reverse {
+        IteratorMode::End
+    } else {
+        IteratorMode::Start
+    };
+    db.iterator_cf_opt(cf, range.read_options(db.read_options()), mode)
+}
+
+/// Iterates the key-value pairs of a column family whose keys start with `prefix`
+pub fn iter_prefix<'a>(
+    db: &'a DbHandle,
+    cf: &ColumnFamily,
+    prefix: &'a [u8],
+) -> impl Iterator<Item = Result<(Box<[u8]>, Box<[u8]>), rocksdb::Error>> + 'a {
+    let mut opts = db.read_options();
+    opts.set_prefix_same_as_start(true);
+    // The prefix iterator only guarantees correct results within the prefix when the
+    // column family has a matching prefix extractor, so stop as soon as we leave it
+    db.iterator_cf_opt(cf, opts, IteratorMode::From(prefix, Direction::Forward))
+        .take_while(move |item| match item {
+            Ok((key, _)) => key.starts_with(prefix),
+            Err(_) => true,
+        })
+}
+
+/// How often [`iter_range_following`] catches a secondary instance up with its primary
+pub const CATCH_UP_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Iterates like [`iter_range`], catching a secondary instance up with its primary every [`CATCH_UP_INTERVAL`]
+///
+/// An iterator only sees the writes made before it was created, so after each catch-up the iteration
+/// continues from a new iterator past the last returned key. Long scans of a database the prover is
+/// writing then include the records it wrote meanwhile. On other handles, and on secondary
+/// instances pinned to the view at open, this is plain [`iter_range`].
+pub fn iter_range_following<'a>(
+    db: &'a DbHandle,
+    cf: &'a ColumnFamily,
+    range: &KeyRange,
+    reverse: bool,
+) -> FollowingIter<'a> {
+    FollowingIter {
+        db,
+        cf,
+        range: range.clone(),
+        reverse,
+        iter: iter_range(db, cf, range, reverse),
+        last_key: None,
+        caught_up: Instant::now(),
+    }
+}
+
+/// Iterator returned by [`iter_range_following`]
+pub struct FollowingIter<'a> {
+    db: &'a DbHandle,
+    cf: &'a ColumnFamily,
+    range: KeyRange,
+    reverse: bool,
+    iter: ThrottledIter<'a>,
+    last_key: Option<Box<[u8]>>,
+    caught_up: Instant,
+}
+
+impl FollowingIter<'_> {
+    /// Catches up with the primary and recreates the iterator past the last returned key
+    fn catch_up(&mut self) -> Result<(), rocksdb::Error> {
+        self.db.try_catch_up_with_primary()?;
+        self.caught_up = Instant::now();
+
+        let mut opts = self.range.read_options(self.db.read_options());
+        let mode = match (&self.last_key, self.reverse) {
+            (Some(last_key), false) => {
+                // The smallest key after the last one is the last one followed by a zero byte
+                let mut lower = last_key.to_vec();
+                lower.push(0);
+                opts.set_iterate_lower_bound(lower);
+                IteratorMode::Start
+            },
+            (Some(last_key), true) => {
+                opts.set_iterate_upper_bound(last_key.to_vec());
+                IteratorMode::End
+            },
+            (None, false) => IteratorMode::Start,
+            (None, true) => IteratorMode::End,
+        };
+        self.iter = self.db.iterator_cf_opt(self.cf, opts, mode);
+        Ok(())
+    }
+}
+
+impl Iterator for FollowingIter<'_> {
+    type Item = Result<(Box<[u8]>, Box<[u8]>), rocksdb::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.db.follows_primary() && self.caught_up.elapsed() >= CATCH_UP_INTERVAL {
+            if let Err(e) = self.catch_up() {
+                return Some(Err(e));
+            }
+        }
+
+        let item = self.iter.next()?;
+        if let Ok((key, _)) = &item {
+            self.last_key = Some(key.clone());
+        }
+        Some(item)
+    }
+}
+
+/// Number of shards the keyspace is split into for parallel iteration, one per leading key byte
+pub const SHARD_COUNT: usize = 256;
+
+/// Restricts `opts` to the keys whose first byte is `shard`
+///
+/// The empty key sorts before every other key and belongs to shard 0.
+pub fn shard_read_options(shard: u8, mut opts: ReadOptions) -> ReadOptions {
+    if shard > 0 {
+        opts.set_iterate_lower_bound(vec![shard]);
+    }
+    if shard < u8::MAX {
+        opts.set_iterate_upper_bound(vec![shard + 1]);
+    }
+    opts
+}
+
+/// Restricts `opts` to the keys of `shard` that fall within `range`
+///
+/// Returns `None` when the shard and the range do not intersect.
+pub fn shard_read_options_in(
+    shard: u8,
+    range: &KeyRange,
+    mut opts: ReadOptions,
+) -> Option<ReadOptions> {
+    let shard_start = if shard > 0 { Some(vec![shard]) } else { None };
+    let shard_end = if shard < u8::MAX {
+        Some(vec![shard + 1])
+    } else {
+        None
+    };
+
+    // Missing bounds are unbounded, so the tighter of two bounds is the one that is set
+    let lower = match (shard_start, range.start.clone()) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    };
+    let upper = match (shard_end, range.exclusive_end()) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    };
+    if let (Some(lower), Some(upper)) = (&lower, &upper) {
+        if lower >= upper {
+            return None;
+        }
+    }
+
+    if let Some(lower) = lower {
+        opts.set_iterate_lower_bound(lower);
+    }
+    if let Some(upper) = upper {
+        opts.set_iterate_upper_bound(upper);
+    }
+    Some(opts)
+}
@@ -0,0 +1,184 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use zkwasm_host_circuits::host::{datahash::DataHashRecord, mongomerkle::MerkleRecord};
+
+use crate::error::{Error, Result};
+use crate::output::to_hex;
+use crate::{DATA_CF_NAME, MERKLE_CF_NAME};
+
+/// A value decoded by one of the built-in decoders
+#[derive(Clone, Debug)]
+pub enum DecodedRecord {
+    Merkle(MerkleRecord),
+    Data(DataHashRecord),
+}
+
+impl fmt::Display for DecodedRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodedRecord::Merkle(record) => write!(f, "MerkleRecord: {:?}", record),
+            DecodedRecord::Data(record) => write!(f, "DataRecord: {:?}", record),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct MerkleRecordView {
+    index: u64,
+    hash: String,
+    left: Option<String>,
+    right: Option<String>,
+    data: Option<String>,
+}
+
+#[derive(Serialize)]
+struct DataHashRecordView {
+    hash: String,
+    data: String,
+    data_len: usize,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum DecodedRecordView {
+    MerkleRecord(MerkleRecordView),
+    DataHashRecord(DataHashRecordView),
+}
+
+impl Serialize for DecodedRecord {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let view = match self {
+            DecodedRecord::Merkle(record) => DecodedRecordView::MerkleRecord(MerkleRecordView {
+                index: record.index,
+                hash: to_hex(&record.hash),
+                left: record.left.as_ref().map(|h| to_hex(h)),
+                right: record.right.as_ref().map(|h| to_hex(h)),
+                data: record.data.as_ref().map(|h| to_hex(h)),
+            }),
+            DecodedRecord::Data(record) => DecodedRecordView::DataHashRecord(DataHashRecordView {
+                hash: to_hex(&record.hash),
+                data: to_hex(&record.data),
+                data_len: record.data.len(),
+            }),
+        };
+        view.serialize(serializer)
+    }
+}
+
+/// Decodes the raw value of a column family into a structured record
+pub trait RecordDecoder: Send + Sync {
+    /// Name used to select the decoder with `--decode-as`
+    fn name(&self) -> &'static str;
+
+    /// Decodes `value`, `None` means the value has no structure beyond its bytes
+    fn decode(&self, value: &[u8]) -> Result<Option<DecodedRecord>>;
+}
+
+/// Decodes `MerkleRecord`s written by mongomerkle
+pub struct MerkleRecordDecoder;
+
+impl RecordDecoder for MerkleRecordDecoder {
+    fn name(&self) -> &'static str {
+        "merkle"
+    }
+
+    fn decode(&self, value: &[u8]) -> Result<Option<DecodedRecord>> {
+        let record = MerkleRecord::from_slice(value).map_err(|e| Error::Decode {
+            decoder: self.name(),
+            message: e.to_string(),
+        })?;
+        Ok(Some(DecodedRecord::Merkle(record)))
+    }
+}
+
+/// Decodes `DataHashRecord`s written by datahash
+pub struct DataHashRecordDecoder;
+
+impl RecordDecoder for DataHashRecordDecoder {
+    fn name(&self) -> &'static str {
+        "data"
+    }
+
+    fn decode(&self, value: &[u8]) -> Result<Option<DecodedRecord>> {
+        let record = DataHashRecord::from_slice(value).map_err(|e| Error::Decode {
+            decoder: self.name(),
+            message: e.to_string(),
+        })?;
+        Ok(Some(DecodedRecord::Data(record)))
+    }
+}
+
+/// Leaves values as raw bytes, used for column families without a known record type
+pub struct RawDecoder;
+
+impl RecordDecoder for RawDecoder {
+    fn name(&self) -> &'static str {
+        "raw"
+    }
+
+    fn decode(&self, _value: &[u8]) -> Result<Option<DecodedRecord>> {
+        Ok(None)
+    }
+}
+
+/// Decoders selectable by name, with a default decoder per column family
+pub struct DecoderRegistry {
+    decoders: Vec<Box<dyn RecordDecoder>>,
+    by_cf: HashMap<String, &'static str>,
+}
+
+impl Default for DecoderRegistry {
+    fn default() -> Self {
+        let mut registry = DecoderRegistry {
+            decoders: vec![Box::new(RawDecoder)],
+            by_cf: HashMap::new(),
+        };
+        registry.register(Box::new(MerkleRecordDecoder), &[MERKLE_CF_NAME]);
+        registry.register(Box::new(DataHashRecordDecoder), &[DATA_CF_NAME]);
+        registry
+    }
+}
+
+impl DecoderRegistry {
+    /// Adds a decoder, replacing any decoder with the same name, and makes it the default for `cf_names`
+    pub fn register(&mut self, decoder: Box<dyn RecordDecoder>, cf_names: &[&str]) {
+        let name = decoder.name();
+        self.decoders.retain(|existing| existing.name() != name);
+        self.decoders.push(decoder);
+        for cf_name in cf_names {
+            self.by_cf.insert(cf_name.to_string(), name);
+        }
+    }
+
+    /// Names of all registered decoders
+    pub fn names(&self) -> Vec<&'static str> {
+        self.decoders.iter().map(|decoder| decoder.name()).collect()
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&dyn RecordDecoder> {
+        self.decoders
+            .iter()
+            .find(|decoder| decoder.name() == name)
+            .map(|decoder| decoder.as_ref())
+    }
+
+    /// Default decoder of a column family, raw bytes for unknown column families
+    pub fn for_cf(&self, cf_name: &str) -> &dyn RecordDecoder {
+        self.by_cf
+            .get(cf_name)
+            .and_then(|name| self.by_name(name))
+            .unwrap_or(&RawDecoder)
+    }
+
+    /// Picks the decoder named by `decode_as`, or the column family default when it is not given
+    pub fn resolve(&self, cf_name: &str, decode_as: Option<&str>) -> Result<&dyn RecordDecoder> {
+        match decode_as {
+            Some(name) => self.by_name(name).ok_or_else(|| Error::UnknownDecoder {
+                name: name.to_string(),
+                expected: self.names().join(", "),
+            }),
+            None => Ok(self.for_cf(cf_name)),
+        }
+    }
+}
@@ -0,0 +1,307 @@
+//! JSON-RPC 2.0 over stdin/stdout or a unix socket, one request and one response per line
+//!
+//! Every call runs against the same open database handle, so a client issuing many small lookups
+//! pays the open cost once. Results are the JSON objects printed by `--output json`.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+
+use crate::commands;
+use crate::db::DbHandle;
+use crate::error;
+use crate::interpret::ValueType;
+use crate::key::parse_key_quiet;
+use crate::paginate::Pagination;
+use crate::range::KeyRange;
+use crate::record::DecoderRegistry;
+
+/// Records returned by `scan` and `prefix_scan` when the call sets no limit
+const DEFAULT_SCAN_LIMIT: u64 = 100;
+
+/// Error codes defined by the JSON-RPC 2.0 specification
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// Code of errors raised by a command, the CLI exit code of the error is in its `data`
+const COMMAND_ERROR: i64 = -32000;
+
+#[derive(Deserialize)]
+struct Request {
+    jsonrpc: String,
+    /// Requests without an id are notifications and get no response
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Serialize)]
+struct Response {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+#[derive(Serialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl ToString) -> Self {
+        RpcError {
+            code,
+            message: message.to_string(),
+            data: None,
+        }
+    }
+
+    fn command(e: &(dyn std::error::Error + 'static)) -> Self {
+        RpcError {
+            code: COMMAND_ERROR,
+            message: e.to_string(),
+            data: Some(json!({ "exit_code": error::exit_code(e) })),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct GetParams {
+    cf: String,
+    key: String,
+    decode_as: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct MultiGetParams {
+    cf: String,
+    keys: Vec<String>,
+    decode_as: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ScanParams {
+    cf: String,
+    start_key: Option<String>,
+    end_key: Option<String>,
+    #[serde(default)]
+    reverse: bool,
+    #[serde(default)]
+    skip: u64,
+    limit: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct PrefixScanParams {
+    cf: String,
+    prefix: String,
+    decode_as: Option<String>,
+    #[serde(default)]
+    skip: u64,
+    limit: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct CountParams {
+    cf: String,
+    #[serde(default)]
+    approximate: bool,
+}
+
+fn params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    // Omitted params are read as an empty object so that calls without arguments need none
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e))
+}
+
+fn key(key: &str) -> Result<Vec<u8>, RpcError> {
+    parse_key_quiet(key).map_err(|e| RpcError::new(INVALID_PARAMS, e))
+}
+
+/// Runs one method and serializes its result
+fn call(
+    db: &DbHandle,
+    decoders: &DecoderRegistry,
+    method: &str,
+    raw_params: Value,
+) -> Result<Value, RpcError> {
+    let result: Result<Value, Box<dyn std::error::Error>> = match method {
+        "get" => {
+            let p: GetParams = params(raw_params)?;
+            let key = key(&p.key)?;
+            let decoder = decoders
+                .resolve(&p.cf, p.decode_as.as_deref())
+                .map_err(|e| RpcError::new(INVALID_PARAMS, e))?;
+            commands::check(db, &p.cf, &key, decoder, ValueType::Auto)
+                .and_then(|result| Ok(serde_json::to_value(result)?))
+        }
+        "multi_get" => {
+            let p: MultiGetParams = params(raw_params)?;
+            let keys = p.keys.iter().map(String::as_str).map(key).collect::<Result<Vec<_>, _>>()?;
+            let decoder = decoders
+                .resolve(&p.cf, p.decode_as.as_deref())
+                .map_err(|e| RpcError::new(INVALID_PARAMS, e))?;
+            keys.iter()
+                .map(|key| commands::check(db, &p.cf, key, decoder, ValueType::Auto))
+                .collect::<Result<Vec<_>, _>>()
+                .and_then(|results| Ok(serde_json::to_value(results)?))
+        }
+        "scan" => {
+            let p: ScanParams = params(raw_params)?;
+            let start = p.start_key.as_deref().map(key).transpose()?;
+            let end = p.end_key.as_deref().map(key).transpose()?;
+            let page = Pagination {
+                skip: p.skip,
+                limit: Some(p.limit.unwrap_or(DEFAULT_SCAN_LIMIT)),
+                page_size: None,
+            };
+            let mut records = Vec::new();
+            commands::scan(db, &p.cf, &KeyRange::new(start, end), p.reverse, page, |entry| {
+                records.push(entry);
+                Ok(())
+            })
+            .and_then(|summary| Ok(json!({ "records": records, "summary": summary })))
+        }
+        "prefix_scan" => {
+            let p: PrefixScanParams = params(raw_params)?;
+            let prefix = key(&p.prefix)?;
+            let decoder = decoders
+                .resolve(&p.cf, p.decode_as.as_deref())
+                .map_err(|e| RpcError::new(INVALID_PARAMS, e))?;
+            let page = Pagination {
+                skip: p.skip,
+                limit: Some(p.limit.unwrap_or(DEFAULT_SCAN_LIMIT)),
+                page_size: None,
+            };
+            let mut records = Vec::new();
+            commands::prefix_scan(db, &p.cf, &prefix, decoder, page, |entry| {
+                records.push(entry);
+                Ok(())
+            })
+            .and_then(|summary| Ok(json!({ "records": records, "summary": summary })))
+        }
+        "count" => {
+            let p: CountParams = params(raw_params)?;
+            let result = if p.approximate {
+                commands::count_approximate(db, &p.cf)
+            } else {
+                let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+                commands::count(db, &p.cf, &KeyRange::default(), threads, None, |_| {})
+            };
+            result.and_then(|result| Ok(serde_json::to_value(result)?))
+        }
+        "stats" => commands::stats(db).and_then(|report| Ok(serde_json::to_value(report)?)),
+        _ => {
+            let message = format!("Unknown method '{}'", method);
+            return Err(RpcError::new(METHOD_NOT_FOUND, message));
+        }
+    };
+    result.map_err(|e| RpcError::command(e.as_ref()))
+}
+
+/// Answers one line, `None` for notifications
+fn handle(db: &DbHandle, decoders: &DecoderRegistry, line: &str) -> Option<Response> {
+    let response = |id: Value, outcome: Result<Value, RpcError>| {
+        let (result, error) = match outcome {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+        Response {
+            jsonrpc: "2.0",
+            id,
+            result,
+            error,
+        }
+    };
+
+    let value: Value = match serde_json::from_str(line) {
+        Ok(value) => value,
+        Err(e) => return Some(response(Value::Null, Err(RpcError::new(PARSE_ERROR, e)))),
+    };
+    let request: Request = match serde_json::from_value(value) {
+        Ok(request) => request,
+        Err(e) => return Some(response(Value::Null, Err(RpcError::new(INVALID_REQUEST, e)))),
+    };
+    if request.jsonrpc != "2.0" {
+        let error = RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\"");
+        return Some(response(request.id.unwrap_or(Value::Null), Err(error)));
+    }
+
+    let _span = tracing::debug_span!("rpc", method = request.method.as_str()).entered();
+    let outcome = call(db, decoders, &request.method, request.params);
+    request.id.map(|id| response(id, outcome))
+}
+
+/// Answers the requests read from `reader` on `writer` until the reader is closed
+pub fn serve_lines<R: BufRead, W: Write>(
+    db: &DbHandle,
+    decoders: &DecoderRegistry,
+    reader: R,
+    mut writer: W,
+) -> io::Result<()> {
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handle(db, decoders, &line) {
+            serde_json::to_writer(&mut writer, &response)?;
+            writer.write_all(b"\n")?;
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Answers requests on stdin until it is closed
+pub fn serve_stdio(db: &DbHandle, decoders: &DecoderRegistry) -> io::Result<()> {
+    serve_lines(db, decoders, io::stdin().lock(), io::stdout().lock())
+}
+
+/// Listens on a unix socket at `path`, answering every connection on its own thread
+///
+/// A socket left at `path` by an earlier run is replaced, any other file is an error.
+#[cfg(unix)]
+pub fn serve_socket(
+    db: &DbHandle,
+    decoders: &DecoderRegistry,
+    path: &std::path::Path,
+) -> io::Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::UnixListener;
+
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a socket", path.display()),
+            ));
+        }
+        std::fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+
+    std::thread::scope(|scope| {
+        for stream in listener.incoming() {
+            let stream = stream?;
+            scope.spawn(move || {
+                let served = stream.try_clone().and_then(|reader| {
+                    serve_lines(db, decoders, io::BufReader::new(reader), stream)
+                });
+                if let Err(e) = served {
+                    tracing::warn!("RPC connection failed: {}", e);
+                }
+            });
+        }
+        Ok(())
+    })
+}
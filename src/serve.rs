@@ -0,0 +1,205 @@
+//! Read-only HTTP API over a database, returning the same JSON as `--output json`
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use crate::commands;
+use crate::db::DbHandle;
+use crate::interpret::ValueType;
+use crate::key::parse_key_quiet;
+use crate::output::RecordEntry;
+use crate::paginate::Pagination;
+use crate::range::KeyRange;
+use crate::record::DecoderRegistry;
+
+/// Number of records returned by a prefix scan when the request sets no limit
+const DEFAULT_SCAN_LIMIT: usize = 100;
+
+#[derive(Clone)]
+struct AppState {
+    db: Arc<DbHandle>,
+    decoders: Arc<DecoderRegistry>,
+}
+
+/// An error answered as `{"error": "..."}` with a status code
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new<E: ToString>(status: StatusCode, e: E) -> Self {
+        ApiError {
+            status,
+            message: e.to_string(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({ "error": self.message });
+        (self.status, Json(body)).into_response()
+    }
+}
+
+/// Runs a command on the blocking thread pool, RocksDB calls must not block the async workers
+async fn blocking<T, F>(state: AppState, f: F) -> Result<T, ApiError>
+where
+    T: Send + 'static,
+    F: FnOnce(&DbHandle, &DecoderRegistry) -> Result<T, Box<dyn std::error::Error>>
+        + Send
+        + 'static,
+{
+    tokio::task::spawn_blocking(move || {
+        f(&state.db, &state.decoders).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e))?
+    .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+fn require_cf(state: &AppState, cf: &str) -> Result<(), ApiError> {
+    state
+        .db
+        .cf(cf)
+        .map(|_| ())
+        .map_err(|e| ApiError::new(StatusCode::NOT_FOUND, e))
+}
+
+#[derive(Deserialize)]
+struct DecodeParams {
+    decode_as: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CountParams {
+    #[serde(default)]
+    approximate: bool,
+}
+
+#[derive(Deserialize)]
+struct PrefixParams {
+    decode_as: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct PrefixScanResponse {
+    column_family: String,
+    records: Vec<RecordEntry>,
+}
+
+async fn get_stats(State(state): State<AppState>) -> Result<Response, ApiError> {
+    let report = blocking(state, |db, _| commands::stats(db)).await?;
+    Ok(Json(report).into_response())
+}
+
+async fn get_count(
+    State(state): State<AppState>,
+    Path(cf): Path<String>,
+    Query(params): Query<CountParams>,
+) -> Result<Response, ApiError> {
+    require_cf(&state, &cf)?;
+    let result = blocking(state, move |db, _| {
+        if params.approximate {
+            commands::count_approximate(db, &cf)
+        } else {
+            let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+            commands::count(db, &cf, &KeyRange::default(), threads, None, |_| {})
+        }
+    })
+    .await?;
+    Ok(Json(result).into_response())
+}
+
+async fn get_key(
+    State(state): State<AppState>,
+    Path((cf, key)): Path<(String, String)>,
+    Query(params): Query<DecodeParams>,
+) -> Result<Response, ApiError> {
+    require_cf(&state, &cf)?;
+    let key = parse_key_quiet(&key).map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?;
+    state
+        .decoders
+        .resolve(&cf, params.decode_as.as_deref())
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?;
+
+    let result = blocking(state, move |db, decoders| {
+        let decoder = decoders.resolve(&cf, params.decode_as.as_deref())?;
+        commands::check(db, &cf, &key, decoder, ValueType::Auto)
+    })
+    .await?;
+    let status = if result.found {
+        StatusCode::OK
+    } else {
+        StatusCode::NOT_FOUND
+    };
+    Ok((status, Json(result)).into_response())
+}
+
+async fn get_prefix(
+    State(state): State<AppState>,
+    Path((cf, prefix)): Path<(String, String)>,
+    Query(params): Query<PrefixParams>,
+) -> Result<Response, ApiError> {
+    require_cf(&state, &cf)?;
+    let prefix =
+        parse_key_quiet(&prefix).map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?;
+    state
+        .decoders
+        .resolve(&cf, params.decode_as.as_deref())
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?;
+
+    let limit = params.limit.unwrap_or(DEFAULT_SCAN_LIMIT);
+    let response = blocking(state, move |db, decoders| {
+        let decoder = decoders.resolve(&cf, params.decode_as.as_deref())?;
+        let mut records = Vec::new();
+        let page = Pagination::limit(limit as u64);
+        commands::prefix_scan(db, &cf, &prefix, decoder, page, |entry| {
+            records.push(entry);
+            Ok(())
+        })?;
+        Ok(PrefixScanResponse {
+            column_family: cf,
+            records,
+        })
+    })
+    .await?;
+    Ok(Json(response).into_response())
+}
+
+/// Serves the API on `addr` until the process is stopped
+///
+/// Endpoints:
+/// - `GET /stats`
+/// - `GET /cf/{cf}/count?approximate=true`
+/// - `GET /cf/{cf}/key/{key}?decode_as=merkle`, 404 when the key is absent
+/// - `GET /cf/{cf}/prefix/{prefix}?limit=100&decode_as=merkle`
+pub async fn serve(
+    db: DbHandle,
+    decoders: DecoderRegistry,
+    addr: SocketAddr,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let state = AppState {
+        db: Arc::new(db),
+        decoders: Arc::new(decoders),
+    };
+    let app = Router::new()
+        .route("/stats", get(get_stats))
+        .route("/cf/:cf/count", get(get_count))
+        .route("/cf/:cf/key/:key", get(get_key))
+        .route("/cf/:cf/prefix/:prefix", get(get_prefix))
+        .with_state(state);
+
+    axum::Server::bind(&addr)
+        .serve(app.into_make_service())
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,108 @@
+//! Wall-clock time and RocksDB perf context counters of a whole command
+
+use rocksdb::perf::{set_perf_stats, PerfContext, PerfMetric, PerfStatsLevel};
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use crate::output::{PerfReport, TimingReport};
+
+/// Perf context counters reported with `--perf`, under the names RocksDB gives them
+const PERF_METRICS: [(&str, PerfMetric); 39] = [
+    ("user_key_comparison_count", PerfMetric::UserKeyComparisonCount),
+    ("block_cache_hit_count", PerfMetric::BlockCacheHitCount),
+    ("block_read_count", PerfMetric::BlockReadCount),
+    ("block_read_byte", PerfMetric::BlockReadByte),
+    ("block_read_time", PerfMetric::BlockReadTime),
+    ("block_checksum_time", PerfMetric::BlockChecksumTime),
+    ("block_decompress_time", PerfMetric::BlockDecompressTime),
+    ("get_read_bytes", PerfMetric::GetReadBytes),
+    ("multiget_read_bytes", PerfMetric::MultigetReadBytes),
+    ("iter_read_bytes", PerfMetric::IterReadBytes),
+    ("internal_key_skipped_count", PerfMetric::InternalKeySkippedCount),
+    ("internal_delete_skipped_count", PerfMetric::InternalDeleteSkippedCount),
+    ("internal_recent_skipped_count", PerfMetric::InternalRecentSkippedCount),
+    ("internal_merge_count", PerfMetric::InternalMergeCount),
+    ("get_snapshot_time", PerfMetric::GetSnapshotTime),
+    ("get_from_memtable_time", PerfMetric::GetFromMemtableTime),
+    ("get_from_memtable_count", PerfMetric::GetFromMemtableCount),
+    ("get_post_process_time", PerfMetric::GetPostProcessTime),
+    ("get_from_output_files_time", PerfMetric::GetFromOutputFilesTime),
+    ("seek_on_memtable_time", PerfMetric::SeekOnMemtableTime),
+    ("seek_on_memtable_count", PerfMetric::SeekOnMemtableCount),
+    ("next_on_memtable_count", PerfMetric::NextOnMemtableCount),
+    ("prev_on_memtable_count", PerfMetric::PrevOnMemtableCount),
+    ("seek_child_seek_time", PerfMetric::SeekChildSeekTime),
+    ("seek_child_seek_count", PerfMetric::SeekChildSeekCount),
+    ("seek_min_heap_time", PerfMetric::SeekMinHeapTime),
+    ("seek_internal_seek_time", PerfMetric::SeekInternalSeekTime),
+    ("find_next_user_entry_time", PerfMetric::FindNextUserEntryTime),
+    ("db_mutex_lock_nanos", PerfMetric::DbMutexLockNanos),
+    ("read_index_block_nanos", PerfMetric::ReadIndexBlockNanos),
+    ("read_filter_block_nanos", PerfMetric::ReadFilterBlockNanos),
+    ("new_table_block_iter_nanos", PerfMetric::NewTableBlockIterNanos),
+    ("new_table_iterator_nanos", PerfMetric::NewTableIteratorNanos),
+    ("block_seek_nanos", PerfMetric::BlockSeekNanos),
+    ("find_table_nanos", PerfMetric::FindTableNanos),
+    ("bloom_memtable_hit_count", PerfMetric::BloomMemtableHitCount),
+    ("bloom_memtable_miss_count", PerfMetric::BloomMemtableMissCount),
+    ("bloom_sst_hit_count", PerfMetric::BloomSstHitCount),
+    ("bloom_sst_miss_count", PerfMetric::BloomSstMissCount),
+];
+
+/// Measures the work done on the current thread from [`Timer::start`] to [`Timer::finish`]
+///
+/// RocksDB keeps its perf context per thread, reads made by worker threads, such as those of
+/// `--threads` exports, are not counted.
+pub struct Timer {
+    started: Instant,
+    context: PerfContext,
+}
+
+impl Timer {
+    /// Enables perf counting on this thread and starts the clock
+    pub fn start() -> Self {
+        Self::start_at(PerfStatsLevel::EnableTimeExceptForMutex)
+    }
+
+    /// Like [`Timer::start`], also timing waits on the database mutex
+    pub fn start_detailed() -> Self {
+        Self::start_at(PerfStatsLevel::EnableTime)
+    }
+
+    fn start_at(level: PerfStatsLevel) -> Self {
+        set_perf_stats(level);
+        let mut context = PerfContext::default();
+        context.reset();
+        Timer {
+            started: Instant::now(),
+            context,
+        }
+    }
+
+    /// Every perf context counter counted so far
+    pub fn perf_counters(&self) -> PerfReport {
+        let counters: BTreeMap<&'static str, u64> = PERF_METRICS
+            .iter()
+            .map(|&(name, metric)| (name, self.context.metric(metric)))
+            .collect();
+        PerfReport { counters }
+    }
+
+    /// Stops the clock and perf counting and reads the counters
+    pub fn finish(self) -> TimingReport {
+        let elapsed = self.started.elapsed();
+        set_perf_stats(PerfStatsLevel::Disable);
+        let metric = |metric| self.context.metric(metric);
+
+        TimingReport {
+            elapsed_ms: elapsed.as_secs_f64() * 1000.0,
+            block_reads: metric(PerfMetric::BlockReadCount),
+            block_read_bytes: metric(PerfMetric::BlockReadByte),
+            block_read_ms: metric(PerfMetric::BlockReadTime) as f64 / 1e6,
+            block_cache_hits: metric(PerfMetric::BlockCacheHitCount),
+            seeks: metric(PerfMetric::SeekChildSeekCount),
+            memtable_gets: metric(PerfMetric::GetFromMemtableCount),
+            internal_keys_skipped: metric(PerfMetric::InternalKeySkippedCount),
+        }
+    }
+}